@@ -0,0 +1,175 @@
+//! Solve the Solomon benchmark instances and compare the results with the best known ones
+//!
+//! Every `*.txt` file in `benches/instances`, or in the directory `ORVRM_BENCH_INSTANCES`
+//! names, is solved in turn. `ORVRM_BENCH_TIME` sets the seconds per instance (default 5)
+//! and `ORVRM_BENCH_ALGORITHM` the algorithm, such as `lns` (default `local_search`). Best
+//! known results are read from `best_known.txt` in the same directory, one instance per line
+//! as `<name> <vehicles> <distance>`.
+//!
+//! Run with `cargo bench -p orvrm-core --bench solomon`.
+
+use orvrm_core::models::{Algorithm, RoutingRequest, RoutingResponse};
+use orvrm_core::services::routing::{RoutingConfig, RoutingService};
+use orvrm_core::utils::solomon::{self, Instance};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Outcome of solving one instance
+struct Outcome {
+    name: String,
+    customers: usize,
+    vehicles: usize,
+    distance: f64,
+    unassigned: u32,
+    time: Duration,
+}
+
+fn main() {
+    let directory = std::env::var_os("ORVRM_BENCH_INSTANCES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/instances"));
+    let max_time: u32 = env_or("ORVRM_BENCH_TIME", "5")
+        .parse()
+        .expect("ORVRM_BENCH_TIME is a number of seconds");
+    let algorithm: Algorithm = serde_json::from_value(serde_json::Value::String(env_or(
+        "ORVRM_BENCH_ALGORITHM",
+        "local_search",
+    )))
+    .expect("ORVRM_BENCH_ALGORITHM names an algorithm");
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&directory)
+        .unwrap_or_else(|err| panic!("Cannot read {}: {}", directory.display(), err))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name != "best_known.txt")
+        })
+        .collect();
+    files.sort();
+    let best_known = read_best_known(&directory.join("best_known.txt"));
+
+    let runtime = tokio::runtime::Runtime::new().expect("tokio runtime");
+    let service = RoutingService::new(RoutingConfig::default());
+
+    println!(
+        "{:<14} {:>9} {:>8} {:>10} {:>16} {:>8} {:>10} {:>8}",
+        "Instance", "Customers", "Vehicles", "Distance", "Best known", "Gap", "Unassigned", "Time"
+    );
+    for file in files {
+        let text = std::fs::read_to_string(&file).expect("readable instance");
+        let instance = match solomon::parse(&text) {
+            Ok(instance) => instance,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", file.display(), err);
+                continue;
+            }
+        };
+
+        let outcome = runtime.block_on(solve(&service, instance, max_time, algorithm));
+        let (best, gap) = match best_known.get(&outcome.name) {
+            Some((vehicles, distance)) => (
+                format!("{} / {:.2}", vehicles, distance),
+                format!("{:.2}%", (outcome.distance - distance) / distance * 100.0),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<14} {:>9} {:>8} {:>10.2} {:>16} {:>8} {:>10} {:>7.2}s",
+            outcome.name,
+            outcome.customers,
+            outcome.vehicles,
+            outcome.distance,
+            best,
+            gap,
+            outcome.unassigned,
+            outcome.time.as_secs_f64()
+        );
+    }
+}
+
+async fn solve(
+    service: &RoutingService,
+    instance: Instance,
+    max_time: u32,
+    algorithm: Algorithm,
+) -> Outcome {
+    let mut request = instance.request;
+    let options = request.options.get_or_insert_with(Default::default);
+    options.max_time = Some(max_time);
+    options.algorithm = Some(algorithm);
+
+    let start = Instant::now();
+    let response = service
+        .process_request(request.clone())
+        .await
+        .unwrap_or_else(|err| panic!("Solving {} failed: {}", instance.name, err));
+
+    Outcome {
+        name: instance.name,
+        customers: request.jobs.len(),
+        vehicles: response
+            .routes
+            .iter()
+            .filter(|route| !route.route.is_empty())
+            .count(),
+        distance: euclidean_distance(&request, &response),
+        unassigned: response.summary.unassigned,
+        time: start.elapsed(),
+    }
+}
+
+/// Exact length of the routes, which the response rounds to whole units per route
+fn euclidean_distance(request: &RoutingRequest, response: &RoutingResponse) -> f64 {
+    let locations: HashMap<u64, [f64; 2]> = request
+        .jobs
+        .iter()
+        .map(|job| (job.id, job.location))
+        .collect();
+    response
+        .routes
+        .iter()
+        .filter(|route| !route.route.is_empty())
+        .map(|route| {
+            let vehicle = request
+                .vehicles
+                .iter()
+                .find(|vehicle| vehicle.id == route.vehicle_id)
+                .expect("vehicle of the route");
+            let stops: Vec<[f64; 2]> = vehicle
+                .start
+                .into_iter()
+                .chain(route.route.iter().map(|id| locations[id]))
+                .chain(vehicle.end)
+                .collect();
+            stops
+                .windows(2)
+                .map(|leg| solomon::distance(leg[0], leg[1]))
+                .sum::<f64>()
+        })
+        .sum()
+}
+
+/// Best known vehicles and distance per instance name
+fn read_best_known(path: &Path) -> HashMap<String, (u32, f64)> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [name, vehicles, distance] => Some((
+                    name.to_string(),
+                    (vehicles.parse().ok()?, distance.parse().ok()?),
+                )),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
@@ -0,0 +1,10 @@
+//! Vehicle routing models, solver and OSRM client.
+//!
+//! Build a [`services::RoutingService`] to optimize a [`models::RoutingRequest`]
+//! without running the HTTP server.
+pub mod models;
+pub mod services;
+pub mod utils;
+
+#[cfg(feature = "test-util")]
+pub mod testing;
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::time;
+
+/// Represents a depot that vehicles start from, return to and reload at
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Depot {
+    /// Unique identifier for the depot
+    pub id: u64,
+    
+    /// Location as [longitude, latitude]
+    pub location: [f64; 2],
+    
+    /// Opening hours; vehicles leave the depot after it opens and return before it closes
+    #[serde(default, deserialize_with = "time::deserialize_window", skip_serializing_if = "Option::is_none")]
+    pub time_window: Option<[i64; 2]>,
+    
+    /// Goods in stock per capacity dimension, shared by the deliveries of all vehicles
+    /// starting at the depot; unlimited when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stock: Option<Vec<u32>>,
+}
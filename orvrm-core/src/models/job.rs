@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::time;
+
+/// Represents a job (delivery, pickup, etc.) in the routing problem
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Job {
+    /// Unique identifier for the job
+    pub id: u64,
+    
+    /// Location as [longitude, latitude]
+    pub location: [f64; 2],
+    
+    /// Service time in seconds
+    #[serde(default)]
+    pub service: u32,
+    
+    /// Setup time in seconds before service, such as parking, only spent when the vehicle
+    /// arrives from another location
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub setup: Option<u32>,
+    
+    /// Delivery amounts (can be multi-dimensional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<Vec<u32>>,
+    
+    /// Pickup amounts (can be multi-dimensional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pickup: Option<Vec<u32>>,
+    
+    /// Time windows for the job
+    #[serde(default, deserialize_with = "time::deserialize_optional_windows", skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<Vec<[i64; 2]>>,
+    
+    /// Skills required to perform this job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skills: Option<Vec<String>>,
+    
+    /// Load types of the goods, such as `frozen`, that the vehicle must be able to carry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_types: Option<Vec<String>>,
+    
+    /// Priority of the job (higher value means higher priority)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    
+    /// IDs of the only vehicles that may serve this job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_vehicles: Option<Vec<u64>>,
+    
+    /// IDs of vehicles that must not serve this job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disallowed_vehicles: Option<Vec<u64>>,
+    
+    /// Name of the group this job belongs to; all jobs of a group are served by one vehicle,
+    /// or all left unassigned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    
+    /// Free text describing the job, such as the customer's name, echoed on its route step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Arbitrary JSON of the client, such as its own references, echoed on the job's route step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl Job {
+    /// Check whether the vehicle with ID `vehicle_id` may serve this job
+    pub fn allows_vehicle(&self, vehicle_id: u64) -> bool {
+        let allowed = self
+            .allowed_vehicles
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(&vehicle_id));
+        let disallowed = self
+            .disallowed_vehicles
+            .as_ref()
+            .is_some_and(|disallowed| disallowed.contains(&vehicle_id));
+        allowed && !disallowed
+    }
+} 
\ No newline at end of file
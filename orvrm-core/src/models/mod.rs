@@ -0,0 +1,29 @@
+pub mod depot;
+pub mod job;
+pub mod relation;
+pub mod shipment;
+pub mod vehicle;
+pub mod request;
+pub mod response;
+pub mod time;
+
+pub use depot::Depot;
+pub use job::Job;
+pub use relation::{Relation, RelationStep, RelationType};
+pub use shipment::{Shipment, ShipmentStep};
+pub use vehicle::{
+    Break, Compatibility, CostBreakdown, Instruction, Vehicle, VehicleCosts, VehicleRoute, RouteLeg,
+    RouteStep, Violation, ViolationType, WorkingRules, Zone,
+};
+pub use request::{
+    Algorithm, BalanceMetric, BalanceOptions, Construction, CoordinateOrder, CostMatrix,
+    DuplicateIds, EtaRequest, GeometryFormat, InitialRoute, InsertRequest, LnsOptions, LoadOrder,
+    LockedRoute, LockedStep, MatrixRequest, PriorityPolicy, RemoveRequest, ReoptimizeRequest,
+    RouteRequest, RoutingRequest, SolveStrategy, TimeWindowPolicy,
+};
+pub use response::{
+    AlternativeSolution, EngineLatency, EtaResponse, FeasibilityReport, InsertResponse,
+    OptimizationProgress, ProgressRoute, RouteFeasibility, RoutePage, RouteResponse,
+    RoutingResponse, RoutingSummary, StepEta, StockOut, StopEta, StopFeasibility, Unassigned,
+    UnassignedCode, UnassignedJob, UnassignedReason,
+};
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Constraint on how the steps of jobs and shipments share routes
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Relation {
+    /// Kind of constraint
+    #[serde(rename = "type")]
+    pub kind: RelationType,
+    
+    /// Steps the constraint applies to, in the order sequences must keep
+    pub steps: Vec<RelationStep>,
+}
+
+/// Kind of relation between steps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationType {
+    /// The steps are served by the same vehicle, in any order
+    InSameRoute,
+    
+    /// The steps are served by the same vehicle in the given order, possibly with other
+    /// stops in between
+    InSequence,
+    
+    /// The steps are served by the same vehicle one right after the other
+    InDirectSequence,
+    
+    /// No two of the steps are served by the same vehicle
+    NotInSameRoute,
+}
+
+/// Job or shipment step a relation refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RelationStep {
+    /// A job
+    Job {
+        /// ID of the job
+        id: u64,
+    },
+    
+    /// The pickup of a shipment
+    Pickup {
+        /// ID of the shipment
+        id: u64,
+    },
+    
+    /// The delivery of a shipment
+    Delivery {
+        /// ID of the shipment
+        id: u64,
+    },
+}
+
+impl RelationStep {
+    /// ID of the job or shipment
+    pub fn id(&self) -> u64 {
+        match self {
+            RelationStep::Job { id }
+            | RelationStep::Pickup { id }
+            | RelationStep::Delivery { id } => *id,
+        }
+    }
+}
@@ -0,0 +1,908 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+use super::{depot::Depot, time, vehicle::{RouteStep, Vehicle, VehicleRoute, Zone}, job::Job, relation::Relation, shipment::Shipment};
+
+/// Represents a complete routing optimization request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoutingRequest {
+    /// List of vehicles available for the routing problem
+    pub vehicles: Vec<Vehicle>,
+    
+    /// List of jobs to be assigned to vehicles
+    pub jobs: Vec<Job>,
+    
+    /// List of shipments to be assigned to vehicles
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shipments: Vec<Shipment>,
+    
+    /// Depots that vehicles refer to by ID as their start and end
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depots: Vec<Depot>,
+    
+    /// Optional routing profile to use (car, bike, foot, etc.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_profile: Option<String>,
+    
+    /// Optional routing options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<RoutingOptions>,
+    
+    /// Optional travel matrix to use instead of querying OSRM
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<CostMatrix>,
+    
+    /// Routes of an earlier solution to start optimizing from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub initial_solution: Vec<InitialRoute>,
+    
+    /// Constraints on which job and shipment steps share a route, and in what order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relations: Vec<Relation>,
+    
+    /// URL to post the outcome of an asynchronous optimization to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+impl RoutingRequest {
+    /// How duplicate vehicle, job and shipment IDs are handled
+    pub fn duplicate_ids(&self) -> DuplicateIds {
+        self.options
+            .as_ref()
+            .and_then(|o| o.duplicate_ids)
+            .unwrap_or_default()
+    }
+    
+    /// Drop or rename the vehicles, jobs and shipments whose ID is already taken, as
+    /// `options.duplicate_ids` asks, returning a warning per duplicate
+    ///
+    /// Later duplicates are the ones changed; the first of each ID stays as it is.
+    pub fn resolve_duplicate_ids(&mut self) -> Vec<String> {
+        let policy = self.duplicate_ids();
+        let mut warnings = Vec::new();
+        if policy != DuplicateIds::Reject {
+            let warn = &mut warnings;
+            resolve_duplicates(&mut self.vehicles, "vehicle", policy, |v| &mut v.id, warn);
+            resolve_duplicates(&mut self.jobs, "job", policy, |j| &mut j.id, warn);
+            resolve_duplicates(&mut self.shipments, "shipment", policy, |s| &mut s.id, warn);
+        }
+        warnings
+    }
+    
+    /// Order of the coordinates in the request's locations
+    pub fn coordinate_order(&self) -> CoordinateOrder {
+        self.options
+            .as_ref()
+            .and_then(|o| o.coordinate_order)
+            .unwrap_or_default()
+    }
+    
+    /// Turn the locations of a request in [latitude, longitude] order into [longitude,
+    /// latitude], as `options.coordinate_order` asks
+    ///
+    /// Zones are GeoJSON and always in [longitude, latitude] order, so they are kept.
+    pub fn resolve_coordinate_order(&mut self) {
+        let order = self.coordinate_order();
+        if order == CoordinateOrder::LonLat {
+            return;
+        }
+        
+        for depot in &mut self.depots {
+            depot.location = order.lonlat(depot.location);
+        }
+        for vehicle in &mut self.vehicles {
+            for location in [&mut vehicle.start, &mut vehicle.end, &mut vehicle.current_position] {
+                *location = location.map(|location| order.lonlat(location));
+            }
+            for step in vehicle.steps.iter_mut().flatten() {
+                step.swap_coordinates();
+            }
+        }
+        for job in &mut self.jobs {
+            job.location = order.lonlat(job.location);
+        }
+        for shipment in &mut self.shipments {
+            shipment.pickup.location = order.lonlat(shipment.pickup.location);
+            shipment.delivery.location = order.lonlat(shipment.delivery.location);
+        }
+        for step in self.initial_solution.iter_mut().flat_map(|route| &mut route.steps) {
+            step.swap_coordinates();
+        }
+        if let Some(matrix) = &mut self.matrix {
+            for location in &mut matrix.locations {
+                *location = order.lonlat(*location);
+            }
+        }
+        if let Some(options) = &mut self.options {
+            options.coordinate_order = None;
+        }
+    }
+    
+    /// Give vehicles that refer to depots the depot locations as their start and end
+    ///
+    /// The vehicle's time window, or each of its shifts, is narrowed to leave after its start
+    /// depot opens and to return before its end depot closes. References to unknown depots
+    /// are left as they are.
+    pub fn resolve_depots(&mut self) {
+        for vehicle in &mut self.vehicles {
+            let depot = |id: Option<u64>| {
+                id.and_then(|id| self.depots.iter().find(|depot| depot.id == id))
+            };
+            let start = depot(vehicle.start_depot);
+            let end = depot(vehicle.end_depot);
+
+            let opens = start.and_then(|depot| depot.time_window).map(|tw| tw[0]);
+            let closes = end.and_then(|depot| depot.time_window).map(|tw| tw[1]);
+            let narrow = |[earliest, latest]: [i64; 2]| {
+                [
+                    opens.map_or(earliest, |opens| opens.max(earliest)),
+                    closes.map_or(latest, |closes| closes.min(latest)),
+                ]
+            };
+            if let Some(shifts) = &mut vehicle.shifts {
+                for shift in shifts.iter_mut() {
+                    *shift = narrow(*shift);
+                }
+            } else if opens.is_some() || closes.is_some() {
+                vehicle.time_window = Some(narrow(vehicle.time_window.unwrap_or([0, i64::MAX])));
+            }
+
+            if let Some(start) = start {
+                vehicle.start = Some(start.location);
+            }
+            if let Some(end) = end {
+                vehicle.end = Some(end.location);
+            }
+        }
+    }
+    
+    /// Plan only the remaining work of vehicles that are already on their way
+    ///
+    /// A vehicle with a `current_position` starts its route there. The jobs it completed
+    /// are taken out of the request and out of its predefined steps; what it picked up at
+    /// them stays on board, so its capacity shrinks by those amounts.
+    pub fn resolve_positions(&mut self) {
+        let mut completed = HashSet::new();
+        for vehicle in &mut self.vehicles {
+            if let Some(position) = vehicle.current_position {
+                vehicle.start = Some(position);
+            }
+            if vehicle.completed_job_ids.is_empty() {
+                continue;
+            }
+
+            let done = &vehicle.completed_job_ids;
+            for job in self.jobs.iter().filter(|job| done.contains(&job.id)) {
+                let picked_up = job.pickup.iter().flatten();
+                for (capacity, amount) in vehicle.capacity.iter_mut().zip(picked_up) {
+                    *capacity = capacity.saturating_sub(*amount);
+                }
+            }
+            if let Some(steps) = &mut vehicle.steps {
+                steps.retain(|step| !matches!(step, RouteStep::Job { id, .. } if done.contains(id)));
+            }
+            completed.extend(vehicle.completed_job_ids.iter().copied());
+        }
+        self.jobs.retain(|job| !completed.contains(&job.id));
+    }
+}
+
+fn resolve_duplicates<T>(
+    items: &mut Vec<T>,
+    kind: &str,
+    policy: DuplicateIds,
+    id: fn(&mut T) -> &mut u64,
+    warnings: &mut Vec<String>,
+) {
+    let mut next_id = items.iter_mut().map(|item| *id(item)).max().unwrap_or(0);
+    let mut seen = HashSet::new();
+    items.retain_mut(|item| {
+        let item_id = id(item);
+        if seen.insert(*item_id) {
+            return true;
+        }
+        match policy {
+            DuplicateIds::Reject => true,
+            DuplicateIds::Drop => {
+                warnings.push(format!("Dropped duplicate {} ID {}", kind, item_id));
+                false
+            }
+            DuplicateIds::Rename => {
+                next_id += 1;
+                warnings.push(format!(
+                    "Renamed duplicate {} ID {} to {}",
+                    kind, item_id, next_id
+                ));
+                *item_id = next_id;
+                true
+            }
+        }
+    });
+}
+
+/// Request to optimize a previous solution again after jobs were added or cancelled
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReoptimizeRequest {
+    /// Request that produced the previous solution
+    pub request: RoutingRequest,
+    
+    /// Routes of the previous solution, with the parts to keep marked as locked
+    #[serde(default)]
+    pub routes: Vec<LockedRoute>,
+    
+    /// Jobs to add to the problem
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_jobs: Vec<Job>,
+    
+    /// Shipments to add to the problem
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_shipments: Vec<Shipment>,
+    
+    /// IDs of jobs to remove from the problem
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cancelled_jobs: Vec<u64>,
+    
+    /// IDs of shipments to remove from the problem
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cancelled_shipments: Vec<u64>,
+}
+
+impl ReoptimizeRequest {
+    /// The previous request with the new jobs and shipments added and the cancelled ones removed
+    ///
+    /// Predefined vehicle steps and the initial solution are dropped, since the previous
+    /// routes take their place.
+    pub fn updated_request(&self) -> RoutingRequest {
+        let mut request = self.request.clone();
+        request.jobs.retain(|job| !self.cancelled_jobs.contains(&job.id));
+        request.jobs.extend(self.new_jobs.iter().cloned());
+        request.shipments.retain(|shipment| !self.cancelled_shipments.contains(&shipment.id));
+        request.shipments.extend(self.new_shipments.iter().cloned());
+        for vehicle in &mut request.vehicles {
+            vehicle.steps = None;
+        }
+        request.initial_solution.clear();
+        request
+    }
+}
+
+/// New jobs and shipments to insert into the routes of a stored solution
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct InsertRequest {
+    /// Jobs to insert
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jobs: Vec<Job>,
+    
+    /// Shipments to insert
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shipments: Vec<Shipment>,
+}
+
+impl InsertRequest {
+    /// The stored request with the new jobs and shipments added, starting from `routes`
+    ///
+    /// Predefined vehicle steps are dropped, since the stored routes take their place.
+    pub fn updated_request(
+        &self,
+        request: &RoutingRequest,
+        routes: &[VehicleRoute],
+    ) -> RoutingRequest {
+        let mut request = request.clone();
+        request.jobs.extend(self.jobs.iter().cloned());
+        request.shipments.extend(self.shipments.iter().cloned());
+        for vehicle in &mut request.vehicles {
+            vehicle.steps = None;
+        }
+        request.initial_solution = routes
+            .iter()
+            .map(|route| InitialRoute {
+                vehicle_id: route.vehicle_id,
+                steps: route.steps.clone(),
+            })
+            .collect();
+        request
+    }
+}
+
+/// Planned route of a vehicle on its way, to estimate its remaining arrivals again
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EtaRequest {
+    /// Steps of the planned route as returned by `/api/optimize`, with their planned times
+    pub steps: Vec<RouteStep>,
+    
+    /// Where the vehicle is now, as [longitude, latitude]
+    pub current_position: [f64; 2],
+    
+    /// Time at the current position, in the unit of the planned times
+    #[serde(deserialize_with = "time::deserialize")]
+    pub current_time: i64,
+    
+    /// Number of job, pickup and delivery steps the vehicle has already served
+    #[serde(default)]
+    pub completed_stops: usize,
+    
+    /// Optional routing profile to use (car, bike, foot, etc.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_profile: Option<String>,
+}
+
+impl EtaRequest {
+    /// Steps still ahead of the vehicle, after the stops it has served
+    pub fn remaining_steps(&self) -> &[RouteStep] {
+        let mut served = 0;
+        let mut from = 0;
+        for (i, step) in self.steps.iter().enumerate() {
+            if served == self.completed_stops {
+                break;
+            }
+            if matches!(
+                step,
+                RouteStep::Job { .. } | RouteStep::Pickup { .. } | RouteStep::Delivery { .. }
+            ) {
+                served += 1;
+            }
+            from = i + 1;
+        }
+        let rest = &self.steps[from..];
+        match rest.first() {
+            Some(RouteStep::Start { .. }) => &rest[1..],
+            _ => rest,
+        }
+    }
+}
+
+/// Jobs and shipments to remove from the routes of a stored solution
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RemoveRequest {
+    /// IDs of the jobs to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jobs: Vec<u64>,
+    
+    /// IDs of the shipments to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shipments: Vec<u64>,
+    
+    /// Improve the routes the work is removed from among themselves, within the request's
+    /// `max_time`; otherwise they are only timed again
+    #[serde(default)]
+    pub reoptimize: bool,
+}
+
+impl RemoveRequest {
+    /// The stored request without the removed jobs and shipments, starting from `routes`
+    ///
+    /// Predefined vehicle steps are dropped, since the stored routes take their place.
+    pub fn updated_request(
+        &self,
+        request: &RoutingRequest,
+        routes: &[VehicleRoute],
+    ) -> RoutingRequest {
+        let mut request = InsertRequest::default().updated_request(request, routes);
+        request.jobs.retain(|job| !self.jobs.contains(&job.id));
+        request.shipments.retain(|shipment| !self.shipments.contains(&shipment.id));
+        request
+    }
+    
+    /// Vehicles of `routes` serving any of the removed jobs and shipments
+    pub fn affected_vehicles(&self, routes: &[VehicleRoute]) -> Vec<u64> {
+        routes
+            .iter()
+            .filter(|route| {
+                route.steps.iter().any(|step| match step {
+                    RouteStep::Job { id, .. } => self.jobs.contains(id),
+                    RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => {
+                        self.shipments.contains(id)
+                    }
+                    _ => false,
+                })
+            })
+            .map(|route| route.vehicle_id)
+            .collect()
+    }
+}
+
+/// Route of a previous solution to keep in part or in full
+///
+/// A locked step also locks every step before it, so the locked part of a route is
+/// always its start. Locking a pickup locks the route up to the matching delivery.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LockedRoute {
+    /// Reference to the vehicle
+    pub vehicle_id: u64,
+    
+    /// Steps of the route as returned in the previous solution
+    #[serde(default)]
+    pub steps: Vec<LockedStep>,
+    
+    /// Keep the whole route
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Route of an earlier solution, such as yesterday's plan, to start optimizing from
+///
+/// The routes of a previous response can be passed as they are; only their job, pickup and
+/// delivery steps are used.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InitialRoute {
+    /// Reference to the vehicle
+    pub vehicle_id: u64,
+    
+    /// Steps of the route in order
+    #[serde(default)]
+    pub steps: Vec<RouteStep>,
+}
+
+/// Step of a previous route, optionally locked in place
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LockedStep {
+    /// The step itself
+    #[serde(flatten)]
+    pub step: RouteStep,
+    
+    /// Keep this step and the steps before it
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Travel costs between locations provided by the client
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CostMatrix {
+    /// Locations as [longitude, latitude], in the order of the matrix rows and columns
+    pub locations: Vec<[f64; 2]>,
+    
+    /// Travel durations in seconds, `null` for pairs without a route
+    #[serde(deserialize_with = "deserialize_entries")]
+    pub durations: Vec<Vec<f64>>,
+    
+    /// Travel distances in meters, `null` for pairs without a route
+    #[serde(default, deserialize_with = "deserialize_optional_entries", skip_serializing_if = "Option::is_none")]
+    pub distances: Option<Vec<Vec<f64>>>,
+}
+
+/// Read matrix entries, treating pairs without a route as infinitely far apart
+fn deserialize_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<f64>>, D::Error> {
+    let rows: Vec<Vec<Option<f64>>> = Vec::deserialize(deserializer)?;
+    Ok(rows
+        .into_iter()
+        .map(|row| row.into_iter().map(|entry| entry.unwrap_or(f64::INFINITY)).collect())
+        .collect())
+}
+
+fn deserialize_optional_entries<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Vec<f64>>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Entries(#[serde(deserialize_with = "deserialize_entries")] Vec<Vec<f64>>);
+    
+    Ok(Option::<Entries>::deserialize(deserializer)?.map(|entries| entries.0))
+}
+
+/// Ordered stops of a single vehicle to time along the road network, without optimizing
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteRequest {
+    /// Vehicle driving the route
+    pub vehicle: Vehicle,
+    
+    /// Stops in visiting order
+    pub stops: Vec<Job>,
+    
+    /// Optional routing profile to use (car, bike, foot, etc.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_profile: Option<String>,
+    
+    /// Optional routing options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<RoutingOptions>,
+}
+
+impl RouteRequest {
+    /// Routing request with the stops as the predefined route of the vehicle
+    pub fn routing_request(&self) -> RoutingRequest {
+        let steps = self
+            .stops
+            .iter()
+            .map(|stop| RouteStep::Job {
+                id: stop.id,
+                location: None,
+                snapped_location: None,
+                snap_distance: None,
+                setup: None,
+                service: None,
+                arrival_time: None,
+                waiting_time: None,
+                departure_time: None,
+                polyline: None,
+                arrival_timestamp: None,
+                departure_timestamp: None,
+                violations: Vec::new(),
+                description: None,
+                metadata: None,
+            })
+            .collect();
+    
+        RoutingRequest {
+            vehicles: vec![Vehicle {
+                steps: Some(steps),
+                ..self.vehicle.clone()
+            }],
+            jobs: self.stops.clone(),
+            shipments: Vec::new(),
+            depots: Vec::new(),
+            routing_profile: self.routing_profile.clone(),
+            options: self.options.clone(),
+            matrix: None,
+            initial_solution: Vec::new(),
+            relations: Vec::new(),
+            callback_url: None,
+        }
+    }
+}
+
+/// Request for the travel matrix between locations
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MatrixRequest {
+    /// Locations as [longitude, latitude]
+    pub locations: Vec<[f64; 2]>,
+    
+    /// Optional routing profile to use (car, bike, foot, etc.)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+/// Options for the routing algorithm
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct RoutingOptions {
+    /// Maximum time to spend on optimization (in seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_time: Option<u32>,
+    
+    /// Number of threads to use for optimization, at most the server's `default_threads`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<u8>,
+    
+    /// Whether to explore all possible vehicle combinations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explore_all: Option<bool>,
+    
+    /// Whether to return detailed route geometry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<bool>,
+    
+    /// Whether to return the geometry of each leg in the step it leads to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leg_geometry: Option<bool>,
+    
+    /// Whether to return the legs of routes with turn-by-turn instructions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<bool>,
+    
+    /// Format of the returned route geometry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry_format: Option<GeometryFormat>,
+    
+    /// Also give the arrival and departure times of steps as RFC 3339 timestamps, reading
+    /// the times as Unix timestamps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<bool>,
+    
+    /// UTC offset of the returned timestamps, such as `+02:00`; UTC when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    
+    /// Road classes of the routing profile to keep off, such as `toll`, `motorway` or
+    /// `ferry`; only supported by OSRM
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    
+    /// Areas such as low emission zones or closed bridges that travel between stops may
+    /// not cross, as GeoJSON polygons
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub avoid_polygons: Vec<Zone>,
+    
+    /// Balance the workload between vehicles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<BalanceOptions>,
+    
+    /// Time at which vehicles leave their start, unless set per vehicle
+    #[serde(default, deserialize_with = "time::deserialize_optional", skip_serializing_if = "Option::is_none")]
+    pub departure_time: Option<i64>,
+    
+    /// Delay the departure of optimized routes to avoid waiting at their first time windows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimize_departure: Option<bool>,
+    
+    /// Return unassigned jobs and shipments as plain IDs, with the job reasons in
+    /// `unassigned_reasons`, as before reasons were structured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub legacy_unassigned: Option<bool>,
+    
+    /// Whether tasks may be served after their time windows close
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_window_policy: Option<TimeWindowPolicy>,
+    
+    /// Order in which job deliveries and pickups may follow each other on a trip
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_order: Option<LoadOrder>,
+    
+    /// Cost per second of serving a task late under the soft policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lateness_penalty: Option<f64>,
+    
+    /// Cost of each task served late under the soft policy, on top of `lateness_penalty`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub late_task_penalty: Option<f64>,
+    
+    /// Largest accepted lateness at a task in seconds under the soft policy, unlimited when
+    /// not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_lateness: Option<u32>,
+    
+    /// Cost of leaving a job or shipment unassigned, multiplied by `1 + priority`. Work is
+    /// only served when that costs less, so a low penalty leaves out expensive stops.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unassigned_penalty: Option<f64>,
+    
+    /// Whether the jobs of each group are also served one after another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consecutive_groups: Option<bool>,
+    
+    /// Whether priorities only weigh the unassigned penalty or also rank work strictly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_policy: Option<PriorityPolicy>,
+    
+    /// Largest accepted number of unassigned jobs and shipments; the optimization fails
+    /// when more are left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unassigned: Option<u32>,
+    
+    /// Largest accepted share of unassigned jobs and shipments, in percent of all of them;
+    /// the optimization fails when more are left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unassigned_percent: Option<f64>,
+    
+    /// Seed for the random decisions of the solver, making runs with the same request
+    /// reproducible
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    
+    /// How the solver approaches the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<SolveStrategy>,
+    
+    /// Heuristic building the first routes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub construction: Option<Construction>,
+    
+    /// Search strategy improving the constructed routes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<Algorithm>,
+    
+    /// Settings of large neighborhood search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lns: Option<LnsOptions>,
+    
+    /// Number of distinct runner-up solutions to return alongside the best one, up to 10
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alternatives: Option<u32>,
+    
+    /// What to do with vehicles, jobs and shipments whose ID is already taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_ids: Option<DuplicateIds>,
+    
+    /// Order of the coordinates of the request's locations and of the returned steps;
+    /// zones and GeoJSON keep [longitude, latitude]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinate_order: Option<CoordinateOrder>,
+}
+
+/// Workload balancing between the vehicles of a request
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BalanceOptions {
+    /// Workload measure to balance
+    #[serde(default)]
+    pub metric: BalanceMetric,
+    
+    /// Largest accepted difference between the busiest and the least busy vehicle
+    #[serde(default)]
+    pub max_deviation: f64,
+    
+    /// Leave work unassigned rather than exceed `max_deviation`
+    #[serde(default)]
+    pub hard: bool,
+    
+    /// Cost per unit of deviation above `max_deviation` when balancing is soft
+    #[serde(default = "default_balance_weight")]
+    pub weight: f64,
+}
+
+fn default_balance_weight() -> f64 {
+    1.0
+}
+
+/// Workload measures that can be balanced between vehicles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceMetric {
+    /// Working time from leaving the start to arriving at the end, in seconds
+    #[default]
+    Duration,
+    
+    /// Number of job, pickup and delivery stops
+    Tasks,
+}
+
+/// Search strategy improving the constructed routes, trading solution quality for speed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// Keep the routes as constructed, the fastest and roughest
+    Greedy,
+    
+    /// Apply improving moves until none is left
+    #[default]
+    LocalSearch,
+    
+    /// Simulated annealing, also accepting worse routes while the temperature is high to
+    /// escape local optima
+    #[serde(rename = "sa")]
+    SimulatedAnnealing,
+    
+    /// Tabu search, taking the best sampled move while recently moved work may not move
+    Tabu,
+    
+    /// Large neighborhood search, removing part of the work and inserting it again
+    Lns,
+}
+
+/// How the solver builds its first routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Construction {
+    /// Extend each route in turn with the nearest feasible job or shipment
+    #[default]
+    Nearest,
+    
+    /// Insert first the job or shipment whose best route beats its second best by most
+    Regret2,
+    
+    /// Insert first the job or shipment whose best route beats its second and third best by
+    /// most, looking further ahead on tightly constrained problems
+    Regret3,
+}
+
+/// How the priorities of jobs and shipments decide which work is left unassigned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityPolicy {
+    /// Priorities scale the unassigned penalty, so costly high priority work may still be
+    /// left out
+    #[default]
+    Weighted,
+    
+    /// No job or shipment is served while one of a higher priority that would fit in its
+    /// place is left unassigned
+    Strict,
+}
+
+/// Settings of large neighborhood search, the `lns` algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LnsOptions {
+    /// Share of the assigned jobs and shipments removed in each round, above 0 and at most 1
+    #[serde(default = "default_removal_fraction")]
+    pub removal_fraction: f64,
+    
+    /// Number of cheapest routes compared when inserting the removed work again. With 1
+    /// the cheapest insertion goes first; with more, the work losing most when it cannot
+    /// have its cheapest route.
+    #[serde(default = "default_regret")]
+    pub regret: u32,
+}
+
+fn default_removal_fraction() -> f64 {
+    0.2
+}
+
+fn default_regret() -> u32 {
+    2
+}
+
+impl Default for LnsOptions {
+    fn default() -> Self {
+        Self {
+            removal_fraction: default_removal_fraction(),
+            regret: default_regret(),
+        }
+    }
+}
+
+/// Approaches the solver can take to a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SolveStrategy {
+    /// Optimize all jobs, shipments and vehicles together
+    #[default]
+    Direct,
+    
+    /// Split the problem into clusters of nearby work, each with its own vehicles, and
+    /// optimize the clusters in parallel; much faster for thousands of jobs
+    Cluster,
+}
+
+/// How the time windows of jobs and shipments constrain the routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeWindowPolicy {
+    /// Leave tasks unassigned rather than serve them late
+    #[default]
+    Hard,
+    
+    /// Serve tasks late at a cost per second of lateness
+    Soft,
+}
+
+/// Order of the job deliveries and pickups on each trip of a vehicle
+///
+/// Deliveries are loaded at the start of the trip and pickups brought back to it.
+/// Shipments may be served anywhere on the trip under every policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadOrder {
+    /// Deliveries and pickups follow each other freely, within the vehicle capacity
+    #[default]
+    Mixed,
+    
+    /// All deliveries come before the first pickup
+    Backhaul,
+    
+    /// A trip either only delivers or only picks up
+    Separate,
+}
+
+/// Handling of vehicles, jobs and shipments that reuse an ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateIds {
+    /// Reject the request
+    #[default]
+    Reject,
+    
+    /// Keep the first of each ID and drop the others
+    Drop,
+    
+    /// Give the duplicates new IDs above the highest one in use
+    Rename,
+}
+
+/// Order of the coordinates in a location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateOrder {
+    /// [longitude, latitude], like OSRM and GeoJSON
+    #[default]
+    LonLat,
+    
+    /// [latitude, longitude]
+    LatLon,
+}
+
+impl CoordinateOrder {
+    /// Location in this order as [longitude, latitude], or the other way around
+    pub fn lonlat(self, location: [f64; 2]) -> [f64; 2] {
+        match self {
+            CoordinateOrder::LonLat => location,
+            CoordinateOrder::LatLon => [location[1], location[0]],
+        }
+    }
+}
+
+/// Formats in which route geometry can be returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GeometryFormat {
+    /// Encoded polyline per route
+    #[default]
+    Polyline,
+    
+    /// GeoJSON `FeatureCollection` per route with the route line and its stops
+    GeoJson,
+} 
\ No newline at end of file
@@ -0,0 +1,434 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use super::request::LoadOrder;
+use super::vehicle::{CostBreakdown, RouteStep, VehicleRoute, Violation};
+
+/// Represents a complete routing optimization response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoutingResponse {
+    /// Summary of the optimization result
+    pub summary: RoutingSummary,
+    
+    /// Routes for each vehicle
+    pub routes: Vec<VehicleRoute>,
+    
+    /// Unassigned jobs with the reason they were left out
+    #[serde(skip_serializing_if = "Unassigned::is_empty", default)]
+    pub unassigned: Unassigned,
+    
+    /// Unassigned shipments with the reason they were left out
+    #[serde(skip_serializing_if = "Unassigned::is_empty", default)]
+    pub unassigned_shipments: Unassigned,
+    
+    /// Reasons for unassigned jobs, only returned with `options.legacy_unassigned`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unassigned_reasons: Vec<UnassignedReason>,
+    
+    /// Detailed route geometries if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<Vec<String>>,
+    
+    /// Distinct runner-up solutions ranked by cost, as requested with `options.alternatives`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub alternatives: Vec<AlternativeSolution>,
+    
+    /// Page of the routes returned, when the routes were requested page by page
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub page: Option<RoutePage>,
+    
+    /// ID under which the solution was stored, if solution storage is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution_id: Option<Uuid>,
+    
+    /// Problems with the request that did not stop the optimization, such as unknown fields
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub warnings: Vec<String>,
+}
+
+/// Solution with different vehicle assignments than the best one
+///
+/// Its routes are timed by the travel matrix and carry no geometry.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlternativeSolution {
+    /// Total cost of the solution, priced like the summary of the best one
+    pub cost: f64,
+    
+    /// Total distance of all routes in meters
+    pub distance: u64,
+    
+    /// Total duration of all routes in seconds
+    pub duration: u64,
+    
+    /// Routes for each vehicle
+    pub routes: Vec<VehicleRoute>,
+    
+    /// IDs of the jobs left out
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unassigned: Vec<u64>,
+    
+    /// IDs of the shipments left out
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unassigned_shipments: Vec<u64>,
+}
+
+/// Position of a page of routes among all routes of a solution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RoutePage {
+    /// Page number, starting at 1
+    pub page: usize,
+    
+    /// Maximum number of routes per page
+    pub per_page: usize,
+    
+    /// Number of routes across all pages
+    pub total_routes: usize,
+    
+    /// Number of pages
+    pub total_pages: usize,
+}
+
+/// Timing of a single vehicle route along ordered stops
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteResponse {
+    /// The timed route with its steps, violations, geometry and total waiting time
+    #[serde(flatten)]
+    pub route: VehicleRoute,
+    
+    /// Estimated times at each stop, in visiting order
+    pub stops: Vec<StopEta>,
+}
+
+/// Estimated times at a stop of a route
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StopEta {
+    /// ID of the stop
+    pub id: u64,
+    
+    /// Time at which the vehicle arrives
+    pub arrival_time: i64,
+    
+    /// Time spent waiting for the time window to open, in seconds
+    pub waiting_time: i64,
+    
+    /// Time at which the vehicle leaves after service
+    pub departure_time: i64,
+}
+
+/// Refreshed arrival estimates for the rest of a route
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EtaResponse {
+    /// Remaining steps of the route, in order
+    pub stops: Vec<StepEta>,
+}
+
+/// Estimated times at a remaining step of a route, against the plan
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepEta {
+    /// ID of the job, shipment or break; not set for other steps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    
+    /// Type of the step, such as `job` or `end`
+    #[serde(rename = "type")]
+    pub kind: String,
+    
+    /// Time at which the vehicle now arrives
+    pub arrival_time: i64,
+    
+    /// Time at which the vehicle now leaves
+    pub departure_time: i64,
+    
+    /// Seconds the vehicle arrives later than planned, negative when it is early
+    pub delay: i64,
+}
+
+/// Routes of a stored solution with new jobs and shipments inserted
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InsertResponse {
+    /// Cost the inserted jobs and shipments add to the routes
+    pub insertion_cost: f64,
+    
+    /// The updated solution; only the new work moves, the routed steps keep their order
+    #[serde(flatten)]
+    pub solution: RoutingResponse,
+}
+
+/// Feasibility of manually planned routes, checked without running the optimizer
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeasibilityReport {
+    /// Whether every route keeps to its capacity, time windows and shift
+    pub feasible: bool,
+    
+    /// Report for each vehicle with steps
+    pub routes: Vec<RouteFeasibility>,
+    
+    /// IDs of the jobs on no route
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unplanned_jobs: Vec<u64>,
+    
+    /// IDs of the shipments on no route
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unplanned_shipments: Vec<u64>,
+}
+
+/// Feasibility of the steps of a single vehicle
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RouteFeasibility {
+    /// ID of the vehicle
+    pub vehicle_id: u64,
+    
+    /// Whether the route has no violations at any stop or as a whole
+    pub feasible: bool,
+    
+    /// Each job, pickup and delivery stop, in visiting order
+    pub stops: Vec<StopFeasibility>,
+    
+    /// Violations of the route as a whole: its shift, time window, route limits and load on
+    /// departure
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+}
+
+/// Load and timing at a stop of a checked route
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StopFeasibility {
+    /// ID of the job or shipment
+    pub id: u64,
+    
+    /// Type of the stop: `job`, `pickup` or `delivery`
+    #[serde(rename = "type")]
+    pub kind: String,
+    
+    /// Time at which the vehicle arrives
+    pub arrival_time: i64,
+    
+    /// Time at which the vehicle leaves after service
+    pub departure_time: i64,
+    
+    /// Load on board after the stop, per capacity dimension
+    pub load: Vec<i32>,
+    
+    /// Late arrival, load above the vehicle capacity and shipment order at this stop
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+}
+
+/// Unassigned jobs or shipments, with their reasons or as plain IDs for older clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum Unassigned {
+    /// Each job or shipment with the reason it was left out
+    Detailed(Vec<UnassignedJob>),
+    
+    /// Only the IDs, as returned with `options.legacy_unassigned`
+    Ids(Vec<u64>),
+}
+
+impl Default for Unassigned {
+    fn default() -> Self {
+        Unassigned::Detailed(Vec::new())
+    }
+}
+
+impl Unassigned {
+    /// IDs of the unassigned jobs or shipments
+    pub fn ids(&self) -> Vec<u64> {
+        match self {
+            Unassigned::Detailed(unassigned) => unassigned.iter().map(|job| job.id).collect(),
+            Unassigned::Ids(ids) => ids.clone(),
+        }
+    }
+    
+    /// Number of unassigned jobs or shipments
+    pub fn len(&self) -> usize {
+        match self {
+            Unassigned::Detailed(unassigned) => unassigned.len(),
+            Unassigned::Ids(ids) => ids.len(),
+        }
+    }
+    
+    /// Whether everything was assigned
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Job or shipment left out of the solution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UnassignedJob {
+    /// ID of the job or shipment
+    pub id: u64,
+    
+    /// Machine readable reason
+    pub code: UnassignedCode,
+    
+    /// Human readable explanation
+    pub description: String,
+}
+
+/// Reasons why a job or shipment was left out of the solution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UnassignedCode {
+    /// No vehicle has the required skills
+    Skills,
+    
+    /// No vehicle can carry its load types
+    Compatibility,
+    
+    /// The job excludes every vehicle
+    VehicleNotAllowed,
+    
+    /// It lies outside the zones of every vehicle
+    Zone,
+    
+    /// The amounts exceed the capacity of every vehicle that could serve it
+    Capacity,
+    
+    /// No vehicle can serve it within the time windows
+    TimeWindow,
+    
+    /// Serving it would exceed the route limits of the vehicles
+    RouteLimit,
+    
+    /// The location cannot be reached over the road network
+    Unreachable,
+    
+    /// The depots of the vehicles that could carry it have run out of stock
+    StockOut,
+    
+    /// Every vehicle that could serve it is busy with other work
+    NoVehicleAvailable,
+    
+    /// It is not part of any predefined route
+    NotInRoute,
+}
+
+/// Reason why a job could not be assigned, as returned with `options.legacy_unassigned`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnassignedReason {
+    /// ID of the unassigned job
+    pub id: u64,
+    
+    /// Human readable explanation
+    pub reason: String,
+}
+
+/// Summary of the optimization result
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoutingSummary {
+    /// Total cost of the solution
+    pub cost: f64,
+    
+    /// Total distance of all routes in meters
+    pub distance: u64,
+    
+    /// Total duration of all routes in seconds
+    pub duration: u64,
+    
+    /// Number of routes in the solution
+    pub routes: u32,
+    
+    /// Number of unassigned jobs and shipments
+    pub unassigned: u32,
+    
+    /// Total time spent waiting for time windows to open in seconds
+    #[serde(default)]
+    pub waiting_time: u64,
+    
+    /// Total time spent serving jobs and shipments in seconds
+    #[serde(default)]
+    pub service_time: u64,
+    
+    /// Computation time in milliseconds
+    pub computing_time: u64,
+    
+    /// Total cost split by what drives it, adding up to `cost`
+    #[serde(default)]
+    pub cost_breakdown: CostBreakdown,
+    
+    /// Depots whose stock ran out before every job they could supply was assigned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stock_outs: Vec<StockOut>,
+    
+    /// Order of deliveries and pickups the routes keep to
+    #[serde(default)]
+    pub load_order: LoadOrder,
+    
+    /// Lower bound on the cost of any solution to the request, from the cheapest legs into
+    /// each job and shipment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower_bound: Option<f64>,
+    
+    /// Estimated optimality gap: how far `cost` is above `lower_bound`, in percent of `cost`.
+    /// The bound is loose, so the true gap is smaller; a gap that shrinks with a longer
+    /// `max_time` shows the search still pays off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_percent: Option<f64>,
+    
+    /// Latency of the requests sent to the routing engine, when any were needed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_latency: Option<EngineLatency>,
+}
+
+/// Latency of the requests an optimization sent to the routing engine
+///
+/// A large matrix is requested in several parts, each counted as a request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EngineLatency {
+    /// Number of requests sent
+    pub requests: u32,
+    
+    /// Time spent waiting for all responses in milliseconds, including requests sent
+    /// at the same time
+    pub total_time: u64,
+    
+    /// Average time until a response in milliseconds
+    pub mean_time: u64,
+    
+    /// Time until the slowest response in milliseconds
+    pub max_time: u64,
+}
+
+/// Depot whose stock ran out before all the jobs it could supply were assigned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StockOut {
+    /// ID of the depot
+    pub depot_id: u64,
+    
+    /// Amounts delivered from the depot's stock per capacity dimension
+    pub delivered: Vec<u32>,
+    
+    /// IDs of the unassigned jobs the remaining stock could not supply
+    pub jobs: Vec<u64>,
+} 
+
+/// Best solution found so far while an optimization is running
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OptimizationProgress {
+    /// Objective value of the solver for this solution
+    pub cost: f64,
+    
+    /// Routes of the solution, timed with the travel matrix
+    pub routes: Vec<ProgressRoute>,
+    
+    /// Number of unassigned jobs and shipments
+    pub unassigned: u32,
+    
+    /// Time spent solving so far in milliseconds
+    pub computing_time: u64,
+}
+
+/// Route of a vehicle in an intermediate solution
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProgressRoute {
+    /// Reference to the vehicle
+    pub vehicle_id: u64,
+    
+    /// Ordered list of jobs in the route
+    pub route: Vec<u64>,
+    
+    /// Sequence of steps in the route including start, jobs, and end
+    pub steps: Vec<RouteStep>,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::time;
+
+/// Represents a shipment: an amount picked up at one location and delivered at another
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Shipment {
+    /// Unique identifier for the shipment
+    pub id: u64,
+    
+    /// Where and when the amount is picked up
+    pub pickup: ShipmentStep,
+    
+    /// Where and when the amount is delivered
+    pub delivery: ShipmentStep,
+    
+    /// Amount carried from pickup to delivery (can be multi-dimensional)
+    #[serde(default)]
+    pub amount: Vec<u32>,
+    
+    /// Skills required to perform this shipment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skills: Option<Vec<String>>,
+    
+    /// Load types of the goods, such as `frozen`, that the vehicle must be able to carry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_types: Option<Vec<String>>,
+    
+    /// Priority of the shipment (higher value means higher priority)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<u8>,
+    
+    /// Free text describing the shipment, echoed on its pickup and delivery steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Arbitrary JSON of the client, such as its own references, echoed on the shipment's
+    /// pickup and delivery steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Pickup or delivery leg of a shipment
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShipmentStep {
+    /// Location as [longitude, latitude]
+    pub location: [f64; 2],
+    
+    /// Service time in seconds
+    #[serde(default)]
+    pub service: u32,
+    
+    /// Time windows for this leg
+    #[serde(default, deserialize_with = "time::deserialize_optional_windows", skip_serializing_if = "Option::is_none")]
+    pub time_windows: Option<Vec<[i64; 2]>>,
+}
@@ -0,0 +1,100 @@
+//! Times in requests, given as seconds or as RFC 3339 timestamps
+//!
+//! Timestamps such as `2026-10-15T08:00:00+02:00` are converted to Unix timestamps, the
+//! planning epoch of requests that use them. Plain numbers are kept as they are, in whatever
+//! unit and epoch the client plans in.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+/// Time given as a number of seconds or an RFC 3339 timestamp
+struct Time(i64);
+
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimeVisitor;
+
+        impl Visitor<'_> for TimeVisitor {
+            type Value = Time;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("seconds or an RFC 3339 timestamp")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Time, E> {
+                Ok(Time(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Time, E> {
+                i64::try_from(value)
+                    .map(Time)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Time, E> {
+                parse(value).map(Time).ok_or_else(|| {
+                    E::custom(format!(
+                        "invalid timestamp \"{}\", expected RFC 3339 such as 2026-10-15T08:00:00+02:00",
+                        value
+                    ))
+                })
+            }
+        }
+
+        deserializer.deserialize_any(TimeVisitor)
+    }
+}
+
+/// Unix timestamp of an RFC 3339 timestamp
+pub fn parse(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+/// UTC offset such as `+02:00`, or `Z` for UTC
+pub fn parse_offset(value: &str) -> Option<FixedOffset> {
+    if value == "Z" || value == "UTC" {
+        return FixedOffset::east_opt(0);
+    }
+    value.parse().ok()
+}
+
+/// RFC 3339 timestamp of a Unix timestamp at the UTC offset `offset`
+pub fn format(timestamp: i64, offset: FixedOffset) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(timestamp, 0).map(|time| time.with_timezone(&offset).to_rfc3339())
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    Ok(Time::deserialize(deserializer)?.0)
+}
+
+pub(crate) fn deserialize_optional<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<i64>, D::Error> {
+    Ok(Option::<Time>::deserialize(deserializer)?.map(|time| time.0))
+}
+
+pub(crate) fn deserialize_window<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<[i64; 2]>, D::Error> {
+    Ok(Option::<[Time; 2]>::deserialize(deserializer)?.map(|[start, end]| [start.0, end.0]))
+}
+
+pub(crate) fn deserialize_windows<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<[i64; 2]>, D::Error> {
+    let windows: Vec<[Time; 2]> = Vec::deserialize(deserializer)?;
+    Ok(windows.into_iter().map(|[start, end]| [start.0, end.0]).collect())
+}
+
+pub(crate) fn deserialize_optional_windows<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<[i64; 2]>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Windows(#[serde(deserialize_with = "deserialize_windows")] Vec<[i64; 2]>);
+
+    Ok(Option::<Windows>::deserialize(deserializer)?.map(|windows| windows.0))
+}
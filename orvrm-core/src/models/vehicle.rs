@@ -0,0 +1,1017 @@
+use crate::utils::geometry;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use super::time;
+
+/// Represents a step in a vehicle's route
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type")]
+pub enum RouteStep {
+    #[serde(rename = "start")]
+    Start {
+        #[serde(default, deserialize_with = "time::deserialize_optional", skip_serializing_if = "Option::is_none")]
+        service_after: Option<i64>,
+        
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+    #[serde(rename = "job")]
+    Job {
+        /// Job ID
+        id: u64,
+        
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Location on the road network the routing engine moved `location` to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snapped_location: Option<[f64; 2]>,
+        
+        /// Distance in meters between `location` and `snapped_location`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snap_distance: Option<u32>,
+        
+        /// Setup time spent before service in seconds, `0` when the vehicle was already there
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        setup: Option<u32>,
+        
+        /// Service time in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Time spent waiting for the time window to open, in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        waiting_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Encoded polyline of the leg leading to this step, with `options.leg_geometry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        polyline: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+        
+        /// Description of the job, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        
+        /// Metadata of the job, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+    },
+    #[serde(rename = "pickup")]
+    Pickup {
+        /// Shipment ID
+        id: u64,
+        
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Location on the road network the routing engine moved `location` to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snapped_location: Option<[f64; 2]>,
+        
+        /// Distance in meters between `location` and `snapped_location`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snap_distance: Option<u32>,
+        
+        /// Service time in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Time spent waiting for the time window to open, in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        waiting_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Encoded polyline of the leg leading to this step, with `options.leg_geometry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        polyline: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+        
+        /// Description of the shipment, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        
+        /// Metadata of the shipment, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+    },
+    #[serde(rename = "delivery")]
+    Delivery {
+        /// Shipment ID
+        id: u64,
+        
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Location on the road network the routing engine moved `location` to
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snapped_location: Option<[f64; 2]>,
+        
+        /// Distance in meters between `location` and `snapped_location`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        snap_distance: Option<u32>,
+        
+        /// Service time in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Time spent waiting for the time window to open, in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        waiting_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Encoded polyline of the leg leading to this step, with `options.leg_geometry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        polyline: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+        
+        /// Description of the shipment, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        
+        /// Metadata of the shipment, echoed from the request
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<serde_json::Value>,
+    },
+    #[serde(rename = "break")]
+    Break {
+        /// Break ID
+        id: u64,
+        
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Duration of the break in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+    #[serde(rename = "reload")]
+    Reload {
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Time spent reloading in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Encoded polyline of the leg leading to this step, with `options.leg_geometry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        polyline: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+    #[serde(rename = "rest")]
+    Rest {
+        /// Location coordinates [longitude, latitude]; not set for a rest taken en route
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Duration of the rest in seconds
+        #[serde(skip_serializing_if = "Option::is_none")]
+        service: Option<u32>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+    #[serde(rename = "end")]
+    End {
+        /// Location coordinates [longitude, latitude]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location: Option<[f64; 2]>,
+        
+        /// Arrival time at this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        arrival_time: Option<i64>,
+        
+        /// Departure time from this step
+        #[serde(skip_serializing_if = "Option::is_none")]
+        departure_time: Option<i64>,
+        
+        /// Arrival time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        arrival_timestamp: Option<String>,
+        
+        /// Departure time as an RFC 3339 timestamp, with `options.timestamps`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        departure_timestamp: Option<String>,
+        
+        /// Encoded polyline of the leg leading to this step, with `options.leg_geometry`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        polyline: Option<String>,
+        
+        /// Constraints violated at this step
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        violations: Vec<Violation>,
+    },
+}
+
+/// Represents a constraint violated by a route or one of its steps
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Violation {
+    /// Kind of constraint that is violated
+    #[serde(rename = "type")]
+    pub kind: ViolationType,
+    
+    /// Size of the violation: seconds for delays and travel time, units for load,
+    /// meters for distance and stops for tasks
+    pub amount: i64,
+    
+    /// Capacity dimension of a load violation, counted from 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<usize>,
+}
+
+/// Kinds of constraint violations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationType {
+    /// Arrival after the end of the last usable time window
+    Delay,
+    
+    /// Load above the vehicle capacity
+    Load,
+    
+    /// Travel time above the vehicle's `max_travel_time`
+    MaxTravelTime,
+    
+    /// Distance above the vehicle's `max_distance`
+    MaxDistance,
+    
+    /// More stops than the vehicle's `max_tasks`
+    MaxTasks,
+    
+    /// Working day longer than the `max_shift` of the vehicle's working rules
+    MaxShift,
+
+    /// Delivery of a shipment before its pickup, or pickup of a shipment that is never
+    /// delivered
+    Precedence,
+}
+
+impl RouteStep {
+    /// Swap the coordinates of the step's locations, between [longitude, latitude] and
+    /// [latitude, longitude] order
+    pub fn swap_coordinates(&mut self) {
+        let (location, snapped_location) = match self {
+            RouteStep::Job { location, snapped_location, .. }
+            | RouteStep::Pickup { location, snapped_location, .. }
+            | RouteStep::Delivery { location, snapped_location, .. } => {
+                (location, Some(snapped_location))
+            }
+            RouteStep::Start { location, .. }
+            | RouteStep::Break { location, .. }
+            | RouteStep::Reload { location, .. }
+            | RouteStep::Rest { location, .. }
+            | RouteStep::End { location, .. } => (location, None),
+        };
+        for location in std::iter::once(location).chain(snapped_location) {
+            *location = location.map(|[first, second]| [second, first]);
+        }
+    }
+}
+
+impl ViolationType {
+    /// Name of the violation type as used in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViolationType::Delay => "delay",
+            ViolationType::Load => "load",
+            ViolationType::MaxTravelTime => "max_travel_time",
+            ViolationType::MaxDistance => "max_distance",
+            ViolationType::MaxTasks => "max_tasks",
+            ViolationType::MaxShift => "max_shift",
+            ViolationType::Precedence => "precedence",
+        }
+    }
+}
+
+/// Represents a vehicle in the routing problem
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Vehicle {
+    /// Unique identifier for the vehicle
+    pub id: u64,
+    
+    /// Starting location as [longitude, latitude]; without it the route starts at the first job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<[f64; 2]>,
+    
+    /// Ending location as [longitude, latitude]; without it the route ends at the last job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<[f64; 2]>,
+    
+    /// ID of the depot the vehicle starts from and reloads at, instead of a `start` location
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_depot: Option<u64>,
+    
+    /// ID of the depot the vehicle returns to, instead of an `end` location
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_depot: Option<u64>,
+    
+    /// Vehicle capacity (can be multi-dimensional)
+    #[serde(default)]
+    pub capacity: Vec<u32>,
+    
+    /// Time window for the vehicle's operation
+    #[serde(default, deserialize_with = "time::deserialize_window", skip_serializing_if = "Option::is_none")]
+    pub time_window: Option<[i64; 2]>,
+    
+    /// Disjoint periods the vehicle may work in instead of a single `time_window`, such as
+    /// the two halves of a split shift; each route fits within one of them
+    #[serde(default, deserialize_with = "time::deserialize_optional_windows", skip_serializing_if = "Option::is_none")]
+    pub shifts: Option<Vec<[i64; 2]>>,
+    
+    /// Predefined steps for the vehicle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub steps: Option<Vec<RouteStep>>,
+    
+    /// Skills that the vehicle possesses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skills: Option<Vec<String>>,
+    
+    /// Load types the vehicle can carry and whether it may carry several at once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compatibility: Option<Compatibility>,
+    
+    /// Breaks the driver must take during the route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breaks: Option<Vec<Break>>,
+    
+    /// Maximum number of trips; between trips the vehicle returns to its start to reload
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_trips: Option<u32>,
+    
+    /// Time needed to reload at the start location in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reload_service: Option<u32>,
+    
+    /// Cost of using the vehicle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub costs: Option<VehicleCosts>,
+    
+    /// Maximum travel time of the route in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_travel_time: Option<u32>,
+    
+    /// Maximum distance of the route in meters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_distance: Option<u32>,
+    
+    /// Maximum number of job, pickup and delivery stops on the route
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tasks: Option<u32>,
+    
+    /// Routing profile of the vehicle, overriding the request's `routing_profile`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    
+    /// Time at which the vehicle leaves its start, overriding the request's `departure_time`
+    #[serde(default, deserialize_with = "time::deserialize_optional", skip_serializing_if = "Option::is_none")]
+    pub departure_time: Option<i64>,
+    
+    /// Driving and working time rules the driver must keep to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub working_rules: Option<WorkingRules>,
+    
+    /// Territories the vehicle must stay in, it only serves locations inside one of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_zones: Option<Vec<Zone>>,
+    
+    /// Areas the vehicle must keep out of, it serves no location inside any of them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forbidden_zones: Option<Vec<Zone>>,
+    
+    /// Factor on the service time of every stop, such as `0.8` for an experienced driver
+    /// or `1.3` for a trainee
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_factor: Option<f64>,
+    
+    /// Where the vehicle is now when planning again during the day; the rest of its route
+    /// starts here instead of at its start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_position: Option<[f64; 2]>,
+    
+    /// IDs of the jobs the vehicle already served today, which are not planned again
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub completed_job_ids: Vec<u64>,
+    
+    /// Free text describing the vehicle, echoed on its route
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Arbitrary JSON of the client, such as its own references, echoed on the vehicle's route
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Load types a vehicle can carry, such as `frozen` and `ambient`
+///
+/// Unlike capacity dimensions these are not amounts: a job or shipment with load types can
+/// only be served by a vehicle that carries all of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Compatibility {
+    /// Load types the vehicle can carry
+    #[serde(default)]
+    pub load_types: Vec<String>,
+    
+    /// Whether different load types may share a trip; without mixing every trip carries
+    /// goods of a single load type
+    #[serde(default = "default_mix")]
+    pub mix: bool,
+}
+
+fn default_mix() -> bool {
+    true
+}
+
+/// Monetary cost model of a vehicle
+///
+/// Without explicit costs a route costs its travel time in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VehicleCosts {
+    /// Cost of using the vehicle at all
+    #[serde(default)]
+    pub fixed: f64,
+    
+    /// Cost per hour of travel
+    #[serde(default = "default_cost_per_hour")]
+    pub per_hour: f64,
+    
+    /// Cost per kilometer travelled
+    #[serde(default)]
+    pub per_km: f64,
+    
+    /// Cost per hour of waiting for time windows to open
+    #[serde(default)]
+    pub per_hour_waiting: f64,
+    
+    /// Cost per hour of serving jobs and shipments
+    #[serde(default)]
+    pub per_hour_service: f64,
+}
+
+fn default_cost_per_hour() -> f64 {
+    3600.0
+}
+
+impl Default for VehicleCosts {
+    fn default() -> Self {
+        Self {
+            fixed: 0.0,
+            per_hour: default_cost_per_hour(),
+            per_km: 0.0,
+            per_hour_waiting: 0.0,
+            per_hour_service: 0.0,
+        }
+    }
+}
+
+impl VehicleCosts {
+    /// Cost of a route travelling `duration` seconds over `distance` meters, waiting
+    /// `waiting` seconds and serving tasks for `service` seconds
+    pub fn route_cost(
+        &self,
+        duration: f64,
+        distance: f64,
+        waiting: f64,
+        service: f64,
+    ) -> CostBreakdown {
+        CostBreakdown {
+            fixed: self.fixed,
+            travel_time: self.per_hour * duration / 3600.0,
+            distance: self.per_km * distance / 1000.0,
+            waiting_time: self.per_hour_waiting * waiting / 3600.0,
+            service_time: self.per_hour_service * service / 3600.0,
+            ..Default::default()
+        }
+    }
+}
+
+/// Cost of a route or solution, split by what drives it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CostBreakdown {
+    /// Fixed costs of the vehicles used
+    pub fixed: f64,
+    
+    /// Cost of the time spent travelling
+    pub travel_time: f64,
+    
+    /// Cost of the distance travelled
+    pub distance: f64,
+    
+    /// Cost of waiting for time windows to open
+    pub waiting_time: f64,
+    
+    /// Cost of serving jobs and shipments
+    pub service_time: f64,
+    
+    /// Penalties for violated constraints, such as lateness under the soft time window policy
+    pub violations: f64,
+    
+    /// Cost of leaving jobs and shipments unassigned, only set in the summary
+    pub unassigned: f64,
+}
+
+impl CostBreakdown {
+    /// Sum of all costs
+    pub fn total(&self) -> f64 {
+        self.fixed
+            + self.travel_time
+            + self.distance
+            + self.waiting_time
+            + self.service_time
+            + self.violations
+            + self.unassigned
+    }
+}
+
+impl std::ops::AddAssign for CostBreakdown {
+    fn add_assign(&mut self, other: Self) {
+        self.fixed += other.fixed;
+        self.travel_time += other.travel_time;
+        self.distance += other.distance;
+        self.waiting_time += other.waiting_time;
+        self.service_time += other.service_time;
+        self.violations += other.violations;
+        self.unassigned += other.unassigned;
+    }
+}
+
+/// Represents a break the driver of a vehicle must take
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Break {
+    /// Unique identifier for the break
+    pub id: u64,
+    
+    /// Time windows in which the break can start
+    #[serde(deserialize_with = "time::deserialize_windows")]
+    pub time_windows: Vec<[i64; 2]>,
+    
+    /// Duration of the break in seconds
+    #[serde(default, alias = "duration")]
+    pub service: u32,
+}
+
+/// Driving and working time rules of a driver, such as the EU driving rules
+///
+/// Rests are planned automatically: before a leg that would exceed the continuous driving
+/// limit, and en route on legs longer than the limit itself.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WorkingRules {
+    /// Longest driving time in seconds before a rest is due, `16200` under EU rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_continuous_driving: Option<u32>,
+    
+    /// Length in seconds of the rest that resets continuous driving, `2700` under EU rules
+    #[serde(default = "default_rest_duration")]
+    pub rest_duration: u32,
+    
+    /// Longest working day in seconds, from leaving the start to arriving at the end
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_shift: Option<u32>,
+}
+
+fn default_rest_duration() -> u32 {
+    2700
+}
+
+impl WorkingRules {
+    /// Excess of a working day of `shift` seconds over `max_shift`, if any
+    pub fn shift_violation(&self, shift: i64) -> Option<Violation> {
+        let limit = self.max_shift? as i64;
+        (shift > limit).then(|| Violation {
+            kind: ViolationType::MaxShift,
+            amount: shift - limit,
+            dimension: None,
+        })
+    }
+}
+
+/// GeoJSON geometry delimiting a zone, with coordinates as [longitude, latitude]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type")]
+pub enum Zone {
+    /// Outer ring followed by optional holes
+    Polygon { coordinates: Vec<Vec<[f64; 2]>> },
+    
+    /// Several polygons, each an outer ring followed by optional holes
+    MultiPolygon { coordinates: Vec<Vec<Vec<[f64; 2]>>> },
+}
+
+impl Zone {
+    /// Rings of each polygon of the zone
+    fn polygons(&self) -> &[Vec<Vec<[f64; 2]>>] {
+        match self {
+            Zone::Polygon { coordinates } => std::slice::from_ref(coordinates),
+            Zone::MultiPolygon { coordinates } => coordinates,
+        }
+    }
+    
+    /// Check whether `location` lies inside the zone
+    pub fn contains(&self, location: [f64; 2]) -> bool {
+        self.polygons()
+            .iter()
+            .any(|rings| geometry::polygon_contains(rings, location))
+    }
+    
+    /// Check whether the straight line from `from` to `to` crosses the boundary of the zone
+    pub fn crosses(&self, from: [f64; 2], to: [f64; 2]) -> bool {
+        self.polygons()
+            .iter()
+            .any(|rings| geometry::polygon_crosses(rings, from, to))
+    }
+    
+    /// Check whether every ring of the zone has at least three corners and finite coordinates
+    pub fn is_valid(&self) -> bool {
+        !self.polygons().is_empty()
+            && self.polygons().iter().all(|rings| geometry::is_valid_polygon(rings))
+    }
+}
+
+impl Vehicle {
+    /// Operating period of shift `shift`, or the vehicle's time window without shifts
+    pub fn shift_window(&self, shift: Option<usize>) -> Option<[i64; 2]> {
+        match (&self.shifts, shift) {
+            (Some(shifts), Some(shift)) => shifts.get(shift).copied(),
+            _ => self.time_window,
+        }
+    }
+    
+    /// Periods the vehicle may operate in: its shifts, or its time window
+    pub fn operating_windows(&self) -> Option<Vec<[i64; 2]>> {
+        self.shifts
+            .clone()
+            .or(self.time_window.map(|time_window| vec![time_window]))
+    }
+    
+    /// Limits exceeded by a route travelling `duration` seconds over `distance` meters
+    /// with `tasks` stops
+    pub fn limit_violations(&self, duration: f64, distance: f64, tasks: usize) -> Vec<Violation> {
+        [
+            (ViolationType::MaxTravelTime, self.max_travel_time, duration),
+            (ViolationType::MaxDistance, self.max_distance, distance),
+            (ViolationType::MaxTasks, self.max_tasks, tasks as f64),
+        ]
+        .into_iter()
+        .filter_map(|(kind, limit, value)| {
+            let limit = limit? as f64;
+            (value > limit).then(|| Violation {
+                kind,
+                amount: (value - limit).ceil() as i64,
+                dimension: None,
+            })
+        })
+        .collect()
+    }
+    
+    /// Excess of `load` over the capacity of this vehicle, one violation per overloaded
+    /// dimension
+    pub fn load_violations(&self, load: &[i32]) -> Vec<Violation> {
+        load.iter()
+            .enumerate()
+            .filter_map(|(dim, value)| {
+                let overload =
+                    *value as i64 - self.capacity.get(dim).copied().unwrap_or(0) as i64;
+                (overload > 0).then_some(Violation {
+                    kind: ViolationType::Load,
+                    amount: overload,
+                    dimension: Some(dim),
+                })
+            })
+            .collect()
+    }
+    
+    /// Cost of a route of this vehicle travelling `duration` seconds over `distance` meters,
+    /// waiting `waiting` seconds and serving tasks for `service` seconds
+    pub fn route_cost(
+        &self,
+        duration: f64,
+        distance: f64,
+        waiting: f64,
+        service: f64,
+    ) -> CostBreakdown {
+        self.costs
+            .unwrap_or_default()
+            .route_cost(duration, distance, waiting, service)
+    }
+    
+    /// Time this vehicle's driver takes for a stop with `service` seconds of service
+    pub fn service_duration(&self, service: u32) -> u32 {
+        match self.service_factor {
+            Some(factor) => (service as f64 * factor).round() as u32,
+            None => service,
+        }
+    }
+    
+    /// Check whether `location` lies inside the zones the vehicle may serve
+    pub fn serves_location(&self, location: [f64; 2]) -> bool {
+        let allowed = self
+            .allowed_zones
+            .as_ref()
+            .is_none_or(|zones| zones.iter().any(|zone| zone.contains(location)));
+        let forbidden = self
+            .forbidden_zones
+            .iter()
+            .flatten()
+            .any(|zone| zone.contains(location));
+        allowed && !forbidden
+    }
+    
+    /// Check whether the vehicle possesses all of the `required` skills
+    pub fn has_skills(&self, required: Option<&[String]>) -> bool {
+        let Some(required) = required else {
+            return true;
+        };
+
+        let available = self.skills.as_deref().unwrap_or_default();
+        required.iter().all(|skill| available.contains(skill))
+    }
+    
+    /// Check whether the vehicle can carry goods of all the `required` load types
+    ///
+    /// A vehicle that cannot mix load types only carries goods of a single load type.
+    pub fn carries(&self, required: Option<&[String]>) -> bool {
+        let required = required.unwrap_or_default();
+        if required.is_empty() {
+            return true;
+        }
+
+        self.compatibility.as_ref().is_some_and(|compatibility| {
+            (compatibility.mix || required.len() == 1)
+                && required
+                    .iter()
+                    .all(|load_type| compatibility.load_types.contains(load_type))
+        })
+    }
+    
+    /// Whether the vehicle keeps goods of different load types on separate trips
+    pub fn separates_load_types(&self) -> bool {
+        self.compatibility
+            .as_ref()
+            .is_some_and(|compatibility| !compatibility.mix)
+    }
+}
+
+/// Represents a vehicle with its assigned route in the solution
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VehicleRoute {
+    /// Reference to the vehicle
+    pub vehicle_id: u64,
+    
+    /// Ordered list of jobs in the route
+    pub route: Vec<u64>,
+    
+    /// Sequence of steps in the route including start, jobs, and end
+    pub steps: Vec<RouteStep>,
+    
+    /// Total distance of the route in meters
+    pub distance: u32,
+    
+    /// Total duration of the route in seconds
+    pub duration: u32,
+    
+    /// Estimated arrival times at each stop
+    pub arrival_times: Vec<i64>,
+    
+    /// Estimated departure times from each stop
+    pub departure_times: Vec<i64>,
+    
+    /// Load of the vehicle after each stop
+    pub load_profile: Vec<Vec<i32>>,
+    
+    /// Polyline representation of the route geometry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polyline: Option<String>,
+    
+    /// GeoJSON `FeatureCollection` of the route line and its stops
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geojson: Option<serde_json::Value>,
+    
+    /// Constraints violated by the route as a whole
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+    
+    /// Departure time the route was delayed to with `options.optimize_departure`, to avoid
+    /// waiting at its first time window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimized_departure: Option<i64>,
+    
+    /// Index of the vehicle shift the route runs in, for vehicles with `shifts`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shift: Option<usize>,
+    
+    /// Total time spent waiting for time windows to open, in seconds
+    #[serde(default)]
+    pub waiting_time: i64,
+    
+    /// Total time spent serving jobs and shipments, in seconds
+    #[serde(default)]
+    pub service_time: i64,
+    
+    /// Cost of the route, split by what drives it
+    #[serde(default)]
+    pub cost_breakdown: CostBreakdown,
+    
+    /// Legs between consecutive locations with their turn-by-turn instructions, with
+    /// `options.instructions`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub legs: Vec<RouteLeg>,
+    
+    /// Description of the vehicle, echoed from the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    
+    /// Metadata of the vehicle, echoed from the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Leg of a route between two consecutive locations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RouteLeg {
+    /// Distance in meters
+    pub distance: u32,
+    
+    /// Duration in seconds
+    pub duration: u32,
+    
+    /// Turn-by-turn instructions from the start of the leg to its end
+    pub instructions: Vec<Instruction>,
+}
+
+/// Turn-by-turn instruction for a driver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Instruction {
+    /// Maneuver in OSRM terms, such as `depart`, `turn`, `roundabout` or `arrive`
+    pub maneuver: String,
+    
+    /// Direction of the maneuver, such as `left`, `slight right` or `straight`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modifier: Option<String>,
+    
+    /// Name of the street followed after the maneuver, empty when it has none
+    #[serde(default)]
+    pub name: String,
+    
+    /// Distance in meters until the next instruction
+    pub distance: u32,
+    
+    /// Duration in seconds until the next instruction
+    pub duration: u32,
+}
+
+impl VehicleRoute {
+    /// Load of the vehicle on leaving each step, from the load profile
+    pub fn step_loads(&self) -> Vec<&[i32]> {
+        let mut tasks = 0;
+        let mut loads = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let task = matches!(
+                step,
+                RouteStep::Job { .. } | RouteStep::Pickup { .. } | RouteStep::Delivery { .. }
+            );
+            if task {
+                tasks += 1;
+            }
+            
+            // The profile starts with the load on leaving the start, then one entry per task
+            let load = self.load_profile.get(tasks).or(self.load_profile.last());
+            loads.push(load.map_or(&[][..], Vec::as_slice));
+        }
+        loads
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,149 @@
+use super::solver::Matrix;
+use crate::models::{EngineLatency, Instruction};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+tokio::task_local! {
+    /// Latencies of the engine requests sent for the optimization running on the task
+    static REQUEST_LATENCIES: RefCell<Vec<Duration>>;
+}
+
+/// Routing engine computing routes and travel matrices over a road network
+#[async_trait]
+pub trait RoutingEngine: Debug + Send + Sync {
+    /// Route visiting `coordinates` in order, if the engine finds one
+    ///
+    /// The route keeps off the road classes in `exclude`. The geometry, when requested, is a
+    /// polyline with a precision of 5 decimals. Legs carry turn-by-turn instructions when
+    /// `instructions` is set.
+    async fn route(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+        geometry: bool,
+        instructions: bool,
+    ) -> Result<Option<EngineRoute>>;
+
+    /// Duration and distance matrix between all `coordinates`, keeping off the road
+    /// classes in `exclude`
+    ///
+    /// Pairs the engine finds no route between have an infinite duration and distance.
+    async fn matrix(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+    ) -> Result<Matrix>;
+
+    /// Probe the servers behind the engine
+    async fn health(&self) -> Vec<BackendHealth>;
+}
+
+/// Fail for an engine named `engine` that cannot keep off road classes, if any are excluded
+pub(crate) fn ensure_no_exclude(engine: &str, exclude: &[String]) -> Result<()> {
+    if !exclude.is_empty() {
+        bail!("{} cannot exclude road classes, only OSRM can", engine);
+    }
+    Ok(())
+}
+
+/// Run `future`, recording the latency of every engine request it sends for
+/// [`request_latency`]
+pub(crate) async fn record_latency<F: Future>(future: F) -> F::Output {
+    REQUEST_LATENCIES.scope(RefCell::new(Vec::new()), future).await
+}
+
+/// Send an engine request, recording its latency when [`record_latency`] runs it
+pub(crate) async fn timed<F: Future>(request: F) -> F::Output {
+    let start = Instant::now();
+    let output = request.await;
+    // Requests outside an optimization, such as health probes, are not recorded
+    let _ = REQUEST_LATENCIES.try_with(|latencies| latencies.borrow_mut().push(start.elapsed()));
+    output
+}
+
+/// Latency of the engine requests recorded so far, if any were sent
+pub(crate) fn request_latency() -> Option<EngineLatency> {
+    let latencies = REQUEST_LATENCIES
+        .try_with(|latencies| latencies.borrow().clone())
+        .unwrap_or_default();
+    if latencies.is_empty() {
+        return None;
+    }
+
+    let total: Duration = latencies.iter().sum();
+    Some(EngineLatency {
+        requests: latencies.len() as u32,
+        total_time: total.as_millis() as u64,
+        mean_time: (total / latencies.len() as u32).as_millis() as u64,
+        max_time: latencies.iter().max().copied().unwrap_or_default().as_millis() as u64,
+    })
+}
+
+/// Routing engine selected in the configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    /// OSRM, configured under `osrm`
+    #[default]
+    Osrm,
+
+    /// Valhalla, configured under `valhalla`
+    Valhalla,
+
+    /// GraphHopper, configured under `graphhopper`
+    GraphHopper,
+}
+
+/// Route through a sequence of coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct EngineRoute {
+    /// Total distance in meters
+    pub distance: f64,
+
+    /// Total duration in seconds
+    pub duration: f64,
+
+    /// Encoded polyline of the route, if requested
+    pub geometry: Option<String>,
+
+    /// Legs between consecutive coordinates
+    pub legs: Vec<EngineLeg>,
+}
+
+/// Leg of a route between two consecutive coordinates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EngineLeg {
+    /// Distance in meters
+    pub distance: f64,
+
+    /// Duration in seconds
+    pub duration: f64,
+
+    /// Turn-by-turn instructions, if requested
+    pub instructions: Vec<Instruction>,
+}
+
+/// Health of a configured routing server
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BackendHealth {
+    /// Profile served by the backend, or `default` for the fallback server
+    pub name: String,
+
+    /// Base URL of the backend
+    pub base_url: String,
+
+    /// Whether the backend answered a probe request
+    pub healthy: bool,
+
+    /// Error returned by the probe, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
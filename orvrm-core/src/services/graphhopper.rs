@@ -0,0 +1,288 @@
+use super::engine::{
+    ensure_no_exclude, timed, BackendHealth, EngineLeg, EngineRoute, RoutingEngine,
+};
+use super::solver::Matrix;
+use crate::models::Instruction;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use log::{debug, error};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Configuration for the GraphHopper routing engine
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GraphHopperConfig {
+    /// Base URL for the GraphHopper API, the cloud API by default
+    pub base_url: String,
+
+    /// API key, required by the cloud API
+    pub api_key: Option<String>,
+
+    /// Timeout for GraphHopper requests in seconds
+    pub timeout_seconds: u64,
+
+    /// GraphHopper profile name by routing profile; unlisted profiles are used as is
+    pub profiles: HashMap<String, String>,
+}
+
+impl Default for GraphHopperConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://graphhopper.com/api/1".to_string(),
+            api_key: None,
+            timeout_seconds: 30,
+            profiles: HashMap::new(),
+        }
+    }
+}
+
+/// Routing engine backed by the GraphHopper Routing and Matrix APIs
+#[derive(Debug, Clone)]
+pub struct GraphHopperService {
+    client: Client,
+    config: GraphHopperConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteResponse {
+    paths: Vec<Path>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Path {
+    distance: f64,
+    /// Travel time in milliseconds
+    time: f64,
+    points: Option<String>,
+    #[serde(default)]
+    instructions: Vec<PathInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PathInstruction {
+    distance: f64,
+    /// Travel time in milliseconds
+    time: f64,
+    sign: i32,
+    #[serde(default)]
+    street_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixResponse {
+    times: Vec<Vec<Option<f64>>>,
+    distances: Vec<Vec<Option<f64>>>,
+}
+
+/// Instruction signs marking the arrival at a via point or the destination
+const SIGN_VIA_REACHED: i32 = 5;
+const SIGN_FINISH: i32 = 4;
+
+impl GraphHopperService {
+    /// Create a new GraphHopper service with the given configuration
+    pub fn new(config: GraphHopperConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self { client, config }
+    }
+
+    /// GraphHopper profile name for a routing profile
+    pub fn profile<'a>(&'a self, profile: &'a str) -> &'a str {
+        self.config
+            .profiles
+            .get(profile)
+            .map(String::as_str)
+            .unwrap_or(profile)
+    }
+
+    /// Add the API key, if configured, to a request
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.config.api_key {
+            Some(key) => request.query(&[("key", key)]),
+            None => request,
+        }
+    }
+
+    /// Send a request to a GraphHopper endpoint and parse its response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        body: serde_json::Value,
+    ) -> Result<T> {
+        let url = format!("{}/{}", self.config.base_url, endpoint);
+        debug!("GraphHopper {} request: {}", endpoint, url);
+
+        let response = timed(self.authorize(self.client.post(&url)).json(&body).send())
+            .await
+            .with_context(|| format!("Failed to send GraphHopper {} request", endpoint))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!(
+                "GraphHopper {} request failed with status {}: {}",
+                endpoint, status, error_text
+            );
+            bail!(
+                "GraphHopper {} request failed with status {}",
+                endpoint,
+                status
+            );
+        }
+
+        response
+            .json::<T>()
+            .await
+            .with_context(|| format!("Failed to parse GraphHopper {} response", endpoint))
+    }
+}
+
+/// Instruction of a GraphHopper path instruction, named as OSRM names its maneuvers
+fn instruction(instruction: &PathInstruction) -> Instruction {
+    let (kind, modifier) = match instruction.sign {
+        -98 | -8 | 8 => ("turn", Some("uturn")),
+        -7 => ("fork", Some("slight left")),
+        -3 => ("turn", Some("sharp left")),
+        -2 => ("turn", Some("left")),
+        -1 => ("turn", Some("slight left")),
+        1 => ("turn", Some("slight right")),
+        2 => ("turn", Some("right")),
+        3 => ("turn", Some("sharp right")),
+        SIGN_FINISH | SIGN_VIA_REACHED => ("arrive", None),
+        6 => ("roundabout", None),
+        -6 => ("exit roundabout", None),
+        7 => ("fork", Some("slight right")),
+        _ => ("continue", Some("straight")),
+    };
+    Instruction {
+        maneuver: kind.to_string(),
+        modifier: modifier.map(str::to_string),
+        name: instruction.street_name.clone(),
+        distance: instruction.distance.round() as u32,
+        duration: (instruction.time / 1000.0).round() as u32,
+    }
+}
+
+#[async_trait]
+impl RoutingEngine for GraphHopperService {
+    async fn route(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+        geometry: bool,
+        instructions: bool,
+    ) -> Result<Option<EngineRoute>> {
+        ensure_no_exclude("GraphHopper", exclude)?;
+        let body = serde_json::json!({
+            "points": coordinates,
+            "profile": self.profile(profile),
+            "points_encoded": true,
+            "calc_points": geometry,
+            "instructions": true,
+        });
+        let response: RouteResponse = self.request("route", body).await?;
+        let Some(path) = response.paths.into_iter().next() else {
+            return Ok(None);
+        };
+
+        // Instructions up to each via point or the destination make up a leg
+        let mut legs = Vec::new();
+        let mut leg = EngineLeg::default();
+        for path_instruction in &path.instructions {
+            leg.distance += path_instruction.distance;
+            leg.duration += path_instruction.time / 1000.0;
+            if instructions {
+                leg.instructions.push(instruction(path_instruction));
+            }
+            if matches!(path_instruction.sign, SIGN_VIA_REACHED | SIGN_FINISH) {
+                legs.push(std::mem::take(&mut leg));
+            }
+        }
+        if legs.len() + 1 != coordinates.len() {
+            bail!(
+                "GraphHopper route has {} legs for {} points",
+                legs.len(),
+                coordinates.len()
+            );
+        }
+
+        Ok(Some(EngineRoute {
+            distance: path.distance,
+            duration: path.time / 1000.0,
+            geometry: path.points.filter(|_| geometry),
+            legs,
+        }))
+    }
+
+    async fn matrix(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+    ) -> Result<Matrix> {
+        ensure_no_exclude("GraphHopper", exclude)?;
+        let body = serde_json::json!({
+            "points": coordinates,
+            "profile": self.profile(profile),
+            "out_arrays": ["times", "distances"],
+            "fail_fast": false,
+        });
+        let response: MatrixResponse = self.request("matrix", body).await?;
+
+        let size = coordinates.len();
+        let square = |rows: &[Vec<Option<f64>>]| {
+            rows.len() == size && rows.iter().all(|row| row.len() == size)
+        };
+        if !square(&response.times) || !square(&response.distances) {
+            bail!(
+                "GraphHopper matrix has the wrong size, expected {}x{}",
+                size,
+                size
+            );
+        }
+
+        // Pairs without a route have no time or distance
+        let impassable = |rows: Vec<Vec<Option<f64>>>| -> Vec<Vec<f64>> {
+            rows.into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|value| value.unwrap_or(f64::INFINITY))
+                        .collect()
+                })
+                .collect()
+        };
+
+        Ok(Matrix {
+            durations: impassable(response.times),
+            distances: Some(impassable(response.distances)),
+            snapped: None,
+        })
+    }
+
+    async fn health(&self) -> Vec<BackendHealth> {
+        let url = format!("{}/info", self.config.base_url);
+        let result = match self.authorize(self.client.get(&url)).send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(format!(
+                "GraphHopper info request failed with status {}",
+                response.status()
+            )),
+            Err(err) => Err(err.to_string()),
+        };
+
+        vec![BackendHealth {
+            name: "default".to_string(),
+            base_url: self.config.base_url.clone(),
+            healthy: result.is_ok(),
+            error: result.err(),
+        }]
+    }
+}
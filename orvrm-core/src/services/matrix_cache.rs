@@ -0,0 +1,67 @@
+use super::solver::{LocationIndex, Matrix};
+use std::collections::VecDeque;
+
+/// Travel matrices fetched for recent optimizations, by routing profile
+///
+/// A matrix covering every location of a later request is cut down to those locations
+/// instead of fetching it from the routing engine again. The least recently used matrix
+/// is dropped once `capacity` are kept.
+#[derive(Debug)]
+pub struct MatrixCache {
+    capacity: usize,
+    entries: VecDeque<CachedMatrix>,
+}
+
+#[derive(Debug)]
+struct CachedMatrix {
+    profile: String,
+    locations: LocationIndex,
+    matrix: Matrix,
+}
+
+impl MatrixCache {
+    /// Create an empty cache keeping up to `capacity` matrices
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Matrix between `locations` by `profile`, if a cached matrix covers all of them
+    pub fn get(&mut self, locations: &[[f64; 2]], profile: &str) -> Option<Matrix> {
+        let (position, indices) = self.entries.iter().enumerate().find_map(|(i, entry)| {
+            if entry.profile != profile {
+                return None;
+            }
+            let indices: Option<Vec<usize>> = locations
+                .iter()
+                .map(|location| entry.locations.get(*location))
+                .collect();
+            indices.map(|indices| (i, indices))
+        })?;
+
+        let entry = self.entries.remove(position)?;
+        let matrix = entry.matrix.submatrix(&indices);
+        self.entries.push_front(entry);
+        Some(matrix)
+    }
+
+    /// Keep the matrix between `locations` by `profile`
+    pub fn insert(&mut self, locations: &[[f64; 2]], profile: &str, matrix: Matrix) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut index = LocationIndex::default();
+        for location in locations {
+            index.insert(*location);
+        }
+        self.entries.push_front(CachedMatrix {
+            profile: profile.to_string(),
+            locations: index,
+            matrix,
+        });
+        self.entries.truncate(self.capacity);
+    }
+}
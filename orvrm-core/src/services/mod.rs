@@ -0,0 +1,14 @@
+pub mod engine;
+pub mod graphhopper;
+pub mod matrix_cache;
+pub mod osrm;
+pub mod routing;
+pub mod solver;
+pub mod solvers;
+pub mod valhalla;
+
+pub use engine::{EngineKind, RoutingEngine};
+pub use graphhopper::GraphHopperConfig;
+pub use osrm::OsrmConfig;
+pub use routing::{RoutingService, RoutingConfig, SolverSettings};
+pub use valhalla::ValhallaConfig;
@@ -0,0 +1,557 @@
+use super::engine::{timed, BackendHealth, EngineLeg, EngineRoute, RoutingEngine};
+use super::solver::{Matrix, Snap};
+use crate::models::Instruction;
+use anyhow::{Result, Context};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
+use log::{debug, error};
+
+/// Configuration for the OSRM service
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsrmConfig {
+    /// Base URL for the OSRM service
+    pub base_url: String,
+    
+    /// Default routing profile (car, bike, foot, etc.)
+    pub default_profile: String,
+    
+    /// Timeout for OSRM requests in seconds
+    pub timeout_seconds: u64,
+    
+    /// Maximum number of coordinates in a single table request
+    pub max_table_size: usize,
+    
+    /// Dedicated OSRM servers by routing profile, used instead of `base_url` for that profile
+    #[serde(default)]
+    pub backends: HashMap<String, OsrmBackendConfig>,
+    
+    /// Connections kept open to the OSRM servers for reuse
+    #[serde(default)]
+    pub pool: OsrmPoolConfig,
+}
+
+/// Connection pool of the HTTP clients talking to the OSRM servers
+///
+/// Every clone of a routing service shares the clients, so connections opened for one
+/// optimization are reused by the next.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OsrmPoolConfig {
+    /// Maximum number of idle connections kept open per server
+    pub max_idle_per_host: usize,
+    
+    /// Time in seconds an idle connection is kept open
+    pub idle_timeout_seconds: u64,
+    
+    /// Interval of keep-alive probes on open connections in seconds, 0 to send none
+    pub keep_alive_seconds: u64,
+    
+    /// Speak HTTP/2 without negotiating it first, for servers behind an HTTP/2 proxy;
+    /// `osrm-routed` itself only speaks HTTP/1.1
+    pub http2: bool,
+}
+
+impl Default for OsrmPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 64,
+            idle_timeout_seconds: 90,
+            keep_alive_seconds: 60,
+            http2: false,
+        }
+    }
+}
+
+/// Configuration of an OSRM server serving a single routing profile
+#[derive(Debug, Clone, Deserialize)]
+pub struct OsrmBackendConfig {
+    /// Base URL for the OSRM server
+    pub base_url: String,
+    
+    /// Timeout for requests to this server in seconds, defaults to the global timeout
+    pub timeout_seconds: Option<u64>,
+}
+
+impl Default for OsrmConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:5000".to_string(),
+            default_profile: "car".to_string(),
+            timeout_seconds: 30,
+            max_table_size: 100,
+            backends: HashMap::new(),
+            pool: OsrmPoolConfig::default(),
+        }
+    }
+}
+
+/// Service for interacting with the OSRM API
+#[derive(Debug, Clone)]
+pub struct OsrmService {
+    default_backend: Backend,
+    backends: HashMap<String, Backend>,
+    config: OsrmConfig,
+}
+
+/// HTTP client for a single OSRM server
+#[derive(Debug, Clone)]
+struct Backend {
+    client: Client,
+    base_url: String,
+}
+
+impl Backend {
+    fn new(base_url: &str, timeout_seconds: u64, pool: &OsrmPoolConfig) -> Self {
+        let keep_alive = Some(Duration::from_secs(pool.keep_alive_seconds))
+            .filter(|interval| !interval.is_zero());
+        let mut builder = Client::builder()
+            .timeout(Duration::from_secs(timeout_seconds))
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(pool.idle_timeout_seconds))
+            .tcp_keepalive(keep_alive)
+            .tcp_nodelay(true);
+        if pool.http2 {
+            builder = builder
+                .http2_prior_knowledge()
+                .http2_adaptive_window(true)
+                .http2_keep_alive_interval(keep_alive);
+        }
+        let client = builder.build().expect("Failed to build HTTP client");
+            
+        Self {
+            client,
+            base_url: base_url.to_string(),
+        }
+    }
+}
+
+/// OSRM route response
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OsrmRouteResponse {
+    pub code: String,
+    pub routes: Vec<OsrmRoute>,
+    pub waypoints: Vec<OsrmWaypoint>,
+}
+
+/// OSRM route
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OsrmRoute {
+    pub distance: f64,
+    pub duration: f64,
+    pub geometry: Option<String>,
+    pub legs: Vec<OsrmRouteLeg>,
+}
+
+/// OSRM route leg
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OsrmRouteLeg {
+    pub distance: f64,
+    pub duration: f64,
+    pub steps: Vec<OsrmRouteStep>,
+}
+
+/// OSRM route step
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OsrmRouteStep {
+    pub distance: f64,
+    pub duration: f64,
+    pub geometry: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub maneuver: OsrmManeuver,
+}
+
+/// OSRM step maneuver
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct OsrmManeuver {
+    #[serde(rename = "type")]
+    pub maneuver_type: String,
+    #[serde(default)]
+    pub modifier: Option<String>,
+}
+
+/// OSRM waypoint
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OsrmWaypoint {
+    #[serde(default)]
+    pub hint: String,
+    pub distance: f64,
+    #[serde(default)]
+    pub name: String,
+    pub location: [f64; 2],
+}
+
+/// Number of table sub-requests sent to OSRM concurrently
+const MAX_CONCURRENT_TABLE_REQUESTS: usize = 4;
+
+/// OSRM table response
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OsrmTableResponse {
+    pub code: String,
+    pub durations: Vec<Vec<Option<f64>>>,
+    pub distances: Option<Vec<Vec<Option<f64>>>>,
+    #[serde(default)]
+    pub sources: Vec<OsrmWaypoint>,
+}
+
+impl OsrmService {
+    /// Create a new OSRM service with the given configuration
+    pub fn new(config: OsrmConfig) -> Self {
+        let default_backend = Backend::new(&config.base_url, config.timeout_seconds, &config.pool);
+        let backends = config
+            .backends
+            .iter()
+            .map(|(profile, backend)| {
+                let timeout = backend.timeout_seconds.unwrap_or(config.timeout_seconds);
+                (profile.clone(), Backend::new(&backend.base_url, timeout, &config.pool))
+            })
+            .collect();
+            
+        Self { default_backend, backends, config }
+    }
+    
+    /// Backend serving a routing profile
+    fn backend(&self, profile: &str) -> &Backend {
+        self.backends.get(profile).unwrap_or(&self.default_backend)
+    }
+    
+    /// Probe every configured backend with a single-coordinate table request
+    pub async fn health(&self) -> Vec<BackendHealth> {
+        let mut backends: Vec<(&str, &str, &Backend)> = vec![(
+            "default",
+            self.config.default_profile.as_str(),
+            &self.default_backend,
+        )];
+        let mut named: Vec<_> = self.backends.iter().collect();
+        named.sort_by_key(|(profile, _)| profile.as_str());
+        backends.extend(named.into_iter().map(|(profile, backend)| (profile.as_str(), profile.as_str(), backend)));
+        
+        let probes = backends.into_iter().map(|(name, profile, backend)| async move {
+            let result = self
+                .table_request(backend, &[[0.0, 0.0]], None, profile, &[], false)
+                .await;
+            
+            BackendHealth {
+                name: name.to_string(),
+                base_url: backend.base_url.clone(),
+                healthy: result.is_ok(),
+                error: result.err().map(|err| err.to_string()),
+            }
+        });
+        
+        futures::future::join_all(probes).await
+    }
+    
+    /// Get the route between multiple coordinates, keeping off the road classes in `exclude`
+    pub async fn route(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: Option<&str>,
+        exclude: &[String],
+        geometry: bool,
+    ) -> Result<OsrmRouteResponse> {
+        let profile = profile.unwrap_or(&self.config.default_profile);
+        let backend = self.backend(profile);
+        
+        // Build coordinates string
+        let coords_str = coordinates
+            .iter()
+            .map(|coord| format!("{},{}", coord[0], coord[1]))
+            .collect::<Vec<_>>()
+            .join(";");
+            
+        // Build URL
+        let url = format!(
+            "{}/route/v1/{}/{}?overview={}&steps=true{}",
+            backend.base_url,
+            profile,
+            coords_str,
+            if geometry { "full" } else { "false" },
+            exclude_param(exclude)
+        );
+        
+        debug!("OSRM route request: {}", url);
+        
+        // Make request
+        let response = timed(backend.client.get(&url).send())
+            .await
+            .context("Failed to send OSRM route request")?;
+            
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!("OSRM route request failed with status {}: {}", status, error_text);
+            anyhow::bail!("OSRM route request failed with status {}", status);
+        }
+        
+        let route_response = response.json::<OsrmRouteResponse>()
+            .await
+            .context("Failed to parse OSRM route response")?;
+            
+        Ok(route_response)
+    }
+    
+    /// Get a duration/distance matrix between multiple coordinates
+    ///
+    /// Coordinate sets larger than the configured `max_table_size` are split into
+    /// blocks of sources and destinations that are requested separately and
+    /// stitched back into the full matrix.
+    pub async fn table(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: Option<&str>,
+        exclude: &[String],
+        include_distances: bool,
+    ) -> Result<OsrmTableResponse> {
+        let profile = profile.unwrap_or(&self.config.default_profile);
+        let backend = self.backend(profile);
+        
+        let max_table_size = self.config.max_table_size.max(2);
+        if coordinates.len() <= max_table_size {
+            return self.table_request(backend, coordinates, None, profile, exclude, include_distances).await;
+        }
+        
+        // Each block holds a chunk of sources and a chunk of destinations
+        let size = coordinates.len();
+        let chunk = max_table_size / 2;
+        let chunks: Vec<Range<usize>> = (0..size)
+            .step_by(chunk)
+            .map(|start| start..(start + chunk).min(size))
+            .collect();
+        let blocks: Vec<(Range<usize>, Range<usize>)> = chunks
+            .iter()
+            .flat_map(|sources| chunks.iter().map(move |destinations| (sources.clone(), destinations.clone())))
+            .collect();
+        
+        debug!("Splitting OSRM table of {} coordinates into {} requests", size, blocks.len());
+        
+        let responses: Vec<_> = stream::iter(blocks)
+            .map(|(sources, destinations)| async move {
+                let mut block_coordinates = coordinates[sources.clone()].to_vec();
+                block_coordinates.extend_from_slice(&coordinates[destinations.clone()]);
+                
+                let source_indices = 0..sources.len();
+                let destination_indices = sources.len()..sources.len() + destinations.len();
+                let response = self
+                    .table_request(
+                        backend,
+                        &block_coordinates,
+                        Some((source_indices, destination_indices)),
+                        profile,
+                        exclude,
+                        include_distances,
+                    )
+                    .await?;
+                
+                Ok::<_, anyhow::Error>((sources, destinations, response))
+            })
+            .buffer_unordered(MAX_CONCURRENT_TABLE_REQUESTS)
+            .try_collect()
+            .await?;
+        
+        // Stitch the blocks into the full matrix
+        let mut durations = vec![vec![None; size]; size];
+        let mut distances = include_distances.then(|| vec![vec![None; size]; size]);
+        let mut waypoints: Vec<Option<OsrmWaypoint>> = vec![None; size];
+        
+        for (sources, destinations, response) in responses {
+            copy_block(&mut durations, &response.durations, &sources, &destinations)?;
+            
+            // Every block of a row snaps the same sources, so keep those of the first
+            if destinations.start == 0 && response.sources.len() == sources.len() {
+                for (idx, waypoint) in sources.clone().zip(response.sources) {
+                    waypoints[idx] = Some(waypoint);
+                }
+            }
+            
+            if let Some(distances) = &mut distances {
+                let block = response
+                    .distances
+                    .context("OSRM table response is missing distances")?;
+                copy_block(distances, &block, &sources, &destinations)?;
+            }
+        }
+        
+        Ok(OsrmTableResponse {
+            code: "Ok".to_string(),
+            durations,
+            distances,
+            sources: waypoints.into_iter().collect::<Option<_>>().unwrap_or_default(),
+        })
+    }
+    
+    /// Send a single table request, optionally restricted to source and destination indices
+    async fn table_request(
+        &self,
+        backend: &Backend,
+        coordinates: &[[f64; 2]],
+        indices: Option<(Range<usize>, Range<usize>)>,
+        profile: &str,
+        exclude: &[String],
+        include_distances: bool,
+    ) -> Result<OsrmTableResponse> {
+
+        // Build coordinates string
+        let coords_str = coordinates
+            .iter()
+            .map(|coord| format!("{},{}", coord[0], coord[1]))
+            .collect::<Vec<_>>()
+            .join(";");
+            
+        // Build URL
+        let mut url = format!(
+            "{}/table/v1/{}/{}?annotations={}",
+            backend.base_url,
+            profile,
+            coords_str,
+            if include_distances { "duration,distance" } else { "duration" }
+        );
+        
+        if let Some((sources, destinations)) = indices {
+            let join = |range: Range<usize>| {
+                range.map(|idx| idx.to_string()).collect::<Vec<_>>().join(";")
+            };
+            url.push_str(&format!("&sources={}&destinations={}", join(sources), join(destinations)));
+        }
+        url.push_str(&exclude_param(exclude));
+        
+        debug!("OSRM table request: {}", url);
+        
+        // Make request
+        let response = timed(backend.client.get(&url).send())
+            .await
+            .context("Failed to send OSRM table request")?;
+            
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!("OSRM table request failed with status {}: {}", status, error_text);
+            anyhow::bail!("OSRM table request failed with status {}", status);
+        }
+        
+        let table_response = response.json::<OsrmTableResponse>()
+            .await
+            .context("Failed to parse OSRM table response")?;
+            
+        Ok(table_response)
+    }
+}
+
+#[async_trait]
+impl RoutingEngine for OsrmService {
+    async fn route(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+        geometry: bool,
+        instructions: bool,
+    ) -> Result<Option<EngineRoute>> {
+        let response = OsrmService::route(self, coordinates, Some(profile), exclude, geometry).await?;
+        
+        Ok(response.routes.into_iter().next().map(|route| EngineRoute {
+            distance: route.distance,
+            duration: route.duration,
+            geometry: route.geometry,
+            legs: route
+                .legs
+                .iter()
+                .map(|leg| EngineLeg {
+                    distance: leg.distance,
+                    duration: leg.duration,
+                    instructions: if instructions {
+                        leg.steps.iter().map(instruction).collect()
+                    } else {
+                        Vec::new()
+                    },
+                })
+                .collect(),
+        }))
+    }
+    
+    async fn matrix(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+    ) -> Result<Matrix> {
+        let response = self.table(coordinates, Some(profile), exclude, true).await?;
+        
+        // OSRM has no duration or distance for pairs it cannot route between
+        let impassable = |rows: Vec<Vec<Option<f64>>>| -> Vec<Vec<f64>> {
+            rows.into_iter()
+                .map(|row| row.into_iter().map(|value| value.unwrap_or(f64::INFINITY)).collect())
+                .collect()
+        };
+        
+        // Waypoints are only complete when OSRM snapped every coordinate
+        let snapped = (response.sources.len() == coordinates.len())
+            .then(|| {
+                response
+                    .sources
+                    .iter()
+                    .map(|waypoint| Snap {
+                        location: waypoint.location,
+                        distance: waypoint.distance,
+                    })
+                    .collect()
+            });
+        
+        Ok(Matrix {
+            durations: impassable(response.durations),
+            distances: response.distances.map(impassable),
+            snapped,
+        })
+    }
+    
+    async fn health(&self) -> Vec<BackendHealth> {
+        OsrmService::health(self).await
+    }
+}
+
+/// Query parameter excluding the road classes in `exclude`, empty when there are none
+fn exclude_param(exclude: &[String]) -> String {
+    if exclude.is_empty() {
+        String::new()
+    } else {
+        format!("&exclude={}", exclude.join(","))
+    }
+}
+
+/// Copy a block of a table response into the full matrix
+fn copy_block<T: Clone>(
+    matrix: &mut [Vec<T>],
+    block: &[Vec<T>],
+    sources: &Range<usize>,
+    destinations: &Range<usize>,
+) -> Result<()> {
+    if block.len() != sources.len() || block.iter().any(|row| row.len() != destinations.len()) {
+        anyhow::bail!(
+            "OSRM table block has the wrong size, expected {}x{}",
+            sources.len(),
+            destinations.len()
+        );
+    }
+    
+    for (row, values) in sources.clone().zip(block) {
+        matrix[row][destinations.clone()].clone_from_slice(values);
+    }
+    
+    Ok(())
+}
+
+/// Instruction of an OSRM step
+fn instruction(step: &OsrmRouteStep) -> Instruction {
+    Instruction {
+        maneuver: step.maneuver.maneuver_type.clone(),
+        modifier: step.maneuver.modifier.clone(),
+        name: step.name.clone(),
+        distance: step.distance.round() as u32,
+        duration: step.duration.round() as u32,
+    }
+}
@@ -0,0 +1,2844 @@
+use super::engine::{self, EngineKind, EngineRoute, RoutingEngine};
+use super::graphhopper::{GraphHopperConfig, GraphHopperService};
+use super::matrix_cache::MatrixCache;
+use super::osrm::{OsrmConfig, OsrmService};
+use super::solver::{
+    self, BreakStop, LocationIndex, Matrix, Problem, ReloadStop, Schedule, Solution, Task,
+    TaskKind, TrafficPeriod,
+};
+use super::valhalla::{ValhallaConfig, ValhallaService};
+use crate::models::{
+    AlternativeSolution, Break, CoordinateOrder, CostBreakdown, CostMatrix, EtaRequest, EtaResponse,
+    FeasibilityReport, GeometryFormat, InsertRequest, InsertResponse, LockedRoute, MatrixRequest, RemoveRequest, OptimizationProgress, ProgressRoute, RelationStep, ReoptimizeRequest,
+    RouteFeasibility, RouteLeg, RouteRequest, RouteResponse, RouteStep, RoutingRequest, RoutingResponse,
+    RoutingSummary, SolveStrategy, StepEta, StockOut, StopEta, StopFeasibility, TimeWindowPolicy, Unassigned, UnassignedCode, UnassignedJob,
+    UnassignedReason, Vehicle, VehicleRoute, Violation, ViolationType, Zone,
+};
+use crate::models::time;
+use crate::utils::{geojson, polyline};
+use anyhow::{bail, Result};
+use crate::utils::validation::misordered_shipments;
+use chrono::{FixedOffset, Offset, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Cost added to the summary for each unassigned job or shipment, multiplied by `1 + priority`,
+/// unless the request sets its own penalty
+const UNASSIGNED_COST: f64 = 3600.0;
+
+/// Default cost per second of serving a task late under the soft time window policy
+const DEFAULT_LATENESS_PENALTY: f64 = 10.0;
+
+/// Time allowed on top of `max_time` for fetching the routes of the solution
+const ROUTING_GRACE: Duration = Duration::from_secs(30);
+
+/// Number of recent travel matrices kept for later requests on the same locations
+const MATRIX_CACHE_SIZE: usize = 4;
+
+/// Callback receiving the best solution found so far while the solver runs
+pub type ProgressCallback = Arc<dyn Fn(OptimizationProgress) + Send + Sync>;
+
+/// Configuration for the routing service
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoutingConfig {
+    /// OSRM service configuration
+    pub osrm: OsrmConfig,
+
+    /// Routing engine to use
+    #[serde(default)]
+    pub engine: EngineKind,
+
+    /// Valhalla service configuration, used when `engine` is `valhalla`
+    #[serde(default)]
+    pub valhalla: ValhallaConfig,
+
+    /// GraphHopper service configuration, used when `engine` is `graphhopper`
+    #[serde(default)]
+    pub graphhopper: GraphHopperConfig,
+
+    /// Default maximum time for optimization in seconds
+    pub default_max_time: u32,
+
+    /// Default number of threads to use
+    pub default_threads: u8,
+
+    /// Time-of-day factors applied to travel durations
+    #[serde(default)]
+    pub traffic: Vec<TrafficPeriod>,
+
+    /// Meters a location may be moved to reach the road network before it is treated as
+    /// unreachable
+    #[serde(default = "default_max_snap_distance")]
+    pub max_snap_distance: f64,
+
+    /// Number of jobs and shipments above which the solver switches to a quick search
+    #[serde(default)]
+    pub quick_search_above: Option<usize>,
+
+    /// Number of jobs and shipments per cluster of the `cluster` strategy
+    #[serde(default = "default_cluster_size")]
+    pub cluster_size: usize,
+
+    /// Maximum number of stops per route for vehicles without their own `max_tasks`
+    #[serde(default)]
+    pub default_max_tasks: Option<u32>,
+
+    /// Cost per second of serving a task late under the soft time window policy, unless
+    /// a request sets its own
+    #[serde(default = "default_lateness_penalty")]
+    pub lateness_penalty: f64,
+
+    /// Cost of each task served late under the soft time window policy, on top of the
+    /// cost per second, unless a request sets its own
+    #[serde(default)]
+    pub late_task_penalty: f64,
+}
+
+fn default_max_snap_distance() -> f64 {
+    1000.0
+}
+
+fn default_lateness_penalty() -> f64 {
+    DEFAULT_LATENESS_PENALTY
+}
+
+fn default_cluster_size() -> usize {
+    100
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            osrm: OsrmConfig::default(),
+            engine: EngineKind::default(),
+            valhalla: ValhallaConfig::default(),
+            graphhopper: GraphHopperConfig::default(),
+            default_max_time: 30,
+            default_threads: 4,
+            traffic: Vec::new(),
+            max_snap_distance: default_max_snap_distance(),
+            quick_search_above: None,
+            cluster_size: default_cluster_size(),
+            default_max_tasks: None,
+            lateness_penalty: default_lateness_penalty(),
+            late_task_penalty: 0.0,
+        }
+    }
+}
+
+/// Solver defaults that can be tuned while the service runs
+///
+/// They start out from the [`RoutingConfig`] and are shared by all clones of a service.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SolverSettings {
+    /// Maximum time for optimization in seconds, unless a request sets its own
+    pub default_max_time: u32,
+
+    /// Number of solver threads, unless a request sets its own
+    pub default_threads: u8,
+
+    /// Meters a location may be moved to reach the road network before it is treated as
+    /// unreachable
+    pub max_snap_distance: f64,
+
+    /// Number of jobs and shipments above which the solver switches to a quick search
+    pub quick_search_above: Option<usize>,
+
+    /// Number of jobs and shipments per cluster of the `cluster` strategy
+    pub cluster_size: usize,
+
+    /// Maximum number of stops per route, unless a vehicle sets its own `max_tasks`
+    pub default_max_tasks: Option<u32>,
+
+    /// Cost per second of serving a task late under the soft time window policy, unless
+    /// a request sets its own
+    pub lateness_penalty: f64,
+
+    /// Cost of each task served late under the soft time window policy, unless a request
+    /// sets its own
+    pub late_task_penalty: f64,
+}
+
+impl From<&RoutingConfig> for SolverSettings {
+    fn from(config: &RoutingConfig) -> Self {
+        Self {
+            default_max_time: config.default_max_time,
+            default_threads: config.default_threads,
+            max_snap_distance: config.max_snap_distance,
+            quick_search_above: config.quick_search_above,
+            cluster_size: config.cluster_size,
+            default_max_tasks: config.default_max_tasks,
+            lateness_penalty: config.lateness_penalty,
+            late_task_penalty: config.late_task_penalty,
+        }
+    }
+}
+
+/// How the routes of an earlier solution constrain the solution of a request
+#[derive(Debug, Clone, Copy, Default)]
+struct Previous<'a> {
+    /// Routes whose locked start stays in place
+    locked: &'a [LockedRoute],
+
+    /// Work to insert into the initial routes of the request, which otherwise keep their order
+    insertion: Option<&'a InsertRequest>,
+
+    /// Vehicles whose routes are improved among themselves after inserting
+    repaired: &'a [u64],
+}
+
+/// Service for handling routing optimization
+#[derive(Debug, Clone)]
+pub struct RoutingService {
+    engine: Arc<dyn RoutingEngine>,
+    config: RoutingConfig,
+    settings: Arc<RwLock<SolverSettings>>,
+    matrices: Arc<Mutex<MatrixCache>>,
+}
+
+impl RoutingService {
+    /// Create a new routing service with the engine selected in the configuration
+    pub fn new(config: RoutingConfig) -> Self {
+        let engine: Arc<dyn RoutingEngine> = match config.engine {
+            EngineKind::Osrm => Arc::new(OsrmService::new(config.osrm.clone())),
+            EngineKind::Valhalla => Arc::new(ValhallaService::new(config.valhalla.clone())),
+            EngineKind::GraphHopper => {
+                Arc::new(GraphHopperService::new(config.graphhopper.clone()))
+            }
+        };
+        Self::with_engine(config, engine)
+    }
+
+    /// Create a new routing service using a custom routing engine
+    pub fn with_engine(config: RoutingConfig, engine: Arc<dyn RoutingEngine>) -> Self {
+        let settings = Arc::new(RwLock::new(SolverSettings::from(&config)));
+        Self {
+            engine,
+            config,
+            settings,
+            matrices: Arc::new(Mutex::new(MatrixCache::new(MATRIX_CACHE_SIZE))),
+        }
+    }
+
+    /// Routing service sharing the solver defaults of this one, but routing with the OSRM
+    /// servers and default profile of `osrm`
+    ///
+    /// With another engine configured, only the default profile changes.
+    pub fn with_osrm(&self, osrm: OsrmConfig) -> Self {
+        let engine: Arc<dyn RoutingEngine> = match self.config.engine {
+            EngineKind::Osrm => Arc::new(OsrmService::new(osrm.clone())),
+            _ => Arc::clone(&self.engine),
+        };
+        Self {
+            engine,
+            config: RoutingConfig {
+                osrm,
+                ..self.config.clone()
+            },
+            settings: Arc::clone(&self.settings),
+            matrices: Arc::new(Mutex::new(MatrixCache::new(MATRIX_CACHE_SIZE))),
+        }
+    }
+
+    /// OSRM configuration of this service
+    pub fn osrm_config(&self) -> &OsrmConfig {
+        &self.config.osrm
+    }
+
+    /// Current solver defaults
+    pub fn settings(&self) -> SolverSettings {
+        self.settings
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Replace the solver defaults, for optimizations started from now on
+    pub fn update_settings(&self, settings: SolverSettings) {
+        *self.settings.write().unwrap_or_else(|err| err.into_inner()) = settings;
+    }
+
+    /// Routing engine used for routes and matrices
+    pub fn engine(&self) -> &dyn RoutingEngine {
+        self.engine.as_ref()
+    }
+
+    /// Travel matrix between `locations` by `profile` keeping off the road classes in
+    /// `exclude`, cut from a recent matrix when one covers them all
+    async fn cached_matrix(
+        &self,
+        locations: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+    ) -> Result<Matrix> {
+        // Matrices excluding road classes are kept apart from those of the plain profile
+        let key = if exclude.is_empty() {
+            profile.to_string()
+        } else {
+            format!("{}?exclude={}", profile, exclude.join(","))
+        };
+        if let Some(matrix) = self.matrix_cache().get(locations, &key) {
+            return Ok(matrix);
+        }
+
+        let matrix = self.engine.matrix(locations, profile, exclude).await?;
+        self.matrix_cache()
+            .insert(locations, &key, matrix.clone());
+        Ok(matrix)
+    }
+
+    fn matrix_cache(&self) -> MutexGuard<'_, MatrixCache> {
+        self.matrices.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Travel matrix between the locations of a request, as used by the solver
+    ///
+    /// Like those of optimizations, it is cut from a recent matrix when one covers the
+    /// locations.
+    pub async fn matrix(&self, request: &MatrixRequest) -> Result<CostMatrix> {
+        let profile = request
+            .profile
+            .as_deref()
+            .unwrap_or(&self.config.osrm.default_profile);
+        let matrix = self.cached_matrix(&request.locations, profile, &[]).await?;
+
+        Ok(CostMatrix {
+            locations: request.locations.clone(),
+            durations: matrix.durations,
+            distances: matrix.distances,
+        })
+    }
+
+    /// Time the ordered stops of a single vehicle, without optimizing their order
+    pub async fn route(&self, request: &RouteRequest) -> Result<RouteResponse> {
+        let response = self.process_request(request.routing_request()).await?;
+        let Some(route) = response.routes.into_iter().next() else {
+            bail!("No route found for vehicle {}", request.vehicle.id);
+        };
+
+        let stops: Vec<StopEta> = route
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                RouteStep::Job {
+                    id,
+                    arrival_time: Some(arrival_time),
+                    waiting_time,
+                    departure_time: Some(departure_time),
+                    ..
+                } => Some(StopEta {
+                    id: *id,
+                    arrival_time: *arrival_time,
+                    waiting_time: waiting_time.unwrap_or(0),
+                    departure_time: *departure_time,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Ok(RouteResponse { route, stops })
+    }
+
+    /// Estimate the remaining arrivals of a planned route again from where the vehicle is now
+    ///
+    /// Travel comes from the routing engine, from the current position along the remaining
+    /// steps. Steps keep their planned service and break times, and a step the plan waited
+    /// at for its time window to open is not served before the planned time.
+    pub async fn eta(&self, request: &EtaRequest) -> Result<EtaResponse> {
+        let steps = request.remaining_steps();
+        let profile = request
+            .routing_profile
+            .as_deref()
+            .unwrap_or(&self.config.osrm.default_profile);
+
+        let mut locations = vec![request.current_position];
+        locations.extend(steps.iter().filter_map(travel_location));
+        let legs = if locations.len() > 1 {
+            let Some(route) = self.engine.route(&locations, profile, &[], false, false).await? else {
+                bail!("No route found from the current position");
+            };
+            route.legs
+        } else {
+            Vec::new()
+        };
+
+        let mut legs = legs.into_iter();
+        let mut time = request.current_time;
+        let mut stops = Vec::with_capacity(steps.len());
+        for step in steps {
+            if travel_location(step).is_some() {
+                let Some(leg) = legs.next() else {
+                    bail!("The routing engine returned too few legs");
+                };
+                time += leg.duration.round() as i64;
+            }
+
+            let arrival_time = time;
+            let planned_arrival = step_arrival_time(step).unwrap_or(arrival_time);
+            let planned_departure = step_departure_time(step).unwrap_or(planned_arrival);
+            let waiting = step_waiting_time(step);
+            let service = planned_departure - planned_arrival - waiting;
+            let service_start = if waiting > 0 {
+                arrival_time.max(planned_arrival + waiting)
+            } else {
+                arrival_time
+            };
+            time = service_start + service.max(0);
+
+            let id = match step {
+                RouteStep::Break { id, .. } => Some(*id),
+                step => step_id(step),
+            };
+            stops.push(StepEta {
+                id,
+                kind: step_kind(step).to_string(),
+                arrival_time,
+                departure_time: time,
+                delay: arrival_time - planned_arrival,
+            });
+        }
+
+        Ok(EtaResponse { stops })
+    }
+
+    /// Check the predefined steps of each vehicle against its capacity, the time windows and
+    /// its shift
+    ///
+    /// The steps are timed in the order given; the optimizer does not run.
+    pub async fn check_feasibility(&self, request: RoutingRequest) -> Result<FeasibilityReport> {
+        if !request
+            .vehicles
+            .iter()
+            .any(|v| v.steps.as_ref().is_some_and(|steps| !steps.is_empty()))
+        {
+            bail!("No vehicle has steps to check");
+        }
+
+        let response = self.process_request(request).await?;
+
+        let routes: Vec<RouteFeasibility> =
+            response.routes.into_iter().map(route_feasibility).collect();
+
+        Ok(FeasibilityReport {
+            feasible: routes.iter().all(|route| route.feasible),
+            routes,
+            unplanned_jobs: response.unassigned.ids(),
+            unplanned_shipments: response.unassigned_shipments.ids(),
+        })
+    }
+
+    /// Process a routing request and return an optimized solution
+    pub async fn process_request(&self, request: RoutingRequest) -> Result<RoutingResponse> {
+        self.process_request_with_progress(request, Arc::new(|_| {}))
+            .await
+    }
+
+    /// Process a routing request, reporting each improved solution to `progress`
+    pub async fn process_request_with_progress(
+        &self,
+        request: RoutingRequest,
+        progress: ProgressCallback,
+    ) -> Result<RoutingResponse> {
+        self.process(request, Previous::default(), progress).await
+    }
+
+    /// Optimize a previous solution again after jobs were added or cancelled
+    ///
+    /// The locked start of each previous route stays in place; the solver only plans the
+    /// remaining work.
+    pub async fn reoptimize(&self, request: &ReoptimizeRequest) -> Result<RoutingResponse> {
+        self.reoptimize_with_progress(request, Arc::new(|_| {}))
+            .await
+    }
+
+    /// Re-optimize like [`Self::reoptimize`], reporting each improved solution to `progress`
+    pub async fn reoptimize_with_progress(
+        &self,
+        request: &ReoptimizeRequest,
+        progress: ProgressCallback,
+    ) -> Result<RoutingResponse> {
+        let previous = Previous {
+            locked: &request.routes,
+            ..Previous::default()
+        };
+        self.process(request.updated_request(), previous, progress)
+            .await
+    }
+
+    /// Insert new jobs and shipments into the routes of an earlier solution, each at its
+    /// cheapest feasible position
+    ///
+    /// The routed steps keep their order and work the earlier solution left unassigned
+    /// stays unassigned.
+    pub async fn insert(
+        &self,
+        request: &RoutingRequest,
+        solution: &RoutingResponse,
+        insert: &InsertRequest,
+    ) -> Result<InsertResponse> {
+        let updated = insert.updated_request(request, &solution.routes);
+        let previous = Previous {
+            insertion: Some(insert),
+            ..Previous::default()
+        };
+        let response = self
+            .process(updated, previous, Arc::new(|_| {}))
+            .await?;
+
+        let route_costs = |response: &RoutingResponse| -> f64 {
+            response
+                .routes
+                .iter()
+                .map(|route| route.cost_breakdown.total())
+                .sum()
+        };
+        Ok(InsertResponse {
+            insertion_cost: route_costs(&response) - route_costs(solution),
+            solution: response,
+        })
+    }
+
+    /// Remove jobs and shipments from the routes of an earlier solution and time the routes
+    /// again
+    ///
+    /// With `remove.reoptimize` the routes the work is removed from are improved among
+    /// themselves; all other routes keep their steps in order.
+    pub async fn remove(
+        &self,
+        request: &RoutingRequest,
+        solution: &RoutingResponse,
+        remove: &RemoveRequest,
+    ) -> Result<RoutingResponse> {
+        let updated = remove.updated_request(request, &solution.routes);
+        let repaired = if remove.reoptimize {
+            remove.affected_vehicles(&solution.routes)
+        } else {
+            Vec::new()
+        };
+        let nothing = InsertRequest::default();
+        let previous = Previous {
+            insertion: Some(&nothing),
+            repaired: &repaired,
+            ..Previous::default()
+        };
+        self.process(updated, previous, Arc::new(|_| {})).await
+    }
+
+    /// Process a routing request within the constraints of the `previous` solution
+    ///
+    /// The solver stops at `max_time` with the best solution found so far; the whole
+    /// request is abandoned when fetching its routes takes much longer than that.
+    async fn process(
+        &self,
+        request: RoutingRequest,
+        previous: Previous<'_>,
+        progress: ProgressCallback,
+    ) -> Result<RoutingResponse> {
+        let max_time = request
+            .options
+            .as_ref()
+            .and_then(|o| o.max_time)
+            .unwrap_or(self.settings().default_max_time);
+        let budget = Duration::from_secs(max_time as u64);
+        let deadline = Instant::now() + budget;
+
+        let processing = self.process_until(request, previous, progress, deadline);
+        match tokio::time::timeout(budget + ROUTING_GRACE, engine::record_latency(processing)).await {
+            Ok(result) => result,
+            Err(_) => bail!(
+                "Optimization did not finish within its time limit of {}s",
+                max_time
+            ),
+        }
+    }
+
+    /// Process a routing request, stopping the solver at `deadline`
+    async fn process_until(
+        &self,
+        mut request: RoutingRequest,
+        previous: Previous<'_>,
+        progress: ProgressCallback,
+        deadline: Instant,
+    ) -> Result<RoutingResponse> {
+        let start_time = Instant::now();
+
+        // Vehicles without their own departure time leave at the request's
+        if let Some(departure_time) = request.options.as_ref().and_then(|o| o.departure_time) {
+            for vehicle in &mut request.vehicles {
+                vehicle.departure_time.get_or_insert(departure_time);
+            }
+        }
+
+        // Vehicles without their own stop limit keep to the configured one
+        if let Some(max_tasks) = self.settings().default_max_tasks {
+            for vehicle in &mut request.vehicles {
+                vehicle.max_tasks.get_or_insert(max_tasks);
+            }
+        }
+
+        // Duplicate IDs the options allow are resolved before anything looks them up
+        let warnings = request.resolve_duplicate_ids();
+        let coordinate_order = request.coordinate_order();
+        request.resolve_coordinate_order();
+        request.resolve_depots();
+        request.resolve_positions();
+
+        // Extract options
+        let geometry_format = request
+            .options
+            .as_ref()
+            .and_then(|o| o.geometry_format)
+            .unwrap_or_default();
+
+        let leg_geometry = request
+            .options
+            .as_ref()
+            .and_then(|o| o.leg_geometry)
+            .unwrap_or(false);
+
+        let timezone = request
+            .options
+            .as_ref()
+            .filter(|o| o.timestamps == Some(true))
+            .map(|o| o.timezone.as_deref().and_then(time::parse_offset).unwrap_or(Utc.fix()));
+
+        // GeoJSON output and leg geometry always carry the route geometry
+        let include_geometry = request
+            .options
+            .as_ref()
+            .and_then(|o| o.geometry)
+            .unwrap_or(false)
+            || leg_geometry
+            || geometry_format == GeometryFormat::GeoJson;
+
+        let routing_profile = request
+            .routing_profile
+            .as_deref()
+            .unwrap_or(&self.config.osrm.default_profile);
+
+        info!(
+            "Processing routing request with {} vehicles and {} jobs",
+            request.vehicles.len(),
+            request.jobs.len()
+        );
+
+        // Check if we have predefined routes
+        let has_predefined_routes = request
+            .vehicles
+            .iter()
+            .any(|v| v.steps.is_some() && !v.steps.as_ref().unwrap().is_empty());
+
+        let (mut routes, mut diagnosis, mut alternatives, lower_bound) = if has_predefined_routes {
+            // Process predefined routes
+            let routes = self
+                .process_predefined_routes(&request, routing_profile, include_geometry)
+                .await?;
+            (routes, UnassignedDiagnosis::default(), Vec::new(), None)
+        } else {
+            // Perform optimization
+            self.optimize_routes(
+                &request,
+                routing_profile,
+                deadline,
+                include_geometry,
+                previous,
+                progress,
+            )
+            .await?
+        };
+
+        let tasks = Task::build(&request.jobs, &request.shipments);
+        let soft = soft_time_windows(&request, &self.settings());
+
+        // Calculate summary
+        let mut cost_breakdown = CostBreakdown::default();
+        let mut total_distance = 0;
+        let mut total_duration = 0;
+        let mut total_waiting_time = 0;
+        let mut total_service_time = 0;
+        let mut time_window_violations = 0;
+
+        // Find unassigned jobs and shipments
+        let mut assigned_jobs = HashSet::new();
+        let mut assigned_shipments = HashSet::new();
+        for route in &routes {
+            for step in &route.steps {
+                match step {
+                    RouteStep::Job { id, .. } => {
+                        assigned_jobs.insert(*id);
+                    }
+                    RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => {
+                        assigned_shipments.insert(*id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // Explain every job and shipment left out of the routes
+        let unassigned_jobs: Vec<UnassignedJob> = request
+            .jobs
+            .iter()
+            .filter(|job| !assigned_jobs.contains(&job.id))
+            .map(|job| {
+                if !request
+                    .vehicles
+                    .iter()
+                    .any(|v| v.has_skills(job.skills.as_deref()))
+                {
+                    missing_skills(job.id, job.skills.as_deref())
+                } else if !request
+                    .vehicles
+                    .iter()
+                    .any(|v| v.carries(job.load_types.as_deref()))
+                {
+                    incompatible_load(job.id, job.load_types.as_deref())
+                } else if !request.vehicles.iter().any(|v| job.allows_vehicle(v.id)) {
+                    unassigned_job(
+                        job.id,
+                        UnassignedCode::VehicleNotAllowed,
+                        "No vehicle is allowed to serve the job",
+                    )
+                } else if !request
+                    .vehicles
+                    .iter()
+                    .any(|v| v.serves_location(job.location))
+                {
+                    out_of_zone(job.id, "job")
+                } else {
+                    diagnosis
+                        .jobs
+                        .remove(&job.id)
+                        .unwrap_or_else(|| not_in_route(job.id, "job"))
+                }
+            })
+            .collect();
+
+        let unassigned_shipments: Vec<UnassignedJob> = request
+            .shipments
+            .iter()
+            .filter(|shipment| !assigned_shipments.contains(&shipment.id))
+            .map(|shipment| {
+                if !request
+                    .vehicles
+                    .iter()
+                    .any(|v| v.has_skills(shipment.skills.as_deref()))
+                {
+                    missing_skills(shipment.id, shipment.skills.as_deref())
+                } else if !request
+                    .vehicles
+                    .iter()
+                    .any(|v| v.carries(shipment.load_types.as_deref()))
+                {
+                    incompatible_load(shipment.id, shipment.load_types.as_deref())
+                } else if !request.vehicles.iter().any(|v| {
+                    v.serves_location(shipment.pickup.location)
+                        && v.serves_location(shipment.delivery.location)
+                }) {
+                    out_of_zone(shipment.id, "shipment")
+                } else {
+                    diagnosis
+                        .shipments
+                        .remove(&shipment.id)
+                        .unwrap_or_else(|| not_in_route(shipment.id, "shipment"))
+                }
+            })
+            .collect();
+
+        check_unassigned_limits(
+            &request,
+            unassigned_jobs.len() + unassigned_shipments.len(),
+        )?;
+
+        for route in &mut routes {
+            total_distance += route.distance as u64;
+            total_duration += route.duration as u64;
+            total_waiting_time += route.waiting_time as u64;
+            total_service_time += route.service_time as u64;
+
+            // Price the route and report time window and capacity violations
+            if let Some(vehicle) = request.vehicles.iter().find(|v| v.id == route.vehicle_id) {
+                time_window_violations += record_violations(route, vehicle, &tasks);
+                route.cost_breakdown = route_cost(vehicle, route, soft.as_ref());
+                cost_breakdown += route.cost_breakdown;
+            }
+        }
+
+        // Weight unassigned work by its priority
+        cost_breakdown.unassigned = request
+            .jobs
+            .iter()
+            .filter(|job| !assigned_jobs.contains(&job.id))
+            .map(|job| job.priority)
+            .chain(
+                request
+                    .shipments
+                    .iter()
+                    .filter(|shipment| !assigned_shipments.contains(&shipment.id))
+                    .map(|shipment| shipment.priority),
+            )
+            .map(|priority| unassigned_cost(&request) * (1.0 + priority.unwrap_or(0) as f64))
+            .sum();
+
+        let cost = cost_breakdown.total();
+        let summary = RoutingSummary {
+            cost,
+            distance: total_distance,
+            duration: total_duration,
+            routes: routes.len() as u32,
+            unassigned: (unassigned_jobs.len() + unassigned_shipments.len()) as u32,
+            waiting_time: total_waiting_time,
+            service_time: total_service_time,
+            computing_time: start_time.elapsed().as_millis() as u64,
+            cost_breakdown,
+            stock_outs: diagnosis.stock_outs,
+            load_order: request
+                .options
+                .as_ref()
+                .and_then(|o| o.load_order)
+                .unwrap_or_default(),
+            lower_bound,
+            gap_percent: lower_bound
+                .filter(|_| cost > 0.0)
+                .map(|bound| ((cost - bound) / cost * 100.0).max(0.0)),
+            engine_latency: engine::request_latency(),
+        };
+
+        // Build response
+        add_metadata(&mut routes, &request);
+        if leg_geometry {
+            for route in &mut routes {
+                add_leg_polylines(route)?;
+            }
+        }
+        if let Some(offset) = timezone {
+            for route in &mut routes {
+                add_timestamps(route, offset);
+            }
+        }
+
+        // Replace the encoded polylines by GeoJSON if requested
+        if geometry_format == GeometryFormat::GeoJson {
+            for route in &mut routes {
+                let line = match route.polyline.take() {
+                    Some(polyline) => polyline::decode(&polyline, 5)?,
+                    None => geojson::step_locations(route),
+                };
+                route.geojson = Some(geojson::route_feature_collection(route, &line));
+            }
+        }
+
+        let geometry = if include_geometry {
+            // Extract polylines from routes if available
+            let polylines = routes
+                .iter()
+                .filter_map(|route| route.polyline.clone())
+                .collect::<Vec<_>>();
+
+            if polylines.is_empty() {
+                None
+            } else {
+                Some(polylines)
+            }
+        } else {
+            None
+        };
+
+        // Older clients get plain IDs, with the job reasons listed separately
+        let legacy_unassigned = request
+            .options
+            .as_ref()
+            .and_then(|o| o.legacy_unassigned)
+            .unwrap_or(false);
+        let (unassigned, unassigned_shipments, unassigned_reasons) = if legacy_unassigned {
+            (
+                Unassigned::Ids(unassigned_jobs.iter().map(|job| job.id).collect()),
+                Unassigned::Ids(unassigned_shipments.iter().map(|job| job.id).collect()),
+                unassigned_jobs
+                    .into_iter()
+                    .map(|job| UnassignedReason {
+                        id: job.id,
+                        reason: job.description,
+                    })
+                    .collect(),
+            )
+        } else {
+            (
+                Unassigned::Detailed(unassigned_jobs),
+                Unassigned::Detailed(unassigned_shipments),
+                Vec::new(),
+            )
+        };
+
+        // Steps are returned in the coordinate order of the request, GeoJSON stays as it is
+        if coordinate_order == CoordinateOrder::LatLon {
+            let alternative_routes = alternatives.iter_mut().flat_map(|alt| &mut alt.routes);
+            for route in routes.iter_mut().chain(alternative_routes) {
+                for step in &mut route.steps {
+                    step.swap_coordinates();
+                }
+            }
+        }
+
+        let response = RoutingResponse {
+            summary,
+            routes,
+            unassigned,
+            unassigned_shipments,
+            unassigned_reasons,
+            geometry,
+            alternatives,
+            page: None,
+            solution_id: None,
+            warnings,
+        };
+
+        info!(
+            "Routing completed in {}ms: {} routes, {} unassigned, {} time window violations",
+            response.summary.computing_time,
+            response.summary.routes,
+            response.summary.unassigned,
+            time_window_violations
+        );
+
+        Ok(response)
+    }
+
+    /// Process predefined routes from the request
+    async fn process_predefined_routes(
+        &self,
+        request: &RoutingRequest,
+        profile: &str,
+        include_geometry: bool,
+    ) -> Result<Vec<VehicleRoute>> {
+        let mut routes = Vec::new();
+
+        let tasks = Task::build(&request.jobs, &request.shipments);
+        let provided_matrix = client_matrix(request)?;
+
+        for vehicle in &request.vehicles {
+            if let Some(steps) = &vehicle.steps {
+                // Resolve the job and shipment steps to tasks
+                let mut stops = Vec::new();
+                for step in steps {
+                    match find_task(&tasks, step) {
+                        Some(task) => stops.push(task),
+                        None => {
+                            if let Some(id) = step_id(step) {
+                                warn!("Step ID {} not found in job or shipment list", id);
+                            }
+                        }
+                    }
+                }
+
+                if stops.is_empty() {
+                    continue;
+                }
+
+                // Collect coordinates for the route, leaving out open ends
+                let mut coordinates = Vec::new();
+                coordinates.extend(vehicle.start);
+
+                for task in &stops {
+                    coordinates.push(task.location);
+                }
+
+                coordinates.extend(vehicle.end);
+
+                // Get the legs from the client matrix, or the route from OSRM
+                let (mut legs, distance, duration, polyline, route_legs) = match &provided_matrix {
+                    Some((locations, matrix)) => {
+                        let mut legs = Vec::new();
+                        let mut distance = 0.0;
+                        for pair in coordinates.windows(2) {
+                            let (leg_duration, leg_distance) = matrix.travel(
+                                locations.get(pair[0]).expect("validated matrix location"),
+                                locations.get(pair[1]).expect("validated matrix location"),
+                            );
+                            legs.push(leg_duration);
+                            distance += leg_distance;
+                        }
+                        let duration = legs.iter().sum::<f64>();
+                        (legs, distance, duration, None, Vec::new())
+                    }
+                    None if coordinates.len() < 2 => (Vec::new(), 0.0, 0.0, None, Vec::new()),
+                    None => {
+                        let engine_route = self
+                            .engine
+                            .route(
+                                &coordinates,
+                                vehicle_profile(vehicle, profile),
+                                excluded_roads(request),
+                                include_geometry,
+                                include_instructions(request),
+                            )
+                            .await?;
+
+                        let Some(engine_route) = engine_route else {
+                            warn!("No route found for vehicle {}", vehicle.id);
+                            continue;
+                        };
+
+                        let legs = engine_route.legs.iter().map(|leg| leg.duration).collect();
+                        let route_legs = instructed_legs(request, &engine_route);
+                        (
+                            legs,
+                            engine_route.distance,
+                            engine_route.duration,
+                            engine_route.geometry,
+                            route_legs,
+                        )
+                    }
+                };
+
+                // Open ends take no travel, so every stop and the end keep a leg
+                if vehicle.start.is_none() {
+                    legs.insert(0, 0.0);
+                }
+                if vehicle.end.is_none() {
+                    legs.push(0.0);
+                }
+
+                // Calculate arrival and departure times
+                // This is a simplified implementation
+                let mut arrival_times = Vec::new();
+                let mut departure_times = Vec::new();
+                let mut current_time = 0;
+
+                // Predefined routes run in the first shift of vehicles with shifts
+                let shift = vehicle.shifts.as_ref().map(|_| 0);
+
+                // Start time
+                arrival_times.push(current_time);
+
+                if let Some(RouteStep::Start { service_after, .. }) = steps.first() {
+                    if let Some(time) = service_after {
+                        current_time = *time;
+                    }
+                } else {
+                    // Leave at the departure time, but not before the time window opens
+                    let earliest = vehicle.shift_window(shift).map(|tw| tw[0]).unwrap_or(0);
+                    current_time = vehicle
+                        .departure_time
+                        .map_or(earliest, |departure| departure.max(earliest));
+                }
+
+                departure_times.push(current_time);
+
+                // Breaks placed among the steps are taken there, the others before the legs
+                // they cannot wait for
+                let breaks = vehicle.breaks.as_deref().unwrap_or(&[]);
+                let mut placed = Vec::new();
+                let mut served = 0;
+                for step in steps {
+                    match step {
+                        RouteStep::Break { id, .. } => {
+                            match breaks.iter().position(|brk| brk.id == *id) {
+                                Some(index) => placed.push((served, index)),
+                                None => {
+                                    warn!("Break ID {} not found for vehicle {}", id, vehicle.id)
+                                }
+                            }
+                        }
+                        _ if find_task(&tasks, step).is_some() => served += 1,
+                        _ => {}
+                    }
+                }
+                let due: Vec<usize> = (0..breaks.len())
+                    .filter(|index| !placed.iter().any(|(_, placed)| placed == index))
+                    .collect();
+                let due_breaks: Vec<Break> =
+                    due.iter().map(|index| breaks[*index].clone()).collect();
+
+                // Job and shipment stops with the breaks and the rests the working rules require
+                let mut next_break = 0;
+                let mut taken_breaks = Vec::new();
+                let mut driving = 0;
+                let mut rests = Vec::new();
+                let mut travelled = 0.0;
+                let mut waiting_time = 0;
+                let mut service_time = 0;
+                let mut setup_times = Vec::new();
+                let mut location = vehicle.start;
+
+                for (i, leg) in legs.iter().enumerate() {
+                    let leg = leg * solver::traffic_factor(&self.config.traffic, current_time);
+                    travelled += leg;
+                    let leg_duration = leg as i64;
+                    let mut taken: Vec<BreakStop> = placed
+                        .iter()
+                        .filter(|(after, _)| *after == i)
+                        .map(|(_, index)| {
+                            solver::take_break_now(&breaks[*index], *index, &mut current_time, i)
+                        })
+                        .collect();
+                    let (taken_due, missed) = solver::take_breaks(
+                        &due_breaks,
+                        &mut next_break,
+                        &mut current_time,
+                        leg_duration,
+                        i == stops.len(),
+                        i,
+                    );
+                    for index in missed {
+                        warn!(
+                            "Vehicle {} cannot take break {} within its time windows",
+                            vehicle.id, due_breaks[index].id
+                        );
+                    }
+                    taken.extend(taken_due.into_iter().map(|taken| BreakStop {
+                        index: due[taken.index],
+                        ..taken
+                    }));
+                    if let Some(rules) = &vehicle.working_rules {
+                        if taken.iter().any(|taken| solver::rests_driver(rules, taken)) {
+                            driving = 0;
+                        }
+                        rests.extend(solver::take_rests(
+                            rules,
+                            &mut driving,
+                            &mut current_time,
+                            leg_duration,
+                            i,
+                        ));
+                    }
+                    taken_breaks.extend(taken);
+
+                    current_time += leg_duration;
+                    let arrival_time = current_time;
+                    arrival_times.push(arrival_time);
+
+                    // The last leg leads to the end location
+                    let Some(task) = stops.get(i) else {
+                        departure_times.push(current_time);
+                        break;
+                    };
+
+                    // Check if we need to wait for a time window
+                    let mut service_start_time = arrival_time;
+
+                    if let Some(time_windows) = &task.time_windows {
+                        for window in time_windows {
+                            if arrival_time <= window[1] {
+                                // We can arrive before the window ends
+                                if arrival_time < window[0] {
+                                    // Need to wait until window starts
+                                    service_start_time = window[0];
+                                }
+                                break;
+                            }
+                        }
+                    }
+
+                    // Setting up is only needed when arriving from another location
+                    let setup = if location == Some(task.location) { 0 } else { task.setup };
+                    location = Some(task.location);
+                    setup_times.push(setup);
+                    let service = vehicle.service_duration(task.service);
+
+                    // Update current time to account for possible waiting, setup and service time
+                    current_time = service_start_time + (setup + service) as i64;
+                    waiting_time += service_start_time - arrival_time;
+                    service_time += (setup + service) as i64;
+
+                    departure_times.push(current_time);
+                }
+
+                // Traffic makes travel take longer than the static route duration
+                let duration = if self.config.traffic.is_empty() {
+                    duration
+                } else {
+                    travelled
+                };
+
+                let schedule = Schedule {
+                    arrival_times,
+                    departure_times,
+                    setup_times,
+                    travel_duration: travelled,
+                    travel_distance: distance,
+                    load_profile: solver::load_profile(&stops, vehicle.capacity.len(), &[]),
+                    breaks: taken_breaks,
+                    reloads: Vec::new(),
+                    lateness: 0,
+                    late_tasks: 0,
+                    waiting_time,
+                    service_time,
+                    rests,
+                    shift,
+                };
+
+                // Create steps for the route
+                let service_after = match steps.first() {
+                    Some(RouteStep::Start { service_after, .. }) => *service_after,
+                    _ => None,
+                };
+                let route_steps = route_steps(vehicle, service_after, &stops, &schedule);
+
+                // Create vehicle route
+                let vehicle_route = VehicleRoute {
+                    vehicle_id: vehicle.id,
+                    route: job_ids(&stops),
+                    steps: route_steps,
+                    distance: distance as u32,
+                    duration: duration as u32,
+                    arrival_times: schedule.arrival_times,
+                    departure_times: schedule.departure_times,
+                    load_profile: schedule.load_profile,
+                    polyline,
+                    geojson: None,
+                    violations: Vec::new(),
+                    optimized_departure: None,
+                    shift: schedule.shift,
+                    waiting_time: schedule.waiting_time,
+                    service_time: schedule.service_time,
+                    cost_breakdown: CostBreakdown::default(),
+                    legs: route_legs,
+                    description: None,
+                    metadata: None,
+                };
+
+                routes.push(vehicle_route);
+            }
+        }
+
+        Ok(routes)
+    }
+
+    /// Optimize routes for the given request
+    async fn optimize_routes(
+        &self,
+        request: &RoutingRequest,
+        profile: &str,
+        deadline: Instant,
+        include_geometry: bool,
+        previous: Previous<'_>,
+        progress: ProgressCallback,
+    ) -> Result<(
+        Vec<VehicleRoute>,
+        UnassignedDiagnosis,
+        Vec<AlternativeSolution>,
+        Option<f64>,
+    )> {
+        let provided_matrix = client_matrix(request)?;
+        let has_client_matrix = provided_matrix.is_some();
+        let (locations, mut matrix) = match provided_matrix {
+            Some(provided) => provided,
+            None => {
+                // Collect all unique locations
+                let locations =
+                    LocationIndex::build(&request.vehicles, &request.jobs, &request.shipments);
+
+                // Get distance/duration matrix from the routing engine
+                let matrix = self
+                    .cached_matrix(locations.locations(), profile, excluded_roads(request))
+                    .await?;
+
+                (locations, matrix)
+            }
+        };
+
+        avoid_polygons(&mut matrix, locations.locations(), avoided_polygons(request));
+
+        // Locations the engine could not place on the road network cannot be visited
+        let unreachable = unreachable_locations(&matrix, self.settings().max_snap_distance);
+        if !unreachable.is_empty() {
+            warn!(
+                "{} location(s) are unreachable on the road network",
+                unreachable.len()
+            );
+        }
+        let is_unreachable = |location: [f64; 2]| {
+            locations
+                .get(location)
+                .is_some_and(|idx| unreachable.contains(&idx))
+        };
+
+        let mut problem = Problem::new(
+            request.vehicles.clone(),
+            request.jobs.clone(),
+            request.shipments.clone(),
+            &locations,
+            matrix,
+        )
+        .with_depot_stock(&request.depots);
+        if let Some(balance) = request.options.as_ref().and_then(|o| o.balance) {
+            problem = problem.with_balance(balance);
+        }
+        if let Some(soft) = soft_time_windows(request, &self.settings()) {
+            problem = problem.with_soft_time_windows(soft);
+        }
+        if let Some(order) = request.options.as_ref().and_then(|o| o.load_order) {
+            problem = problem.with_load_order(order);
+        }
+        if let Some(penalty) = request.options.as_ref().and_then(|o| o.unassigned_penalty) {
+            problem = problem.with_unassigned_penalty(penalty);
+        }
+        if let Some(construction) = request.options.as_ref().and_then(|o| o.construction) {
+            problem = problem.with_construction(construction);
+        }
+        if request
+            .options
+            .as_ref()
+            .and_then(|o| o.consecutive_groups)
+            .unwrap_or(false)
+        {
+            problem = problem.with_consecutive_groups();
+        }
+        if let Some(policy) = request.options.as_ref().and_then(|o| o.priority_policy) {
+            problem = problem.with_priority_policy(policy);
+        }
+        if let Some(algorithm) = request.options.as_ref().and_then(|o| o.algorithm) {
+            problem = problem.with_algorithm(algorithm);
+        }
+        if let Some(lns) = request.options.as_ref().and_then(|o| o.lns) {
+            problem = problem.with_lns(lns);
+        }
+        if let Some(seed) = request.options.as_ref().and_then(|o| o.seed) {
+            problem = problem.with_seed(seed);
+        }
+        for relation in &request.relations {
+            let tasks = relation
+                .steps
+                .iter()
+                .filter_map(|step| relation_task(&problem.tasks, step))
+                .collect();
+            problem = problem.with_relation(relation.kind, tasks);
+        }
+        if !self.config.traffic.is_empty() {
+            problem = problem.with_traffic(self.config.traffic.clone());
+        }
+        if !unreachable.is_empty() {
+            problem = problem.with_unreachable(&unreachable);
+        }
+        problem = problem.with_time_limit(deadline.saturating_duration_since(Instant::now()));
+        let size = request.jobs.len() + request.shipments.len();
+        if self
+            .settings()
+            .quick_search_above
+            .is_some_and(|threshold| size > threshold)
+        {
+            info!("Using a quick search for {} jobs and shipments", size);
+            problem = problem.with_quick_search();
+        }
+
+        // Vehicles with their own profile travel by that profile's matrix, unless the
+        // client provided the only matrix to use
+        if !has_client_matrix {
+            let mut profiles: Vec<&str> = Vec::new();
+            for vehicle in &request.vehicles {
+                let vehicle_profile = vehicle_profile(vehicle, profile);
+                if vehicle_profile != profile && !profiles.contains(&vehicle_profile) {
+                    profiles.push(vehicle_profile);
+                }
+            }
+
+            for other in profiles {
+                let mut matrix = self
+                    .cached_matrix(locations.locations(), other, excluded_roads(request))
+                    .await?;
+                avoid_polygons(&mut matrix, locations.locations(), avoided_polygons(request));
+                let vehicles: Vec<usize> = request
+                    .vehicles
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, vehicle)| vehicle_profile(vehicle, profile) == other)
+                    .map(|(idx, _)| idx)
+                    .collect();
+
+                problem = problem.with_vehicle_matrix(matrix, &vehicles);
+            }
+        }
+
+        // Keep the locked start of previous routes as it was planned
+        for route in previous.locked {
+            let Some(vehicle) = request
+                .vehicles
+                .iter()
+                .position(|vehicle| vehicle.id == route.vehicle_id)
+            else {
+                bail!(
+                    "Locked route refers to unknown vehicle {}",
+                    route.vehicle_id
+                );
+            };
+
+            let tasks = locked_tasks(&problem.tasks, route)?;
+            if tasks.is_empty() {
+                continue;
+            }
+            if problem.schedule(vehicle, &tasks).is_none() {
+                bail!(
+                    "The locked part of the route of vehicle {} is no longer feasible",
+                    route.vehicle_id
+                );
+            }
+            problem = problem.with_locked_route(vehicle, tasks);
+        }
+
+        // Start from the routes of an earlier solution, if given
+        for route in &request.initial_solution {
+            let Some(vehicle) = request
+                .vehicles
+                .iter()
+                .position(|vehicle| vehicle.id == route.vehicle_id)
+            else {
+                continue;
+            };
+            let tasks = route
+                .steps
+                .iter()
+                .filter_map(|step| task_index(&problem.tasks, step))
+                .collect();
+            problem = problem.with_initial_route(vehicle, tasks);
+        }
+
+        // Only the new jobs and shipments go into the initial routes
+        if let Some(insertion) = previous.insertion {
+            let tasks = problem
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| match task.kind {
+                    TaskKind::Job(_) => insertion.jobs.iter().any(|job| job.id == task.id),
+                    _ => insertion
+                        .shipments
+                        .iter()
+                        .any(|shipment| shipment.id == task.id),
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+            problem = problem.with_insertion_only(tasks);
+
+            let repaired = request
+                .vehicles
+                .iter()
+                .enumerate()
+                .filter(|(_, vehicle)| previous.repaired.contains(&vehicle.id))
+                .map(|(idx, _)| idx)
+                .collect();
+            problem = problem.with_repaired_routes(repaired);
+        }
+        let problem = Arc::new(problem);
+
+        // Requests can use fewer threads than configured, not more
+        let default_threads = self.settings().default_threads;
+        let threads = request
+            .options
+            .as_ref()
+            .and_then(|o| o.threads)
+            .map_or(default_threads, |threads| threads.min(default_threads));
+
+        let strategy = request
+            .options
+            .as_ref()
+            .and_then(|o| o.strategy)
+            .unwrap_or_default();
+        let cluster_size = self.settings().cluster_size;
+        let alternatives = request
+            .options
+            .as_ref()
+            .and_then(|o| o.alternatives)
+            .unwrap_or(0) as usize;
+
+        // Run the solver instances and the lower bound off the async runtime
+        let solver_problem = Arc::clone(&problem);
+        let solve_start = Instant::now();
+        let penalty = unassigned_cost(request);
+        let (solution, runners_up, lower_bound) = tokio::task::spawn_blocking(move || {
+            let (solution, runners_up) = match strategy {
+                SolveStrategy::Direct => {
+                    let report = |solution: &Solution| {
+                        progress(solution_progress(&solver_problem, solution, solve_start))
+                    };
+                    if alternatives > 0 {
+                        solver::solve_alternatives(
+                            &solver_problem,
+                            threads as usize,
+                            alternatives,
+                            &report,
+                        )
+                    } else {
+                        let solution =
+                            solver::solve_with_progress(&solver_problem, threads as usize, &report);
+                        (solution, Vec::new())
+                    }
+                }
+                // Clusters are merged at the end, so only the final solution is reported and
+                // there are no runners-up
+                SolveStrategy::Cluster => {
+                    let solution =
+                        solver::solve_clustered(&solver_problem, threads as usize, cluster_size);
+                    progress(solution_progress(&solver_problem, &solution, solve_start));
+                    (solution, Vec::new())
+                }
+            };
+            let lower_bound = solver_problem.lower_bound(penalty);
+            (solution, runners_up, lower_bound)
+        })
+        .await?;
+        let soft = soft_time_windows(request, &self.settings());
+        let alternatives = runners_up
+            .iter()
+            .map(|solution| alternative_solution(request, &problem, solution, soft.as_ref()))
+            .collect();
+
+        // Explain why the solver left jobs and shipments unassigned
+        let no_vehicle_reachable = request.vehicles.iter().all(|vehicle| {
+            vehicle
+                .start
+                .iter()
+                .chain(&vehicle.end)
+                .any(|location| is_unreachable(*location))
+        });
+        let mut diagnosis = UnassignedDiagnosis {
+            stock_outs: problem.stock_outs(&solution),
+            ..Default::default()
+        };
+        for &task in &solution.unassigned {
+            let id = problem.tasks[task].id;
+            let (noun, locations) = match problem.tasks[task].kind {
+                TaskKind::Job(job) => ("job", vec![problem.jobs[job].location]),
+                TaskKind::Pickup(shipment) => {
+                    let shipment = &problem.shipments[shipment];
+                    (
+                        "shipment",
+                        vec![shipment.pickup.location, shipment.delivery.location],
+                    )
+                }
+                TaskKind::Delivery(_) => continue,
+            };
+
+            let reason = if let Some(location) = locations
+                .into_iter()
+                .find(|location| is_unreachable(*location))
+            {
+                unassigned_job(
+                    id,
+                    UnassignedCode::Unreachable,
+                    format!(
+                        "Location {:?} of the {} is not reachable on the road network",
+                        location, noun
+                    ),
+                )
+            } else if no_vehicle_reachable {
+                unassigned_job(
+                    id,
+                    UnassignedCode::Unreachable,
+                    "No vehicle location is reachable on the road network",
+                )
+            } else {
+                diagnose(&problem, &solution, task, noun)
+            };
+
+            match problem.tasks[task].kind {
+                TaskKind::Job(_) => diagnosis.jobs.insert(id, reason),
+                _ => diagnosis.shipments.insert(id, reason),
+            };
+        }
+
+        let optimize_departure = request
+            .options
+            .as_ref()
+            .and_then(|o| o.optimize_departure)
+            .unwrap_or(false);
+
+        let mut routes = Vec::new();
+
+        for solved_route in solution.routes {
+            let vehicle = &request.vehicles[solved_route.vehicle];
+            let stops: Vec<&Task> = solved_route
+                .tasks
+                .iter()
+                .map(|task_idx| &problem.tasks[*task_idx])
+                .collect();
+
+            // Leave later rather than wait at the first time window, unless the route was
+            // already dispatched
+            let mut schedule = solved_route.schedule;
+            let mut optimized_departure = None;
+            let dispatched = previous.locked.iter().any(|route| route.vehicle_id == vehicle.id);
+            if optimize_departure && !dispatched {
+                if let Some((departure, delayed)) =
+                    problem.delay_departure(solved_route.vehicle, &solved_route.tasks, &schedule)
+                {
+                    optimized_departure = Some(departure);
+                    schedule = delayed;
+                }
+            }
+
+            // Calculate route, including the returns to the start location to reload
+            let mut coordinates = Vec::new();
+            coordinates.extend(vehicle.start);
+
+            for (position, task) in stops.iter().enumerate() {
+                if schedule
+                    .reloads
+                    .iter()
+                    .any(|reload| reload.after == position)
+                {
+                    coordinates.extend(vehicle.start);
+                }
+                coordinates.push(task.location);
+            }
+
+            coordinates.extend(vehicle.end);
+
+            // Without OSRM the route totals come from the client matrix, and a lone stop
+            // on a route without start or end has nothing to travel
+            let (distance, mut duration, polyline, route_legs) =
+                if request.matrix.is_some() || coordinates.len() < 2 {
+                    (schedule.travel_distance, schedule.travel_duration, None, Vec::new())
+                } else {
+                    let engine_route = self
+                        .engine
+                        .route(
+                            &coordinates,
+                            vehicle_profile(vehicle, profile),
+                            excluded_roads(request),
+                            include_geometry,
+                            include_instructions(request),
+                        )
+                        .await?;
+
+                    let Some(engine_route) = engine_route else {
+                        warn!("No route found for vehicle {}", vehicle.id);
+                        continue;
+                    };
+
+                    let route_legs = instructed_legs(request, &engine_route);
+                    (
+                        engine_route.distance,
+                        engine_route.duration,
+                        engine_route.geometry,
+                        route_legs,
+                    )
+                };
+
+            // Traffic makes travel take longer than the static route duration
+            if !self.config.traffic.is_empty() {
+                duration = schedule.travel_duration;
+            }
+
+            // Create steps for the route, starting no earlier than the delayed departure
+            let shift_window = vehicle.shift_window(schedule.shift);
+            let route_steps = route_steps(
+                vehicle,
+                optimized_departure.or(shift_window.map(|time_window| time_window[0])),
+                &stops,
+                &schedule,
+            );
+
+            // Create vehicle route
+            let mut vehicle_route = VehicleRoute {
+                vehicle_id: vehicle.id,
+                route: job_ids(&stops),
+                steps: route_steps,
+                distance: distance as u32,
+                duration: duration as u32,
+                arrival_times: schedule.arrival_times,
+                departure_times: schedule.departure_times,
+                load_profile: schedule.load_profile,
+                polyline,
+                geojson: None,
+                violations: Vec::new(),
+                optimized_departure,
+                shift: schedule.shift,
+                waiting_time: schedule.waiting_time,
+                service_time: schedule.service_time,
+                cost_breakdown: CostBreakdown::default(),
+                legs: route_legs,
+                description: None,
+                metadata: None,
+            };
+            add_snapped_locations(
+                &mut vehicle_route,
+                &locations,
+                problem.matrix_for(solved_route.vehicle),
+            );
+
+            routes.push(vehicle_route);
+        }
+
+        Ok((routes, diagnosis, alternatives, Some(lower_bound)))
+    }
+}
+
+/// Describe a runner-up solution, with its routes timed by the travel matrix
+///
+/// The cost is priced like the summary of the best solution.
+fn alternative_solution(
+    request: &RoutingRequest,
+    problem: &Problem,
+    solution: &Solution,
+    soft: Option<&solver::SoftTimeWindows>,
+) -> AlternativeSolution {
+    let mut cost = 0.0;
+
+    let mut routes = Vec::new();
+    for solved_route in &solution.routes {
+        let vehicle = &request.vehicles[solved_route.vehicle];
+        let stops: Vec<&Task> = solved_route
+            .tasks
+            .iter()
+            .map(|task_idx| &problem.tasks[*task_idx])
+            .collect();
+        let schedule = &solved_route.schedule;
+
+        let mut route = VehicleRoute {
+            vehicle_id: vehicle.id,
+            route: job_ids(&stops),
+            steps: route_steps(
+                vehicle,
+                vehicle
+                    .shift_window(schedule.shift)
+                    .map(|time_window| time_window[0]),
+                &stops,
+                schedule,
+            ),
+            distance: schedule.travel_distance as u32,
+            duration: schedule.travel_duration as u32,
+            arrival_times: schedule.arrival_times.clone(),
+            departure_times: schedule.departure_times.clone(),
+            load_profile: schedule.load_profile.clone(),
+            polyline: None,
+            geojson: None,
+            violations: Vec::new(),
+            optimized_departure: None,
+            shift: schedule.shift,
+            waiting_time: schedule.waiting_time,
+            service_time: schedule.service_time,
+            cost_breakdown: CostBreakdown::default(),
+            legs: Vec::new(),
+            description: None,
+            metadata: None,
+        };
+        record_violations(&mut route, vehicle, &problem.tasks);
+        add_metadata(std::slice::from_mut(&mut route), request);
+
+        route.cost_breakdown = route_cost(vehicle, &route, soft);
+        cost += route.cost_breakdown.total();
+        routes.push(route);
+    }
+
+    let mut unassigned = Vec::new();
+    let mut unassigned_shipments = Vec::new();
+    for &task in &solution.unassigned {
+        let priority = match problem.tasks[task].kind {
+            TaskKind::Job(job) => {
+                unassigned.push(problem.jobs[job].id);
+                problem.jobs[job].priority
+            }
+            TaskKind::Pickup(shipment) => {
+                unassigned_shipments.push(problem.shipments[shipment].id);
+                problem.shipments[shipment].priority
+            }
+            TaskKind::Delivery(_) => continue,
+        };
+        cost += unassigned_cost(request) * (1.0 + priority.unwrap_or(0) as f64);
+    }
+
+    AlternativeSolution {
+        cost,
+        distance: routes.iter().map(|route| route.distance as u64).sum(),
+        duration: routes.iter().map(|route| route.duration as u64).sum(),
+        routes,
+        unassigned,
+        unassigned_shipments,
+    }
+}
+
+/// Reasons the solver found for leaving jobs and shipments unassigned, by ID
+#[derive(Default)]
+struct UnassignedDiagnosis {
+    jobs: HashMap<u64, UnassignedJob>,
+    shipments: HashMap<u64, UnassignedJob>,
+    stock_outs: Vec<StockOut>,
+}
+
+/// Explain why the solver left the job or shipment of `task` unassigned
+///
+/// Route limits are named when they are all that keeps it out of the routes. Otherwise it
+/// either does not fit any vehicle, cannot be reached by any vehicle, cannot be served in
+/// time even on an otherwise empty route, finds its depots out of stock, or lost out to
+/// other work.
+fn diagnose(problem: &Problem, solution: &Solution, task: usize, noun: &str) -> UnassignedJob {
+    let id = problem.tasks[task].id;
+    let vehicles = 0..problem.vehicles.len();
+
+    let limits = problem.blocking_limits(solution, task);
+    if !limits.is_empty() {
+        let names: Vec<&str> = limits.iter().map(|limit| limit.as_str()).collect();
+        return unassigned_job(
+            id,
+            UnassignedCode::RouteLimit,
+            format!(
+                "Serving the {} exceeds vehicle limits: {}",
+                noun,
+                names.join(", ")
+            ),
+        );
+    }
+
+    if !vehicles
+        .clone()
+        .any(|vehicle| problem.fits_capacity(vehicle, task))
+    {
+        return unassigned_job(
+            id,
+            UnassignedCode::Capacity,
+            format!("The {} exceeds the capacity of every vehicle", noun),
+        );
+    }
+
+    // A shipment's delivery directly follows its pickup
+    let reaches = |vehicle: usize| {
+        problem.reaches(vehicle, task)
+            && (!matches!(problem.tasks[task].kind, TaskKind::Pickup(_))
+                || problem.reaches(vehicle, task + 1))
+    };
+    if !vehicles.clone().any(reaches) {
+        return unassigned_job(
+            id,
+            UnassignedCode::Unreachable,
+            format!("No vehicle can reach the {} on the road network", noun),
+        );
+    }
+
+    if !vehicles
+        .clone()
+        .any(|vehicle| problem.serves_alone(vehicle, task))
+    {
+        return unassigned_job(
+            id,
+            UnassignedCode::TimeWindow,
+            format!("No vehicle can serve the {} within the time windows", noun),
+        );
+    }
+
+    if problem.out_of_stock(solution, task) {
+        return unassigned_job(
+            id,
+            UnassignedCode::StockOut,
+            format!("The depots that could supply the {} are out of stock", noun),
+        );
+    }
+
+    unassigned_job(
+        id,
+        UnassignedCode::NoVehicleAvailable,
+        format!(
+            "Every vehicle that could serve the {} is busy with other work",
+            noun
+        ),
+    )
+}
+
+fn unassigned_job(id: u64, code: UnassignedCode, description: impl Into<String>) -> UnassignedJob {
+    UnassignedJob {
+        id,
+        code,
+        description: description.into(),
+    }
+}
+
+fn missing_skills(id: u64, skills: Option<&[String]>) -> UnassignedJob {
+    unassigned_job(
+        id,
+        UnassignedCode::Skills,
+        format!(
+            "No vehicle has the required skills: {}",
+            skills.unwrap_or_default().join(", ")
+        ),
+    )
+}
+
+fn incompatible_load(id: u64, load_types: Option<&[String]>) -> UnassignedJob {
+    unassigned_job(
+        id,
+        UnassignedCode::Compatibility,
+        format!(
+            "No vehicle can carry the load types: {}",
+            load_types.unwrap_or_default().join(", ")
+        ),
+    )
+}
+
+fn out_of_zone(id: u64, noun: &str) -> UnassignedJob {
+    unassigned_job(
+        id,
+        UnassignedCode::Zone,
+        format!("The {} lies outside the zones of every vehicle", noun),
+    )
+}
+
+fn not_in_route(id: u64, noun: &str) -> UnassignedJob {
+    unassigned_job(
+        id,
+        UnassignedCode::NotInRoute,
+        format!("The {} is not part of any route", noun),
+    )
+}
+
+/// Routing profile of a vehicle, falling back to the profile of the request
+fn vehicle_profile<'a>(vehicle: &'a Vehicle, request_profile: &'a str) -> &'a str {
+    vehicle.profile.as_deref().unwrap_or(request_profile)
+}
+
+/// Location index and solver matrix for the cost matrix provided with a request, if any
+///
+/// The matrix must be square over its locations and cover every location in the request.
+fn client_matrix(request: &RoutingRequest) -> Result<Option<(LocationIndex, Matrix)>> {
+    let Some(cost_matrix) = &request.matrix else {
+        return Ok(None);
+    };
+    let size = cost_matrix.locations.len();
+
+    let mut locations = LocationIndex::default();
+    for (idx, location) in cost_matrix.locations.iter().enumerate() {
+        if locations.insert(*location) != idx {
+            bail!("Matrix location {:?} is listed more than once", location);
+        }
+    }
+
+    let square = |rows: &[Vec<f64>]| rows.len() == size && rows.iter().all(|row| row.len() == size);
+    if !square(&cost_matrix.durations) {
+        bail!("Matrix durations must be a {}x{} table", size, size);
+    }
+    if let Some(distances) = &cost_matrix.distances {
+        if !square(distances) {
+            bail!("Matrix distances must be a {}x{} table", size, size);
+        }
+    }
+
+    let required = LocationIndex::build(&request.vehicles, &request.jobs, &request.shipments);
+    if let Some(missing) = required
+        .locations()
+        .iter()
+        .find(|location| locations.get(**location).is_none())
+    {
+        bail!("Location {:?} is missing from the matrix", missing);
+    }
+
+    Ok(Some((
+        locations,
+        Matrix {
+            durations: cost_matrix.durations.clone(),
+            distances: cost_matrix.distances.clone(),
+            snapped: None,
+        },
+    )))
+}
+
+/// Locations that cannot be visited on the road network of `matrix`
+///
+/// A location is unreachable when the engine had to move it more than `max_snap_distance`
+/// meters to reach a road, or when no other location has a finite travel time to or from it.
+fn unreachable_locations(matrix: &Matrix, max_snap_distance: f64) -> HashSet<usize> {
+    let size = matrix.durations.len();
+    let cut_off = |location: usize| {
+        let mut others = (0..size).filter(|other| *other != location);
+        let no_way_out = others
+            .clone()
+            .all(|other| !matrix.durations[location][other].is_finite());
+        let no_way_in = others.all(|other| !matrix.durations[other][location].is_finite());
+        size > 1 && (no_way_out || no_way_in)
+    };
+
+    (0..size)
+        .filter(|location| {
+            matrix
+                .snapped
+                .as_ref()
+                .is_some_and(|snapped| snapped[*location].distance > max_snap_distance)
+                || cut_off(*location)
+        })
+        .collect()
+}
+
+/// Summarize an intermediate solver solution for progress reporting
+fn solution_progress(
+    problem: &Problem,
+    solution: &Solution,
+    solve_start: Instant,
+) -> OptimizationProgress {
+    let routes = solution
+        .routes
+        .iter()
+        .map(|solved_route| {
+            let vehicle = &problem.vehicles[solved_route.vehicle];
+            let stops: Vec<&Task> = solved_route
+                .tasks
+                .iter()
+                .map(|task_idx| &problem.tasks[*task_idx])
+                .collect();
+            let schedule = &solved_route.schedule;
+
+            ProgressRoute {
+                vehicle_id: vehicle.id,
+                route: job_ids(&stops),
+                steps: route_steps(
+                    vehicle,
+                    vehicle
+                        .shift_window(schedule.shift)
+                        .map(|time_window| time_window[0]),
+                    &stops,
+                    schedule,
+                ),
+            }
+        })
+        .collect();
+
+    // Shipments count once, by their pickup
+    let unassigned = solution
+        .unassigned
+        .iter()
+        .filter(|task| !matches!(problem.tasks[**task].kind, TaskKind::Delivery(_)))
+        .count();
+
+    OptimizationProgress {
+        cost: solution.cost,
+        routes,
+        unassigned: unassigned as u32,
+        computing_time: solve_start.elapsed().as_millis() as u64,
+    }
+}
+
+/// Build the steps of a route: start, the stops with breaks, rests and reloads in between,
+/// and end
+///
+/// The schedule's arrival and departure times cover the start, each stop and the end.
+/// Without a start or end location the route starts at its first stop or ends at its last.
+fn route_steps(
+    vehicle: &Vehicle,
+    service_after: Option<i64>,
+    stops: &[&Task],
+    schedule: &Schedule,
+) -> Vec<RouteStep> {
+    let (arrival_times, departure_times) = (&schedule.arrival_times, &schedule.departure_times);
+    let start = vehicle.start.or(stops.first().map(|task| task.location));
+    let end = vehicle.end.or(stops.last().map(|task| task.location));
+
+    let mut route_steps = vec![RouteStep::Start {
+        service_after,
+        location: start,
+        arrival_time: arrival_times.first().cloned(),
+        departure_time: departure_times.first().cloned(),
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+    }];
+
+    let vehicle_breaks = vehicle.breaks.as_deref().unwrap_or(&[]);
+    let rest_duration = vehicle.working_rules.map(|rules| rules.rest_duration);
+    for position in 0..=stops.len() {
+        // Breaks and rests are taken at the previous stop, or at the start location
+        // once the vehicle has returned there to reload
+        let previous = match position {
+            0 => start,
+            _ => Some(stops[position - 1].location),
+        };
+        let reload = schedule
+            .reloads
+            .iter()
+            .find(|reload| reload.after == position);
+        let location = |arrival_time: i64| match reload {
+            Some(reload) if reload.arrival_time <= arrival_time => vehicle.start,
+            _ => previous,
+        };
+
+        // Everything between two stops happens in time order, a reload first on ties
+        let mut pauses: Vec<RouteStep> = reload
+            .map(|reload| reload_step(vehicle, reload))
+            .into_iter()
+            .chain(
+                schedule
+                    .breaks
+                    .iter()
+                    .filter(|taken| taken.after == position)
+                    .map(|taken| RouteStep::Break {
+                        id: vehicle_breaks[taken.index].id,
+                        location: location(taken.arrival_time),
+                        service: Some(vehicle_breaks[taken.index].service),
+                        arrival_time: Some(taken.arrival_time),
+                        departure_time: Some(taken.departure_time),
+                        arrival_timestamp: None,
+                        departure_timestamp: None,
+                        violations: Vec::new(),
+                    }),
+            )
+            .chain(
+                schedule
+                    .rests
+                    .iter()
+                    .filter(|rest| rest.after == position)
+                    .map(|rest| RouteStep::Rest {
+                        location: if rest.en_route {
+                            None
+                        } else {
+                            location(rest.arrival_time)
+                        },
+                        service: rest_duration,
+                        arrival_time: Some(rest.arrival_time),
+                        departure_time: Some(rest.departure_time),
+                        arrival_timestamp: None,
+                        departure_timestamp: None,
+                        violations: Vec::new(),
+                    }),
+            )
+            .collect();
+        pauses.sort_by_key(step_arrival_time);
+        route_steps.extend(pauses);
+
+        if let Some(task) = stops.get(position) {
+            route_steps.push(task_step(
+                task,
+                vehicle,
+                arrival_times[position + 1],
+                departure_times[position + 1],
+                schedule.setup_times[position],
+            ));
+        }
+    }
+
+    route_steps.push(RouteStep::End {
+        location: end,
+        arrival_time: arrival_times.last().cloned(),
+        departure_time: departure_times.last().cloned(),
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+    });
+
+    route_steps
+}
+
+/// Build the response step for a return to the start location to reload
+fn reload_step(vehicle: &Vehicle, reload: &ReloadStop) -> RouteStep {
+    RouteStep::Reload {
+        location: vehicle.start,
+        service: Some(vehicle.reload_service.unwrap_or(0)),
+        arrival_time: Some(reload.arrival_time),
+        departure_time: Some(reload.departure_time),
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+    }
+}
+
+/// Build the response step for a job or shipment task
+fn task_step(
+    task: &Task,
+    vehicle: &Vehicle,
+    arrival_time: i64,
+    departure_time: i64,
+    setup: u32,
+) -> RouteStep {
+    let service = vehicle.service_duration(task.service);
+    let busy = (setup + service) as i64;
+    let (id, location, service) = (task.id, Some(task.location), Some(service));
+    let waiting_time = Some((departure_time - arrival_time - busy).max(0));
+    let (arrival_time, departure_time) = (Some(arrival_time), Some(departure_time));
+
+    match task.kind {
+        TaskKind::Job(_) => RouteStep::Job {
+            id,
+            location,
+            snapped_location: None,
+            snap_distance: None,
+            setup: (task.setup > 0).then_some(setup),
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            polyline: None,
+            arrival_timestamp: None,
+            departure_timestamp: None,
+            violations: Vec::new(),
+            description: None,
+            metadata: None,
+        },
+        TaskKind::Pickup(_) => RouteStep::Pickup {
+            id,
+            location,
+            snapped_location: None,
+            snap_distance: None,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            polyline: None,
+            arrival_timestamp: None,
+            departure_timestamp: None,
+            violations: Vec::new(),
+            description: None,
+            metadata: None,
+        },
+        TaskKind::Delivery(_) => RouteStep::Delivery {
+            id,
+            location,
+            snapped_location: None,
+            snap_distance: None,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            polyline: None,
+            arrival_timestamp: None,
+            departure_timestamp: None,
+            violations: Vec::new(),
+            description: None,
+            metadata: None,
+        },
+    }
+}
+
+/// Give the job, pickup and delivery steps of a route the locations the routing engine
+/// moved them to on the road network, when it reports them
+fn add_snapped_locations(route: &mut VehicleRoute, locations: &LocationIndex, matrix: &Matrix) {
+    let Some(snapped) = &matrix.snapped else {
+        return;
+    };
+
+    for step in &mut route.steps {
+        if let RouteStep::Job {
+            location: Some(location),
+            snapped_location,
+            snap_distance,
+            ..
+        }
+        | RouteStep::Pickup {
+            location: Some(location),
+            snapped_location,
+            snap_distance,
+            ..
+        }
+        | RouteStep::Delivery {
+            location: Some(location),
+            snapped_location,
+            snap_distance,
+            ..
+        } = step
+        {
+            if let Some(snap) = locations.get(*location).map(|idx| snapped[idx]) {
+                *snapped_location = Some(snap.location);
+                *snap_distance = Some(snap.distance.round() as u32);
+            }
+        }
+    }
+}
+
+/// Give every step the vehicle travels to the encoded polyline of the leg leading to it
+fn add_leg_polylines(route: &mut VehicleRoute) -> Result<()> {
+    let Some(encoded) = &route.polyline else {
+        return Ok(());
+    };
+
+    let line = polyline::decode(encoded, 5)?;
+    let waypoints: Vec<[f64; 2]> = route.steps.iter().filter_map(travel_location).collect();
+    let mut legs = polyline::split(&line, &waypoints).into_iter();
+
+    // The first waypoint has no leg leading to it
+    let travelled = route
+        .steps
+        .iter_mut()
+        .filter(|step| travel_location(step).is_some());
+    for step in travelled.skip(1) {
+        let leg = legs.next().map(|leg| polyline::encode(&leg, 5));
+        if let RouteStep::Job { polyline, .. }
+        | RouteStep::Pickup { polyline, .. }
+        | RouteStep::Delivery { polyline, .. }
+        | RouteStep::Reload { polyline, .. }
+        | RouteStep::End { polyline, .. } = step
+        {
+            *polyline = leg;
+        }
+    }
+
+    Ok(())
+}
+
+/// Location of a step the vehicle travels to or from, leaving out breaks and rests
+fn travel_location(step: &RouteStep) -> Option<[f64; 2]> {
+    match step {
+        RouteStep::Start { location, .. }
+        | RouteStep::Job { location, .. }
+        | RouteStep::Pickup { location, .. }
+        | RouteStep::Delivery { location, .. }
+        | RouteStep::Reload { location, .. }
+        | RouteStep::End { location, .. } => *location,
+        RouteStep::Break { .. } | RouteStep::Rest { .. } => None,
+    }
+}
+
+/// ID referenced by a job or shipment step
+fn step_id(step: &RouteStep) -> Option<u64> {
+    match step {
+        RouteStep::Job { id, .. }
+        | RouteStep::Pickup { id, .. }
+        | RouteStep::Delivery { id, .. } => Some(*id),
+        _ => None,
+    }
+}
+
+/// Find the task a job or shipment step refers to
+fn find_task<'a>(tasks: &'a [Task], step: &RouteStep) -> Option<&'a Task> {
+    task_index(tasks, step).map(|idx| &tasks[idx])
+}
+
+/// Index of the task a job or shipment step refers to
+fn task_index(tasks: &[Task], step: &RouteStep) -> Option<usize> {
+    let id = step_id(step)?;
+    tasks.iter().position(|task| {
+        task.id == id
+            && matches!(
+                (step, task.kind),
+                (RouteStep::Job { .. }, TaskKind::Job(_))
+                    | (RouteStep::Pickup { .. }, TaskKind::Pickup(_))
+                    | (RouteStep::Delivery { .. }, TaskKind::Delivery(_))
+            )
+    })
+}
+
+/// Index of the task a relation step refers to
+fn relation_task(tasks: &[Task], step: &RelationStep) -> Option<usize> {
+    tasks.iter().position(|task| {
+        task.id == step.id()
+            && matches!(
+                (step, task.kind),
+                (RelationStep::Job { .. }, TaskKind::Job(_))
+                    | (RelationStep::Pickup { .. }, TaskKind::Pickup(_))
+                    | (RelationStep::Delivery { .. }, TaskKind::Delivery(_))
+            )
+    })
+}
+
+/// Indices of the tasks in the locked start of a previous route
+///
+/// Steps of cancelled jobs and shipments are skipped. The locked start runs up to the last
+/// locked step, extended to the delivery of every shipment picked up in it.
+fn locked_tasks(tasks: &[Task], route: &LockedRoute) -> Result<Vec<usize>> {
+    let steps: Vec<(usize, bool)> = route
+        .steps
+        .iter()
+        .filter_map(|step| task_index(tasks, &step.step).map(|idx| (idx, step.locked)))
+        .collect();
+    let indices: Vec<usize> = steps.iter().map(|(idx, _)| *idx).collect();
+
+    let mut end = if route.locked {
+        indices.len()
+    } else {
+        steps
+            .iter()
+            .rposition(|(_, locked)| *locked)
+            .map_or(0, |position| position + 1)
+    };
+
+    loop {
+        let locked = &indices[..end];
+        let open_pickup = locked.iter().copied().find(|idx| {
+            matches!(tasks[*idx].kind, TaskKind::Pickup(_)) && !locked.contains(&(idx + 1))
+        });
+        let Some(pickup) = open_pickup else {
+            break;
+        };
+
+        match indices.iter().position(|idx| *idx == pickup + 1) {
+            Some(delivery) => end = delivery + 1,
+            None => bail!(
+                "Shipment {} is picked up by vehicle {} but never delivered",
+                tasks[pickup].id,
+                route.vehicle_id
+            ),
+        }
+    }
+
+    Ok(indices[..end].to_vec())
+}
+
+/// IDs of the standalone jobs among the stops of a route
+fn job_ids(stops: &[&Task]) -> Vec<u64> {
+    stops
+        .iter()
+        .filter(|task| matches!(task.kind, TaskKind::Job(_)))
+        .map(|task| task.id)
+        .collect()
+}
+
+/// Record time window, capacity, route limit and precedence violations on a route and its
+/// steps
+///
+/// Returns the number of steps reached after their time windows have closed.
+fn record_violations(route: &mut VehicleRoute, vehicle: &Vehicle, tasks: &[Task]) -> usize {
+    let mut delays = 0;
+
+    for step in &mut route.steps {
+        let time_windows = match step {
+            RouteStep::End { .. } => vehicle.operating_windows(),
+            RouteStep::Break { id, .. } => vehicle
+                .breaks
+                .iter()
+                .flatten()
+                .find(|brk| brk.id == *id)
+                .map(|brk| brk.time_windows.clone()),
+            _ => find_task(tasks, step).and_then(|task| task.time_windows.clone()),
+        };
+        let (Some(time_windows), Some(arrival_time)) = (time_windows, step_arrival_time(step))
+        else {
+            continue;
+        };
+
+        // Arriving early only means waiting, so only arrivals after every window count
+        let latest = time_windows.iter().map(|window| window[1]).max();
+        if let Some(latest) = latest.filter(|latest| arrival_time > *latest) {
+            step_violations(step).push(Violation {
+                kind: ViolationType::Delay,
+                amount: arrival_time - latest,
+                dimension: None,
+            });
+            delays += 1;
+        }
+    }
+
+    // The load profile starts with the load on departure, followed by the load after
+    // each stop
+    let mut stops = 0;
+    for step in &mut route.steps {
+        let position = match step {
+            RouteStep::Start { .. } => 0,
+            RouteStep::Job { .. } | RouteStep::Pickup { .. } | RouteStep::Delivery { .. } => {
+                stops += 1;
+                stops
+            }
+            _ => continue,
+        };
+        if let Some(load) = route.load_profile.get(position) {
+            step_violations(step).extend(vehicle.load_violations(load));
+        }
+    }
+
+    // Shipments are picked up before they are delivered
+    for (s, _) in misordered_shipments(&route.steps) {
+        step_violations(&mut route.steps[s]).push(Violation {
+            kind: ViolationType::Precedence,
+            amount: 1,
+            dimension: None,
+        });
+    }
+
+    // The route as a whole reports its largest overload
+    let overload = route
+        .load_profile
+        .iter()
+        .flat_map(|load| vehicle.load_violations(load))
+        .min_by_key(|violation| std::cmp::Reverse(violation.amount));
+    route.violations.extend(overload);
+
+    let stops = route
+        .steps
+        .iter()
+        .filter(|step| step_id(step).is_some())
+        .count();
+    route.violations.extend(vehicle.limit_violations(
+        route.duration as f64,
+        route.distance as f64,
+        stops,
+    ));
+    if let (Some(rules), Some(start), Some(end)) = (
+        vehicle.working_rules,
+        route.departure_times.first(),
+        route.arrival_times.last(),
+    ) {
+        route.violations.extend(rules.shift_violation(end - start));
+    }
+
+    delays
+}
+
+/// Feasibility of a timed predefined route, with the load and violations at each stop
+///
+/// Violations at the start, end, breaks and rests count towards the route as a whole, as
+/// does too much load on departure.
+fn route_feasibility(mut route: VehicleRoute) -> RouteFeasibility {
+    // The route's load violation repeats those of its start and stops
+    let mut violations = std::mem::take(&mut route.violations);
+    violations.retain(|violation| violation.kind != ViolationType::Load);
+
+    let mut stops = Vec::new();
+    for step in &mut route.steps {
+        let arrival_time = step_arrival_time(step).unwrap_or(0);
+        let (id, kind, departure_time) = match step {
+            RouteStep::Job { id, departure_time, .. } => (*id, "job", *departure_time),
+            RouteStep::Pickup { id, departure_time, .. } => (*id, "pickup", *departure_time),
+            RouteStep::Delivery { id, departure_time, .. } => (*id, "delivery", *departure_time),
+            _ => {
+                violations.append(step_violations(step));
+                continue;
+            }
+        };
+
+        // The load profile starts with the load on departure
+        let load = route
+            .load_profile
+            .get(stops.len() + 1)
+            .cloned()
+            .unwrap_or_default();
+        let stop_violations = std::mem::take(step_violations(step));
+
+        stops.push(StopFeasibility {
+            id,
+            kind: kind.to_string(),
+            arrival_time,
+            departure_time: departure_time.unwrap_or(arrival_time),
+            load,
+            violations: stop_violations,
+        });
+    }
+
+    RouteFeasibility {
+        vehicle_id: route.vehicle_id,
+        feasible: violations.is_empty() && stops.iter().all(|stop| stop.violations.is_empty()),
+        stops,
+        violations,
+    }
+}
+
+/// Cost of a route of `vehicle`, including the lateness penalties under the soft time window
+/// policy
+fn route_cost(
+    vehicle: &Vehicle,
+    route: &VehicleRoute,
+    soft: Option<&solver::SoftTimeWindows>,
+) -> CostBreakdown {
+    let mut cost = vehicle.route_cost(
+        route.duration as f64,
+        route.distance as f64,
+        route.waiting_time as f64,
+        route.service_time as f64,
+    );
+    if let Some(soft) = soft {
+        let lateness = task_lateness(route);
+        cost.violations = soft.cost(lateness.iter().sum(), lateness.len());
+    }
+    cost
+}
+
+/// Seconds of late arrival at each late job, pickup and delivery step of a route
+fn task_lateness(route: &VehicleRoute) -> Vec<i64> {
+    route
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            RouteStep::Job { violations, .. }
+            | RouteStep::Pickup { violations, .. }
+            | RouteStep::Delivery { violations, .. } => Some(violations),
+            _ => None,
+        })
+        .flatten()
+        .filter(|violation| violation.kind == ViolationType::Delay)
+        .map(|violation| violation.amount)
+        .collect()
+}
+
+/// Late service allowed by the request's soft time window policy, if it is soft
+///
+/// Lateness is priced by the penalties of `settings` unless the request sets its own.
+fn soft_time_windows(
+    request: &RoutingRequest,
+    settings: &SolverSettings,
+) -> Option<solver::SoftTimeWindows> {
+    let options = request.options.as_ref()?;
+    (options.time_window_policy? == TimeWindowPolicy::Soft).then(|| solver::SoftTimeWindows {
+        penalty: options.lateness_penalty.unwrap_or(settings.lateness_penalty),
+        late_task_penalty: options
+            .late_task_penalty
+            .unwrap_or(settings.late_task_penalty),
+        max_lateness: options.max_lateness.map(i64::from),
+    })
+}
+
+/// Violations recorded on a route step
+fn step_violations(step: &mut RouteStep) -> &mut Vec<Violation> {
+    match step {
+        RouteStep::Start { violations, .. }
+        | RouteStep::Job { violations, .. }
+        | RouteStep::Pickup { violations, .. }
+        | RouteStep::Delivery { violations, .. }
+        | RouteStep::Break { violations, .. }
+        | RouteStep::Reload { violations, .. }
+        | RouteStep::Rest { violations, .. }
+        | RouteStep::End { violations, .. } => violations,
+    }
+}
+
+/// Summary cost of leaving a job or shipment of a request unassigned, before its priority
+fn unassigned_cost(request: &RoutingRequest) -> f64 {
+    request
+        .options
+        .as_ref()
+        .and_then(|options| options.unassigned_penalty)
+        .unwrap_or(UNASSIGNED_COST)
+}
+
+/// Solution leaving more jobs and shipments unassigned than `options.max_unassigned` or
+/// `options.max_unassigned_percent` accept
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnassignedLimitExceeded {
+    /// Jobs and shipments left unassigned
+    pub unassigned: usize,
+
+    /// Jobs and shipments of the request
+    pub total: usize,
+
+    /// Description naming the limit
+    pub message: String,
+}
+
+impl fmt::Display for UnassignedLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for UnassignedLimitExceeded {}
+
+/// Fail when more jobs and shipments are unassigned than the request accepts
+fn check_unassigned_limits(
+    request: &RoutingRequest,
+    unassigned: usize,
+) -> Result<(), UnassignedLimitExceeded> {
+    let Some(options) = &request.options else {
+        return Ok(());
+    };
+    let total = request.jobs.len() + request.shipments.len();
+    let exceeded = |message| UnassignedLimitExceeded {
+        unassigned,
+        total,
+        message,
+    };
+
+    if let Some(limit) = options.max_unassigned {
+        if unassigned > limit as usize {
+            return Err(exceeded(format!(
+                "{} of {} jobs and shipments are unassigned, more than the {} allowed by options.max_unassigned",
+                unassigned,
+                total,
+                limit
+            )));
+        }
+    }
+    if let Some(percent) = options.max_unassigned_percent {
+        let share = 100.0 * unassigned as f64 / total.max(1) as f64;
+        if share > percent {
+            return Err(exceeded(format!(
+                "{} of {} jobs and shipments ({:.1}%) are unassigned, more than the {}% allowed by options.max_unassigned_percent",
+                unassigned,
+                total,
+                share,
+                percent
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether a request asks for the legs of routes with turn-by-turn instructions
+fn include_instructions(request: &RoutingRequest) -> bool {
+    request
+        .options
+        .as_ref()
+        .and_then(|options| options.instructions)
+        .unwrap_or(false)
+}
+
+/// Legs of an engine route with their instructions, if the request asks for them
+fn instructed_legs(request: &RoutingRequest, route: &EngineRoute) -> Vec<RouteLeg> {
+    if !include_instructions(request) {
+        return Vec::new();
+    }
+    route
+        .legs
+        .iter()
+        .map(|leg| RouteLeg {
+            distance: leg.distance as u32,
+            duration: leg.duration as u32,
+            instructions: leg.instructions.clone(),
+        })
+        .collect()
+}
+
+/// Road classes the routes of a request keep off
+fn excluded_roads(request: &RoutingRequest) -> &[String] {
+    request
+        .options
+        .as_ref()
+        .map_or(&[], |options| options.exclude.as_slice())
+}
+
+/// Areas the routes of a request may not cross
+fn avoided_polygons(request: &RoutingRequest) -> &[Zone] {
+    request
+        .options
+        .as_ref()
+        .map_or(&[], |options| options.avoid_polygons.as_slice())
+}
+
+/// Make travel between `locations` whose straight line crosses one of `zones` impossible
+///
+/// Travel to or from a location inside a zone stays possible, as the zone must be entered
+/// to get there.
+fn avoid_polygons(matrix: &mut Matrix, locations: &[[f64; 2]], zones: &[Zone]) {
+    if zones.is_empty() {
+        return;
+    }
+    let inside: Vec<bool> = locations
+        .iter()
+        .map(|location| zones.iter().any(|zone| zone.contains(*location)))
+        .collect();
+
+    for (from, &origin) in locations.iter().enumerate() {
+        for (to, &destination) in locations.iter().enumerate() {
+            if from == to || inside[from] || inside[to] {
+                continue;
+            }
+            if zones.iter().any(|zone| zone.crosses(origin, destination)) {
+                matrix.durations[from][to] = f64::INFINITY;
+                if let Some(distances) = &mut matrix.distances {
+                    distances[from][to] = f64::INFINITY;
+                }
+            }
+        }
+    }
+}
+
+/// Echo the descriptions and metadata of the vehicles, jobs and shipments of the request
+/// on their routes and steps
+fn add_metadata(routes: &mut [VehicleRoute], request: &RoutingRequest) {
+    type Echo<'a> = (&'a Option<String>, &'a Option<serde_json::Value>);
+    let vehicles: HashMap<u64, Echo> = request
+        .vehicles
+        .iter()
+        .filter(|vehicle| vehicle.description.is_some() || vehicle.metadata.is_some())
+        .map(|vehicle| (vehicle.id, (&vehicle.description, &vehicle.metadata)))
+        .collect();
+    let jobs: HashMap<u64, Echo> = request
+        .jobs
+        .iter()
+        .filter(|job| job.description.is_some() || job.metadata.is_some())
+        .map(|job| (job.id, (&job.description, &job.metadata)))
+        .collect();
+    let shipments: HashMap<u64, Echo> = request
+        .shipments
+        .iter()
+        .filter(|shipment| shipment.description.is_some() || shipment.metadata.is_some())
+        .map(|shipment| (shipment.id, (&shipment.description, &shipment.metadata)))
+        .collect();
+    if vehicles.is_empty() && jobs.is_empty() && shipments.is_empty() {
+        return;
+    }
+
+    for route in routes {
+        if let Some((description, metadata)) = vehicles.get(&route.vehicle_id) {
+            route.description = (*description).clone();
+            route.metadata = (*metadata).clone();
+        }
+        for step in &mut route.steps {
+            let (echo, description, metadata) = match step {
+                RouteStep::Job { id, description, metadata, .. } => {
+                    (jobs.get(id), description, metadata)
+                }
+                RouteStep::Pickup { id, description, metadata, .. }
+                | RouteStep::Delivery { id, description, metadata, .. } => {
+                    (shipments.get(id), description, metadata)
+                }
+                _ => continue,
+            };
+            if let Some((echoed_description, echoed_metadata)) = echo {
+                *description = (*echoed_description).clone();
+                *metadata = (*echoed_metadata).clone();
+            }
+        }
+    }
+}
+
+/// Give the arrival and departure times of the steps of a route as RFC 3339 timestamps at
+/// the UTC offset `offset`
+fn add_timestamps(route: &mut VehicleRoute, offset: FixedOffset) {
+    for step in &mut route.steps {
+        let arrival = step_arrival_time(step).and_then(|t| time::format(t, offset));
+        let departure = step_departure_time(step).and_then(|t| time::format(t, offset));
+        match step {
+            RouteStep::Start { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Job { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Pickup { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Delivery { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Break { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Reload { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::Rest { arrival_timestamp, departure_timestamp, .. }
+            | RouteStep::End { arrival_timestamp, departure_timestamp, .. } => {
+                *arrival_timestamp = arrival;
+                *departure_timestamp = departure;
+            }
+        }
+    }
+}
+
+/// Departure time recorded on a route step
+fn step_departure_time(step: &RouteStep) -> Option<i64> {
+    match step {
+        RouteStep::Start { departure_time, .. }
+        | RouteStep::Job { departure_time, .. }
+        | RouteStep::Pickup { departure_time, .. }
+        | RouteStep::Delivery { departure_time, .. }
+        | RouteStep::Break { departure_time, .. }
+        | RouteStep::Reload { departure_time, .. }
+        | RouteStep::Rest { departure_time, .. }
+        | RouteStep::End { departure_time, .. } => *departure_time,
+    }
+}
+
+/// Time a job or shipment step waits for its time window to open, `0` for other steps
+fn step_waiting_time(step: &RouteStep) -> i64 {
+    match step {
+        RouteStep::Job { waiting_time, .. }
+        | RouteStep::Pickup { waiting_time, .. }
+        | RouteStep::Delivery { waiting_time, .. } => waiting_time.unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Type of a route step, as it is serialized
+fn step_kind(step: &RouteStep) -> &'static str {
+    match step {
+        RouteStep::Start { .. } => "start",
+        RouteStep::Job { .. } => "job",
+        RouteStep::Pickup { .. } => "pickup",
+        RouteStep::Delivery { .. } => "delivery",
+        RouteStep::Break { .. } => "break",
+        RouteStep::Reload { .. } => "reload",
+        RouteStep::Rest { .. } => "rest",
+        RouteStep::End { .. } => "end",
+    }
+}
+
+/// Arrival time recorded on a route step
+fn step_arrival_time(step: &RouteStep) -> Option<i64> {
+    match step {
+        RouteStep::Start { arrival_time, .. }
+        | RouteStep::Job { arrival_time, .. }
+        | RouteStep::Pickup { arrival_time, .. }
+        | RouteStep::Delivery { arrival_time, .. }
+        | RouteStep::Break { arrival_time, .. }
+        | RouteStep::Reload { arrival_time, .. }
+        | RouteStep::Rest { arrival_time, .. }
+        | RouteStep::End { arrival_time, .. } => *arrival_time,
+    }
+}
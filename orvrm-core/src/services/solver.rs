@@ -0,0 +1,3287 @@
+use super::solvers::{self, Search};
+use crate::models::{
+    Algorithm, BalanceMetric, BalanceOptions, Break, Construction, Depot, Job, LnsOptions,
+    LoadOrder, PriorityPolicy, RelationType, Shipment, StockOut, Vehicle, Violation,
+    ViolationType, WorkingRules,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default objective penalty for each job or shipment left unassigned, scaled by its priority
+const UNASSIGNED_PENALTY: f64 = 1_000_000.0;
+
+/// Objective penalty per unit of deviation above a hard balance limit, outweighing any unassigned work
+const HARD_BALANCE_PENALTY: f64 = 1_000_000_000.0;
+
+/// Weight of waiting time when choosing the next task during construction
+const WAITING_WEIGHT: f64 = 0.5;
+
+/// Upper bound on local search passes per solver instance
+const MAX_IMPROVEMENT_PASSES: usize = 100;
+
+/// Duration and distance matrix indexed by location
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    /// Travel durations in seconds
+    pub durations: Vec<Vec<f64>>,
+
+    /// Travel distances in meters
+    pub distances: Option<Vec<Vec<f64>>>,
+
+    /// Where each location was moved to reach the road network, when the routing engine
+    /// reports it
+    pub snapped: Option<Vec<Snap>>,
+}
+
+/// Location moved onto the road network by the routing engine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snap {
+    /// Location on the road network [longitude, latitude]
+    pub location: [f64; 2],
+
+    /// Distance in meters the location was moved
+    pub distance: f64,
+}
+
+impl Matrix {
+    /// Duration and distance of travelling between two locations
+    pub fn travel(&self, from: usize, to: usize) -> (f64, f64) {
+        let duration = self.durations[from][to];
+        let distance = self
+            .distances
+            .as_ref()
+            .map(|distances| distances[from][to])
+            .unwrap_or(0.0);
+        (duration, distance)
+    }
+
+    /// Matrix between a subset of the locations, in the order of `locations`
+    pub fn submatrix(&self, locations: &[usize]) -> Matrix {
+        let pick = |rows: &Vec<Vec<f64>>| -> Vec<Vec<f64>> {
+            locations
+                .iter()
+                .map(|from| locations.iter().map(|to| rows[*from][*to]).collect())
+                .collect()
+        };
+
+        Matrix {
+            durations: pick(&self.durations),
+            distances: self.distances.as_ref().map(pick),
+            snapped: self
+                .snapped
+                .as_ref()
+                .map(|snapped| locations.iter().map(|idx| snapped[*idx]).collect()),
+        }
+    }
+}
+
+/// Late service allowed at tasks whose time windows have all closed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoftTimeWindows {
+    /// Cost per second of arriving after the end of a task's last time window
+    pub penalty: f64,
+
+    /// Cost of each task arrived at late, on top of the cost per second
+    pub late_task_penalty: f64,
+
+    /// Largest accepted lateness at a task in seconds, unlimited when not set
+    pub max_lateness: Option<i64>,
+}
+
+impl SoftTimeWindows {
+    /// Penalty for arriving `lateness` seconds late in total at `late_tasks` tasks
+    pub fn cost(&self, lateness: i64, late_tasks: usize) -> f64 {
+        self.penalty * lateness as f64 + self.late_task_penalty * late_tasks as f64
+    }
+}
+
+/// Speed adjustment for travel starting in a period of the day
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrafficPeriod {
+    /// Start of the period in seconds since midnight
+    pub start: u32,
+
+    /// End of the period in seconds since midnight, exclusive
+    pub end: u32,
+
+    /// Factor applied to the duration of legs departing in the period
+    pub factor: f64,
+}
+
+/// Duration factor for a leg departing at `time`
+///
+/// Times are taken modulo a day, so both seconds since midnight and UTC timestamps work.
+/// Outside all periods travel takes the matrix duration.
+pub fn traffic_factor(periods: &[TrafficPeriod], time: i64) -> f64 {
+    let time_of_day = time.rem_euclid(86_400);
+    periods
+        .iter()
+        .find(|period| (period.start as i64..period.end as i64).contains(&time_of_day))
+        .map(|period| period.factor)
+        .unwrap_or(1.0)
+}
+
+/// Deduplicated list of locations used to build a matrix
+#[derive(Debug, Clone, Default)]
+pub struct LocationIndex {
+    locations: Vec<[f64; 2]>,
+    index: HashMap<[u64; 2], usize>,
+}
+
+impl LocationIndex {
+    /// Collect the unique locations of all vehicles, jobs and shipments
+    pub fn build(vehicles: &[Vehicle], jobs: &[Job], shipments: &[Shipment]) -> Self {
+        let mut index = Self::default();
+
+        for vehicle in vehicles {
+            for location in [vehicle.start, vehicle.end].into_iter().flatten() {
+                index.insert(location);
+            }
+        }
+
+        for job in jobs {
+            index.insert(job.location);
+        }
+
+        for shipment in shipments {
+            index.insert(shipment.pickup.location);
+            index.insert(shipment.delivery.location);
+        }
+
+        index
+    }
+
+    /// Add a location and return its matrix index
+    pub fn insert(&mut self, location: [f64; 2]) -> usize {
+        let key = Self::key(location);
+        if let Some(idx) = self.index.get(&key) {
+            return *idx;
+        }
+
+        let idx = self.locations.len();
+        self.locations.push(location);
+        self.index.insert(key, idx);
+        idx
+    }
+
+    /// Get the matrix index of a location
+    pub fn get(&self, location: [f64; 2]) -> Option<usize> {
+        self.index.get(&Self::key(location)).copied()
+    }
+
+    /// All unique locations in matrix order
+    pub fn locations(&self) -> &[[f64; 2]] {
+        &self.locations
+    }
+
+    fn key(location: [f64; 2]) -> [u64; 2] {
+        [location[0].to_bits(), location[1].to_bits()]
+    }
+}
+
+/// What a task stands for in the request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    /// Standalone job, by index into the jobs
+    Job(usize),
+
+    /// Pickup leg of a shipment, by index into the shipments
+    Pickup(usize),
+
+    /// Delivery leg of a shipment, by index into the shipments
+    Delivery(usize),
+}
+
+/// A single stop the solver can schedule
+#[derive(Debug, Clone)]
+pub struct Task {
+    /// What the task stands for
+    pub kind: TaskKind,
+
+    /// ID of the job or shipment
+    pub id: u64,
+
+    /// Location as [longitude, latitude]
+    pub location: [f64; 2],
+
+    /// Service time in seconds
+    pub service: u32,
+
+    /// Setup time in seconds before service, only spent when arriving from another location
+    pub setup: u32,
+
+    /// Time windows for the task
+    pub time_windows: Option<Vec<[i64; 2]>>,
+
+    /// Skills required to perform the task
+    pub skills: Option<Vec<String>>,
+
+    /// Load types of the goods handled at the task
+    pub load_types: Option<Vec<String>>,
+
+    /// Priority of the job or shipment (higher value means higher priority)
+    pub priority: u8,
+
+    /// Amounts loaded at the route start and dropped at this task
+    pub delivery: Vec<u32>,
+
+    /// Amounts loaded at this task
+    pub pickup: Vec<u32>,
+
+    /// Amounts loaded earlier in the route and dropped at this task
+    pub unload: Vec<u32>,
+
+    /// Group of the job, whose jobs are served by the same vehicle
+    pub group: Option<String>,
+}
+
+impl Task {
+    /// Build the task list for a problem: jobs first, then each shipment's pickup and delivery
+    pub fn build(jobs: &[Job], shipments: &[Shipment]) -> Vec<Task> {
+        let mut tasks: Vec<Task> = jobs
+            .iter()
+            .enumerate()
+            .map(|(idx, job)| Task {
+                kind: TaskKind::Job(idx),
+                id: job.id,
+                location: job.location,
+                service: job.service,
+                setup: job.setup.unwrap_or(0),
+                time_windows: job.time_windows.clone(),
+                skills: job.skills.clone(),
+                load_types: job.load_types.clone(),
+                priority: job.priority.unwrap_or(0),
+                delivery: job.delivery.clone().unwrap_or_default(),
+                pickup: job.pickup.clone().unwrap_or_default(),
+                unload: Vec::new(),
+                group: job.group.clone(),
+            })
+            .collect();
+
+        for (idx, shipment) in shipments.iter().enumerate() {
+            for (kind, step) in [
+                (TaskKind::Pickup(idx), &shipment.pickup),
+                (TaskKind::Delivery(idx), &shipment.delivery),
+            ] {
+                let is_pickup = matches!(kind, TaskKind::Pickup(_));
+                tasks.push(Task {
+                    kind,
+                    id: shipment.id,
+                    location: step.location,
+                    service: step.service,
+                    setup: 0,
+                    time_windows: step.time_windows.clone(),
+                    skills: shipment.skills.clone(),
+                    load_types: shipment.load_types.clone(),
+                    priority: shipment.priority.unwrap_or(0),
+                    delivery: Vec::new(),
+                    pickup: if is_pickup {
+                        shipment.amount.clone()
+                    } else {
+                        Vec::new()
+                    },
+                    unload: if is_pickup {
+                        Vec::new()
+                    } else {
+                        shipment.amount.clone()
+                    },
+                    group: None,
+                });
+            }
+        }
+
+        tasks
+    }
+
+    /// Check that the task has no amounts in dimensions the vehicle lacks
+    fn fits_dimensions(&self, vehicle: &Vehicle) -> bool {
+        let dims = vehicle.capacity.len();
+        [&self.delivery, &self.pickup, &self.unload]
+            .into_iter()
+            .all(|amounts| amounts.iter().skip(dims).all(|value| *value == 0))
+    }
+}
+
+/// Routing problem prepared for the solver
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// Vehicles available for the problem
+    pub vehicles: Vec<Vehicle>,
+
+    /// Jobs to be assigned
+    pub jobs: Vec<Job>,
+
+    /// Shipments to be assigned
+    pub shipments: Vec<Shipment>,
+
+    /// Tasks derived from the jobs and shipments
+    pub tasks: Vec<Task>,
+
+    /// Travel matrix between all locations
+    pub matrix: Matrix,
+
+    vehicle_matrices: Vec<Matrix>,
+    vehicle_matrix: Vec<Option<usize>>,
+    task_locations: Vec<usize>,
+    vehicle_starts: Vec<Option<usize>>,
+    vehicle_ends: Vec<Option<usize>>,
+    compatible: Vec<Vec<bool>>,
+    /// Tasks that must share a route get the same group: the jobs of a job group, the
+    /// tasks of a shipment in a relation and the tasks related to stay on one route
+    task_groups: Vec<Option<usize>>,
+    consecutive_groups: bool,
+    relations: Vec<(RelationType, Vec<usize>)>,
+    balance: Option<BalanceOptions>,
+    traffic: Vec<TrafficPeriod>,
+    locked: Vec<Vec<usize>>,
+    quick: bool,
+    time_limit: Option<Duration>,
+    soft_time_windows: Option<SoftTimeWindows>,
+    load_order: LoadOrder,
+    unassigned_penalty: f64,
+    construction: Construction,
+    priority_policy: PriorityPolicy,
+    algorithm: Algorithm,
+    lns: LnsOptions,
+    seed: Option<u64>,
+    initial: Vec<Vec<usize>>,
+    insertion: Option<Vec<usize>>,
+    repaired: Vec<usize>,
+    stocks: Vec<DepotStock>,
+    vehicle_stock: Vec<Option<usize>>,
+}
+
+/// Limited stock of a depot, supplying the job deliveries of the vehicles starting there
+#[derive(Debug, Clone)]
+struct DepotStock {
+    depot_id: u64,
+    amounts: Vec<u32>,
+}
+
+/// Timing of a route as computed from the matrix
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// Arrival times at the start, each task and the end
+    pub arrival_times: Vec<i64>,
+
+    /// Departure times from the start, each task and the end
+    pub departure_times: Vec<i64>,
+
+    /// Setup time spent at each task
+    pub setup_times: Vec<u32>,
+
+    /// Total travel duration in seconds
+    pub travel_duration: f64,
+
+    /// Total travel distance in meters
+    pub travel_distance: f64,
+
+    /// Load at the start, after each task and at the end
+    pub load_profile: Vec<Vec<i32>>,
+
+    /// Breaks taken along the route
+    pub breaks: Vec<BreakStop>,
+
+    /// Returns to the start location to reload between trips
+    pub reloads: Vec<ReloadStop>,
+
+    /// Seconds of arriving at tasks after their time windows closed
+    pub lateness: i64,
+
+    /// Number of tasks arrived at after their time windows closed
+    pub late_tasks: usize,
+
+    /// Seconds spent at tasks waiting for their time windows to open
+    pub waiting_time: i64,
+
+    /// Seconds spent serving tasks
+    pub service_time: i64,
+
+    /// Rests required by the vehicle's working rules
+    pub rests: Vec<RestStop>,
+
+    /// Shift of the vehicle the route runs in, for vehicles with shifts
+    pub shift: Option<usize>,
+}
+
+/// A vehicle break scheduled along a route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakStop {
+    /// Index of the break in the vehicle's breaks
+    pub index: usize,
+
+    /// Number of tasks served before the break
+    pub after: usize,
+
+    /// Time at which the vehicle is ready for the break
+    pub arrival_time: i64,
+
+    /// Time at which the break ends
+    pub departure_time: i64,
+}
+
+/// A rest required by the working rules of a vehicle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestStop {
+    /// Number of tasks served before the rest
+    pub after: usize,
+
+    /// Whether the rest interrupts a leg rather than being taken at the previous stop
+    pub en_route: bool,
+
+    /// Time at which the rest starts
+    pub arrival_time: i64,
+
+    /// Time at which the rest ends
+    pub departure_time: i64,
+}
+
+/// A return to the vehicle's start location to reload for its next trip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadStop {
+    /// Number of tasks served before the reload
+    pub after: usize,
+
+    /// Time at which the vehicle arrives at its start location
+    pub arrival_time: i64,
+
+    /// Time at which the vehicle leaves for its next trip
+    pub departure_time: i64,
+}
+
+/// Route of a single vehicle in a solution
+#[derive(Debug, Clone)]
+pub struct SolvedRoute {
+    /// Index of the vehicle in the problem
+    pub vehicle: usize,
+
+    /// Indices of the tasks in visiting order
+    pub tasks: Vec<usize>,
+
+    /// Timing of the route
+    pub schedule: Schedule,
+}
+
+/// Result of a solver run
+#[derive(Debug, Clone)]
+pub struct Solution {
+    /// Non-empty routes ordered by vehicle index
+    pub routes: Vec<SolvedRoute>,
+
+    /// Indices of the tasks that could not be assigned
+    pub unassigned: Vec<usize>,
+
+    /// Objective value of the solution
+    pub cost: f64,
+}
+
+impl Solution {
+    /// Tasks of each vehicle regardless of their visiting order
+    pub fn assignment(&self) -> Vec<(usize, Vec<usize>)> {
+        self.routes
+            .iter()
+            .map(|route| {
+                let mut tasks = route.tasks.clone();
+                tasks.sort_unstable();
+                (route.vehicle, tasks)
+            })
+            .collect()
+    }
+}
+
+/// Partial state of a route while it is being built
+#[derive(Debug, Clone)]
+struct RouteState {
+    vehicle: usize,
+    /// Shift of the vehicle the route runs in, for vehicles with shifts
+    shift: Option<usize>,
+    /// Current location, or `None` before the first task of a route without a start
+    location: Option<usize>,
+    time: i64,
+    load: Vec<u32>,
+    peak_load: Vec<u32>,
+    open_shipments: Vec<usize>,
+    visited: usize,
+    next_break: usize,
+    breaks: Vec<BreakStop>,
+    trips: u32,
+    reloads: Vec<ReloadStop>,
+    /// Task whose load type the current trip carries, for vehicles that cannot mix
+    load_type: Option<usize>,
+    /// Whether the current trip has served job deliveries and job pickups
+    delivered: bool,
+    picked_up: bool,
+    travel_duration: f64,
+    travel_distance: f64,
+    lateness: i64,
+    late_tasks: usize,
+    waiting_time: i64,
+    service_time: i64,
+    /// Time the route starts, from which the working day is measured
+    shift_start: i64,
+    /// Driving time since the last rest
+    driving: i64,
+    rests: Vec<RestStop>,
+}
+
+/// Outcome of visiting a task from a route state
+struct Visit {
+    state: RouteState,
+    arrival_time: i64,
+    waiting_time: i64,
+    setup: u32,
+    travel_duration: f64,
+}
+
+impl Problem {
+    /// Create a problem from vehicles, jobs, shipments and a matrix built over `locations`
+    pub fn new(
+        vehicles: Vec<Vehicle>,
+        jobs: Vec<Job>,
+        shipments: Vec<Shipment>,
+        locations: &LocationIndex,
+        matrix: Matrix,
+    ) -> Self {
+        let locate = |location: [f64; 2]| {
+            locations
+                .get(location)
+                .expect("location missing from location index")
+        };
+
+        let tasks = Task::build(&jobs, &shipments);
+        let task_locations = tasks.iter().map(|task| locate(task.location)).collect();
+        let vehicle_starts = vehicles
+            .iter()
+            .map(|vehicle| vehicle.start.map(locate))
+            .collect();
+        let vehicle_ends = vehicles
+            .iter()
+            .map(|vehicle| vehicle.end.map(locate))
+            .collect();
+        let compatible = tasks
+            .iter()
+            .map(|task| {
+                vehicles
+                    .iter()
+                    .map(|vehicle| {
+                        let allowed = match task.kind {
+                            TaskKind::Job(job) => jobs[job].allows_vehicle(vehicle.id),
+                            _ => true,
+                        };
+                        allowed
+                            && vehicle.has_skills(task.skills.as_deref())
+                            && vehicle.carries(task.load_types.as_deref())
+                            && task.fits_dimensions(vehicle)
+                            && vehicle.serves_location(task.location)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let task_groups = task_groups(&tasks);
+
+        Self {
+            vehicle_matrix: vec![None; vehicles.len()],
+            locked: vec![Vec::new(); vehicles.len()],
+            initial: vec![Vec::new(); vehicles.len()],
+            insertion: None,
+            repaired: Vec::new(),
+            stocks: Vec::new(),
+            vehicle_stock: vec![None; vehicles.len()],
+            vehicles,
+            jobs,
+            shipments,
+            tasks,
+            matrix,
+            vehicle_matrices: Vec::new(),
+            task_locations,
+            vehicle_starts,
+            vehicle_ends,
+            compatible,
+            task_groups,
+            consecutive_groups: false,
+            relations: Vec::new(),
+            balance: None,
+            traffic: Vec::new(),
+            quick: false,
+            time_limit: None,
+            soft_time_windows: None,
+            load_order: LoadOrder::default(),
+            unassigned_penalty: UNASSIGNED_PENALTY,
+            construction: Construction::default(),
+            priority_policy: PriorityPolicy::default(),
+            algorithm: Algorithm::default(),
+            lns: LnsOptions::default(),
+            seed: None,
+        }
+    }
+
+    /// Allow tasks to be served after their time windows close, at a cost per second late
+    pub fn with_soft_time_windows(mut self, soft: SoftTimeWindows) -> Self {
+        self.soft_time_windows = Some(soft);
+        self
+    }
+
+    /// Keep the job deliveries and pickups of each trip in `order`
+    pub fn with_load_order(mut self, order: LoadOrder) -> Self {
+        self.load_order = order;
+        self
+    }
+
+    /// Balance the workload between the vehicles
+    pub fn with_balance(mut self, balance: BalanceOptions) -> Self {
+        self.balance = Some(balance);
+        self
+    }
+
+    /// Scale travel durations by the time of day at which each leg departs
+    pub fn with_traffic(mut self, traffic: Vec<TrafficPeriod>) -> Self {
+        self.traffic = traffic;
+        self
+    }
+
+    /// Limit the job deliveries of vehicles starting at a depot to the depot's stock
+    ///
+    /// Vehicles refer to their depot by `start_depot`; depots without stock are unlimited.
+    pub fn with_depot_stock(mut self, depots: &[Depot]) -> Self {
+        for depot in depots {
+            let Some(amounts) = &depot.stock else {
+                continue;
+            };
+            let stock = self.stocks.len();
+            self.stocks.push(DepotStock {
+                depot_id: depot.id,
+                amounts: amounts.clone(),
+            });
+            for (vehicle, data) in self.vehicles.iter().enumerate() {
+                if data.start_depot == Some(depot.id) {
+                    self.vehicle_stock[vehicle] = Some(stock);
+                }
+            }
+        }
+        self
+    }
+
+    /// Let the given vehicles travel by `matrix`, built over the same locations, instead
+    /// of the default matrix
+    pub fn with_vehicle_matrix(mut self, matrix: Matrix, vehicles: &[usize]) -> Self {
+        for vehicle in vehicles {
+            self.vehicle_matrix[*vehicle] = Some(self.vehicle_matrices.len());
+        }
+        self.vehicle_matrices.push(matrix);
+        self
+    }
+
+    /// Keep `tasks` as the fixed start of a vehicle's route
+    ///
+    /// The solver only adds work after the locked tasks and never moves or unassigns them,
+    /// so they must form a feasible route on their own. Shipments must be locked with both
+    /// their pickup and delivery.
+    pub fn with_locked_route(mut self, vehicle: usize, tasks: Vec<usize>) -> Self {
+        self.locked[vehicle] = tasks;
+        self
+    }
+
+    /// Start one solver instance from `tasks` as the route of a vehicle, instead of
+    /// constructing its routes from scratch
+    ///
+    /// Tasks the vehicle can no longer serve in this order are left out and inserted by the
+    /// local search, like any other unassigned work.
+    pub fn with_initial_route(mut self, vehicle: usize, tasks: Vec<usize>) -> Self {
+        self.initial[vehicle] = tasks;
+        self
+    }
+
+    /// Keep the initial routes in their order and only insert the jobs and shipments of
+    /// `tasks` into them, each at its cheapest feasible position
+    ///
+    /// Other unassigned work stays unassigned and no other moves are made.
+    pub fn with_insertion_only(mut self, tasks: Vec<usize>) -> Self {
+        self.insertion = Some(tasks);
+        self
+    }
+
+    /// Improve the routes of `vehicles` among themselves after inserting, keeping the
+    /// other routes as they are
+    ///
+    /// Only has an effect together with [`Self::with_insertion_only`].
+    pub fn with_repaired_routes(mut self, vehicles: Vec<usize>) -> Self {
+        self.repaired = vehicles;
+        self
+    }
+
+    /// Only construct routes and fill them up with unassigned work, skipping the slower
+    /// improvement moves
+    ///
+    /// Meant for problems too large to search thoroughly in reasonable time.
+    pub fn with_quick_search(mut self) -> Self {
+        self.quick = true;
+        self
+    }
+
+    /// Stop improving routes once `limit` has passed since solving started
+    ///
+    /// The best routes found by then are returned, so a solution is always available even
+    /// when the limit is shorter than a single improvement pass.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Penalize each job or shipment left unassigned by `penalty`, scaled by `1 + priority`,
+    /// instead of a penalty outweighing any travel
+    ///
+    /// Work costing more to serve than its penalty is left unassigned.
+    pub fn with_unassigned_penalty(mut self, penalty: f64) -> Self {
+        self.unassigned_penalty = penalty;
+        self
+    }
+
+    /// Serve the jobs of each group one after another, not only by the same vehicle
+    pub fn with_consecutive_groups(mut self) -> Self {
+        self.consecutive_groups = true;
+        self
+    }
+
+    /// Relate `tasks` as `kind` asks
+    ///
+    /// All relations but `NotInSameRoute` keep the tasks, with the rest of their shipments
+    /// and job groups, on one route.
+    pub fn with_relation(mut self, kind: RelationType, tasks: Vec<usize>) -> Self {
+        if kind != RelationType::NotInSameRoute {
+            let joined: Vec<usize> = tasks.iter().flat_map(|task| self.unit_of(*task)).collect();
+            let group = self.task_groups.iter().flatten().max().map_or(0, |group| group + 1);
+            for task in joined {
+                self.task_groups[task] = Some(group);
+            }
+        }
+        self.relations.push((kind, tasks));
+        self
+    }
+
+    /// Rank jobs and shipments by priority according to `policy`
+    pub fn with_priority_policy(mut self, policy: PriorityPolicy) -> Self {
+        self.priority_policy = policy;
+        self
+    }
+
+    /// Build the first routes with `construction` instead of nearest neighbors
+    pub fn with_construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    /// Improve the constructed routes with `algorithm` instead of local search
+    ///
+    /// Quick search only constructs routes, whatever the algorithm.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Tune large neighborhood search, used when the algorithm is `Lns`
+    pub fn with_lns(mut self, lns: LnsOptions) -> Self {
+        self.lns = lns;
+        self
+    }
+
+    /// Draw the random decisions of the solver instances from `seed`
+    ///
+    /// The same problem solved with the same seed and number of threads yields the same
+    /// solution, unless the time limit cuts the search short.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Keep tasks at `locations`, and vehicles starting or ending at them, out of the routes
+    ///
+    /// Shipments are left out as a whole when either their pickup or delivery is at one of
+    /// the locations.
+    pub fn with_unreachable(mut self, locations: &HashSet<usize>) -> Self {
+        let vehicles: Vec<bool> = self
+            .vehicle_starts
+            .iter()
+            .zip(&self.vehicle_ends)
+            .map(|(start, end)| start.iter().chain(end).any(|l| locations.contains(l)))
+            .collect();
+        let shipments: HashSet<usize> = self
+            .tasks
+            .iter()
+            .zip(&self.task_locations)
+            .filter_map(|(task, location)| match task.kind {
+                TaskKind::Pickup(shipment) | TaskKind::Delivery(shipment)
+                    if locations.contains(location) =>
+                {
+                    Some(shipment)
+                }
+                _ => None,
+            })
+            .collect();
+
+        for (task, compatible) in self.compatible.iter_mut().enumerate() {
+            let unreachable = locations.contains(&self.task_locations[task])
+                || matches!(
+                    self.tasks[task].kind,
+                    TaskKind::Pickup(shipment) | TaskKind::Delivery(shipment)
+                        if shipments.contains(&shipment)
+                );
+            for (allowed, vehicle_unreachable) in compatible.iter_mut().zip(&vehicles) {
+                *allowed &= !unreachable && !vehicle_unreachable;
+            }
+        }
+        self
+    }
+
+    /// Settings of large neighborhood search
+    pub(crate) fn lns(&self) -> LnsOptions {
+        self.lns
+    }
+
+    /// Number of locked tasks at the start of a vehicle's route
+    pub(crate) fn locked_len(&self, vehicle: usize) -> usize {
+        self.locked[vehicle].len()
+    }
+
+    /// Travel matrix used by a vehicle
+    pub fn matrix_for(&self, vehicle: usize) -> &Matrix {
+        match self.vehicle_matrix[vehicle] {
+            Some(idx) => &self.vehicle_matrices[idx],
+            None => &self.matrix,
+        }
+    }
+
+    /// Compute the schedule of a vehicle serving `tasks` in order, if feasible
+    ///
+    /// A vehicle with shifts runs the route in the cheapest shift it fits in, the earliest
+    /// one on ties.
+    pub fn schedule(&self, vehicle: usize, tasks: &[usize]) -> Option<Schedule> {
+        if self.consecutive_groups && !self.groups_consecutive(tasks) {
+            return None;
+        }
+        if !self.relations.is_empty() && !self.keeps_relations(tasks) {
+            return None;
+        }
+        let Some(shifts) = &self.vehicles[vehicle].shifts else {
+            return self.schedule_departing(vehicle, tasks, None, None);
+        };
+        (0..shifts.len())
+            .filter_map(|shift| self.schedule_departing(vehicle, tasks, Some(shift), None))
+            .min_by(|a, b| {
+                self.schedule_cost(vehicle, a)
+                    .total_cmp(&self.schedule_cost(vehicle, b))
+            })
+    }
+
+    /// Delay the departure of a route so it no longer waits at the first task that has to
+    /// wait for its time window
+    ///
+    /// The delay is shortened until the route stays feasible without more lateness or a
+    /// later arrival at the end. Returns the new departure time with its schedule, or
+    /// `None` when the route cannot leave any later.
+    pub fn delay_departure(
+        &self,
+        vehicle: usize,
+        tasks: &[usize],
+        schedule: &Schedule,
+    ) -> Option<(i64, Schedule)> {
+        let departure = *schedule.departure_times.first()?;
+        let arrival = *schedule.arrival_times.last()?;
+        let vehicle_data = &self.vehicles[vehicle];
+        let mut delay = tasks
+            .iter()
+            .enumerate()
+            .map(|(position, task)| {
+                let service = vehicle_data.service_duration(self.tasks[*task].service);
+                schedule.departure_times[position + 1]
+                    - schedule.arrival_times[position + 1]
+                    - (schedule.setup_times[position] + service) as i64
+            })
+            .find(|waiting| *waiting > 0)?;
+
+        while delay > 0 {
+            let delayed = self
+                .schedule_departing(vehicle, tasks, schedule.shift, Some(departure + delay))
+                .filter(|delayed| {
+                    delayed.lateness <= schedule.lateness
+                        && delayed.arrival_times.last() <= Some(&arrival)
+                });
+            if let Some(delayed) = delayed {
+                return Some((departure + delay, delayed));
+            }
+            delay /= 2;
+        }
+
+        None
+    }
+
+    /// Compute the schedule of a vehicle serving `tasks` in order within `shift`, leaving
+    /// at `departure` instead of its usual departure time when given
+    fn schedule_departing(
+        &self,
+        vehicle: usize,
+        tasks: &[usize],
+        shift: Option<usize>,
+        departure: Option<i64>,
+    ) -> Option<Schedule> {
+        let mut state = self.shift_start_state(vehicle, shift);
+        if let Some(departure) = departure {
+            state.time = departure;
+            state.shift_start = departure;
+        }
+        let mut arrival_times = vec![state.time];
+        let mut departure_times = vec![state.time];
+        let mut setup_times = Vec::with_capacity(tasks.len());
+
+        for &task in tasks {
+            let visit = self.visit(&state, task)?;
+            arrival_times.push(visit.arrival_time);
+            departure_times.push(visit.state.time);
+            setup_times.push(visit.setup);
+            state = visit.state;
+        }
+
+        let state = self.finish(vehicle, &state)?;
+        arrival_times.push(state.time);
+        departure_times.push(state.time);
+
+        let stops: Vec<&Task> = tasks.iter().map(|task| &self.tasks[*task]).collect();
+        let reloads: Vec<usize> = state.reloads.iter().map(|reload| reload.after).collect();
+        let load_profile = load_profile(&stops, self.vehicles[vehicle].capacity.len(), &reloads);
+
+        Some(Schedule {
+            arrival_times,
+            departure_times,
+            setup_times,
+            travel_duration: state.travel_duration,
+            travel_distance: state.travel_distance,
+            load_profile,
+            breaks: state.breaks,
+            reloads: state.reloads,
+            lateness: state.lateness,
+            late_tasks: state.late_tasks,
+            waiting_time: state.waiting_time,
+            service_time: state.service_time,
+            rests: state.rests,
+            shift,
+        })
+    }
+
+    /// Whether the jobs of every group on a route directly follow each other
+    fn groups_consecutive(&self, tasks: &[usize]) -> bool {
+        let mut finished = Vec::new();
+        let mut current = None;
+        for task in tasks {
+            let group = self.tasks[*task].group.as_deref();
+            if group != current {
+                if group.is_some_and(|group| finished.contains(&group)) {
+                    return false;
+                }
+                finished.extend(current);
+                current = group;
+            }
+        }
+        true
+    }
+
+    /// Whether a route keeps the order and separation of the relations between its tasks
+    fn keeps_relations(&self, tasks: &[usize]) -> bool {
+        let position = |task: usize| tasks.iter().position(|t| *t == task);
+        self.relations.iter().all(|(kind, related)| match kind {
+            RelationType::InSameRoute => true,
+            RelationType::InSequence | RelationType::InDirectSequence => {
+                related.windows(2).all(|pair| {
+                    match (position(pair[0]), position(pair[1])) {
+                        (Some(first), Some(second)) if *kind == RelationType::InSequence => {
+                            first < second
+                        }
+                        (Some(first), Some(second)) => second == first + 1,
+                        _ => true,
+                    }
+                })
+            }
+            RelationType::NotInSameRoute => {
+                related.iter().filter(|task| tasks.contains(task)).count() <= 1
+            }
+        })
+    }
+
+    /// Tasks of a unit in an order that serves pickups before their delivery and keeps the
+    /// order of sequences, with direct sequences unbroken
+    ///
+    /// Tasks in cyclic sequences, which no route can serve, keep their order.
+    fn ordered_unit(&self, mut pending: Vec<usize>) -> Vec<usize> {
+        let sequences: Vec<(&[usize], bool)> = self
+            .relations
+            .iter()
+            .filter(|(_, related)| related.iter().any(|task| pending.contains(task)))
+            .filter_map(|(kind, related)| match kind {
+                RelationType::InSequence => Some((related.as_slice(), false)),
+                RelationType::InDirectSequence => Some((related.as_slice(), true)),
+                _ => None,
+            })
+            .collect();
+        if sequences.is_empty() {
+            return pending;
+        }
+
+        let mut ordered: Vec<usize> = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let ready = |task: usize| {
+                let picked_up = !matches!(self.tasks[task].kind, TaskKind::Delivery(_))
+                    || !pending.contains(&(task - 1));
+                picked_up
+                    && sequences.iter().all(|(sequence, _)| {
+                        sequence
+                            .windows(2)
+                            .all(|pair| pair[1] != task || !pending.contains(&pair[0]))
+                    })
+            };
+            // Continue a direct sequence with its next task first
+            let direct = ordered.last().and_then(|last| {
+                sequences
+                    .iter()
+                    .filter(|(_, direct)| *direct)
+                    .flat_map(|(sequence, _)| sequence.windows(2))
+                    .find(|pair| pair[0] == *last && pending.contains(&pair[1]) && ready(pair[1]))
+                    .map(|pair| pair[1])
+            });
+            let index = direct
+                .and_then(|task| pending.iter().position(|t| *t == task))
+                .or_else(|| pending.iter().position(|task| ready(*task)))
+                .unwrap_or(0);
+            ordered.push(pending.remove(index));
+        }
+        ordered
+    }
+
+    /// Seconds late when arriving after all `time_windows`, if late service is allowed
+    fn lateness(&self, time_windows: &[[i64; 2]], arrival_time: i64) -> Option<i64> {
+        let soft = self.soft_time_windows?;
+        let latest = time_windows.iter().map(|window| window[1]).max()?;
+        let lateness = arrival_time - latest;
+        soft.max_lateness
+            .is_none_or(|max| lateness <= max)
+            .then_some(lateness)
+    }
+
+    /// Tasks that must be inserted together, with shipment pickups directly before their delivery
+    /// and the jobs and shipments that share a route, by group or relation, in one unit
+    ///
+    /// Units are ordered by decreasing priority so scarce capacity goes to important work first.
+    /// Locked tasks are not part of any unit since they never move.
+    pub(crate) fn units(&self) -> Vec<Vec<usize>> {
+        let locked: HashSet<usize> = self.locked.iter().flatten().copied().collect();
+        let mut units: Vec<Vec<usize>> = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !locked.contains(idx))
+            .filter_map(|(idx, task)| match task.kind {
+                TaskKind::Job(_) => Some(vec![idx]),
+                TaskKind::Pickup(_) => Some(vec![idx, idx + 1]),
+                TaskKind::Delivery(_) => None,
+            })
+            .collect();
+
+        // Merge the units sharing a route into the first of them
+        let mut grouped: HashMap<usize, usize> = HashMap::new();
+        let mut index = 0;
+        while index < units.len() {
+            match self.task_groups[units[index][0]] {
+                Some(group) => match grouped.get(&group) {
+                    Some(&first) => {
+                        let unit = units.remove(index);
+                        units[first].extend(unit);
+                    }
+                    None => {
+                        grouped.insert(group, index);
+                        index += 1;
+                    }
+                },
+                None => index += 1,
+            }
+        }
+
+        let mut units: Vec<Vec<usize>> =
+            units.into_iter().map(|unit| self.ordered_unit(unit)).collect();
+        units.sort_by_key(|unit| {
+            std::cmp::Reverse(unit.iter().map(|task| self.tasks[*task].priority).max())
+        });
+        units
+    }
+
+    /// Objective penalty for leaving a unit unassigned
+    pub(crate) fn unassigned_penalty(&self, unit: &[usize]) -> f64 {
+        self.unassigned_penalty_at(self.unassigned_penalty, unit)
+    }
+
+    /// State at the start of a route, in the first shift of vehicles with shifts
+    fn start_state(&self, vehicle: usize) -> RouteState {
+        let shift = self.vehicles[vehicle].shifts.as_ref().map(|_| 0);
+        self.shift_start_state(vehicle, shift)
+    }
+
+    fn shift_start_state(&self, vehicle: usize, shift: Option<usize>) -> RouteState {
+        let vehicle_data = &self.vehicles[vehicle];
+        let dims = vehicle_data.capacity.len();
+        let earliest = vehicle_data.shift_window(shift).map(|tw| tw[0]).unwrap_or(0);
+        let time = vehicle_data
+            .departure_time
+            .map_or(earliest, |departure| departure.max(earliest));
+
+        RouteState {
+            vehicle,
+            shift,
+            location: self.vehicle_starts[vehicle],
+            time,
+            load: vec![0; dims],
+            peak_load: vec![0; dims],
+            open_shipments: Vec::new(),
+            visited: 0,
+            next_break: 0,
+            breaks: Vec::new(),
+            trips: 1,
+            reloads: Vec::new(),
+            load_type: None,
+            delivered: false,
+            picked_up: false,
+            travel_duration: 0.0,
+            travel_distance: 0.0,
+            lateness: 0,
+            late_tasks: 0,
+            waiting_time: 0,
+            service_time: 0,
+            shift_start: time,
+            driving: 0,
+            rests: Vec::new(),
+        }
+    }
+
+    fn visit(&self, state: &RouteState, task: usize) -> Option<Visit> {
+        let task_data = &self.tasks[task];
+        let location = self.task_locations[task];
+        let travelled = state.travel_duration;
+
+        // Check skills
+        if !self.compatible[task][state.vehicle] {
+            return None;
+        }
+
+        // Head back to reload first if the task no longer fits
+        let fits = self.loads_after(state, task).is_some()
+            && self.mixes_with(state, task)
+            && self.follows_load_order(state, task);
+        let reloaded;
+        let state = if !fits && self.can_reload(state) {
+            reloaded = self.reload(state)?;
+            &reloaded
+        } else {
+            state
+        };
+
+        let mut next = state.clone();
+
+        // A shipment must be picked up before it is delivered
+        match task_data.kind {
+            TaskKind::Pickup(shipment) => next.open_shipments.push(shipment),
+            TaskKind::Delivery(shipment) => {
+                let open = next.open_shipments.iter().position(|s| *s == shipment)?;
+                next.open_shipments.swap_remove(open);
+            }
+            TaskKind::Job(_) => {}
+        }
+
+        (next.load, next.peak_load) = self.loads_after(state, task)?;
+
+        // A vehicle that cannot mix load types keeps to the load type of the trip
+        if !self.mixes_with(state, task) {
+            return None;
+        }
+        let typed = task_data.load_types.as_ref().is_some_and(|types| !types.is_empty());
+        if typed && self.vehicles[state.vehicle].separates_load_types() {
+            next.load_type.get_or_insert(task);
+        }
+
+        // Deliveries and pickups keep to the required order within the trip
+        if !self.follows_load_order(state, task) {
+            return None;
+        }
+        let (delivers, picks_up) = job_directions(task_data);
+        next.delivered |= delivers;
+        next.picked_up |= picks_up;
+
+        let (travel_duration, travel_distance) = self.travel(state, Some(location))?;
+
+        // Take the breaks that cannot wait until after this task
+        self.pause(&mut next, travel_duration as i64, false)?;
+
+        let arrival_time = next.time + travel_duration as i64;
+
+        // Find the earliest time window we can still make, or serve late after the last one
+        let mut service_start_time = arrival_time;
+        if let Some(time_windows) = &task_data.time_windows {
+            match time_windows.iter().find(|window| arrival_time <= window[1]) {
+                Some(window) => service_start_time = service_start_time.max(window[0]),
+                None => {
+                    next.lateness += self.lateness(time_windows, arrival_time)?;
+                    next.late_tasks += 1;
+                }
+            }
+        }
+
+        // Setting up is only needed when arriving from another location
+        let setup = if state.location == Some(location) {
+            0
+        } else {
+            task_data.setup
+        };
+        let service = self.vehicles[state.vehicle].service_duration(task_data.service);
+
+        next.location = Some(location);
+        next.time = service_start_time + (setup + service) as i64;
+        next.visited += 1;
+        next.waiting_time += service_start_time - arrival_time;
+        next.service_time += (setup + service) as i64;
+        next.travel_duration += travel_duration;
+        next.travel_distance += travel_distance;
+
+        Some(Visit {
+            arrival_time,
+            waiting_time: service_start_time - arrival_time,
+            setup,
+            travel_duration: next.travel_duration - travelled,
+            state: next,
+        })
+    }
+
+    /// Load and peak load of the current trip after serving `task`, if within capacity
+    ///
+    /// Deliveries are on board from the start of the trip, pickups from the task onwards.
+    fn loads_after(&self, state: &RouteState, task: usize) -> Option<(Vec<u32>, Vec<u32>)> {
+        let task_data = &self.tasks[task];
+        let mut load = state.load.clone();
+        let mut peak_load = state.peak_load.clone();
+
+        let capacity = &self.vehicles[state.vehicle].capacity;
+        for (dim, limit) in capacity.iter().enumerate() {
+            let delivery = amount(&task_data.delivery, dim);
+            let pickup = amount(&task_data.pickup, dim);
+            let unload = amount(&task_data.unload, dim);
+
+            load[dim] = (load[dim] + pickup).saturating_sub(unload);
+            peak_load[dim] = (peak_load[dim] + delivery).max(load[dim]);
+
+            if peak_load[dim] > *limit {
+                return None;
+            }
+        }
+
+        Some((load, peak_load))
+    }
+
+    /// Whether `task` may share the current trip with the load type already carried
+    fn mixes_with(&self, state: &RouteState, task: usize) -> bool {
+        if !self.vehicles[state.vehicle].separates_load_types() {
+            return true;
+        }
+
+        let load_types = self.tasks[task].load_types.as_deref().unwrap_or_default();
+        state.load_type.is_none_or(|carried| {
+            load_types.is_empty() || self.tasks[carried].load_types.as_deref() == Some(load_types)
+        })
+    }
+
+    /// Whether serving `task` keeps the job deliveries and pickups of the current trip in
+    /// the required order
+    fn follows_load_order(&self, state: &RouteState, task: usize) -> bool {
+        let (delivers, picks_up) = job_directions(&self.tasks[task]);
+        match self.load_order {
+            LoadOrder::Mixed => true,
+            LoadOrder::Backhaul => !(delivers && state.picked_up),
+            LoadOrder::Separate => {
+                !(delivers && state.picked_up || picks_up && state.delivered)
+            }
+        }
+    }
+
+    /// Whether the vehicle can start another trip now
+    ///
+    /// Reloading needs a start location to return to, shipments on board must be delivered
+    /// first, and an empty trip gains nothing.
+    fn can_reload(&self, state: &RouteState) -> bool {
+        self.vehicle_starts[state.vehicle].is_some()
+            && state.trips < self.vehicles[state.vehicle].max_trips.unwrap_or(1)
+            && state.open_shipments.is_empty()
+            && state.peak_load.iter().any(|load| *load > 0)
+    }
+
+    /// Return to the start location and reload for the next trip
+    fn reload(&self, state: &RouteState) -> Option<RouteState> {
+        let vehicle = &self.vehicles[state.vehicle];
+        let depot = self.vehicle_starts[state.vehicle];
+        let (travel_duration, travel_distance) = self.travel(state, depot)?;
+
+        let mut next = state.clone();
+        self.pause(&mut next, travel_duration as i64, false)?;
+
+        let arrival_time = next.time + travel_duration as i64;
+        let departure_time = arrival_time + vehicle.reload_service.unwrap_or(0) as i64;
+        next.reloads.push(ReloadStop {
+            after: state.visited,
+            arrival_time,
+            departure_time,
+        });
+
+        next.location = depot;
+        next.time = departure_time;
+        next.trips += 1;
+        next.load_type = None;
+        next.delivered = false;
+        next.picked_up = false;
+        next.load.iter_mut().for_each(|load| *load = 0);
+        next.peak_load.iter_mut().for_each(|load| *load = 0);
+        next.travel_duration += travel_duration;
+        next.travel_distance += travel_distance;
+
+        Some(next)
+    }
+
+    /// Take the breaks and rests due before travelling a leg of `leg` seconds
+    ///
+    /// Breaks at least as long as a rest reset the continuous driving time. Fails when a
+    /// break can no longer be taken within its time windows.
+    fn pause(&self, state: &mut RouteState, leg: i64, force: bool) -> Option<()> {
+        let vehicle = &self.vehicles[state.vehicle];
+        let breaks = vehicle.breaks.as_deref().unwrap_or(&[]);
+        let (taken, missed) = take_breaks(
+            breaks,
+            &mut state.next_break,
+            &mut state.time,
+            leg,
+            force,
+            state.visited,
+        );
+        if !missed.is_empty() {
+            return None;
+        }
+
+        if let Some(rules) = &vehicle.working_rules {
+            if taken.iter().any(|taken| rests_driver(rules, taken)) {
+                state.driving = 0;
+            }
+            let rests = take_rests(
+                rules,
+                &mut state.driving,
+                &mut state.time,
+                leg,
+                state.visited,
+            );
+            state.rests.extend(rests);
+        }
+        state.breaks.extend(taken);
+
+        Some(())
+    }
+
+    /// Duration and distance of the leg from the current location of a route, free to or
+    /// from an open route end
+    ///
+    /// The duration is scaled by the traffic at the time the leg departs. Legs without a
+    /// finite duration cannot be travelled.
+    fn travel(&self, state: &RouteState, to: Option<usize>) -> Option<(f64, f64)> {
+        let (Some(from), Some(to)) = (state.location, to) else {
+            return Some((0.0, 0.0));
+        };
+        let (duration, distance) = self.matrix_for(state.vehicle).travel(from, to);
+        duration.is_finite().then(|| {
+            (
+                duration * traffic_factor(&self.traffic, state.time),
+                distance,
+            )
+        })
+    }
+
+    /// Whether the vehicle can travel from its start to the task and on to its end
+    pub fn reaches(&self, vehicle: usize, task: usize) -> bool {
+        let matrix = self.matrix_for(vehicle);
+        let location = self.task_locations[task];
+        let start = self.vehicle_starts[vehicle].map(|start| (start, location));
+        let end = self.vehicle_ends[vehicle].map(|end| (location, end));
+        start
+            .into_iter()
+            .chain(end)
+            .all(|(from, to)| matrix.travel(from, to).0.is_finite())
+    }
+
+    fn finish(&self, vehicle: usize, state: &RouteState) -> Option<RouteState> {
+        let next = self.finish_unchecked(vehicle, state)?;
+        self.limit_violations(&next).is_empty().then_some(next)
+    }
+
+    /// Head to the end location without checking the vehicle's route limits
+    fn finish_unchecked(&self, vehicle: usize, state: &RouteState) -> Option<RouteState> {
+        // Every shipment picked up must also be delivered
+        if !state.open_shipments.is_empty() {
+            return None;
+        }
+
+        let end = self.vehicle_ends[vehicle];
+        let (travel_duration, travel_distance) = self.travel(state, end)?;
+
+        // All remaining breaks are taken before heading to the end location
+        let mut next = state.clone();
+        self.pause(&mut next, travel_duration as i64, true)?;
+
+        let arrival_time = next.time + travel_duration as i64;
+
+        if let Some(time_window) = self.vehicles[vehicle].shift_window(state.shift) {
+            if arrival_time > time_window[1] {
+                return None;
+            }
+        }
+
+        next.location = end.or(state.location);
+        next.time = arrival_time;
+        next.travel_duration += travel_duration;
+        next.travel_distance += travel_distance;
+
+        Some(next)
+    }
+
+    /// Route limits of the vehicle exceeded by a finished route
+    fn limit_violations(&self, state: &RouteState) -> Vec<Violation> {
+        let vehicle = &self.vehicles[state.vehicle];
+        let mut violations =
+            vehicle.limit_violations(state.travel_duration, state.travel_distance, state.visited);
+        violations.extend(
+            vehicle
+                .working_rules
+                .and_then(|rules| rules.shift_violation(state.time - state.shift_start)),
+        );
+        violations
+    }
+
+    /// Vehicle limits that keep an unassigned job or shipment out of every route
+    ///
+    /// Tries every insertion of the task's job or shipment into the routes of `solution`.
+    /// Returns the limits exceeded by insertions that are otherwise feasible; the result is
+    /// empty when no insertion would be feasible even without limits.
+    pub fn blocking_limits(&self, solution: &Solution, task: usize) -> Vec<ViolationType> {
+        let unit = self.unit_of(task);
+        let routes = solution_routes(self, solution);
+
+        let mut limits = Vec::new();
+        for (vehicle, route) in routes.iter().enumerate() {
+            for candidate in insertions(route, &unit, self.locked_len(vehicle)) {
+                let Some(violations) = self.unchecked_limit_violations(vehicle, &candidate) else {
+                    continue;
+                };
+                if violations.is_empty() {
+                    return Vec::new();
+                }
+                for violation in violations {
+                    if !limits.contains(&violation.kind) {
+                        limits.push(violation.kind);
+                    }
+                }
+            }
+        }
+
+        limits
+    }
+
+    /// Amounts each limited depot stock supplies to `routes`, indexed like the stocks
+    pub(crate) fn stock_usage(&self, routes: &[Vec<usize>]) -> Vec<Vec<u32>> {
+        let mut usage: Vec<Vec<u32>> = self
+            .stocks
+            .iter()
+            .map(|stock| vec![0; stock.amounts.len()])
+            .collect();
+        for (vehicle, route) in routes.iter().enumerate() {
+            self.draw_stock(&mut usage, vehicle, route);
+        }
+        usage
+    }
+
+    /// Add the deliveries of `tasks` served by `vehicle` to the usage of its stock
+    pub(crate) fn draw_stock(&self, usage: &mut [Vec<u32>], vehicle: usize, tasks: &[usize]) {
+        let Some(stock) = self.vehicle_stock[vehicle] else {
+            return;
+        };
+        for (dim, used) in usage[stock].iter_mut().enumerate() {
+            *used += tasks
+                .iter()
+                .map(|task| amount(&self.tasks[*task].delivery, dim))
+                .sum::<u32>();
+        }
+    }
+
+    /// Whether the stock supplying `vehicle` still covers the deliveries of `added` once
+    /// those of `removed` are no longer drawn from it
+    ///
+    /// Only deliveries of jobs are loaded at the depot; shipments bring their own goods.
+    pub(crate) fn within_stock(
+        &self,
+        usage: &[Vec<u32>],
+        vehicle: usize,
+        added: &[usize],
+        removed: &[usize],
+    ) -> bool {
+        let Some(stock) = self.vehicle_stock[vehicle] else {
+            return true;
+        };
+
+        let total = |tasks: &[usize], dim: usize| {
+            tasks
+                .iter()
+                .map(|task| amount(&self.tasks[*task].delivery, dim))
+                .sum::<u32>()
+        };
+        self.stocks[stock]
+            .amounts
+            .iter()
+            .enumerate()
+            .all(|(dim, available)| {
+                let added = total(added, dim);
+                added == 0
+                    || (usage[stock][dim] + added).saturating_sub(total(removed, dim)) <= *available
+            })
+    }
+
+    /// Whether two vehicles draw from the same stock, so moving work between them uses none
+    pub(crate) fn shares_stock(&self, a: usize, b: usize) -> bool {
+        self.vehicle_stock[a] == self.vehicle_stock[b]
+    }
+
+    /// Whether depot stock alone keeps an unassigned task out of every vehicle that could
+    /// otherwise carry it
+    pub fn out_of_stock(&self, solution: &Solution, task: usize) -> bool {
+        let usage = self.stock_usage(&solution_routes(self, solution));
+        let unit = self.unit_of(task);
+        let mut carriers = (0..self.vehicles.len())
+            .filter(|vehicle| self.fits_capacity(*vehicle, task))
+            .peekable();
+        carriers.peek().is_some()
+            && carriers.all(|vehicle| !self.within_stock(&usage, vehicle, &unit, &[]))
+    }
+
+    /// Depots whose stock could not supply some of the unassigned jobs
+    pub fn stock_outs(&self, solution: &Solution) -> Vec<StockOut> {
+        let usage = self.stock_usage(&solution_routes(self, solution));
+        self.stocks
+            .iter()
+            .enumerate()
+            .filter_map(|(stock, depot)| {
+                let jobs: Vec<u64> = solution
+                    .unassigned
+                    .iter()
+                    .filter(|task| {
+                        (0..self.vehicles.len()).any(|vehicle| {
+                            self.vehicle_stock[vehicle] == Some(stock)
+                                && self.fits_capacity(vehicle, **task)
+                                && !self.within_stock(&usage, vehicle, &[**task], &[])
+                        })
+                    })
+                    .map(|task| self.tasks[*task].id)
+                    .collect();
+                (!jobs.is_empty()).then(|| StockOut {
+                    depot_id: depot.depot_id,
+                    delivered: usage[stock].clone(),
+                    jobs,
+                })
+            })
+            .collect()
+    }
+
+    /// Lower bound on the cost of any solution, pricing each job or shipment left unassigned
+    /// at `unassigned_penalty` times `1 + priority`
+    ///
+    /// Every served task is reached over a leg of its own, so a job or shipment costs at
+    /// least the shortest duration and distance into its tasks and their service on the
+    /// cheapest vehicle allowed to serve it, unless leaving it unassigned costs less. Fixed
+    /// costs, waiting, setup and the legs to the end locations are left out.
+    pub fn lower_bound(&self, unassigned_penalty: f64) -> f64 {
+        let locked: HashSet<usize> = self.locked.iter().flatten().copied().collect();
+
+        // The nearest neighbours only depend on the matrix, so vehicles share them
+        let mut nearest: HashMap<Option<usize>, Vec<(f64, f64)>> = HashMap::new();
+        for vehicle in 0..self.vehicles.len() {
+            nearest
+                .entry(self.vehicle_matrix[vehicle])
+                .or_insert_with(|| self.nearest_arrivals(self.matrix_for(vehicle)));
+        }
+        let arrivals: Vec<Vec<f64>> = (0..self.vehicles.len())
+            .map(|vehicle| {
+                let into = &nearest[&self.vehicle_matrix[vehicle]];
+                // A route without a start begins at its first task for free
+                (0..self.tasks.len())
+                    .map(|task| match self.vehicle_starts[vehicle] {
+                        Some(start) => {
+                            let (duration, distance) = into[task];
+                            self.travel_cost(vehicle, duration, distance)
+                                .min(self.leg_cost(vehicle, start, self.task_locations[task]))
+                        }
+                        None => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let service_cost = |vehicle: usize, task: usize| {
+            let vehicle = &self.vehicles[vehicle];
+            let service = vehicle.service_duration(self.tasks[task].service);
+            vehicle
+                .route_cost(0.0, 0.0, 0.0, service as f64)
+                .service_time
+        };
+
+        self.tasks
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, task)| match task.kind {
+                TaskKind::Job(_) => Some(vec![idx]),
+                TaskKind::Pickup(_) => Some(vec![idx, idx + 1]),
+                TaskKind::Delivery(_) => None,
+            })
+            .map(|unit| {
+                let served = (0..self.vehicles.len())
+                    .filter(|vehicle| unit.iter().all(|task| self.compatible[*task][*vehicle]))
+                    .map(|vehicle| {
+                        unit.iter()
+                            .map(|task| arrivals[vehicle][*task] + service_cost(vehicle, *task))
+                            .sum::<f64>()
+                    })
+                    .fold(f64::INFINITY, f64::min);
+                if unit.iter().any(|task| locked.contains(task)) {
+                    served
+                } else {
+                    served.min(self.unassigned_penalty_at(unassigned_penalty, &unit))
+                }
+            })
+            .filter(|cost| cost.is_finite())
+            .sum()
+    }
+
+    /// Penalty `penalty` scaled by the priority of a unit, like the unassigned penalty
+    ///
+    /// Each job of a group counts on its own; a shipment counts once.
+    fn unassigned_penalty_at(&self, penalty: f64, unit: &[usize]) -> f64 {
+        unit.iter()
+            .filter(|task| !matches!(self.tasks[**task].kind, TaskKind::Delivery(_)))
+            .map(|task| penalty * (1.0 + self.tasks[*task].priority as f64))
+            .sum()
+    }
+
+    /// Shortest duration and shortest distance into each task from any other task, taken
+    /// separately and zero when another task shares its location
+    fn nearest_arrivals(&self, matrix: &Matrix) -> Vec<(f64, f64)> {
+        let mut tasks_at: HashMap<usize, usize> = HashMap::new();
+        for location in &self.task_locations {
+            *tasks_at.entry(*location).or_default() += 1;
+        }
+        let nearest: HashMap<usize, (f64, f64)> = tasks_at
+            .iter()
+            .map(|(&to, &count)| {
+                if count > 1 {
+                    return (to, (0.0, 0.0));
+                }
+                let shortest = tasks_at
+                    .keys()
+                    .filter(|from| **from != to)
+                    .map(|from| matrix.travel(*from, to))
+                    .filter(|(duration, distance)| duration.is_finite() && distance.is_finite())
+                    .fold((f64::INFINITY, f64::INFINITY), |(duration, distance), leg| {
+                        (duration.min(leg.0), distance.min(leg.1))
+                    });
+                (to, shortest)
+            })
+            .collect();
+        self.task_locations
+            .iter()
+            .map(|location| nearest[location])
+            .collect()
+    }
+
+    /// Travel cost of `vehicle` between two locations, infinite when it cannot get there
+    fn leg_cost(&self, vehicle: usize, from: usize, to: usize) -> f64 {
+        let (duration, distance) = self.matrix_for(vehicle).travel(from, to);
+        self.travel_cost(vehicle, duration, distance)
+    }
+
+    /// Cost of `vehicle` travelling a duration and distance, infinite when either is
+    fn travel_cost(&self, vehicle: usize, duration: f64, distance: f64) -> f64 {
+        if !duration.is_finite() || !distance.is_finite() {
+            return f64::INFINITY;
+        }
+        let cost = self.vehicles[vehicle].route_cost(duration, distance, 0.0, 0.0);
+        cost.travel_time + cost.distance
+    }
+
+    /// Whether a vehicle able to serve a task could carry the amounts of its job or shipment
+    pub fn fits_capacity(&self, vehicle: usize, task: usize) -> bool {
+        let capacity = &self.vehicles[vehicle].capacity;
+        let compatible = self.compatible[task][vehicle];
+        let task = &self.tasks[task];
+        compatible
+            && [&task.delivery, &task.pickup, &task.unload]
+                .into_iter()
+                .all(|amounts| (0..capacity.len()).all(|dim| amount(amounts, dim) <= capacity[dim]))
+    }
+
+    /// Whether a vehicle could serve a task's job or shipment with no other work besides
+    /// its locked tasks
+    pub fn serves_alone(&self, vehicle: usize, task: usize) -> bool {
+        let mut tasks = self.locked[vehicle].clone();
+        tasks.extend(self.unit_of(task));
+        self.schedule(vehicle, &tasks).is_some()
+    }
+
+    /// Problem of serving `tasks` with `vehicles` only, over a matrix of just their locations
+    ///
+    /// `tasks` must hold whole shipments and be in ascending order; tasks and vehicles of
+    /// the subproblem are numbered by their position in `tasks` and `vehicles`. The
+    /// subproblem is only solved, so it carries the tasks without their jobs and shipments.
+    fn subproblem(&self, vehicles: &[usize], tasks: &[usize]) -> Problem {
+        let mut locations = Vec::new();
+        let mut positions = HashMap::new();
+        let mut locate = |location: usize| {
+            *positions.entry(location).or_insert_with(|| {
+                locations.push(location);
+                locations.len() - 1
+            })
+        };
+
+        let vehicle_starts = vehicles
+            .iter()
+            .map(|vehicle| self.vehicle_starts[*vehicle].map(&mut locate))
+            .collect();
+        let vehicle_ends = vehicles
+            .iter()
+            .map(|vehicle| self.vehicle_ends[*vehicle].map(&mut locate))
+            .collect();
+        let task_locations = tasks
+            .iter()
+            .map(|task| locate(self.task_locations[*task]))
+            .collect();
+
+        let mut matrices = HashMap::new();
+        let mut vehicle_matrices = Vec::new();
+        let vehicle_matrix = vehicles
+            .iter()
+            .map(|vehicle| {
+                let idx = self.vehicle_matrix[*vehicle]?;
+                Some(*matrices.entry(idx).or_insert_with(|| {
+                    vehicle_matrices.push(self.vehicle_matrices[idx].submatrix(&locations));
+                    vehicle_matrices.len() - 1
+                }))
+            })
+            .collect();
+
+        Problem {
+            vehicles: vehicles.iter().map(|v| self.vehicles[*v].clone()).collect(),
+            jobs: Vec::new(),
+            shipments: Vec::new(),
+            tasks: tasks.iter().map(|task| self.tasks[*task].clone()).collect(),
+            matrix: self.matrix.submatrix(&locations),
+            vehicle_matrices,
+            vehicle_matrix,
+            task_locations,
+            vehicle_starts,
+            vehicle_ends,
+            compatible: tasks
+                .iter()
+                .map(|task| {
+                    vehicles
+                        .iter()
+                        .map(|v| self.compatible[*task][*v])
+                        .collect()
+                })
+                .collect(),
+            task_groups: tasks.iter().map(|task| self.task_groups[*task]).collect(),
+            consecutive_groups: self.consecutive_groups,
+            relations: self
+                .relations
+                .iter()
+                .map(|(kind, related)| {
+                    let related = related
+                        .iter()
+                        .filter_map(|task| tasks.iter().position(|t| t == task))
+                        .collect();
+                    (*kind, related)
+                })
+                .collect(),
+            balance: self.balance,
+            traffic: self.traffic.clone(),
+            locked: vec![Vec::new(); vehicles.len()],
+            quick: self.quick,
+            time_limit: self.time_limit,
+            soft_time_windows: self.soft_time_windows,
+            load_order: self.load_order,
+            unassigned_penalty: self.unassigned_penalty,
+            construction: self.construction,
+            priority_policy: self.priority_policy,
+            algorithm: self.algorithm,
+            lns: self.lns,
+            seed: self.seed,
+            initial: vec![Vec::new(); vehicles.len()],
+            insertion: None,
+            repaired: Vec::new(),
+            stocks: self.stocks.clone(),
+            vehicle_stock: vehicles.iter().map(|v| self.vehicle_stock[*v]).collect(),
+        }
+    }
+
+    /// Tasks of the job or shipment a task belongs to, with the tasks that share its route
+    fn unit_of(&self, task: usize) -> Vec<usize> {
+        if let Some(group) = self.task_groups[task] {
+            let unit = (0..self.tasks.len())
+                .filter(|other| self.task_groups[*other] == Some(group))
+                .collect();
+            return self.ordered_unit(unit);
+        }
+        match self.tasks[task].kind {
+            TaskKind::Job(_) => vec![task],
+            TaskKind::Pickup(_) => vec![task, task + 1],
+            TaskKind::Delivery(_) => vec![task - 1, task],
+        }
+    }
+
+    /// Route limits exceeded by a vehicle serving `tasks`, if the route is otherwise feasible
+    fn unchecked_limit_violations(
+        &self,
+        vehicle: usize,
+        tasks: &[usize],
+    ) -> Option<Vec<Violation>> {
+        let mut state = self.start_state(vehicle);
+        for &task in tasks {
+            state = self.visit(&state, task)?.state;
+        }
+        let state = self.finish_unchecked(vehicle, &state)?;
+        Some(self.limit_violations(&state))
+    }
+
+    pub(crate) fn route_cost(&self, vehicle: usize, tasks: &[usize]) -> Option<f64> {
+        self.evaluate(vehicle, tasks).map(|(cost, _)| cost)
+    }
+
+    /// Cost and workload of a vehicle serving `tasks` in order, if feasible
+    pub(crate) fn evaluate(&self, vehicle: usize, tasks: &[usize]) -> Option<(f64, f64)> {
+        if tasks.is_empty() {
+            return Some((0.0, 0.0));
+        }
+
+        let schedule = self.schedule(vehicle, tasks)?;
+        let workload = match self.balance.map(|balance| balance.metric) {
+            Some(BalanceMetric::Tasks) => tasks.len() as f64,
+            _ => {
+                (schedule.arrival_times[schedule.arrival_times.len() - 1]
+                    - schedule.departure_times[0]) as f64
+            }
+        };
+        Some((self.schedule_cost(vehicle, &schedule), workload))
+    }
+
+    /// Workload of every vehicle's route
+    pub(crate) fn workloads(&self, routes: &[Vec<usize>]) -> Vec<f64> {
+        routes
+            .iter()
+            .enumerate()
+            .map(|(vehicle, tasks)| {
+                self.evaluate(vehicle, tasks)
+                    .map_or(0.0, |(_, workload)| workload)
+            })
+            .collect()
+    }
+
+    /// Objective penalty for the spread between the busiest and the least busy vehicle
+    fn balance_penalty(&self, workloads: &[f64]) -> f64 {
+        let Some(balance) = self.balance else {
+            return 0.0;
+        };
+
+        let busiest = workloads.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let idlest = workloads.iter().copied().fold(f64::INFINITY, f64::min);
+        let excess = (busiest - idlest - balance.max_deviation).max(0.0);
+        if balance.hard {
+            excess * HARD_BALANCE_PENALTY
+        } else {
+            excess * balance.weight
+        }
+    }
+
+    /// Change of the balance penalty when some vehicles get new workloads
+    pub(crate) fn balance_delta(&self, workloads: &[f64], changes: &[(usize, f64)]) -> f64 {
+        if self.balance.is_none() {
+            return 0.0;
+        }
+
+        let mut updated = workloads.to_vec();
+        for (vehicle, workload) in changes {
+            updated[*vehicle] = *workload;
+        }
+        self.balance_penalty(&updated) - self.balance_penalty(workloads)
+    }
+
+    fn has_hard_balance(&self) -> bool {
+        self.balance.is_some_and(|balance| balance.hard)
+    }
+
+    /// Cost of a vehicle driving a route with `schedule`, including any late service
+    fn schedule_cost(&self, vehicle: usize, schedule: &Schedule) -> f64 {
+        let mut cost = self.vehicles[vehicle].route_cost(
+            schedule.travel_duration,
+            schedule.travel_distance,
+            schedule.waiting_time as f64,
+            schedule.service_time as f64,
+        );
+        if let Some(soft) = self.soft_time_windows {
+            cost.violations = soft.cost(schedule.lateness, schedule.late_tasks);
+        }
+        cost.total()
+    }
+}
+
+/// Group of each task, numbering the groups of `tasks` in order of appearance
+fn task_groups(tasks: &[Task]) -> Vec<Option<usize>> {
+    let mut names: Vec<&str> = Vec::new();
+    tasks
+        .iter()
+        .map(|task| {
+            let name = task.group.as_deref()?;
+            Some(names.iter().position(|known| *known == name).unwrap_or_else(|| {
+                names.push(name);
+                names.len() - 1
+            }))
+        })
+        .collect()
+}
+
+/// Amount for a capacity dimension, treating missing entries as zero
+fn amount(amounts: &[u32], dim: usize) -> u32 {
+    amounts.get(dim).copied().unwrap_or(0)
+}
+
+/// Take the pending breaks that are due before travelling a leg of `leg` seconds
+///
+/// Breaks are taken in order, starting at `next`. A break is due when one of its
+/// windows is already open, when waiting for the leg would make it miss its
+/// window, or when `force` is set. The vehicle waits for the window to open if
+/// needed. Returns the breaks taken together with the indices of breaks whose
+/// windows have all passed; `next` and `time` are advanced past both.
+pub fn take_breaks(
+    breaks: &[Break],
+    next: &mut usize,
+    time: &mut i64,
+    leg: i64,
+    force: bool,
+    after: usize,
+) -> (Vec<BreakStop>, Vec<usize>) {
+    let mut taken = Vec::new();
+    let mut missed = Vec::new();
+
+    while let Some(brk) = breaks.get(*next) {
+        let Some(window) = brk.time_windows.iter().find(|window| *time <= window[1]) else {
+            missed.push(*next);
+            *next += 1;
+            continue;
+        };
+
+        if !force && *time < window[0] && *time + leg <= window[1] {
+            break;
+        }
+
+        let start = (*time).max(window[0]);
+        taken.push(BreakStop {
+            index: *next,
+            after,
+            arrival_time: *time,
+            departure_time: start + brk.service as i64,
+        });
+        *time = start + brk.service as i64;
+        *next += 1;
+    }
+
+    (taken, missed)
+}
+
+/// Take a break at a fixed position of a route, waiting for its next window to open
+///
+/// A break whose windows have all passed is taken right away. `time` is advanced past the
+/// break.
+pub fn take_break_now(brk: &Break, index: usize, time: &mut i64, after: usize) -> BreakStop {
+    let start = brk
+        .time_windows
+        .iter()
+        .find(|window| *time <= window[1])
+        .map_or(*time, |window| (*time).max(window[0]));
+
+    let taken = BreakStop {
+        index,
+        after,
+        arrival_time: *time,
+        departure_time: start + brk.service as i64,
+    };
+    *time = taken.departure_time;
+    taken
+}
+
+/// Whether a break is long enough to count as a rest under the working rules
+pub fn rests_driver(rules: &WorkingRules, taken: &BreakStop) -> bool {
+    taken.departure_time - taken.arrival_time >= rules.rest_duration as i64
+}
+
+/// Take the rests the working rules require while driving a leg of `leg` seconds
+///
+/// `driving` is the driving time since the last rest. A driver who has already been driving
+/// rests at the previous stop when the leg would exceed the limit, and rests en route when
+/// the rest of the leg is still longer than the limit. `time` is advanced past the rests but
+/// not the leg; `driving` includes the leg afterwards.
+pub fn take_rests(
+    rules: &WorkingRules,
+    driving: &mut i64,
+    time: &mut i64,
+    leg: i64,
+    after: usize,
+) -> Vec<RestStop> {
+    let mut rests = Vec::new();
+    let Some(max) = rules.max_continuous_driving.filter(|max| *max > 0) else {
+        *driving += leg;
+        return rests;
+    };
+    let (max, rest) = (max as i64, rules.rest_duration as i64);
+
+    if *driving > 0 && *driving + leg > max {
+        rests.push(RestStop {
+            after,
+            en_route: false,
+            arrival_time: *time,
+            departure_time: *time + rest,
+        });
+        *time += rest;
+        *driving = 0;
+    }
+
+    let mut driven = 0;
+    while *driving + leg - driven > max {
+        driven += max - *driving;
+        rests.push(RestStop {
+            after,
+            en_route: true,
+            arrival_time: *time + driven,
+            departure_time: *time + driven + rest,
+        });
+        *time += rest;
+        *driving = 0;
+    }
+    *driving += leg - driven;
+
+    rests
+}
+
+/// Whether a task is a job delivering goods from the start of its trip, and whether it is
+/// a job picking up goods to bring back
+fn job_directions(task: &Task) -> (bool, bool) {
+    match task.kind {
+        TaskKind::Job(_) => (
+            task.delivery.iter().any(|amount| *amount > 0),
+            task.pickup.iter().any(|amount| *amount > 0),
+        ),
+        _ => (false, false),
+    }
+}
+
+/// Load of a vehicle at the start, after each stop and at the end of a route
+///
+/// Deliveries of standalone jobs are on board from the start of their trip. The
+/// vehicle reloads before the stops at the positions in `reloads`, unloading what
+/// it picked up. The profile has at least `dims` dimensions, more if the stops
+/// carry additional ones.
+pub fn load_profile(stops: &[&Task], dims: usize, reloads: &[usize]) -> Vec<Vec<i32>> {
+    let dims = stops
+        .iter()
+        .flat_map(|task| [&task.delivery, &task.pickup, &task.unload])
+        .map(|amounts| amounts.len())
+        .fold(dims, usize::max);
+
+    // Deliveries loaded for the trip starting before the stop at `position`
+    let trip_load = |position: usize| -> Vec<i32> {
+        let end = reloads
+            .iter()
+            .copied()
+            .filter(|after| *after > position)
+            .min()
+            .unwrap_or(stops.len());
+        (0..dims)
+            .map(|dim| {
+                stops[position..end]
+                    .iter()
+                    .map(|task| amount(&task.delivery, dim) as i32)
+                    .sum()
+            })
+            .collect()
+    };
+
+    let mut load = trip_load(0);
+    let mut profile = vec![load.clone()];
+    for (position, task) in stops.iter().enumerate() {
+        if position > 0 && reloads.contains(&position) {
+            load = trip_load(position);
+        }
+        for (dim, value) in load.iter_mut().enumerate() {
+            *value -= amount(&task.delivery, dim) as i32;
+            *value += amount(&task.pickup, dim) as i32;
+            *value -= amount(&task.unload, dim) as i32;
+        }
+        profile.push(load.clone());
+    }
+    profile.push(load);
+
+    profile
+}
+
+/// Solve the problem with `threads` concurrent solver instances and keep the best solution
+pub fn solve(problem: &Problem, threads: usize) -> Solution {
+    solve_with_progress(problem, threads, &|_| {})
+}
+
+/// Solve like [`solve`], calling `progress` each time any instance improves on the best solution
+pub fn solve_with_progress(
+    problem: &Problem,
+    threads: usize,
+    progress: &(dyn Fn(&Solution) + Sync),
+) -> Solution {
+    run_instances(problem, threads, progress)
+        .into_iter()
+        .min_by(|a, b| a.cost.total_cmp(&b.cost))
+        .expect("at least one solver instance")
+}
+
+/// Solve like [`solve_with_progress`] and also keep up to `count` runner-up solutions
+///
+/// At least `count + 1` instances run. Solutions that assign the same tasks to the same
+/// vehicles, in any order, count once; the runners-up are ordered by cost.
+pub fn solve_alternatives(
+    problem: &Problem,
+    threads: usize,
+    count: usize,
+    progress: &(dyn Fn(&Solution) + Sync),
+) -> (Solution, Vec<Solution>) {
+    let mut candidates = run_instances(problem, threads.max(count + 1), progress);
+    candidates.sort_by(|a, b| a.cost.total_cmp(&b.cost));
+
+    let mut seen = HashSet::new();
+    candidates.retain(|solution| seen.insert(solution.assignment()));
+    candidates.truncate(count + 1);
+
+    let best = candidates.remove(0);
+    (best, candidates)
+}
+
+/// Run `threads` concurrent solver instances and collect their solutions
+fn run_instances(
+    problem: &Problem,
+    threads: usize,
+    progress: &(dyn Fn(&Solution) + Sync),
+) -> Vec<Solution> {
+    let threads = threads.max(1);
+    let incumbent = &Incumbent {
+        cost: Mutex::new(f64::INFINITY),
+        progress,
+        deadline: problem.time_limit.map(|limit| Instant::now() + limit),
+    };
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|worker| scope.spawn(move || run_instance(problem, worker, incumbent)))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("solver thread panicked"))
+            .collect()
+    })
+}
+
+/// Solve a large problem cluster-first, route-second
+///
+/// The jobs and shipments are swept into clusters of about `cluster_size` around the
+/// vehicles, each served by its own group of vehicles. The clusters are solved as
+/// independent problems on `threads` threads, after which work a cluster could not serve
+/// is offered to all routes. Problems with locked routes, limited depot stock or only
+/// insertions, or too small to split, are solved as a whole.
+pub fn solve_clustered(problem: &Problem, threads: usize, cluster_size: usize) -> Solution {
+    // Clusters would each draw from the whole stock of a shared depot
+    let locked = problem.locked.iter().any(|tasks| !tasks.is_empty());
+    let clusters = if locked || !problem.stocks.is_empty() || problem.insertion.is_some() {
+        Vec::new()
+    } else {
+        sweep_clusters(problem, cluster_size.max(1))
+    };
+    if clusters.len() < 2 {
+        return solve(problem, threads);
+    }
+
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let solved = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, clusters.len()) {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                let Some((vehicles, tasks)) = clusters.get(idx) else {
+                    break;
+                };
+
+                // Clusters solved later only get what is left of the time limit
+                let mut subproblem = problem.subproblem(vehicles, tasks);
+                subproblem.time_limit = problem
+                    .time_limit
+                    .map(|limit| limit.saturating_sub(started.elapsed()));
+                let solution = solve(&subproblem, 1);
+                solved.lock().unwrap().push((idx, solution));
+            });
+        }
+    });
+
+    let mut routes = vec![Vec::new(); problem.vehicles.len()];
+    for (idx, solution) in solved.into_inner().unwrap() {
+        let (vehicles, tasks) = &clusters[idx];
+        for route in solution.routes {
+            routes[vehicles[route.vehicle]] = route.tasks.iter().map(|task| tasks[*task]).collect();
+        }
+    }
+
+    insert_unassigned(problem, &mut routes);
+    enforce_priorities(problem, &mut routes);
+    build_solution(problem, routes)
+}
+
+/// Sweep the jobs and shipments into clusters by their angle around the vehicles
+///
+/// The vehicles are split by the angle of their start into as many groups as there are
+/// clusters, at most one per vehicle. Each cluster then takes the next jobs and shipments in
+/// angle order until it holds its share of the work or its vehicles could carry no more.
+/// Returns the vehicles and tasks of each cluster, or nothing when there is one cluster.
+fn sweep_clusters(problem: &Problem, cluster_size: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut units = problem.units();
+    let count = units
+        .len()
+        .div_ceil(cluster_size)
+        .min(problem.vehicles.len());
+    if count < 2 {
+        return Vec::new();
+    }
+
+    // Sweep around the vehicles' depots, or around the work when no vehicle has one
+    let depots: Vec<[f64; 2]> = problem
+        .vehicles
+        .iter()
+        .filter_map(|vehicle| vehicle.start.or(vehicle.end))
+        .collect();
+    let points = if depots.is_empty() {
+        units
+            .iter()
+            .map(|unit| problem.tasks[unit[0]].location)
+            .collect()
+    } else {
+        depots
+    };
+    let center = points.iter().fold([0.0, 0.0], |sum, point| {
+        [
+            sum[0] + point[0] / points.len() as f64,
+            sum[1] + point[1] / points.len() as f64,
+        ]
+    });
+    let angle = |point: [f64; 2]| (point[1] - center[1]).atan2(point[0] - center[0]);
+
+    let mut vehicles: Vec<usize> = (0..problem.vehicles.len()).collect();
+    vehicles.sort_by(|a, b| {
+        let depot = |vehicle: usize| {
+            let vehicle = &problem.vehicles[vehicle];
+            vehicle.start.or(vehicle.end).unwrap_or(center)
+        };
+        angle(depot(*a)).total_cmp(&angle(depot(*b)))
+    });
+    units.sort_by(|a, b| {
+        angle(problem.tasks[a[0]].location).total_cmp(&angle(problem.tasks[b[0]].location))
+    });
+
+    let dims = problem
+        .vehicles
+        .iter()
+        .map(|vehicle| vehicle.capacity.len())
+        .max()
+        .unwrap_or(0);
+    let total = units.len();
+    let mut units = units.into_iter().peekable();
+    let mut assigned = 0;
+    let mut clusters = Vec::new();
+
+    for cluster in 0..count {
+        let group =
+            &vehicles[cluster * vehicles.len() / count..(cluster + 1) * vehicles.len() / count];
+        let share = total * (cluster + 1) / count;
+        let capacity: Vec<u64> = (0..dims)
+            .map(|dim| {
+                group
+                    .iter()
+                    .map(|vehicle| {
+                        let vehicle = &problem.vehicles[*vehicle];
+                        amount(&vehicle.capacity, dim) as u64
+                            * vehicle.max_trips.unwrap_or(1) as u64
+                    })
+                    .sum()
+            })
+            .collect();
+
+        let mut load = vec![0u64; dims];
+        let mut tasks = Vec::new();
+        while let Some(unit) = units.peek() {
+            let first = &problem.tasks[unit[0]];
+            let demand: Vec<u64> = (0..dims)
+                .map(|dim| (amount(&first.delivery, dim) + amount(&first.pickup, dim)) as u64)
+                .collect();
+            let full =
+                assigned >= share || (0..dims).any(|dim| load[dim] + demand[dim] > capacity[dim]);
+            if full && !tasks.is_empty() && cluster + 1 < count {
+                break;
+            }
+
+            (0..dims).for_each(|dim| load[dim] += demand[dim]);
+            tasks.extend(units.next().into_iter().flatten());
+            assigned += 1;
+        }
+
+        tasks.sort_unstable();
+        clusters.push((group.to_vec(), tasks));
+    }
+
+    clusters
+}
+
+/// Run a single construction and improvement pass
+///
+/// Worker 0 processes vehicles in request order; the others shuffle the order
+/// to explore different starting solutions, seeded per worker when the problem has a seed.
+/// The constructed routes are then improved with the algorithm of the problem.
+fn run_instance(problem: &Problem, worker: usize, incumbent: &Incumbent) -> Solution {
+    let mut rng = match problem.seed {
+        Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(worker as u64)),
+        None => StdRng::from_entropy(),
+    };
+    let mut vehicle_order: Vec<usize> = (0..problem.vehicles.len()).collect();
+    if worker > 0 {
+        vehicle_order.shuffle(&mut rng);
+    }
+
+    // Insertion is deterministic, so every instance finds the same routes
+    if let Some(tasks) = &problem.insertion {
+        let mut routes = warm_start(problem);
+        insert_units(problem, &mut routes, |unit| tasks.contains(&unit[0]));
+        if !problem.repaired.is_empty() {
+            repair(problem, &mut routes, incumbent);
+        }
+        incumbent.offer(problem, &routes);
+        return build_solution(problem, routes);
+    }
+
+    let warm = worker == 0 && problem.initial.iter().any(|tasks| !tasks.is_empty());
+    let mut routes = if warm {
+        warm_start(problem)
+    } else {
+        construct(problem, &vehicle_order)
+    };
+    incumbent.offer(problem, &routes);
+    let algorithm = if problem.quick {
+        Algorithm::Greedy
+    } else {
+        problem.algorithm
+    };
+    let mut search = Search::new(incumbent, rng);
+    solvers::solver(algorithm).improve(problem, &mut routes, &mut search);
+    enforce_priorities(problem, &mut routes);
+    build_solution(problem, routes)
+}
+
+/// Best cost found across solver instances, used to report improvements as they happen
+pub(crate) struct Incumbent<'a> {
+    cost: Mutex<f64>,
+    progress: &'a (dyn Fn(&Solution) + Sync),
+    pub(crate) deadline: Option<Instant>,
+}
+
+impl Incumbent<'_> {
+    /// Whether the time limit of the search has passed
+    pub(crate) fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Report `routes` if they beat the best solution found so far
+    pub(crate) fn offer(&self, problem: &Problem, routes: &[Vec<usize>]) {
+        let cost = solution_cost(problem, routes);
+        let mut best = self.cost.lock().expect("incumbent lock poisoned");
+        if cost < *best - f64::EPSILON {
+            *best = cost;
+            (self.progress)(&build_solution(problem, routes.to_vec()));
+        }
+    }
+}
+
+/// Routes of the initial solution, keeping the tasks each vehicle can still serve in order
+///
+/// Routes start with their locked tasks. Shipments are only kept when the same vehicle
+/// picks them up and delivers them, and a route that is infeasible as a whole keeps just
+/// its locked tasks.
+fn warm_start(problem: &Problem) -> Vec<Vec<usize>> {
+    let mut used: HashSet<usize> = problem.locked.iter().flatten().copied().collect();
+    let mut routes = problem.locked.clone();
+
+    for (vehicle, initial) in problem.initial.iter().enumerate() {
+        let Some(mut state) = routes[vehicle]
+            .iter()
+            .try_fold(problem.start_state(vehicle), |state, &task| {
+                problem.visit(&state, task).map(|visit| visit.state)
+            })
+        else {
+            continue;
+        };
+
+        let mut kept = routes[vehicle].clone();
+        for &task in initial {
+            let paired = match problem.tasks[task].kind {
+                TaskKind::Job(_) => true,
+                TaskKind::Pickup(_) => initial.contains(&(task + 1)),
+                TaskKind::Delivery(_) => kept.contains(&(task - 1)),
+            };
+            if !paired || used.contains(&task) {
+                continue;
+            }
+            let mut drawn = routes.clone();
+            drawn[vehicle] = kept.clone();
+            if !problem.within_stock(&problem.stock_usage(&drawn), vehicle, &[task], &[]) {
+                continue;
+            }
+            if let Some(visit) = problem.visit(&state, task) {
+                state = visit.state;
+                kept.push(task);
+            }
+        }
+
+        // A pickup whose delivery could not follow is left out together with it, as is work
+        // whose group or related work could not all follow
+        let kept: Vec<usize> = kept
+            .iter()
+            .copied()
+            .filter(|task| {
+                problem.locked[vehicle].contains(task)
+                    || problem.unit_of(*task).iter().all(|task| kept.contains(task))
+            })
+            .collect();
+        if problem.schedule(vehicle, &kept).is_some() {
+            used.extend(&kept);
+            routes[vehicle] = kept;
+        }
+    }
+
+    routes
+}
+
+/// Build routes from their locked tasks with the construction heuristic of the problem
+fn construct(problem: &Problem, vehicle_order: &[usize]) -> Vec<Vec<usize>> {
+    match problem.construction {
+        Construction::Nearest => construct_nearest(problem, vehicle_order),
+        Construction::Regret2 => construct_regret(problem, 2),
+        Construction::Regret3 => construct_regret(problem, 3),
+    }
+}
+
+/// Build routes by regret-`k` insertion, placing first the work with the fewest good options
+///
+/// Every vehicle is considered at once, so the vehicle order of the instance plays no part.
+fn construct_regret(problem: &Problem, k: usize) -> Vec<Vec<usize>> {
+    let mut routes = problem.locked.clone();
+    insert_regret(problem, &mut routes, k);
+    routes
+}
+
+/// Build routes by repeatedly appending the nearest feasible job or shipment
+fn construct_nearest(problem: &Problem, vehicle_order: &[usize]) -> Vec<Vec<usize>> {
+    let units = problem.units();
+    let mut routes = vec![Vec::new(); problem.vehicles.len()];
+    let mut assigned = vec![false; units.len()];
+    let mut assigned_count = 0;
+    for (vehicle, locked) in problem.locked.iter().enumerate() {
+        routes[vehicle].extend(locked);
+    }
+    let mut usage = problem.stock_usage(&routes);
+
+    for &vehicle in vehicle_order {
+        let route = &mut routes[vehicle];
+
+        // Continue after the locked tasks, leaving infeasible locked routes as they are
+        let Some(mut state) = route
+            .iter()
+            .try_fold(problem.start_state(vehicle), |state, &task| {
+                problem.visit(&state, task).map(|visit| visit.state)
+            })
+        else {
+            continue;
+        };
+
+        while assigned_count < units.len() {
+            let mut best: Option<(usize, f64, RouteState)> = None;
+
+            for (unit, _) in assigned.iter().enumerate().filter(|(_, done)| !**done) {
+                if !problem.within_stock(&usage, vehicle, &units[unit], &[]) {
+                    continue;
+                }
+                if !problem.relations.is_empty()
+                    && !problem.keeps_relations(&[&route[..], &units[unit]].concat())
+                {
+                    continue;
+                }
+
+                let mut unit_state = state.clone();
+                let mut score = 0.0;
+                let mut feasible = true;
+
+                for (i, &task) in units[unit].iter().enumerate() {
+                    let Some(visit) = problem.visit(&unit_state, task) else {
+                        feasible = false;
+                        break;
+                    };
+
+                    // Rank by the distance to the first stop of the unit
+                    if i == 0 {
+                        score += visit.travel_duration;
+                    }
+                    score += visit.waiting_time as f64 * WAITING_WEIGHT;
+                    unit_state = visit.state;
+                }
+
+                // Make sure we can still return to the end location in time
+                if !feasible || problem.finish(vehicle, &unit_state).is_none() {
+                    continue;
+                }
+
+                if best
+                    .as_ref()
+                    .is_none_or(|(_, best_score, _)| score < *best_score)
+                {
+                    best = Some((unit, score, unit_state));
+                }
+            }
+
+            match best {
+                Some((unit, _, next_state)) => {
+                    problem.draw_stock(&mut usage, vehicle, &units[unit]);
+                    route.extend(&units[unit]);
+                    assigned[unit] = true;
+                    assigned_count += 1;
+                    state = next_state;
+                }
+                None => break,
+            }
+        }
+    }
+
+    routes
+}
+
+/// Improve the routes of the repaired vehicles among themselves, as a problem of their own
+///
+/// The search stops at the time limit of `problem`; improvements are not reported.
+fn repair(problem: &Problem, routes: &mut [Vec<usize>], incumbent: &Incumbent) {
+    let vehicles = &problem.repaired;
+
+    // Sorted, the delivery of a shipment still follows its pickup
+    let mut tasks: Vec<usize> = vehicles.iter().flat_map(|v| routes[*v].clone()).collect();
+    tasks.sort_unstable();
+    let position = |task: &usize| tasks.binary_search(task).expect("repaired task");
+
+    let subproblem = problem.subproblem(vehicles, &tasks);
+    let mut sub_routes: Vec<Vec<usize>> = vehicles
+        .iter()
+        .map(|v| routes[*v].iter().map(position).collect())
+        .collect();
+    let quiet = Incumbent {
+        cost: Mutex::new(solution_cost(&subproblem, &sub_routes)),
+        progress: &|_| {},
+        deadline: incumbent.deadline,
+    };
+    improve(&subproblem, &mut sub_routes, &quiet);
+
+    for (vehicle, sub_route) in vehicles.iter().zip(sub_routes) {
+        routes[*vehicle] = sub_route.into_iter().map(|task| tasks[task]).collect();
+    }
+}
+
+/// Local search move applied to the routes, returning whether it improved them
+type Operator = fn(&Problem, &mut [Vec<usize>]) -> bool;
+
+/// Improve routes with 2-opt, relocation and insertion of unassigned tasks until no move helps
+pub(crate) fn improve(problem: &Problem, routes: &mut [Vec<usize>], incumbent: &Incumbent) {
+    if problem.quick {
+        if insert_unassigned(problem, routes) {
+            incumbent.offer(problem, routes);
+        }
+        return;
+    }
+
+    let operators: [Operator; 6] = [
+        insert_unassigned,
+        swap_unassigned,
+        two_opt,
+        relocate,
+        shed_unbalanced,
+        shed_unprofitable,
+    ];
+
+    for _ in 0..MAX_IMPROVEMENT_PASSES {
+        let mut improved = false;
+        for operator in operators {
+            if incumbent.expired() {
+                break;
+            }
+            improved |= operator(problem, routes);
+        }
+
+        if improved {
+            incumbent.offer(problem, routes);
+        }
+        if !improved || incumbent.expired() {
+            break;
+        }
+    }
+}
+
+pub(crate) fn unassigned_tasks(problem: &Problem, routes: &[Vec<usize>]) -> Vec<usize> {
+    let mut assigned = vec![false; problem.tasks.len()];
+    for task in routes.iter().flatten() {
+        assigned[*task] = true;
+    }
+    (0..problem.tasks.len())
+        .filter(|task| !assigned[*task])
+        .collect()
+}
+
+/// Insert unassigned jobs and shipments at their cheapest feasible position
+pub(crate) fn insert_unassigned(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    insert_units(problem, routes, |_| true)
+}
+
+/// Insert the unrouted jobs and shipments `select` picks at their cheapest feasible position
+fn insert_units(
+    problem: &Problem,
+    routes: &mut [Vec<usize>],
+    select: impl Fn(&[usize]) -> bool,
+) -> bool {
+    let mut improved = false;
+    let unassigned = unassigned_tasks(problem, routes);
+    let mut workloads = problem.workloads(routes);
+    let mut usage = problem.stock_usage(routes);
+
+    for unit in problem.units() {
+        if !unassigned.contains(&unit[0]) || !select(&unit) {
+            continue;
+        }
+
+        let best = (0..routes.len())
+            .filter_map(|vehicle| {
+                cheapest_insertion(problem, vehicle, &routes[vehicle], &unit, &workloads, &usage)
+            })
+            .min_by(|a, b| a.delta.total_cmp(&b.delta));
+
+        // Only insert when serving the unit beats leaving it unassigned
+        if let Some(best) = best {
+            if best.delta < problem.unassigned_penalty(&unit) {
+                problem.draw_stock(&mut usage, best.vehicle, &unit);
+                routes[best.vehicle] = best.tasks;
+                workloads[best.vehicle] = best.workload;
+                improved = true;
+            }
+        }
+    }
+
+    improved
+}
+
+/// Insert unassigned jobs and shipments by regret, serving first the one that loses most
+/// when it cannot have its cheapest route
+///
+/// The regret of a job or shipment adds up how much more inserting it into its 2nd to
+/// `k`th cheapest route costs than into its cheapest; a route it does not fit costs its
+/// unassigned penalty. With `k` of 1 the cheapest insertion goes first. Higher priorities
+/// are still inserted before lower ones.
+pub(crate) fn insert_regret(problem: &Problem, routes: &mut [Vec<usize>], k: usize) -> bool {
+    let unassigned = unassigned_tasks(problem, routes);
+    let mut pending: Vec<Vec<usize>> = problem
+        .units()
+        .into_iter()
+        .filter(|unit| unassigned.contains(&unit[0]))
+        .collect();
+    let mut workloads = problem.workloads(routes);
+    let mut usage = problem.stock_usage(routes);
+    let insertions =
+        |routes: &[Vec<usize>], workloads: &[f64], usage: &[Vec<u32>], unit: &[usize]| {
+            (0..routes.len())
+                .map(|vehicle| {
+                    cheapest_insertion(problem, vehicle, &routes[vehicle], unit, workloads, usage)
+                })
+                .collect::<Vec<_>>()
+        };
+    let mut options: Vec<Vec<Option<Insertion>>> = pending
+        .iter()
+        .map(|unit| insertions(routes, &workloads, &usage, unit))
+        .collect();
+    let mut improved = false;
+
+    loop {
+        let mut chosen: Option<(usize, usize, (u8, f64, f64))> = None;
+        for (idx, unit) in pending.iter().enumerate() {
+            let penalty = problem.unassigned_penalty(unit);
+            let mut deltas: Vec<(f64, usize)> = options[idx]
+                .iter()
+                .flatten()
+                .map(|insertion| (insertion.delta, insertion.vehicle))
+                .collect();
+            deltas.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let Some(&(cheapest, vehicle)) = deltas.first() else {
+                continue;
+            };
+            if cheapest >= penalty {
+                continue;
+            }
+
+            let regret: f64 = (1..k)
+                .map(|rank| deltas.get(rank).map_or(penalty, |d| d.0.min(penalty)) - cheapest)
+                .sum();
+            let key = (problem.tasks[unit[0]].priority, regret, -cheapest);
+            let better = chosen.as_ref().is_none_or(|(_, _, best)| {
+                key.0
+                    .cmp(&best.0)
+                    .then(key.1.total_cmp(&best.1))
+                    .then(key.2.total_cmp(&best.2))
+                    .is_gt()
+            });
+            if better {
+                chosen = Some((idx, vehicle, key));
+            }
+        }
+        let Some((idx, vehicle, _)) = chosen else {
+            break;
+        };
+
+        let unit = pending.swap_remove(idx);
+        let insertion = options.swap_remove(idx).swap_remove(vehicle).expect("chosen insertion");
+        problem.draw_stock(&mut usage, vehicle, &unit);
+        routes[vehicle] = insertion.tasks;
+        workloads[vehicle] = insertion.workload;
+        improved = true;
+
+        // Balance and shared stock make every route's insertions depend on this one
+        let refresh_all = problem.balance.is_some() || problem.vehicle_stock[vehicle].is_some();
+        for (unit, options) in pending.iter().zip(&mut options) {
+            if refresh_all {
+                *options = insertions(routes, &workloads, &usage, unit);
+            } else {
+                options[vehicle] = cheapest_insertion(
+                    problem,
+                    vehicle,
+                    &routes[vehicle],
+                    unit,
+                    &workloads,
+                    &usage,
+                );
+            }
+        }
+    }
+
+    improved
+}
+
+/// Route of a vehicle with a job or shipment inserted
+struct Insertion {
+    vehicle: usize,
+    tasks: Vec<usize>,
+    /// Change of the objective value, without the unassigned penalty saved
+    delta: f64,
+    workload: f64,
+}
+
+/// Cheapest feasible insertion of `unit` into the route `tasks` of `vehicle`
+fn cheapest_insertion(
+    problem: &Problem,
+    vehicle: usize,
+    tasks: &[usize],
+    unit: &[usize],
+    workloads: &[f64],
+    usage: &[Vec<u32>],
+) -> Option<Insertion> {
+    if !problem.within_stock(usage, vehicle, unit, &[]) {
+        return None;
+    }
+    let current_cost = problem.route_cost(vehicle, tasks)?;
+
+    let mut best: Option<Insertion> = None;
+    for candidate in insertions(tasks, unit, problem.locked_len(vehicle)) {
+        if let Some((cost, workload)) = problem.evaluate(vehicle, &candidate) {
+            let delta =
+                cost - current_cost + problem.balance_delta(workloads, &[(vehicle, workload)]);
+            if best.as_ref().is_none_or(|best| delta < best.delta) {
+                best = Some(Insertion {
+                    vehicle,
+                    tasks: candidate,
+                    delta,
+                    workload,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Assign, under the strict priority policy, each unassigned job or shipment that fits once
+/// all work of a lower priority is taken out of the routes
+///
+/// The tiers are filled from the highest priority down: a job or shipment that fits is
+/// inserted at its cheapest position, whatever it costs, and the lower priority work taken
+/// out is inserted again where it still fits and pays off.
+fn enforce_priorities(problem: &Problem, routes: &mut [Vec<usize>]) {
+    if problem.priority_policy != PriorityPolicy::Strict {
+        return;
+    }
+    let units = problem.units();
+
+    'tiers: loop {
+        let unassigned = unassigned_tasks(problem, routes);
+        for unit in units.iter().filter(|unit| unassigned.contains(&unit[0])) {
+            let priority = problem.tasks[unit[0]].priority;
+            let lower: Vec<usize> = routes
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|task| problem.tasks[*task].priority < priority)
+                .filter(|task| !problem.locked.iter().flatten().any(|locked| locked == task))
+                .collect();
+            if lower.is_empty() {
+                continue;
+            }
+
+            let mut stripped: Vec<Vec<usize>> = routes
+                .iter()
+                .map(|route| {
+                    route
+                        .iter()
+                        .copied()
+                        .filter(|task| !lower.contains(task))
+                        .collect()
+                })
+                .collect();
+            let workloads = problem.workloads(&stripped);
+            let usage = problem.stock_usage(&stripped);
+            let best = (0..stripped.len())
+                .filter_map(|vehicle| {
+                    let route = &stripped[vehicle];
+                    cheapest_insertion(problem, vehicle, route, unit, &workloads, &usage)
+                })
+                .min_by(|a, b| a.delta.total_cmp(&b.delta));
+            if let Some(best) = best {
+                stripped[best.vehicle] = best.tasks;
+                insert_unassigned(problem, &mut stripped);
+                routes.clone_from_slice(&stripped);
+                continue 'tiers;
+            }
+        }
+        break;
+    }
+}
+
+/// Replace an assigned job or shipment by an unassigned one with a higher priority
+fn swap_unassigned(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    let mut improved = false;
+    let units = problem.units();
+    let mut workloads = problem.workloads(routes);
+    let mut usage = problem.stock_usage(routes);
+
+    for unit in &units {
+        if routes.iter().flatten().any(|task| *task == unit[0]) {
+            continue;
+        }
+
+        let priority = problem.tasks[unit[0]].priority;
+        let mut best: Option<(usize, Vec<usize>, (u8, f64))> = None;
+
+        for (vehicle, route) in routes.iter().enumerate() {
+            for removed in units.iter().filter(|removed| route.contains(&removed[0])) {
+                let removed_priority = problem.tasks[removed[0]].priority;
+                if removed_priority >= priority
+                    || !problem.within_stock(&usage, vehicle, unit, removed)
+                {
+                    continue;
+                }
+
+                let reduced: Vec<usize> = route
+                    .iter()
+                    .copied()
+                    .filter(|task| !removed.contains(task))
+                    .collect();
+
+                for candidate in insertions(&reduced, unit, problem.locked_len(vehicle)) {
+                    if let Some((cost, workload)) = problem.evaluate(vehicle, &candidate) {
+                        let balance = problem.balance_delta(&workloads, &[(vehicle, workload)]);
+                        if problem.has_hard_balance() && balance > f64::EPSILON {
+                            continue;
+                        }
+
+                        // Prefer dropping the least important unit, then the cheapest route
+                        let key = (removed_priority, cost + balance);
+                        if best.as_ref().is_none_or(|(_, _, best_key)| {
+                            key.0 < best_key.0 || (key.0 == best_key.0 && key.1 < best_key.1)
+                        }) {
+                            best = Some((vehicle, candidate, key));
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some((vehicle, candidate, _)) = best {
+            workloads[vehicle] = problem
+                .evaluate(vehicle, &candidate)
+                .map_or(0.0, |(_, workload)| workload);
+            routes[vehicle] = candidate;
+            usage = problem.stock_usage(routes);
+            improved = true;
+        }
+    }
+
+    improved
+}
+
+/// Unassign the job or shipment whose removal best restores a hard balance limit
+fn shed_unbalanced(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    if !problem.has_hard_balance() {
+        return false;
+    }
+
+    let workloads = problem.workloads(routes);
+    if problem.balance_penalty(&workloads) <= 0.0 {
+        return false;
+    }
+
+    let mut best: Option<(usize, Vec<usize>, f64)> = None;
+    for unit in problem.units() {
+        for (vehicle, route) in routes.iter().enumerate() {
+            if !route.contains(&unit[0]) {
+                continue;
+            }
+
+            let reduced: Vec<usize> = route
+                .iter()
+                .copied()
+                .filter(|task| !unit.contains(task))
+                .collect();
+            let (Some(cost), Some((reduced_cost, workload))) = (
+                problem.route_cost(vehicle, route),
+                problem.evaluate(vehicle, &reduced),
+            ) else {
+                continue;
+            };
+
+            let gain = cost
+                - reduced_cost
+                - problem.unassigned_penalty(&unit)
+                - problem.balance_delta(&workloads, &[(vehicle, workload)]);
+            if best
+                .as_ref()
+                .is_none_or(|(_, _, best_gain)| gain > *best_gain)
+            {
+                best = Some((vehicle, reduced, gain));
+            }
+        }
+    }
+
+    match best {
+        Some((vehicle, reduced, gain)) if gain > f64::EPSILON => {
+            routes[vehicle] = reduced;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Unassign the job or shipment that costs the most beyond its unassigned penalty to serve
+///
+/// Only a penalty below the default makes serving work unprofitable, as routes are built
+/// without regard to it. Locked tasks stay in their routes.
+fn shed_unprofitable(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    if problem.unassigned_penalty >= UNASSIGNED_PENALTY {
+        return false;
+    }
+
+    let workloads = problem.workloads(routes);
+    let mut best: Option<(usize, Vec<usize>, f64)> = None;
+    for unit in problem.units() {
+        for (vehicle, route) in routes.iter().enumerate() {
+            let locked = &route[..problem.locked_len(vehicle).min(route.len())];
+            if !route.contains(&unit[0]) || locked.contains(&unit[0]) {
+                continue;
+            }
+
+            let reduced: Vec<usize> = route
+                .iter()
+                .copied()
+                .filter(|task| !unit.contains(task))
+                .collect();
+            let (Some(cost), Some((reduced_cost, workload))) = (
+                problem.route_cost(vehicle, route),
+                problem.evaluate(vehicle, &reduced),
+            ) else {
+                continue;
+            };
+
+            let gain = cost
+                - reduced_cost
+                - problem.unassigned_penalty(&unit)
+                - problem.balance_delta(&workloads, &[(vehicle, workload)]);
+            if best
+                .as_ref()
+                .is_none_or(|(_, _, best_gain)| gain > *best_gain)
+            {
+                best = Some((vehicle, reduced, gain));
+            }
+        }
+    }
+
+    match best {
+        Some((vehicle, reduced, gain)) if gain > f64::EPSILON => {
+            routes[vehicle] = reduced;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// All routes obtained by inserting a unit into `route` at or after position `from`, keeping
+/// the unit's order
+///
+/// Units of more than two tasks, the jobs of a group, are inserted as one block.
+pub(crate) fn insertions(route: &[usize], unit: &[usize], from: usize) -> Vec<Vec<usize>> {
+    match unit {
+        [task] => (from..=route.len())
+            .map(|position| {
+                let mut candidate = route.to_vec();
+                candidate.insert(position, *task);
+                candidate
+            })
+            .collect(),
+        [pickup, delivery] => {
+            let mut candidates = Vec::new();
+            for first in from..=route.len() {
+                for second in first..=route.len() {
+                    let mut candidate = route.to_vec();
+                    candidate.insert(second, *delivery);
+                    candidate.insert(first, *pickup);
+                    candidates.push(candidate);
+                }
+            }
+            candidates
+        }
+        _ => (from..=route.len())
+            .map(|position| {
+                let mut candidate = route.to_vec();
+                candidate.splice(position..position, unit.iter().copied());
+                candidate
+            })
+            .collect(),
+    }
+}
+
+/// Reverse route segments when this shortens the route
+fn two_opt(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    let mut improved = false;
+    let mut workloads = problem.workloads(routes);
+
+    for (vehicle, route) in routes.iter_mut().enumerate() {
+        let Some(mut current_cost) = problem.route_cost(vehicle, route) else {
+            continue;
+        };
+
+        let len = route.len();
+        for i in problem.locked_len(vehicle)..len {
+            for j in (i + 1)..len {
+                let mut candidate = route.clone();
+                candidate[i..=j].reverse();
+
+                if let Some((cost, workload)) = problem.evaluate(vehicle, &candidate) {
+                    let delta = cost - current_cost
+                        + problem.balance_delta(&workloads, &[(vehicle, workload)]);
+                    if delta < -f64::EPSILON {
+                        *route = candidate;
+                        current_cost = cost;
+                        workloads[vehicle] = workload;
+                        improved = true;
+                    }
+                }
+            }
+        }
+    }
+
+    improved
+}
+
+/// Move single tasks to a better position in the same or another route
+fn relocate(problem: &Problem, routes: &mut [Vec<usize>]) -> bool {
+    let mut improved = false;
+    let mut workloads = problem.workloads(routes);
+    let mut usage = problem.stock_usage(routes);
+
+    for from in 0..routes.len() {
+        let mut position = problem.locked_len(from);
+        while position < routes[from].len() {
+            let Some(from_cost) = problem.route_cost(from, &routes[from]) else {
+                break;
+            };
+
+            let mut reduced = routes[from].clone();
+            let task = reduced.remove(position);
+            let Some((reduced_cost, reduced_workload)) = problem.evaluate(from, &reduced) else {
+                position += 1;
+                continue;
+            };
+
+            // The jobs of a group only move within their route
+            let grouped = problem.task_groups[task].is_some();
+            let mut best: Option<(usize, usize, f64, f64)> = None;
+            for (to, route) in routes.iter().enumerate() {
+                if grouped && to != from {
+                    continue;
+                }
+                if !problem.shares_stock(from, to)
+                    && !problem.within_stock(&usage, to, &[task], &[])
+                {
+                    continue;
+                }
+                let base = if to == from { &reduced } else { route };
+                let Some(base_cost) = problem.route_cost(to, base) else {
+                    continue;
+                };
+
+                for insert_at in problem.locked_len(to)..=base.len() {
+                    if to == from && insert_at == position {
+                        continue;
+                    }
+
+                    let mut candidate = base.clone();
+                    candidate.insert(insert_at, task);
+
+                    if let Some((cost, workload)) = problem.evaluate(to, &candidate) {
+                        let balance = if to == from {
+                            problem.balance_delta(&workloads, &[(to, workload)])
+                        } else {
+                            problem.balance_delta(
+                                &workloads,
+                                &[(from, reduced_workload), (to, workload)],
+                            )
+                        };
+                        let gain = from_cost - reduced_cost - (cost - base_cost) - balance;
+                        if gain > f64::EPSILON
+                            && best.is_none_or(|(_, _, best_gain, _)| gain > best_gain)
+                        {
+                            best = Some((to, insert_at, gain, workload));
+                        }
+                    }
+                }
+            }
+
+            match best {
+                Some((to, insert_at, _, workload)) => {
+                    routes[from] = reduced;
+                    routes[to].insert(insert_at, task);
+                    usage = problem.stock_usage(routes);
+                    if to != from {
+                        workloads[from] = reduced_workload;
+                    }
+                    workloads[to] = workload;
+                    improved = true;
+                }
+                None => position += 1,
+            }
+        }
+    }
+
+    improved
+}
+
+/// Tasks of every vehicle in `solution`, including the vehicles without a route
+fn solution_routes(problem: &Problem, solution: &Solution) -> Vec<Vec<usize>> {
+    let mut routes = vec![Vec::new(); problem.vehicles.len()];
+    for route in &solution.routes {
+        routes[route.vehicle] = route.tasks.clone();
+    }
+    routes
+}
+
+fn build_solution(problem: &Problem, routes: Vec<Vec<usize>>) -> Solution {
+    let unassigned = unassigned_tasks(problem, &routes);
+    let balance_penalty = problem.balance_penalty(&problem.workloads(&routes));
+
+    let routes: Vec<SolvedRoute> = routes
+        .into_iter()
+        .enumerate()
+        .filter(|(_, tasks)| !tasks.is_empty())
+        .map(|(vehicle, tasks)| {
+            let schedule = problem
+                .schedule(vehicle, &tasks)
+                .expect("solver produced an infeasible route");
+            SolvedRoute {
+                vehicle,
+                tasks,
+                schedule,
+            }
+        })
+        .collect();
+
+    let cost = routes
+        .iter()
+        .map(|route| problem.schedule_cost(route.vehicle, &route.schedule))
+        .sum::<f64>()
+        + unassigned_cost(problem, &unassigned)
+        + balance_penalty;
+
+    Solution {
+        routes,
+        unassigned,
+        cost,
+    }
+}
+
+/// Objective value of a set of routes, without building their schedules
+pub(crate) fn solution_cost(problem: &Problem, routes: &[Vec<usize>]) -> f64 {
+    routes
+        .iter()
+        .enumerate()
+        .map(|(vehicle, tasks)| problem.route_cost(vehicle, tasks).unwrap_or(f64::INFINITY))
+        .sum::<f64>()
+        + unassigned_cost(problem, &unassigned_tasks(problem, routes))
+        + problem.balance_penalty(&problem.workloads(routes))
+}
+
+/// Penalty for the jobs and shipments whose tasks are in `unassigned`
+fn unassigned_cost(problem: &Problem, unassigned: &[usize]) -> f64 {
+    problem
+        .units()
+        .iter()
+        .filter(|unit| unassigned.contains(&unit[0]))
+        .map(|unit| problem.unassigned_penalty(unit))
+        .sum()
+}
@@ -0,0 +1,56 @@
+use super::{cost_per_task, Current, LocalSearch, Search, Solver};
+use crate::services::solver::Problem;
+use rand::Rng;
+
+/// Random moves tried per job or shipment
+const ITERATIONS_PER_UNIT: usize = 2000;
+
+/// Starting temperature, as a share of the average cost of serving a task
+const INITIAL_TEMPERATURE: f64 = 0.2;
+
+/// Temperature at the end of the search, relative to the starting temperature
+const FINAL_TEMPERATURE: f64 = 0.001;
+
+/// Simulated annealing over random moves
+///
+/// Worse routes are accepted with a probability that shrinks as the temperature cools,
+/// letting the search leave local optima early on and settle later. The best routes found
+/// are finished with local search.
+pub struct SimulatedAnnealing;
+
+impl Solver for SimulatedAnnealing {
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search) {
+        let units = problem.units();
+        let mut current = Current::new(problem, routes);
+        let mut best = (current.routes.clone(), current.cost);
+
+        let iterations = ITERATIONS_PER_UNIT * units.len();
+        let mut temperature = INITIAL_TEMPERATURE * cost_per_task(problem, routes);
+        let cooling = FINAL_TEMPERATURE.powf(1.0 / iterations.max(1) as f64);
+
+        for _ in 0..iterations {
+            if search.expired() {
+                break;
+            }
+            temperature *= cooling;
+            let Some(change) = current.random_move(problem, &units, search.rng()) else {
+                continue;
+            };
+            let accepted = change.delta <= 0.0
+                || (temperature > 0.0
+                    && search.rng().gen::<f64>() < (-change.delta / temperature).exp());
+            if !accepted {
+                continue;
+            }
+
+            current.apply(problem, change);
+            if current.cost < best.1 - f64::EPSILON {
+                best = (current.routes.clone(), current.cost);
+                search.offer(problem, &best.0);
+            }
+        }
+
+        routes.clone_from_slice(&best.0);
+        LocalSearch.improve(problem, routes, search);
+    }
+}
@@ -0,0 +1,15 @@
+use super::{Search, Solver};
+use crate::services::solver::{insert_unassigned, Problem};
+
+/// Keep the constructed routes, only inserting work construction left out
+///
+/// The fastest strategy, for requests that need an answer more than a good one.
+pub struct Greedy;
+
+impl Solver for Greedy {
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search) {
+        if insert_unassigned(problem, routes) {
+            search.offer(problem, routes);
+        }
+    }
+}
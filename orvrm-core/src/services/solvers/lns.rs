@@ -0,0 +1,114 @@
+use super::{LocalSearch, Search, Solver};
+use crate::services::solver::{insert_regret, solution_cost, Problem};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Ruin and recreate rounds per job or shipment when the search has no time limit
+const ITERATIONS_PER_UNIT: usize = 20;
+
+/// How strongly worst removal sticks to the costliest work; 1 removes uniformly at random
+const WORST_REMOVAL_DETERMINISM: i32 = 3;
+
+/// Large neighborhood search
+///
+/// Each round ruins the routes by removing part of the assigned jobs and shipments, either
+/// at random or preferring those that cost most to serve, and recreates them by regret
+/// insertion. Better routes are kept, and rounds continue until the time limit. The best
+/// routes found are finished with local search.
+pub struct LargeNeighborhoodSearch;
+
+impl Solver for LargeNeighborhoodSearch {
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search) {
+        let settings = problem.lns();
+        let units = problem.units();
+        let rounds = match search.deadline() {
+            Some(_) => usize::MAX,
+            None => ITERATIONS_PER_UNIT * units.len(),
+        };
+        let mut cost = solution_cost(problem, routes);
+
+        for _ in 0..rounds {
+            if search.expired() {
+                break;
+            }
+
+            let assigned: Vec<&[usize]> = units
+                .iter()
+                .filter(|unit| routes.iter().any(|route| route.contains(&unit[0])))
+                .map(Vec::as_slice)
+                .collect();
+            if assigned.is_empty() {
+                break;
+            }
+            let count = ((assigned.len() as f64 * settings.removal_fraction).round() as usize)
+                .clamp(1, assigned.len());
+            let removed = if search.rng().gen_bool(0.5) {
+                random_removal(&assigned, count, search.rng())
+            } else {
+                worst_removal(problem, routes, &assigned, count, search.rng())
+            };
+
+            let mut candidate = routes.to_vec();
+            for route in &mut candidate {
+                route.retain(|task| !removed.contains(task));
+            }
+            insert_regret(problem, &mut candidate, settings.regret as usize);
+
+            let candidate_cost = solution_cost(problem, &candidate);
+            if candidate_cost < cost - f64::EPSILON {
+                routes.clone_from_slice(&candidate);
+                cost = candidate_cost;
+                search.offer(problem, routes);
+            }
+        }
+
+        LocalSearch.improve(problem, routes, search);
+    }
+}
+
+/// Tasks of `count` assigned jobs and shipments picked at random
+fn random_removal(assigned: &[&[usize]], count: usize, rng: &mut StdRng) -> Vec<usize> {
+    assigned
+        .choose_multiple(rng, count)
+        .flat_map(|unit| unit.iter().copied())
+        .collect()
+}
+
+/// Tasks of `count` assigned jobs and shipments, preferring those whose removal saves most
+fn worst_removal(
+    problem: &Problem,
+    routes: &[Vec<usize>],
+    assigned: &[&[usize]],
+    count: usize,
+    rng: &mut StdRng,
+) -> Vec<usize> {
+    let mut savings: Vec<(f64, &[usize])> = assigned
+        .iter()
+        .map(|unit| {
+            let vehicle = routes
+                .iter()
+                .position(|route| route.contains(&unit[0]))
+                .expect("assigned unit");
+            let without: Vec<usize> = routes[vehicle]
+                .iter()
+                .copied()
+                .filter(|task| !unit.contains(task))
+                .collect();
+            let saving = problem
+                .route_cost(vehicle, &routes[vehicle])
+                .zip(problem.route_cost(vehicle, &without))
+                .map_or(0.0, |(with, without)| with - without);
+            (saving, *unit)
+        })
+        .collect();
+    savings.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut removed = Vec::new();
+    for _ in 0..count {
+        let pick = rng.gen::<f64>().powi(WORST_REMOVAL_DETERMINISM);
+        let (_, unit) = savings.remove((pick * savings.len() as f64) as usize);
+        removed.extend_from_slice(unit);
+    }
+    removed
+}
@@ -0,0 +1,13 @@
+use super::{Search, Solver};
+use crate::services::solver::{improve, Problem};
+
+/// Apply 2-opt, relocation and insertion moves while they improve the routes
+///
+/// Stops in the first local optimum, which is usually close to the best routes.
+pub struct LocalSearch;
+
+impl Solver for LocalSearch {
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search) {
+        improve(problem, routes, search.incumbent);
+    }
+}
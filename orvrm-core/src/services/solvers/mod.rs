@@ -0,0 +1,235 @@
+//! Search strategies improving the routes the solver constructs
+//!
+//! Each strategy trades solution quality for speed: `greedy` keeps the constructed routes,
+//! `local_search` applies improving moves until none is left, and the metaheuristics also
+//! take worsening steps to escape the local optima local search stops in. All of them stop
+//! at the time limit of the problem and report better routes as they find them.
+
+mod annealing;
+mod greedy;
+mod lns;
+mod local_search;
+mod tabu;
+
+pub use annealing::SimulatedAnnealing;
+pub use greedy::Greedy;
+pub use lns::LargeNeighborhoodSearch;
+pub use local_search::LocalSearch;
+pub use tabu::TabuSearch;
+
+use super::solver::{insertions, solution_cost, unassigned_tasks, Incumbent, Problem};
+use crate::models::Algorithm;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::time::Instant;
+
+/// Strategy improving the routes of a problem
+pub trait Solver: Send + Sync {
+    /// Improve `routes`, the tasks of each vehicle in order, within the time limit of `search`
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search);
+}
+
+/// Solver implementing `algorithm`
+pub fn solver(algorithm: Algorithm) -> &'static dyn Solver {
+    match algorithm {
+        Algorithm::Greedy => &Greedy,
+        Algorithm::LocalSearch => &LocalSearch,
+        Algorithm::SimulatedAnnealing => &SimulatedAnnealing,
+        Algorithm::Tabu => &TabuSearch,
+        Algorithm::Lns => &LargeNeighborhoodSearch,
+    }
+}
+
+/// State of one solver instance: its time limit, the best routes of all instances and its
+/// random decisions
+pub struct Search<'a> {
+    incumbent: &'a Incumbent<'a>,
+    rng: StdRng,
+}
+
+impl<'a> Search<'a> {
+    pub(crate) fn new(incumbent: &'a Incumbent<'a>, rng: StdRng) -> Self {
+        Self { incumbent, rng }
+    }
+
+    /// Whether the time limit of the search has passed
+    pub fn expired(&self) -> bool {
+        self.incumbent.expired()
+    }
+
+    /// When the search has to stop, if it has a time limit
+    pub fn deadline(&self) -> Option<Instant> {
+        self.incumbent.deadline
+    }
+
+    /// Report `routes` if they beat the best solution found so far
+    pub fn offer(&self, problem: &Problem, routes: &[Vec<usize>]) {
+        self.incumbent.offer(problem, routes);
+    }
+
+    /// Source of the random decisions, seeded when the problem has a seed
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+/// Change to the routes of at most two vehicles
+struct Move {
+    /// First task of the job or shipment that moved, if any
+    task: Option<usize>,
+
+    /// New tasks, cost and workload of each changed vehicle
+    routes: Vec<(usize, Vec<usize>, f64, f64)>,
+
+    /// Change of the objective value
+    delta: f64,
+}
+
+/// Routes being searched, with the cost and workload of each and the depot stock they use
+struct Current {
+    routes: Vec<Vec<usize>>,
+    costs: Vec<f64>,
+    workloads: Vec<f64>,
+    usage: Vec<Vec<u32>>,
+    cost: f64,
+}
+
+impl Current {
+    fn new(problem: &Problem, routes: &[Vec<usize>]) -> Self {
+        let costs = routes
+            .iter()
+            .enumerate()
+            .map(|(vehicle, tasks)| problem.route_cost(vehicle, tasks).unwrap_or(f64::INFINITY))
+            .collect();
+        Self {
+            routes: routes.to_vec(),
+            costs,
+            workloads: problem.workloads(routes),
+            usage: problem.stock_usage(routes),
+            cost: solution_cost(problem, routes),
+        }
+    }
+
+    /// Random feasible move: relocating, inserting or removing a job or shipment, or
+    /// reversing part of a route
+    fn random_move(
+        &self,
+        problem: &Problem,
+        units: &[Vec<usize>],
+        rng: &mut StdRng,
+    ) -> Option<Move> {
+        if rng.gen_bool(0.25) {
+            return self.random_reversal(problem, rng);
+        }
+
+        let unit = units.choose(rng)?;
+        let vehicles = self.routes.len();
+        let from = self.routes.iter().position(|route| route.contains(&unit[0]));
+        let to = Some(rng.gen_range(0..=vehicles)).filter(|to| *to < vehicles);
+
+        let mut routes = Vec::new();
+        let mut penalty = 0.0;
+        let reduced = from.map(|from| {
+            let reduced: Vec<usize> = self.routes[from]
+                .iter()
+                .copied()
+                .filter(|task| !unit.contains(task))
+                .collect();
+            (from, reduced)
+        });
+        match (reduced, to) {
+            (None, None) => return None,
+            (Some((from, reduced)), None) => {
+                routes.push((from, reduced));
+                penalty += problem.unassigned_penalty(unit);
+            }
+            (reduced, Some(to)) => {
+                let base = match &reduced {
+                    Some((from, reduced)) if *from == to => reduced.clone(),
+                    _ => self.routes[to].clone(),
+                };
+                let moved_from = reduced.as_ref().map(|(from, _)| *from);
+                let draws = moved_from.is_none_or(|from| !problem.shares_stock(from, to));
+                if draws && !problem.within_stock(&self.usage, to, unit, &[]) {
+                    return None;
+                }
+                let candidate = insertions(&base, unit, problem.locked_len(to))
+                    .choose(rng)?
+                    .clone();
+                match reduced {
+                    Some((from, reduced)) if from != to => routes.push((from, reduced)),
+                    Some(_) => {}
+                    None => penalty -= problem.unassigned_penalty(unit),
+                }
+                routes.push((to, candidate));
+            }
+        }
+
+        self.evaluate(problem, Some(unit[0]), routes, penalty)
+    }
+
+    /// Reverse a random stretch of unlocked tasks in a random route
+    fn random_reversal(&self, problem: &Problem, rng: &mut StdRng) -> Option<Move> {
+        let vehicle = rng.gen_range(0..self.routes.len());
+        let route = &self.routes[vehicle];
+        let locked = problem.locked_len(vehicle);
+        if route.len() < locked + 2 {
+            return None;
+        }
+        let start = rng.gen_range(locked..route.len() - 1);
+        let end = rng.gen_range(start + 1..route.len());
+        let mut reversed = route.clone();
+        reversed[start..=end].reverse();
+        self.evaluate(problem, None, vec![(vehicle, reversed)], 0.0)
+    }
+
+    /// Move giving vehicles new routes, if they are feasible
+    fn evaluate(
+        &self,
+        problem: &Problem,
+        task: Option<usize>,
+        routes: Vec<(usize, Vec<usize>)>,
+        penalty: f64,
+    ) -> Option<Move> {
+        let mut evaluated = Vec::with_capacity(routes.len());
+        let mut delta = penalty;
+        for (vehicle, tasks) in routes {
+            let (cost, workload) = problem.evaluate(vehicle, &tasks)?;
+            delta += cost - self.costs[vehicle];
+            evaluated.push((vehicle, tasks, cost, workload));
+        }
+        let workloads: Vec<(usize, f64)> = evaluated
+            .iter()
+            .map(|(vehicle, _, _, workload)| (*vehicle, *workload))
+            .collect();
+        delta += problem.balance_delta(&self.workloads, &workloads);
+
+        Some(Move {
+            task,
+            routes: evaluated,
+            delta,
+        })
+    }
+
+    fn apply(&mut self, problem: &Problem, change: Move) {
+        for (vehicle, tasks, cost, workload) in change.routes {
+            self.routes[vehicle] = tasks;
+            self.costs[vehicle] = cost;
+            self.workloads[vehicle] = workload;
+        }
+        self.usage = problem.stock_usage(&self.routes);
+        self.cost += change.delta;
+    }
+}
+
+/// Average cost of serving an assigned task, the scale of the temperature and of the moves
+fn cost_per_task(problem: &Problem, routes: &[Vec<usize>]) -> f64 {
+    let assigned = problem.tasks.len() - unassigned_tasks(problem, routes).len();
+    let cost: f64 = routes
+        .iter()
+        .enumerate()
+        .filter_map(|(vehicle, tasks)| problem.route_cost(vehicle, tasks))
+        .sum();
+    cost / assigned.max(1) as f64
+}
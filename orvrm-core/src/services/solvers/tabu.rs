@@ -0,0 +1,65 @@
+use super::{Current, LocalSearch, Move, Search, Solver};
+use crate::services::solver::Problem;
+
+/// Iterations per job or shipment
+const ITERATIONS_PER_UNIT: usize = 100;
+
+/// Random moves sampled each iteration, of which the best allowed one is taken
+const SAMPLED_MOVES: usize = 30;
+
+/// Longest number of iterations a moved job or shipment stays in place
+const MAX_TENURE: usize = 10;
+
+/// Tabu search over sampled moves
+///
+/// Each iteration takes the best of a sample of moves, even when it makes the routes worse.
+/// A job or shipment that moved may not move again for a while, unless that yields the best
+/// routes so far, which keeps the search from undoing its last steps. The best routes found
+/// are finished with local search.
+pub struct TabuSearch;
+
+impl Solver for TabuSearch {
+    fn improve(&self, problem: &Problem, routes: &mut [Vec<usize>], search: &mut Search) {
+        let units = problem.units();
+        let mut current = Current::new(problem, routes);
+        let mut best = (current.routes.clone(), current.cost);
+
+        let tenure = (units.len() / 3).clamp(1, MAX_TENURE);
+        let mut tabu_until = vec![0; problem.tasks.len()];
+
+        for iteration in 1..=ITERATIONS_PER_UNIT * units.len() {
+            if search.expired() {
+                break;
+            }
+
+            let mut chosen: Option<Move> = None;
+            for _ in 0..SAMPLED_MOVES {
+                let Some(change) = current.random_move(problem, &units, search.rng()) else {
+                    continue;
+                };
+                let aspires = current.cost + change.delta < best.1 - f64::EPSILON;
+                let tabu = change.task.is_some_and(|task| tabu_until[task] > iteration);
+                if (!tabu || aspires)
+                    && chosen.as_ref().is_none_or(|chosen| change.delta < chosen.delta)
+                {
+                    chosen = Some(change);
+                }
+            }
+            let Some(change) = chosen else {
+                continue;
+            };
+
+            if let Some(task) = change.task {
+                tabu_until[task] = iteration + tenure;
+            }
+            current.apply(problem, change);
+            if current.cost < best.1 - f64::EPSILON {
+                best = (current.routes.clone(), current.cost);
+                search.offer(problem, &best.0);
+            }
+        }
+
+        routes.clone_from_slice(&best.0);
+        LocalSearch.improve(problem, routes, search);
+    }
+}
@@ -0,0 +1,315 @@
+use super::engine::{
+    ensure_no_exclude, timed, BackendHealth, EngineLeg, EngineRoute, RoutingEngine,
+};
+use super::solver::Matrix;
+use crate::models::Instruction;
+use crate::utils::polyline;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use log::{debug, error};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Configuration for the Valhalla routing engine
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ValhallaConfig {
+    /// Base URL for the Valhalla service
+    pub base_url: String,
+
+    /// Timeout for Valhalla requests in seconds
+    pub timeout_seconds: u64,
+
+    /// Valhalla costing model by routing profile
+    ///
+    /// Unlisted profiles map `car`, `bike` and `foot` to `auto`, `bicycle` and
+    /// `pedestrian`, and use any other profile name as the costing model itself.
+    pub costing: HashMap<String, String>,
+}
+
+impl Default for ValhallaConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8002".to_string(),
+            timeout_seconds: 30,
+            costing: HashMap::new(),
+        }
+    }
+}
+
+/// Routing engine backed by a Valhalla server
+#[derive(Debug, Clone)]
+pub struct ValhallaService {
+    client: Client,
+    config: ValhallaConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct TripResponse {
+    trip: Trip,
+}
+
+#[derive(Debug, Deserialize)]
+struct Trip {
+    legs: Vec<TripLeg>,
+    summary: Summary,
+}
+
+#[derive(Debug, Deserialize)]
+struct TripLeg {
+    summary: Summary,
+    shape: String,
+    #[serde(default)]
+    maneuvers: Vec<Maneuver>,
+}
+
+/// Maneuver of a leg, with its length in kilometers and time in seconds
+#[derive(Debug, Deserialize)]
+struct Maneuver {
+    #[serde(rename = "type")]
+    maneuver_type: u32,
+    #[serde(default)]
+    street_names: Vec<String>,
+    length: f64,
+    time: f64,
+}
+
+/// Length in kilometers and time in seconds
+#[derive(Debug, Deserialize)]
+struct Summary {
+    length: f64,
+    time: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixResponse {
+    sources_to_targets: Vec<Vec<MatrixCell>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatrixCell {
+    distance: Option<f64>,
+    time: Option<f64>,
+}
+
+/// Valhalla encodes shapes with a precision of 6 decimals
+const SHAPE_PRECISION: u32 = 6;
+
+impl ValhallaService {
+    /// Create a new Valhalla service with the given configuration
+    pub fn new(config: ValhallaConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Self { client, config }
+    }
+
+    /// Valhalla costing model for a routing profile
+    pub fn costing<'a>(&'a self, profile: &'a str) -> &'a str {
+        if let Some(costing) = self.config.costing.get(profile) {
+            return costing;
+        }
+        match profile {
+            "car" => "auto",
+            "bike" => "bicycle",
+            "foot" => "pedestrian",
+            other => other,
+        }
+    }
+
+    /// Send a request to a Valhalla action and parse its response
+    async fn request<T: DeserializeOwned>(
+        &self,
+        action: &str,
+        body: serde_json::Value,
+    ) -> Result<T> {
+        let url = format!("{}/{}", self.config.base_url, action);
+        debug!("Valhalla {} request: {}", action, url);
+
+        let response = timed(self.client.post(&url).json(&body).send())
+            .await
+            .with_context(|| format!("Failed to send Valhalla {} request", action))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            error!(
+                "Valhalla {} request failed with status {}: {}",
+                action, status, error_text
+            );
+            bail!("Valhalla {} request failed with status {}", action, status);
+        }
+
+        response
+            .json::<T>()
+            .await
+            .with_context(|| format!("Failed to parse Valhalla {} response", action))
+    }
+}
+
+/// Valhalla location for a [longitude, latitude] coordinate
+fn location(coordinate: &[f64; 2]) -> serde_json::Value {
+    serde_json::json!({ "lon": coordinate[0], "lat": coordinate[1] })
+}
+
+/// Instruction of a Valhalla maneuver, named as OSRM names its maneuvers
+fn instruction(maneuver: &Maneuver) -> Instruction {
+    let (kind, modifier) = match maneuver.maneuver_type {
+        1 => ("depart", None),
+        2 => ("depart", Some("right")),
+        3 => ("depart", Some("left")),
+        4 => ("arrive", None),
+        5 => ("arrive", Some("right")),
+        6 => ("arrive", Some("left")),
+        7 => ("new name", Some("straight")),
+        9 => ("turn", Some("slight right")),
+        10 => ("turn", Some("right")),
+        11 => ("turn", Some("sharp right")),
+        12 | 13 => ("turn", Some("uturn")),
+        14 => ("turn", Some("sharp left")),
+        15 => ("turn", Some("left")),
+        16 => ("turn", Some("slight left")),
+        17 => ("on ramp", Some("straight")),
+        18 => ("on ramp", Some("right")),
+        19 => ("on ramp", Some("left")),
+        20 => ("off ramp", Some("right")),
+        21 => ("off ramp", Some("left")),
+        22 => ("fork", Some("straight")),
+        23 => ("fork", Some("right")),
+        24 => ("fork", Some("left")),
+        25 => ("merge", None),
+        26 => ("roundabout", None),
+        27 => ("exit roundabout", None),
+        28 | 29 => ("notification", None),
+        37 => ("merge", Some("slight right")),
+        38 => ("merge", Some("slight left")),
+        _ => ("continue", Some("straight")),
+    };
+    Instruction {
+        maneuver: kind.to_string(),
+        modifier: modifier.map(str::to_string),
+        name: maneuver.street_names.join(" / "),
+        distance: (maneuver.length * 1000.0).round() as u32,
+        duration: maneuver.time.round() as u32,
+    }
+}
+
+#[async_trait]
+impl RoutingEngine for ValhallaService {
+    async fn route(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+        geometry: bool,
+        instructions: bool,
+    ) -> Result<Option<EngineRoute>> {
+        ensure_no_exclude("Valhalla", exclude)?;
+        let body = serde_json::json!({
+            "locations": coordinates.iter().map(location).collect::<Vec<_>>(),
+            "costing": self.costing(profile),
+            "units": "kilometers",
+            "directions_type": if instructions { "maneuvers" } else { "none" },
+        });
+        let response: TripResponse = self.request("route", body).await?;
+        let trip = response.trip;
+
+        // Each leg has its own shape, starting where the previous one ended
+        let geometry = if geometry {
+            let mut points: Vec<[f64; 2]> = Vec::new();
+            for leg in &trip.legs {
+                let shape = polyline::decode(&leg.shape, SHAPE_PRECISION)?;
+                let skip = usize::from(!points.is_empty());
+                points.extend(shape.into_iter().skip(skip));
+            }
+            Some(polyline::encode(&points, 5))
+        } else {
+            None
+        };
+
+        Ok(Some(EngineRoute {
+            distance: trip.summary.length * 1000.0,
+            duration: trip.summary.time,
+            geometry,
+            legs: trip
+                .legs
+                .iter()
+                .map(|leg| EngineLeg {
+                    distance: leg.summary.length * 1000.0,
+                    duration: leg.summary.time,
+                    instructions: leg.maneuvers.iter().map(instruction).collect(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn matrix(
+        &self,
+        coordinates: &[[f64; 2]],
+        profile: &str,
+        exclude: &[String],
+    ) -> Result<Matrix> {
+        ensure_no_exclude("Valhalla", exclude)?;
+        let locations: Vec<_> = coordinates.iter().map(location).collect();
+        let body = serde_json::json!({
+            "sources": locations,
+            "targets": locations,
+            "costing": self.costing(profile),
+            "units": "kilometers",
+        });
+        let response: MatrixResponse = self.request("sources_to_targets", body).await?;
+
+        let size = coordinates.len();
+        if response.sources_to_targets.len() != size
+            || response
+                .sources_to_targets
+                .iter()
+                .any(|row| row.len() != size)
+        {
+            bail!(
+                "Valhalla matrix has the wrong size, expected {}x{}",
+                size,
+                size
+            );
+        }
+
+        let mut durations = vec![vec![0.0; size]; size];
+        let mut distances = vec![vec![0.0; size]; size];
+        for (from, row) in response.sources_to_targets.iter().enumerate() {
+            for (to, cell) in row.iter().enumerate() {
+                durations[from][to] = cell.time.unwrap_or(f64::INFINITY);
+                distances[from][to] = cell.distance.map_or(f64::INFINITY, |km| km * 1000.0);
+            }
+        }
+
+        Ok(Matrix {
+            durations,
+            distances: Some(distances),
+            snapped: None,
+        })
+    }
+
+    async fn health(&self) -> Vec<BackendHealth> {
+        let url = format!("{}/status", self.config.base_url);
+        let result = match self.client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(format!(
+                "Valhalla status request failed with status {}",
+                response.status()
+            )),
+            Err(err) => Err(err.to_string()),
+        };
+
+        vec![BackendHealth {
+            name: "default".to_string(),
+            base_url: self.config.base_url.clone(),
+            healthy: result.is_ok(),
+            error: result.err(),
+        }]
+    }
+}
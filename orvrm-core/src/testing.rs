@@ -0,0 +1,408 @@
+//! Stub OSRM server for tests, enabled by the `test-util` feature.
+//!
+//! The stub answers OSRM `/table` and `/route` requests for any profile,
+//! Valhalla `/sources_to_targets` and `/route` requests for any costing, and
+//! GraphHopper `/matrix` and `/route` requests under `/graphhopper`, with travel
+//! derived from the straight-line distance between coordinates, so tests can
+//! predict every duration without a real routing engine. Its roads run through
+//! whole degrees, and OSRM tables report how far each coordinate was snapped to
+//! the nearest of them.
+
+use crate::services::{GraphHopperConfig, OsrmConfig, ValhallaConfig};
+use crate::utils::polyline;
+use actix_web::{web, App, HttpResponse, HttpServer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Seconds of travel per degree of straight-line distance
+pub const SECONDS_PER_DEGREE: f64 = 100.0;
+
+/// Meters of travel per degree of straight-line distance
+pub const METERS_PER_DEGREE: f64 = 1000.0;
+
+/// Running stub OSRM server
+#[derive(Debug, Clone)]
+pub struct StubOsrm {
+    base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubOsrm {
+    /// Start the stub on a free local port
+    ///
+    /// Must be called from within an actix runtime, such as an `#[actix_web::test]`.
+    pub fn start() -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = web::Data::new(Arc::clone(&requests));
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(log.clone())
+                .route("/table/v1/{profile}/{coordinates}", web::get().to(table))
+                .route("/route/v1/{profile}/{coordinates}", web::get().to(route))
+                .route("/sources_to_targets", web::post().to(valhalla_matrix))
+                .route("/route", web::post().to(valhalla_route))
+                .route("/status", web::get().to(HttpResponse::Ok))
+                .route("/graphhopper/matrix", web::post().to(graphhopper_matrix))
+                .route("/graphhopper/route", web::post().to(graphhopper_route))
+                .route("/graphhopper/info", web::get().to(HttpResponse::Ok))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("failed to bind stub OSRM server");
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        Self {
+            base_url: format!("http://{}", address),
+            requests,
+        }
+    }
+
+    /// Base URL of the stub
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// OSRM configuration pointing at the stub
+    pub fn config(&self) -> OsrmConfig {
+        OsrmConfig {
+            base_url: self.base_url.clone(),
+            ..OsrmConfig::default()
+        }
+    }
+
+    /// Valhalla configuration pointing at the stub
+    pub fn valhalla_config(&self) -> ValhallaConfig {
+        ValhallaConfig {
+            base_url: self.base_url.clone(),
+            ..ValhallaConfig::default()
+        }
+    }
+
+    /// GraphHopper configuration pointing at the stub
+    pub fn graphhopper_config(&self) -> GraphHopperConfig {
+        GraphHopperConfig {
+            base_url: format!("{}/graphhopper", self.base_url),
+            api_key: Some("test".to_string()),
+            ..GraphHopperConfig::default()
+        }
+    }
+
+    /// Services and profiles or costings requested so far, such as `table/car`, followed
+    /// by `?exclude=<classes>` when OSRM was asked to exclude road classes
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Name of every street in the turn-by-turn instructions of the stub
+pub const STUB_STREET: &str = "Stub Street";
+
+/// Travel duration and distance the stub reports between two coordinates
+pub fn stub_travel(from: [f64; 2], to: [f64; 2]) -> (f64, f64) {
+    let degrees = ((from[0] - to[0]).powi(2) + (from[1] - to[1]).powi(2)).sqrt();
+    (degrees * SECONDS_PER_DEGREE, degrees * METERS_PER_DEGREE)
+}
+
+/// Meters the stub moves a coordinate to reach the nearest whole degree
+pub fn stub_snap_distance(coordinate: [f64; 2]) -> f64 {
+    let nearest = [coordinate[0].round(), coordinate[1].round()];
+    stub_travel(coordinate, nearest).1
+}
+
+type RequestLog = web::Data<Arc<Mutex<Vec<String>>>>;
+
+fn parse_coordinates(coordinates: &str) -> Option<Vec<[f64; 2]>> {
+    coordinates
+        .split(';')
+        .map(|pair| {
+            let (lon, lat) = pair.split_once(',')?;
+            Some([lon.parse().ok()?, lat.parse().ok()?])
+        })
+        .collect()
+}
+
+fn invalid_coordinates() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "code": "InvalidQuery",
+        "message": "Invalid coordinates",
+    }))
+}
+
+/// Logged form of an OSRM request
+fn logged(service: &str, profile: &str, query: &HashMap<String, String>) -> String {
+    match query.get("exclude") {
+        Some(exclude) => format!("{}/{}?exclude={}", service, profile, exclude),
+        None => format!("{}/{}", service, profile),
+    }
+}
+
+async fn table(
+    path: web::Path<(String, String)>,
+    query: web::Query<HashMap<String, String>>,
+    log: RequestLog,
+) -> HttpResponse {
+    let (profile, coordinates) = path.into_inner();
+    log.lock().unwrap().push(logged("table", &profile, &query));
+    let Some(coordinates) = parse_coordinates(&coordinates) else {
+        return invalid_coordinates();
+    };
+
+    let indices = |name: &str| -> Vec<usize> {
+        match query.get(name) {
+            Some(list) => list.split(';').filter_map(|idx| idx.parse().ok()).collect(),
+            None => (0..coordinates.len()).collect(),
+        }
+    };
+    let (sources, destinations) = (indices("sources"), indices("destinations"));
+    let table = |pick: fn((f64, f64)) -> f64| -> Vec<Vec<f64>> {
+        sources
+            .iter()
+            .map(|from| {
+                destinations
+                    .iter()
+                    .map(|to| pick(stub_travel(coordinates[*from], coordinates[*to])))
+                    .collect()
+            })
+            .collect()
+    };
+
+    let waypoints: Vec<serde_json::Value> = sources
+        .iter()
+        .map(|idx| {
+            let coordinate = coordinates[*idx];
+            serde_json::json!({
+                "distance": stub_snap_distance(coordinate),
+                "location": [coordinate[0].round(), coordinate[1].round()],
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "code": "Ok",
+        "durations": table(|(duration, _)| duration),
+        "distances": table(|(_, distance)| distance),
+        "sources": waypoints,
+    }))
+}
+
+async fn route(
+    path: web::Path<(String, String)>,
+    query: web::Query<HashMap<String, String>>,
+    log: RequestLog,
+) -> HttpResponse {
+    let (profile, coordinates) = path.into_inner();
+    log.lock().unwrap().push(logged("route", &profile, &query));
+    let Some(coordinates) = parse_coordinates(&coordinates) else {
+        return invalid_coordinates();
+    };
+
+    let legs: Vec<(f64, f64)> = coordinates
+        .windows(2)
+        .map(|pair| stub_travel(pair[0], pair[1]))
+        .collect();
+    let geometry = (query.get("overview").map(String::as_str) == Some("full"))
+        .then(|| polyline::encode(&coordinates, 5));
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "code": "Ok",
+        "routes": [{
+            "duration": legs.iter().map(|(duration, _)| duration).sum::<f64>(),
+            "distance": legs.iter().map(|(_, distance)| distance).sum::<f64>(),
+            "geometry": geometry,
+            "legs": legs
+                .iter()
+                .map(|(duration, distance)| serde_json::json!({
+                    "duration": duration,
+                    "distance": distance,
+                    "steps": [
+                        {
+                            "duration": duration,
+                            "distance": distance,
+                            "name": STUB_STREET,
+                            "maneuver": {"type": "depart", "modifier": "straight"},
+                        },
+                        {
+                            "duration": 0.0,
+                            "distance": 0.0,
+                            "name": STUB_STREET,
+                            "maneuver": {"type": "arrive"},
+                        },
+                    ],
+                }))
+                .collect::<Vec<_>>(),
+        }],
+        "waypoints": coordinates
+            .iter()
+            .map(|location| serde_json::json!({
+                "hint": "",
+                "distance": 0.0,
+                "name": "",
+                "location": location,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Coordinates of Valhalla locations in a request body
+fn valhalla_locations(body: &serde_json::Value, key: &str) -> Vec<[f64; 2]> {
+    body[key]
+        .as_array()
+        .map(|locations| {
+            locations
+                .iter()
+                .map(|location| {
+                    [
+                        location["lon"].as_f64().unwrap_or_default(),
+                        location["lat"].as_f64().unwrap_or_default(),
+                    ]
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn valhalla_costing(body: &serde_json::Value) -> &str {
+    body["costing"].as_str().unwrap_or_default()
+}
+
+async fn valhalla_matrix(body: web::Json<serde_json::Value>, log: RequestLog) -> HttpResponse {
+    log.lock()
+        .unwrap()
+        .push(format!("sources_to_targets/{}", valhalla_costing(&body)));
+    let sources = valhalla_locations(&body, "sources");
+    let targets = valhalla_locations(&body, "targets");
+
+    let rows: Vec<Vec<serde_json::Value>> = sources
+        .iter()
+        .enumerate()
+        .map(|(from_index, from)| {
+            targets
+                .iter()
+                .enumerate()
+                .map(|(to_index, to)| {
+                    let (duration, distance) = stub_travel(*from, *to);
+                    serde_json::json!({
+                        "from_index": from_index,
+                        "to_index": to_index,
+                        "time": duration,
+                        "distance": distance / 1000.0,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "sources_to_targets": rows,
+        "units": "kilometers",
+    }))
+}
+
+async fn valhalla_route(body: web::Json<serde_json::Value>, log: RequestLog) -> HttpResponse {
+    log.lock()
+        .unwrap()
+        .push(format!("route/{}", valhalla_costing(&body)));
+    let locations = valhalla_locations(&body, "locations");
+
+    let legs: Vec<serde_json::Value> = locations
+        .windows(2)
+        .map(|pair| {
+            let (duration, distance) = stub_travel(pair[0], pair[1]);
+            serde_json::json!({
+                "summary": {"time": duration, "length": distance / 1000.0},
+                "shape": polyline::encode(pair, 6),
+                "maneuvers": [
+                    {"type": 1, "street_names": [STUB_STREET], "time": duration, "length": distance / 1000.0},
+                    {"type": 4, "time": 0.0, "length": 0.0},
+                ],
+            })
+        })
+        .collect();
+    let (duration, distance) = locations
+        .windows(2)
+        .map(|pair| stub_travel(pair[0], pair[1]))
+        .fold((0.0, 0.0), |total, leg| (total.0 + leg.0, total.1 + leg.1));
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "trip": {
+            "legs": legs,
+            "summary": {"time": duration, "length": distance / 1000.0},
+            "status": 0,
+            "units": "kilometers",
+        }
+    }))
+}
+
+/// Coordinates of the GraphHopper points in a request body
+fn graphhopper_points(body: &serde_json::Value) -> Vec<[f64; 2]> {
+    serde_json::from_value(body["points"].clone()).unwrap_or_default()
+}
+
+fn graphhopper_profile(body: &serde_json::Value) -> &str {
+    body["profile"].as_str().unwrap_or_default()
+}
+
+async fn graphhopper_matrix(body: web::Json<serde_json::Value>, log: RequestLog) -> HttpResponse {
+    log.lock()
+        .unwrap()
+        .push(format!("matrix/{}", graphhopper_profile(&body)));
+    let points = graphhopper_points(&body);
+
+    let table = |pick: fn((f64, f64)) -> f64| -> Vec<Vec<f64>> {
+        points
+            .iter()
+            .map(|from| {
+                points
+                    .iter()
+                    .map(|to| pick(stub_travel(*from, *to)))
+                    .collect()
+            })
+            .collect()
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "times": table(|(duration, _)| duration),
+        "distances": table(|(_, distance)| distance),
+    }))
+}
+
+async fn graphhopper_route(body: web::Json<serde_json::Value>, log: RequestLog) -> HttpResponse {
+    log.lock()
+        .unwrap()
+        .push(format!("route/{}", graphhopper_profile(&body)));
+    let points = graphhopper_points(&body);
+
+    // One instruction per leg, followed by reaching the via point or destination
+    let legs = points.len().saturating_sub(1);
+    let mut instructions = Vec::new();
+    for (idx, pair) in points.windows(2).enumerate() {
+        let (duration, distance) = stub_travel(pair[0], pair[1]);
+        instructions.push(serde_json::json!({
+            "distance": distance,
+            "time": duration * 1000.0,
+            "sign": 0,
+            "street_name": STUB_STREET,
+        }));
+        instructions.push(serde_json::json!({
+            "distance": 0.0,
+            "time": 0.0,
+            "sign": if idx + 1 == legs { 4 } else { 5 },
+        }));
+    }
+    let (duration, distance) = points
+        .windows(2)
+        .map(|pair| stub_travel(pair[0], pair[1]))
+        .fold((0.0, 0.0), |total, leg| (total.0 + leg.0, total.1 + leg.1));
+    let calc_points = body["calc_points"].as_bool().unwrap_or(true);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "paths": [{
+            "distance": distance,
+            "time": duration * 1000.0,
+            "points": calc_points.then(|| polyline::encode(&points, 5)),
+            "instructions": instructions,
+        }]
+    }))
+}
@@ -0,0 +1,72 @@
+use crate::models::RoutingResponse;
+use serde_json::Value;
+
+/// Columns of a solution rendered as CSV
+const COLUMNS: [&str; 10] = [
+    "vehicle_id",
+    "sequence",
+    "type",
+    "id",
+    "arrival_time",
+    "departure_time",
+    "service",
+    "waiting_time",
+    "load",
+    "distance",
+];
+
+/// Render the routes of a solution as CSV with one row per step
+///
+/// Steps are numbered from `0` for the start of each route. Multi-dimensional loads are
+/// joined with commas within their cell. The `distance` is filled in on the `end` row, with
+/// the total distance of the route in meters. Values a step does not have are left empty.
+pub fn export(response: &RoutingResponse) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push_str("\r\n");
+
+    for route in &response.routes {
+        for (sequence, (step, load)) in route.steps.iter().zip(route.step_loads()).enumerate() {
+            let Ok(fields) = serde_json::to_value(step) else {
+                continue;
+            };
+            let value = |name: &str| match fields.get(name) {
+                Some(Value::String(text)) => text.clone(),
+                Some(Value::Number(number)) => number.to_string(),
+                _ => String::new(),
+            };
+            let kind = value("type");
+            let load: Vec<String> = load.iter().map(i32::to_string).collect();
+            let distance = match kind.as_str() {
+                "end" => route.distance.to_string(),
+                _ => String::new(),
+            };
+
+            let row = [
+                route.vehicle_id.to_string(),
+                sequence.to_string(),
+                kind,
+                value("id"),
+                value("arrival_time"),
+                value("departure_time"),
+                value("service"),
+                value("waiting_time"),
+                load.join(","),
+                distance,
+            ];
+            let cells: Vec<String> = row.iter().map(|cell| escape(cell)).collect();
+            out.push_str(&cells.join(","));
+            out.push_str("\r\n");
+        }
+    }
+
+    out
+}
+
+/// Quote a cell holding a separator, quote or line break, doubling its quotes
+fn escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
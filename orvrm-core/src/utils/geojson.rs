@@ -0,0 +1,63 @@
+use crate::models::{RouteStep, VehicleRoute};
+use serde_json::{json, Value};
+
+/// Build a GeoJSON `FeatureCollection` for a route
+///
+/// The collection holds the route itself as a `LineString` along `line`, followed
+/// by a `Point` for every step that has a location. Step properties are the step's
+/// own fields, such as its type, ID and timing.
+pub fn route_feature_collection(route: &VehicleRoute, line: &[[f64; 2]]) -> Value {
+    let mut features = vec![json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": line,
+        },
+        "properties": {
+            "vehicle_id": route.vehicle_id,
+            "distance": route.distance,
+            "duration": route.duration,
+        },
+    })];
+
+    for step in &route.steps {
+        let Ok(Value::Object(mut properties)) = serde_json::to_value(step) else {
+            continue;
+        };
+        let Some(location) = properties.remove("location") else {
+            continue;
+        };
+
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": location,
+            },
+            "properties": Value::Object(properties),
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+/// Locations of the steps of a route, in order
+pub fn step_locations(route: &VehicleRoute) -> Vec<[f64; 2]> {
+    route
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            RouteStep::Start { location, .. }
+            | RouteStep::Job { location, .. }
+            | RouteStep::Pickup { location, .. }
+            | RouteStep::Delivery { location, .. }
+            | RouteStep::Break { location, .. }
+            | RouteStep::Reload { location, .. }
+            | RouteStep::Rest { location, .. }
+            | RouteStep::End { location, .. } => *location,
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+/// Check whether `point` lies inside a polygon of `[longitude, latitude]` rings
+///
+/// The first ring is the outer boundary and any further rings are holes, as in a
+/// GeoJSON `Polygon`. Rings may be given open or closed. Coordinates are treated as
+/// planar, which is accurate enough for territories of a city or region.
+pub fn polygon_contains(rings: &[Vec<[f64; 2]>], point: [f64; 2]) -> bool {
+    let Some((outer, holes)) = rings.split_first() else {
+        return false;
+    };
+
+    ring_contains(outer, point) && !holes.iter().any(|hole| ring_contains(hole, point))
+}
+
+/// Check whether `point` lies inside a single ring, by casting a ray along the longitude
+pub fn ring_contains(ring: &[[f64; 2]], point: [f64; 2]) -> bool {
+    let [x, y] = point;
+    let mut inside = false;
+
+    let mut previous = match ring.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+    for &[x1, y1] in ring {
+        let [x0, y0] = previous;
+        if (y1 > y) != (y0 > y) && x < x0 + (y - y0) * (x1 - x0) / (y1 - y0) {
+            inside = !inside;
+        }
+        previous = [x1, y1];
+    }
+
+    inside
+}
+
+/// Check whether the rings form a usable polygon: every ring has at least three distinct
+/// corners and finite coordinates
+pub fn is_valid_polygon(rings: &[Vec<[f64; 2]>]) -> bool {
+    !rings.is_empty()
+        && rings.iter().all(|ring| {
+            let open = match ring.split_last() {
+                Some((last, rest)) if rest.first() == Some(last) => rest,
+                _ => ring.as_slice(),
+            };
+            open.len() >= 3
+                && open
+                    .iter()
+                    .all(|[lon, lat]| lon.is_finite() && lat.is_finite())
+        })
+}
+
+/// Check whether the straight line from `from` to `to` crosses the boundary of a polygon,
+/// given as rings like in [`polygon_contains`]
+pub fn polygon_crosses(rings: &[Vec<[f64; 2]>], from: [f64; 2], to: [f64; 2]) -> bool {
+    rings.iter().any(|ring| {
+        let Some(mut previous) = ring.last().copied() else {
+            return false;
+        };
+        ring.iter().any(|&corner| {
+            let edge = (previous, corner);
+            previous = corner;
+            segments_intersect((from, to), edge)
+        })
+    })
+}
+
+/// Check whether two line segments share at least one point
+fn segments_intersect(a: ([f64; 2], [f64; 2]), b: ([f64; 2], [f64; 2])) -> bool {
+    // Side of the line through `p` and `q` that `r` lies on
+    let orientation = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| {
+        let cross = (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0]);
+        if cross > 0.0 {
+            1
+        } else if cross < 0.0 {
+            -1
+        } else {
+            0
+        }
+    };
+    // Whether `r`, on the line through `p` and `q`, lies between them
+    let within = |p: [f64; 2], q: [f64; 2], r: [f64; 2]| {
+        r[0] >= p[0].min(q[0]) && r[0] <= p[0].max(q[0]) && r[1] >= p[1].min(q[1]) && r[1] <= p[1].max(q[1])
+    };
+
+    let ((p1, q1), (p2, q2)) = (a, b);
+    let (o1, o2) = (orientation(p1, q1, p2), orientation(p1, q1, q2));
+    let (o3, o4) = (orientation(p2, q2, p1), orientation(p2, q2, q1));
+
+    (o1 != o2 && o3 != o4)
+        || (o1 == 0 && within(p1, q1, p2))
+        || (o2 == 0 && within(p1, q1, q2))
+        || (o3 == 0 && within(p2, q2, p1))
+        || (o4 == 0 && within(p2, q2, q1))
+}
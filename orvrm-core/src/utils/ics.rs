@@ -0,0 +1,135 @@
+use crate::models::VehicleRoute;
+use chrono::DateTime;
+use serde_json::Value;
+
+/// Render a route as an iCalendar file with one event per step
+///
+/// Step times are read as Unix timestamps. Each event runs from the arrival to the departure
+/// at the step and carries its location and service time. Event UIDs are built from
+/// `calendar_id`, so a re-published calendar replaces the events of the earlier one, and
+/// `stamp` is the time the calendar was created, also as a Unix timestamp.
+pub fn export(route: &VehicleRoute, calendar_id: &str, stamp: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//ORVRM//Route Calendar//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:Vehicle {}", route.vehicle_id),
+    ];
+
+    for (sequence, step) in route.steps.iter().enumerate() {
+        let Ok(fields) = serde_json::to_value(step) else {
+            continue;
+        };
+        let number = |name: &str| fields.get(name).and_then(Value::as_i64);
+        let Some(arrival) = number("arrival_time") else {
+            continue;
+        };
+        let departure = number("departure_time").unwrap_or(arrival);
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!(
+            "UID:{}-{}-{}@orvrm",
+            calendar_id, route.vehicle_id, sequence
+        ));
+        lines.push(format!("DTSTAMP:{}", timestamp(stamp)));
+        lines.push(format!("DTSTART:{}", timestamp(arrival)));
+        lines.push(format!("DTEND:{}", timestamp(departure.max(arrival))));
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape(&summary(
+                fields["type"].as_str().unwrap_or_default(),
+                number("id")
+            ))
+        ));
+        if let Some([lon, lat]) = fields
+            .get("location")
+            .and_then(|location| serde_json::from_value::<[f64; 2]>(location.clone()).ok())
+        {
+            lines.push(format!("LOCATION:{}", escape(&format!("{}, {}", lat, lon))));
+            lines.push(format!("GEO:{};{}", lat, lon));
+        }
+
+        let mut description = Vec::new();
+        if let Some(setup) = number("setup").filter(|setup| *setup > 0) {
+            description.push(format!("Setup {}", duration(setup)));
+        }
+        if let Some(service) = number("service").filter(|service| *service > 0) {
+            description.push(format!("Service {}", duration(service)));
+        }
+        if let Some(waiting) = number("waiting_time").filter(|waiting| *waiting > 0) {
+            description.push(format!("Waiting {}", duration(waiting)));
+        }
+        if !description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&description.join("\n"))));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Title of the event for a step
+fn summary(kind: &str, id: Option<i64>) -> String {
+    let id = id.map(|id| id.to_string()).unwrap_or_default();
+    match kind {
+        "start" => "Start of route".to_string(),
+        "job" => format!("Job {}", id),
+        "pickup" => format!("Pickup of shipment {}", id),
+        "delivery" => format!("Delivery of shipment {}", id),
+        "break" => format!("Break {}", id),
+        "reload" => "Reload".to_string(),
+        "rest" => "Rest".to_string(),
+        "end" => "End of route".to_string(),
+        kind => kind.to_string(),
+    }
+}
+
+/// UTC date-time in iCalendar notation, such as `20240501T083000Z`
+fn timestamp(seconds: i64) -> String {
+    DateTime::from_timestamp(seconds, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Readable duration, such as `1 h 5 min` or `45 s`
+fn duration(seconds: i64) -> String {
+    let parts = [
+        (seconds / 3600, "h"),
+        (seconds % 3600 / 60, "min"),
+        (seconds % 60, "s"),
+    ];
+    parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{} {}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escape the characters with a meaning in iCalendar text values
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line into lines of at most 75 octets, ending each with CRLF
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 4);
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
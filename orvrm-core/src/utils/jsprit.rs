@@ -0,0 +1,356 @@
+use crate::models::{
+    RouteStep, RoutingRequest, RoutingResponse, ShipmentStep, Vehicle, VehicleRoute,
+};
+use std::fmt::Display;
+
+/// Convert a request and its solution to a jsprit problem file with the solution included
+///
+/// Every vehicle gets a vehicle type of its own, named `type-<vehicle id>`, carrying its
+/// capacity and costs. Jobs become services of type `delivery`, `pickup` or `service`
+/// depending on the amounts they carry, with their own ID. jsprit shares one ID space between
+/// services and shipments, so shipments are named `shipment-<id>`. Only the first break of a
+/// vehicle is exported, as jsprit supports one per vehicle. Vehicles that refer to depots
+/// get the depot locations, and vehicles on their way start at their current position,
+/// without the jobs they completed.
+pub fn export(request: &RoutingRequest, response: &RoutingResponse) -> String {
+    let mut request = request.clone();
+    request.resolve_coordinate_order();
+    request.resolve_depots();
+    request.resolve_positions();
+    let request = &request;
+
+    let mut xml = Xml::default();
+    xml.out
+        .push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.open(
+        "problem",
+        &[
+            ("xmlns", "http://www.w3schools.com"),
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            (
+                "xsi:schemaLocation",
+                "http://www.w3schools.com vrp_xml_schema.xsd",
+            ),
+        ],
+    );
+
+    xml.open("problemType", &[]);
+    xml.leaf("fleetSize", "FINITE");
+    xml.close("problemType");
+
+    xml.open("vehicles", &[]);
+    for vehicle in &request.vehicles {
+        write_vehicle(&mut xml, vehicle);
+    }
+    xml.close("vehicles");
+
+    xml.open("vehicleTypes", &[]);
+    for vehicle in &request.vehicles {
+        write_vehicle_type(&mut xml, vehicle);
+    }
+    xml.close("vehicleTypes");
+
+    if !request.jobs.is_empty() {
+        xml.open("services", &[]);
+        for job in &request.jobs {
+            let id = job.id.to_string();
+            let kind = match (&job.delivery, &job.pickup) {
+                (Some(_), None) => "delivery",
+                (None, Some(_)) => "pickup",
+                _ => "service",
+            };
+            xml.open("service", &[("id", &id), ("type", kind)]);
+            write_location(&mut xml, "location", job.location);
+            let amount = job.delivery.as_ref().or(job.pickup.as_ref());
+            write_capacity(&mut xml, amount.map_or(&[], |amount| amount));
+            xml.leaf("duration", job.service);
+            write_time_windows(&mut xml, job.time_windows.as_deref());
+            write_skills(&mut xml, "requiredSkills", job.skills.as_deref());
+            xml.close("service");
+        }
+        xml.close("services");
+    }
+
+    if !request.shipments.is_empty() {
+        xml.open("shipments", &[]);
+        for shipment in &request.shipments {
+            xml.open("shipment", &[("id", &shipment_id(shipment.id))]);
+            write_shipment_step(&mut xml, "pickup", &shipment.pickup);
+            write_shipment_step(&mut xml, "delivery", &shipment.delivery);
+            write_capacity(&mut xml, &shipment.amount);
+            write_skills(&mut xml, "requiredSkills", shipment.skills.as_deref());
+            xml.close("shipment");
+        }
+        xml.close("shipments");
+    }
+
+    xml.open("solutions", &[]);
+    xml.open("solution", &[]);
+    xml.leaf("cost", response.summary.cost);
+    xml.open("routes", &[]);
+    for route in &response.routes {
+        write_route(&mut xml, route);
+    }
+    xml.close("routes");
+    xml.open("unassignedJobs", &[]);
+    for id in response.unassigned.ids() {
+        xml.empty("job", &[("id", &id.to_string())]);
+    }
+    for id in response.unassigned_shipments.ids() {
+        xml.empty("job", &[("id", &shipment_id(id))]);
+    }
+    xml.close("unassignedJobs");
+    xml.close("solution");
+    xml.close("solutions");
+
+    xml.close("problem");
+    xml.out
+}
+
+fn write_vehicle(xml: &mut Xml, vehicle: &Vehicle) {
+    xml.open("vehicle", &[]);
+    xml.leaf("id", vehicle.id);
+    xml.leaf("typeId", type_id(vehicle.id));
+    if let Some(start) = vehicle.start {
+        write_location(xml, "startLocation", start);
+    }
+    if let Some(end) = vehicle.end {
+        write_location(xml, "endLocation", end);
+    }
+    if let Some([start, end]) = vehicle.time_window {
+        xml.open("timeSchedule", &[]);
+        xml.leaf("start", start);
+        xml.leaf("end", end);
+        xml.close("timeSchedule");
+    }
+    xml.leaf("returnToDepot", vehicle.end.is_some());
+    write_skills(xml, "skills", vehicle.skills.as_deref());
+    if let Some(brk) = vehicle.breaks.iter().flatten().next() {
+        xml.open("breaks", &[]);
+        write_time_windows(xml, Some(&brk.time_windows));
+        xml.leaf("duration", brk.service);
+        xml.close("breaks");
+    }
+    xml.close("vehicle");
+}
+
+fn write_vehicle_type(xml: &mut Xml, vehicle: &Vehicle) {
+    let costs = vehicle.costs.unwrap_or_default();
+    xml.open("type", &[]);
+    xml.leaf("id", type_id(vehicle.id));
+    write_capacity(xml, &vehicle.capacity);
+    xml.open("costs", &[]);
+    xml.leaf("fixed", costs.fixed);
+    xml.leaf("distance", costs.per_km / 1000.0);
+    xml.leaf("time", costs.per_hour / 3600.0);
+    xml.leaf("wait", costs.per_hour_waiting / 3600.0);
+    xml.leaf("service", costs.per_hour_service / 3600.0);
+    xml.close("costs");
+    xml.close("type");
+}
+
+fn write_shipment_step(xml: &mut Xml, tag: &str, step: &ShipmentStep) {
+    xml.open(tag, &[]);
+    write_location(xml, "location", step.location);
+    xml.leaf("duration", step.service);
+    write_time_windows(xml, step.time_windows.as_deref());
+    xml.close(tag);
+}
+
+fn write_route(xml: &mut Xml, route: &VehicleRoute) {
+    xml.open("route", &[]);
+    xml.leaf("driverId", "noDriver");
+    xml.leaf("vehicleId", route.vehicle_id);
+    for step in &route.steps {
+        match step {
+            RouteStep::Start { departure_time, .. } => {
+                xml.leaf("start", departure_time.unwrap_or(0));
+            }
+            RouteStep::Job {
+                id,
+                arrival_time,
+                departure_time,
+                ..
+            } => write_act(
+                xml,
+                "service",
+                "serviceId",
+                id.to_string(),
+                arrival_time,
+                departure_time,
+            ),
+            RouteStep::Pickup {
+                id,
+                arrival_time,
+                departure_time,
+                ..
+            } => write_act(
+                xml,
+                "pickupShipment",
+                "shipmentId",
+                shipment_id(*id),
+                arrival_time,
+                departure_time,
+            ),
+            RouteStep::Delivery {
+                id,
+                arrival_time,
+                departure_time,
+                ..
+            } => write_act(
+                xml,
+                "deliverShipment",
+                "shipmentId",
+                shipment_id(*id),
+                arrival_time,
+                departure_time,
+            ),
+            RouteStep::Break {
+                id,
+                arrival_time,
+                departure_time,
+                ..
+            } => write_act(
+                xml,
+                "break",
+                "breakId",
+                id.to_string(),
+                arrival_time,
+                departure_time,
+            ),
+            RouteStep::End { arrival_time, .. } => {
+                xml.leaf("end", arrival_time.unwrap_or(0));
+            }
+            RouteStep::Reload { .. } | RouteStep::Rest { .. } => {}
+        }
+    }
+    xml.close("route");
+}
+
+fn write_act(
+    xml: &mut Xml,
+    kind: &str,
+    id_tag: &str,
+    id: String,
+    arrival_time: &Option<i64>,
+    departure_time: &Option<i64>,
+) {
+    xml.open("act", &[("type", kind)]);
+    xml.leaf(id_tag, id);
+    xml.leaf("arrTime", arrival_time.unwrap_or(0));
+    xml.leaf("endTime", departure_time.unwrap_or(0));
+    xml.close("act");
+}
+
+fn write_location(xml: &mut Xml, tag: &str, [lon, lat]: [f64; 2]) {
+    xml.open(tag, &[]);
+    xml.empty("coord", &[("x", &lon.to_string()), ("y", &lat.to_string())]);
+    xml.close(tag);
+}
+
+fn write_capacity(xml: &mut Xml, amounts: &[u32]) {
+    xml.open("capacity-dimensions", &[]);
+    for (index, amount) in amounts.iter().enumerate() {
+        xml.open_inline("dimension", &[("index", &index.to_string())]);
+        xml.text_and_close("dimension", amount);
+    }
+    xml.close("capacity-dimensions");
+}
+
+fn write_time_windows(xml: &mut Xml, time_windows: Option<&[[i64; 2]]>) {
+    let Some(time_windows) = time_windows.filter(|time_windows| !time_windows.is_empty()) else {
+        return;
+    };
+    xml.open("timeWindows", &[]);
+    for [start, end] in time_windows {
+        xml.open("timeWindow", &[]);
+        xml.leaf("start", start);
+        xml.leaf("end", end);
+        xml.close("timeWindow");
+    }
+    xml.close("timeWindows");
+}
+
+fn write_skills(xml: &mut Xml, tag: &str, skills: Option<&[String]>) {
+    if let Some(skills) = skills.filter(|skills| !skills.is_empty()) {
+        xml.leaf(tag, skills.join(","));
+    }
+}
+
+fn type_id(vehicle_id: u64) -> String {
+    format!("type-{}", vehicle_id)
+}
+
+fn shipment_id(id: u64) -> String {
+    format!("shipment-{}", id)
+}
+
+/// Minimal indenting XML writer
+#[derive(Default)]
+struct Xml {
+    out: String,
+    depth: usize,
+}
+
+impl Xml {
+    fn open(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.open_inline(tag, attributes);
+        self.out.push('\n');
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        self.out.push_str(&format!("</{}>\n", tag));
+    }
+
+    fn leaf(&mut self, tag: &str, text: impl Display) {
+        self.open_inline(tag, &[]);
+        self.text_and_close(tag, text);
+    }
+
+    fn empty(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.indent();
+        self.out
+            .push_str(&format!("<{}{}/>\n", tag, attribute_list(attributes)));
+    }
+
+    fn open_inline(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.indent();
+        self.out
+            .push_str(&format!("<{}{}>", tag, attribute_list(attributes)));
+    }
+
+    fn text_and_close(&mut self, tag: &str, text: impl Display) {
+        self.out
+            .push_str(&format!("{}</{}>\n", escape(&text.to_string()), tag));
+    }
+
+    fn indent(&mut self) {
+        self.out.push_str(&"    ".repeat(self.depth));
+    }
+}
+
+fn attribute_list(attributes: &[(&str, &str)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(" {}=\"{}\"", name, escape(value)))
+        .collect()
+}
+
+/// Escape the characters with a meaning in XML text and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
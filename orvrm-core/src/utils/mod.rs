@@ -0,0 +1,10 @@
+pub mod csv;
+pub mod geojson;
+pub mod geometry;
+pub mod ics;
+pub mod jsprit;
+pub mod msgpack;
+pub mod polyline;
+pub mod solomon;
+pub mod validation;
+pub mod vroom;
@@ -0,0 +1,669 @@
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt;
+
+/// Encode a value as MessagePack, with the same structure and field names as its JSON
+///
+/// Integers take the smallest encoding that holds them, other numbers are 64-bit floats
+/// and non-finite ones nil. Map keys are strings, and enum variants are tagged by name.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    let mut encoder = Encoder { out: Vec::new() };
+    value.serialize(&mut encoder)?;
+    Ok(encoder.out)
+}
+
+/// Failure to encode a value, raised by its `Serialize` implementation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+/// Markers of the fixed, 16-bit and 32-bit forms of arrays and maps
+const ARRAY: [u8; 3] = [0x90, 0xdc, 0xdd];
+const MAP: [u8; 3] = [0x80, 0xde, 0xdf];
+
+/// Serializer appending the encoding of a value to its buffer
+struct Encoder {
+    out: Vec<u8>,
+}
+
+impl Encoder {
+    fn write_nil(&mut self) {
+        self.out.push(0xc0);
+    }
+
+    fn write_str(&mut self, text: &str) {
+        let len = text.len();
+        match len {
+            0..=31 => self.out.push(0xa0 | len as u8),
+            32..=0xff => self.out.extend_from_slice(&[0xd9, len as u8]),
+            0x100..=0xffff => {
+                self.out.push(0xda);
+                self.out.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            _ => {
+                self.out.push(0xdb);
+                self.out.extend_from_slice(&(len as u32).to_be_bytes());
+            }
+        }
+        self.out.extend_from_slice(text.as_bytes());
+    }
+
+    fn write_unsigned(&mut self, number: u64) {
+        match number {
+            0..=0x7f => self.out.push(number as u8),
+            0x80..=0xff => self.out.extend_from_slice(&[0xcc, number as u8]),
+            0x100..=0xffff => {
+                self.out.push(0xcd);
+                self.out.extend_from_slice(&(number as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.out.push(0xce);
+                self.out.extend_from_slice(&(number as u32).to_be_bytes());
+            }
+            _ => {
+                self.out.push(0xcf);
+                self.out.extend_from_slice(&number.to_be_bytes());
+            }
+        }
+    }
+
+    /// Encode an integer, with non-negative ones as unsigned
+    fn write_signed(&mut self, number: i64) {
+        if number >= 0 {
+            self.write_unsigned(number as u64);
+        } else if number >= -32 {
+            self.out.push(number as i8 as u8);
+        } else if number >= i8::MIN as i64 {
+            self.out.extend_from_slice(&[0xd0, number as i8 as u8]);
+        } else if number >= i16::MIN as i64 {
+            self.out.push(0xd1);
+            self.out.extend_from_slice(&(number as i16).to_be_bytes());
+        } else if number >= i32::MIN as i64 {
+            self.out.push(0xd2);
+            self.out.extend_from_slice(&(number as i32).to_be_bytes());
+        } else {
+            self.out.push(0xd3);
+            self.out.extend_from_slice(&number.to_be_bytes());
+        }
+    }
+
+    /// Encode a float, or nil where JSON has no number for it
+    fn write_float(&mut self, number: f64) {
+        if number.is_finite() {
+            self.out.push(0xcb);
+            self.out.extend_from_slice(&number.to_be_bytes());
+        } else {
+            self.write_nil();
+        }
+    }
+
+    /// Start an array or map whose length may only be known once all items are written
+    fn begin(&mut self, len: Option<usize>, markers: [u8; 3]) -> Compound<'_> {
+        let start = self.out.len();
+        let hinted = len.unwrap_or(0);
+        write_header(hinted, markers, &mut self.out);
+        Compound {
+            encoder: self,
+            markers,
+            start,
+            hinted,
+            count: 0,
+        }
+    }
+
+    /// Start the single-entry map naming the variant of an enum value
+    fn begin_variant(&mut self, variant: &str) {
+        self.out.push(0x81);
+        self.write_str(variant);
+    }
+}
+
+/// Length of an array or map, with the markers of its fixed, 16-bit and 32-bit form
+fn write_header(len: usize, [fixed, short, long]: [u8; 3], out: &mut Vec<u8>) {
+    match len {
+        0..=15 => out.push(fixed | len as u8),
+        16..=0xffff => {
+            out.push(short);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(long);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+fn header_len(len: usize) -> usize {
+    match len {
+        0..=15 => 1,
+        16..=0xffff => 3,
+        _ => 5,
+    }
+}
+
+/// Array or map being written, counting its items
+struct Compound<'a> {
+    encoder: &'a mut Encoder,
+    markers: [u8; 3],
+    start: usize,
+    hinted: usize,
+    count: usize,
+}
+
+impl Compound<'_> {
+    /// Rewrite the header when the items written differ from the length given up front,
+    /// as with flattened fields
+    fn finish(self) -> Result<(), Error> {
+        if self.count != self.hinted {
+            let mut header = Vec::new();
+            write_header(self.count, self.markers, &mut header);
+            let end = self.start + header_len(self.hinted);
+            self.encoder.out.splice(self.start..end, header);
+        }
+        Ok(())
+    }
+
+    fn item<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.count += 1;
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), Error> {
+        self.count += 1;
+        self.encoder.write_str(name);
+        value.serialize(&mut *self.encoder)
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Encoder {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        self.out.push(if value { 0xc3 } else { 0xc2 });
+        Ok(())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.serialize_i64(value as i64)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.write_signed(value);
+        Ok(())
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), Error> {
+        if let Ok(value) = u64::try_from(value) {
+            self.write_unsigned(value);
+        } else if let Ok(value) = i64::try_from(value) {
+            self.write_signed(value);
+        } else {
+            return Err(Error(format!("{} is out of range", value)));
+        }
+        Ok(())
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.serialize_u64(value as u64)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.serialize_u64(value as u64)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.serialize_u64(value as u64)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        self.write_unsigned(value);
+        Ok(())
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), Error> {
+        let value =
+            u64::try_from(value).map_err(|_| Error(format!("{} is out of range", value)))?;
+        self.write_unsigned(value);
+        Ok(())
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.serialize_f64(value as f64)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        self.write_float(value);
+        Ok(())
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        self.write_str(value.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        self.write_str(value);
+        Ok(())
+    }
+
+    /// Bytes are a list of numbers, as in JSON
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), Error> {
+        write_header(value.len(), ARRAY, &mut self.out);
+        for byte in value {
+            self.write_unsigned(*byte as u64);
+        }
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.write_nil();
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.write_nil();
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.write_nil();
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.write_str(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.begin_variant(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(len, ARRAY))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(Some(len), ARRAY))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(Some(len), ARRAY))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_variant(variant);
+        Ok(self.begin(Some(len), ARRAY))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(len, MAP))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        Ok(self.begin(Some(len), MAP))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_variant(variant);
+        Ok(self.begin(Some(len), MAP))
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.item(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.count += 1;
+        key.serialize(MapKey(&mut *self.encoder))
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.encoder)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(name, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Serializer of map keys, writing them as strings like JSON does
+struct MapKey<'a>(&'a mut Encoder);
+
+impl MapKey<'_> {
+    fn write(self, key: impl fmt::Display) -> Result<(), Error> {
+        self.0.write_str(&key.to_string());
+        Ok(())
+    }
+}
+
+fn key_must_be_a_string() -> Error {
+    Error("Map keys must be strings".to_string())
+}
+
+impl ser::Serializer for MapKey<'_> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), Error> {
+        self.serialize_f64(value as f64)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), Error> {
+        if !value.is_finite() {
+            return Err(key_must_be_a_string());
+        }
+        self.write(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), Error> {
+        self.write(value)
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), Error> {
+        self.0.write_str(value);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.0.write_str(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(key_must_be_a_string())
+    }
+}
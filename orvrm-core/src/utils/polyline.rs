@@ -0,0 +1,108 @@
+use anyhow::{bail, Result};
+
+/// Decode an encoded polyline into [longitude, latitude] coordinates
+///
+/// OSRM encodes geometries with a precision of 5 decimals by default.
+pub fn decode(encoded: &str, precision: u32) -> Result<Vec<[f64; 2]>> {
+    let factor = 10f64.powi(precision as i32);
+    let bytes = encoded.as_bytes();
+
+    let mut coordinates = Vec::new();
+    let mut index = 0;
+    let mut lat = 0i64;
+    let mut lon = 0i64;
+
+    while index < bytes.len() {
+        // Latitude comes first in each pair
+        for value in [&mut lat, &mut lon] {
+            let mut shift = 0;
+            let mut result = 0i64;
+
+            loop {
+                let Some(byte) = bytes.get(index) else {
+                    bail!("Polyline ends in the middle of a coordinate");
+                };
+                if *byte < 63 || shift > 60 {
+                    bail!("Invalid polyline character at position {}", index);
+                }
+
+                let chunk = (*byte - 63) as i64;
+                result |= (chunk & 0x1f) << shift;
+                shift += 5;
+                index += 1;
+
+                if chunk < 0x20 {
+                    break;
+                }
+            }
+
+            *value += if result & 1 != 0 {
+                !(result >> 1)
+            } else {
+                result >> 1
+            };
+        }
+
+        coordinates.push([lon as f64 / factor, lat as f64 / factor]);
+    }
+
+    Ok(coordinates)
+}
+
+/// Encode [longitude, latitude] coordinates as a polyline
+pub fn encode(coordinates: &[[f64; 2]], precision: u32) -> String {
+    let factor = 10f64.powi(precision as i32);
+    let mut encoded = String::new();
+    let mut previous = [0i64; 2];
+
+    for coordinate in coordinates {
+        // Latitude comes first in each pair
+        let current = [
+            (coordinate[1] * factor).round() as i64,
+            (coordinate[0] * factor).round() as i64,
+        ];
+
+        for (value, last) in current.iter().zip(previous) {
+            let delta = value - last;
+            let mut rest = if delta < 0 { !(delta << 1) } else { delta << 1 };
+
+            while rest >= 0x20 {
+                encoded.push((((rest & 0x1f) | 0x20) as u8 + 63) as char);
+                rest >>= 5;
+            }
+            encoded.push((rest as u8 + 63) as char);
+        }
+
+        previous = current;
+    }
+
+    encoded
+}
+
+/// Split a line into the legs between consecutive waypoints
+///
+/// Each waypoint is matched to the nearest point of the line at or after the previous
+/// match, so waypoints off the road split the line where it passes closest to them.
+/// Consecutive legs share their boundary point.
+pub fn split(line: &[[f64; 2]], waypoints: &[[f64; 2]]) -> Vec<Vec<[f64; 2]>> {
+    if line.is_empty() || waypoints.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut legs = Vec::with_capacity(waypoints.len() - 1);
+    let mut from = 0;
+    for waypoint in &waypoints[1..waypoints.len() - 1] {
+        let gap = |index: &usize| {
+            let point = line[*index];
+            (point[0] - waypoint[0]).powi(2) + (point[1] - waypoint[1]).powi(2)
+        };
+        let to = (from..line.len())
+            .min_by(|a, b| gap(a).total_cmp(&gap(b)))
+            .unwrap_or(from);
+        legs.push(line[from..=to].to_vec());
+        from = to;
+    }
+    legs.push(line[from..].to_vec());
+
+    legs
+}
@@ -0,0 +1,123 @@
+//! Benchmark instances in the Solomon format
+//!
+//! The capacitated vehicle routing instances with time windows of Solomon (1987) and of
+//! Gehring and Homberger (1999) share a plain text format: the instance name, the number and
+//! capacity of the vehicles, and a table of customers with their coordinates, demand, time
+//! window and service time. Customer 0 is the depot.
+
+use crate::models::RoutingRequest;
+use crate::services::solver::LocationIndex;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+
+/// Benchmark instance read from a Solomon file
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// Name on the first line of the file, such as `C101`
+    pub name: String,
+
+    /// Request with every available vehicle and a job per customer
+    pub request: RoutingRequest,
+}
+
+/// Customer row of an instance
+struct Customer {
+    id: u64,
+    location: [f64; 2],
+    demand: u32,
+    ready_time: i64,
+    due_date: i64,
+    service: u32,
+}
+
+/// Read an instance in the Solomon format
+///
+/// The coordinates become locations as they are. The request carries a matrix in which
+/// travel takes the Euclidean distance between the coordinates in time units, and covers
+/// the same distance. Vehicles start and end at the depot within its time window.
+pub fn parse(text: &str) -> Result<Instance> {
+    let name = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .context("The instance file is empty")?
+        .to_string();
+
+    // Tables are recognized by their number of columns, whatever their headers say
+    let mut fleet = None;
+    let mut customers = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let Ok(values) = line
+            .split_whitespace()
+            .map(str::parse::<f64>)
+            .collect::<Result<Vec<f64>, _>>()
+        else {
+            continue;
+        };
+        match values.as_slice() {
+            [] => {}
+            [count, capacity] if fleet.is_none() => fleet = Some((*count as u64, *capacity as u32)),
+            [id, x, y, demand, ready_time, due_date, service] => customers.push(Customer {
+                id: *id as u64,
+                location: [*x, *y],
+                demand: *demand as u32,
+                ready_time: *ready_time as i64,
+                due_date: *due_date as i64,
+                service: *service as u32,
+            }),
+            _ => bail!("Unexpected values on line {}: {}", number + 1, line.trim()),
+        }
+    }
+
+    let (count, capacity) = fleet.context("The instance has no vehicle section")?;
+    let Some((depot, customers)) = customers.split_first() else {
+        bail!("The instance has no customers");
+    };
+
+    let vehicles: Vec<_> = (1..=count)
+        .map(|id| {
+            json!({
+                "id": id,
+                "start": depot.location,
+                "end": depot.location,
+                "capacity": [capacity],
+                "time_window": [depot.ready_time, depot.due_date],
+            })
+        })
+        .collect();
+    let jobs: Vec<_> = customers
+        .iter()
+        .map(|customer| {
+            json!({
+                "id": customer.id,
+                "location": customer.location,
+                "delivery": [customer.demand],
+                "service": customer.service,
+                "time_windows": [[customer.ready_time, customer.due_date]],
+            })
+        })
+        .collect();
+    let mut request: RoutingRequest =
+        serde_json::from_value(json!({ "vehicles": vehicles, "jobs": jobs }))?;
+
+    // Customers sharing coordinates share a row of the matrix
+    let locations = LocationIndex::build(&request.vehicles, &request.jobs, &request.shipments)
+        .locations()
+        .to_vec();
+    let distances: Vec<Vec<f64>> = locations
+        .iter()
+        .map(|from| locations.iter().map(|to| distance(*from, *to)).collect())
+        .collect();
+    request.matrix = serde_json::from_value(json!({
+        "locations": locations,
+        "durations": distances,
+        "distances": distances,
+    }))?;
+
+    Ok(Instance { name, request })
+}
+
+/// Euclidean distance between two coordinates
+pub fn distance(from: [f64; 2], to: [f64; 2]) -> f64 {
+    (from[0] - to[0]).hypot(from[1] - to[1])
+}
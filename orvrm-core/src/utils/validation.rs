@@ -0,0 +1,998 @@
+use crate::models::{
+    CoordinateOrder, DuplicateIds, EtaRequest, InsertRequest, MatrixRequest, RelationStep,
+    RemoveRequest, ReoptimizeRequest, RouteRequest, RouteStep, RoutingRequest,
+};
+use crate::models::time;
+use crate::services::SolverSettings;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+/// Most runner-up solutions a request can ask for
+const MAX_ALTERNATIVES: u32 = 10;
+
+/// Most solver threads a request can ask for
+const MAX_THREADS: u8 = 64;
+
+/// Problem with a single field of a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// Path of the offending field, such as `jobs[2].location`
+    pub field: String,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+impl FieldError {
+    /// Create a field error
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Validate a routing request, reporting every problem found at once
+///
+/// Amounts must have as many dimensions as the vehicle capacities, which in
+/// turn must all have the same number of dimensions.
+pub fn validate_request(request: &RoutingRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = request_errors(request);
+
+    // Predefined routes pick up each shipment before delivering it
+    for (i, vehicle) in request.vehicles.iter().enumerate() {
+        let steps = vehicle.steps.as_deref().unwrap_or_default();
+        for (s, message) in misordered_shipments(steps) {
+            errors.push(FieldError::new(
+                format!("vehicles[{}].steps[{}]", i, s),
+                message,
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Steps delivering a shipment before it is picked up, or picking up one that is never
+/// delivered, by index with the problem
+pub fn misordered_shipments(steps: &[RouteStep]) -> Vec<(usize, String)> {
+    let mut picked_up = HashMap::new();
+    let mut delivered_early = HashSet::new();
+    let mut misordered = Vec::new();
+    for (s, step) in steps.iter().enumerate() {
+        match step {
+            // The pickup after an early delivery is reported with the delivery
+            RouteStep::Pickup { id, .. } if !delivered_early.contains(id) => {
+                picked_up.insert(*id, s);
+            }
+            RouteStep::Delivery { id, .. } if picked_up.remove(id).is_none() => {
+                delivered_early.insert(*id);
+                let message = format!("Shipment {} is delivered before it is picked up", id);
+                misordered.push((s, message));
+            }
+            _ => {}
+        }
+    }
+    misordered.extend(picked_up.into_iter().map(|(id, s)| {
+        (
+            s,
+            format!("Shipment {} is picked up but never delivered", id),
+        )
+    }));
+    misordered.sort_by_key(|(s, _)| *s);
+    misordered
+}
+
+/// Problems of a routing request apart from the order of the shipments on predefined
+/// routes
+fn request_errors(request: &RoutingRequest) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let order = request.coordinate_order();
+
+    if request.vehicles.is_empty() {
+        errors.push(FieldError::new(
+            "vehicles",
+            "At least one vehicle is required",
+        ));
+    }
+    if let Some(url) = &request.callback_url {
+        let valid = reqwest::Url::parse(url)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !valid {
+            errors.push(FieldError::new(
+                "callback_url",
+                "Callback URL must be an http or https URL",
+            ));
+        }
+    }
+
+    let dims = request
+        .vehicles
+        .first()
+        .map(|vehicle| vehicle.capacity.len())
+        .unwrap_or(0);
+
+    // Duplicates are only an error when the options do not resolve them
+    let reject_duplicates = request.duplicate_ids() == DuplicateIds::Reject;
+
+    let mut depot_ids = HashSet::new();
+    for (i, depot) in request.depots.iter().enumerate() {
+        let field = format!("depots[{}]", i);
+
+        if !depot_ids.insert(depot.id) {
+            errors.push(duplicate_id(&field, "depot", depot.id));
+        }
+        check_location(&mut errors, &format!("{}.location", field), depot.location, order);
+        if let Some(time_window) = depot.time_window {
+            check_time_window(&mut errors, &format!("{}.time_window", field), time_window);
+        }
+        if let Some(stock) = &depot.stock {
+            check_dimensions(&mut errors, &format!("{}.stock", field), stock, dims);
+        }
+    }
+
+    let mut vehicle_ids = HashSet::new();
+    for (i, vehicle) in request.vehicles.iter().enumerate() {
+        let field = format!("vehicles[{}]", i);
+
+        if !vehicle_ids.insert(vehicle.id) && reject_duplicates {
+            errors.push(duplicate_id(&field, "vehicle", vehicle.id));
+        }
+        for (name, location, depot) in [
+            ("start", vehicle.start, vehicle.start_depot),
+            ("end", vehicle.end, vehicle.end_depot),
+        ] {
+            let Some(depot) = depot else {
+                continue;
+            };
+            let field = format!("{}.{}_depot", field, name);
+            if location.is_some() {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Set either {} or {}_depot, not both", name, name),
+                ));
+            } else if !depot_ids.contains(&depot) {
+                errors.push(FieldError::new(field, format!("Unknown depot ID {}", depot)));
+            }
+        }
+        if let Some(start) = vehicle.start {
+            check_location(&mut errors, &format!("{}.start", field), start, order);
+        }
+        if let Some(end) = vehicle.end {
+            check_location(&mut errors, &format!("{}.end", field), end, order);
+        }
+        if let Some(position) = vehicle.current_position {
+            check_location(&mut errors, &format!("{}.current_position", field), position, order);
+        }
+
+        if vehicle.capacity.len() != dims {
+            errors.push(FieldError::new(
+                format!("{}.capacity", field),
+                format!(
+                    "Expected {} dimensions like the first vehicle, got {}",
+                    dims,
+                    vehicle.capacity.len()
+                ),
+            ));
+        }
+
+        if let Some(shifts) = &vehicle.shifts {
+            check_shifts(&mut errors, &format!("{}.shifts", field), shifts);
+            if vehicle.time_window.is_some() {
+                errors.push(FieldError::new(
+                    format!("{}.shifts", field),
+                    "Set either shifts or a time window, not both",
+                ));
+            }
+        }
+        if let Some(time_window) = vehicle.time_window {
+            check_time_window(&mut errors, &format!("{}.time_window", field), time_window);
+            if vehicle
+                .departure_time
+                .is_some_and(|departure| departure > time_window[1])
+            {
+                errors.push(FieldError::new(
+                    format!("{}.departure_time", field),
+                    "Departure time is after the end of the vehicle's time window",
+                ));
+            }
+        }
+        if let Some(costs) = vehicle.costs {
+            for (name, value) in [
+                ("fixed", costs.fixed),
+                ("per_hour", costs.per_hour),
+                ("per_km", costs.per_km),
+            ] {
+                if !value.is_finite() || value < 0.0 {
+                    errors.push(FieldError::new(
+                        format!("{}.costs.{}", field, name),
+                        "Costs must be finite and not negative",
+                    ));
+                }
+            }
+        }
+        if vehicle
+            .service_factor
+            .is_some_and(|factor| !factor.is_finite() || factor <= 0.0)
+        {
+            errors.push(FieldError::new(
+                format!("{}.service_factor", field),
+                "Service factor must be a positive number",
+            ));
+        }
+        if vehicle.max_trips == Some(0) {
+            errors.push(FieldError::new(
+                format!("{}.max_trips", field),
+                "A vehicle needs at least one trip",
+            ));
+        }
+        if vehicle.max_trips.unwrap_or(1) > 1
+            && vehicle.start.is_none()
+            && vehicle.start_depot.is_none()
+        {
+            errors.push(FieldError::new(
+                format!("{}.max_trips", field),
+                "A vehicle needs a start location to reload between trips",
+            ));
+        }
+        for (b, brk) in vehicle.breaks.iter().flatten().enumerate() {
+            let field = format!("{}.breaks[{}].time_windows", field, b);
+            check_time_windows(&mut errors, &field, &brk.time_windows);
+        }
+        for (name, zones) in [
+            ("allowed_zones", &vehicle.allowed_zones),
+            ("forbidden_zones", &vehicle.forbidden_zones),
+        ] {
+            for (z, zone) in zones.iter().flatten().enumerate() {
+                if !zone.is_valid() {
+                    errors.push(FieldError::new(
+                        format!("{}.{}[{}]", field, name, z),
+                        "Every ring of a zone needs at least three finite corners",
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut job_ids = HashSet::new();
+    for (i, job) in request.jobs.iter().enumerate() {
+        let field = format!("jobs[{}]", i);
+
+        if !job_ids.insert(job.id) && reject_duplicates {
+            errors.push(duplicate_id(&field, "job", job.id));
+        }
+        check_location(&mut errors, &format!("{}.location", field), job.location, order);
+
+        for (name, amounts) in [("delivery", &job.delivery), ("pickup", &job.pickup)] {
+            if let Some(amounts) = amounts {
+                check_dimensions(&mut errors, &format!("{}.{}", field, name), amounts, dims);
+            }
+        }
+        if let Some(time_windows) = &job.time_windows {
+            check_time_windows(
+                &mut errors,
+                &format!("{}.time_windows", field),
+                time_windows,
+            );
+        }
+        for (name, ids) in [
+            ("allowed_vehicles", &job.allowed_vehicles),
+            ("disallowed_vehicles", &job.disallowed_vehicles),
+        ] {
+            for (v, id) in ids.iter().flatten().enumerate() {
+                if !vehicle_ids.contains(id) {
+                    errors.push(FieldError::new(
+                        format!("{}.{}[{}]", field, name, v),
+                        format!("Unknown vehicle ID {}", id),
+                    ));
+                }
+            }
+        }
+    }
+
+    // A job is completed by one vehicle at most
+    let mut completed = HashMap::new();
+    for (i, vehicle) in request.vehicles.iter().enumerate() {
+        for (c, id) in vehicle.completed_job_ids.iter().enumerate() {
+            let field = format!("vehicles[{}].completed_job_ids[{}]", i, c);
+            if !job_ids.contains(id) {
+                errors.push(FieldError::new(field, format!("Unknown job ID {}", id)));
+            } else if let Some(other) = completed.insert(*id, vehicle.id) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Job {} is already completed by vehicle {}", id, other),
+                ));
+            }
+        }
+    }
+
+    // Predefined routes may only contain jobs that allow their vehicle, and breaks of
+    // their vehicle; the jobs of a group keep to one route
+    let mut grouped = HashMap::new();
+    for (i, vehicle) in request.vehicles.iter().enumerate() {
+        for (s, step) in vehicle.steps.iter().flatten().enumerate() {
+            if let RouteStep::Break { id, .. } = step {
+                if !vehicle.breaks.iter().flatten().any(|brk| brk.id == *id) {
+                    errors.push(FieldError::new(
+                        format!("vehicles[{}].steps[{}]", i, s),
+                        format!("Unknown break ID {} of vehicle {}", id, vehicle.id),
+                    ));
+                }
+                continue;
+            }
+            let RouteStep::Job { id, .. } = step else {
+                continue;
+            };
+            let Some(job) = request.jobs.iter().find(|job| job.id == *id) else {
+                continue;
+            };
+            if !job.allows_vehicle(vehicle.id) {
+                errors.push(FieldError::new(
+                    format!("vehicles[{}].steps[{}]", i, s),
+                    format!("Job {} may not be served by vehicle {}", id, vehicle.id),
+                ));
+            }
+            if let Some(group) = &job.group {
+                check_group(
+                    &mut errors,
+                    &mut grouped,
+                    format!("vehicles[{}].steps[{}]", i, s),
+                    group,
+                    vehicle.id,
+                );
+            }
+        }
+    }
+
+    let mut shipment_ids = HashSet::new();
+    for (i, shipment) in request.shipments.iter().enumerate() {
+        let field = format!("shipments[{}]", i);
+
+        if !shipment_ids.insert(shipment.id) && reject_duplicates {
+            errors.push(duplicate_id(&field, "shipment", shipment.id));
+        }
+        check_dimensions(
+            &mut errors,
+            &format!("{}.amount", field),
+            &shipment.amount,
+            dims,
+        );
+
+        for (name, step) in [
+            ("pickup", &shipment.pickup),
+            ("delivery", &shipment.delivery),
+        ] {
+            let field = format!("{}.{}", field, name);
+            check_location(&mut errors, &format!("{}.location", field), step.location, order);
+            if let Some(time_windows) = &step.time_windows {
+                check_time_windows(
+                    &mut errors,
+                    &format!("{}.time_windows", field),
+                    time_windows,
+                );
+            }
+        }
+    }
+
+    if let Some(balance) = request.options.as_ref().and_then(|options| options.balance) {
+        for (name, value) in [
+            ("max_deviation", balance.max_deviation),
+            ("weight", balance.weight),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                errors.push(FieldError::new(
+                    format!("options.balance.{}", name),
+                    "Must be finite and not negative",
+                ));
+            }
+        }
+    }
+
+    if let Some(penalty) = request.options.as_ref().and_then(|options| options.unassigned_penalty) {
+        if !penalty.is_finite() || penalty < 0.0 {
+            errors.push(FieldError::new(
+                "options.unassigned_penalty",
+                "Must be finite and not negative",
+            ));
+        }
+    }
+    if let Some(percent) = request
+        .options
+        .as_ref()
+        .and_then(|options| options.max_unassigned_percent)
+    {
+        if !(0.0..=100.0).contains(&percent) {
+            errors.push(FieldError::new(
+                "options.max_unassigned_percent",
+                "Must be between 0 and 100",
+            ));
+        }
+    }
+
+    if let Some(lns) = request.options.as_ref().and_then(|options| options.lns) {
+        if !(lns.removal_fraction > 0.0 && lns.removal_fraction <= 1.0) {
+            errors.push(FieldError::new(
+                "options.lns.removal_fraction",
+                "Must be above 0 and at most 1",
+            ));
+        }
+        if lns.regret == 0 {
+            errors.push(FieldError::new("options.lns.regret", "Must be at least 1"));
+        }
+    }
+
+    for (z, zone) in request
+        .options
+        .iter()
+        .flat_map(|options| &options.avoid_polygons)
+        .enumerate()
+    {
+        if !zone.is_valid() {
+            errors.push(FieldError::new(
+                format!("options.avoid_polygons[{}]", z),
+                "Every ring of a zone needs at least three finite corners",
+            ));
+        }
+    }
+
+    if let Some(timezone) = request.options.as_ref().and_then(|options| options.timezone.as_deref()) {
+        if time::parse_offset(timezone).is_none() {
+            errors.push(FieldError::new(
+                "options.timezone",
+                "Must be a UTC offset such as +02:00",
+            ));
+        }
+    }
+
+    let mut vehicle_ids = HashSet::new();
+    let mut routed = HashSet::new();
+    let mut grouped = HashMap::new();
+    for (i, route) in request.initial_solution.iter().enumerate() {
+        let field = format!("initial_solution[{}]", i);
+
+        if !request
+            .vehicles
+            .iter()
+            .any(|vehicle| vehicle.id == route.vehicle_id)
+        {
+            errors.push(FieldError::new(
+                format!("{}.vehicle_id", field),
+                format!("Unknown vehicle ID {}", route.vehicle_id),
+            ));
+        } else if !vehicle_ids.insert(route.vehicle_id) {
+            errors.push(FieldError::new(
+                format!("{}.vehicle_id", field),
+                format!("Duplicate route for vehicle {}", route.vehicle_id),
+            ));
+        }
+
+        for (s, step) in route.steps.iter().enumerate() {
+            let (kind, id, known) = match step {
+                RouteStep::Job { id, .. } => {
+                    ("job", *id, request.jobs.iter().any(|job| job.id == *id))
+                }
+                RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => (
+                    "shipment",
+                    *id,
+                    request.shipments.iter().any(|shipment| shipment.id == *id),
+                ),
+                _ => continue,
+            };
+            let field = format!("{}.steps[{}]", field, s);
+
+            if !known {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Unknown {} ID {}", kind, id),
+                ));
+            } else if matches!(step, RouteStep::Job { .. } | RouteStep::Pickup { .. })
+                && !routed.insert((kind, id))
+            {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Duplicate {} ID {} across routes", kind, id),
+                ));
+            } else if let Some(group) = request
+                .jobs
+                .iter()
+                .find(|job| kind == "job" && job.id == id)
+                .and_then(|job| job.group.as_ref())
+            {
+                check_group(&mut errors, &mut grouped, field, group, route.vehicle_id);
+            }
+        }
+    }
+
+    for (i, relation) in request.relations.iter().enumerate() {
+        let field = format!("relations[{}]", i);
+        if relation.steps.len() < 2 {
+            errors.push(FieldError::new(
+                format!("{}.steps", field),
+                "A relation needs at least two steps",
+            ));
+        }
+        for (s, step) in relation.steps.iter().enumerate() {
+            let field = format!("{}.steps[{}]", field, s);
+            let (kind, known) = match step {
+                RelationStep::Job { id } => ("job", job_ids.contains(id)),
+                RelationStep::Pickup { id } | RelationStep::Delivery { id } => {
+                    ("shipment", shipment_ids.contains(id))
+                }
+            };
+            if !known {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Unknown {} ID {}", kind, step.id()),
+                ));
+            } else if relation.steps[..s].contains(step) {
+                errors.push(FieldError::new(field, "Duplicate step in relation"));
+            }
+        }
+    }
+
+    if let Some(options) = &request.options {
+        for (field, penalty) in [
+            ("options.lateness_penalty", options.lateness_penalty),
+            ("options.late_task_penalty", options.late_task_penalty),
+        ] {
+            if penalty.is_some_and(|penalty| !penalty.is_finite() || penalty < 0.0) {
+                errors.push(FieldError::new(field, "Must be finite and not negative"));
+            }
+        }
+    }
+
+    if request
+        .options
+        .as_ref()
+        .and_then(|options| options.alternatives)
+        .is_some_and(|alternatives| alternatives > MAX_ALTERNATIVES)
+    {
+        errors.push(FieldError::new(
+            "options.alternatives",
+            format!("At most {} alternatives can be requested", MAX_ALTERNATIVES),
+        ));
+    }
+    if request
+        .options
+        .as_ref()
+        .and_then(|options| options.threads)
+        .is_some_and(|threads| threads > MAX_THREADS)
+    {
+        errors.push(FieldError::new(
+            "options.threads",
+            format!("At most {} threads can be requested", MAX_THREADS),
+        ));
+    }
+
+    errors
+}
+
+/// Validate a re-optimization request, reporting every problem found at once
+///
+/// Fields of the problem refer to the updated request, with the new jobs and shipments
+/// appended and the cancelled ones removed.
+pub fn validate_reoptimize_request(request: &ReoptimizeRequest) -> Result<(), Vec<FieldError>> {
+    let updated = request.updated_request();
+    let mut errors = validate_request(&updated).err().unwrap_or_default();
+
+    let mut vehicle_ids = HashSet::new();
+    let mut routed = HashSet::new();
+    for (i, route) in request.routes.iter().enumerate() {
+        let field = format!("routes[{}]", i);
+
+        if !updated
+            .vehicles
+            .iter()
+            .any(|vehicle| vehicle.id == route.vehicle_id)
+        {
+            errors.push(FieldError::new(
+                format!("{}.vehicle_id", field),
+                format!("Unknown vehicle ID {}", route.vehicle_id),
+            ));
+        } else if !vehicle_ids.insert(route.vehicle_id) {
+            errors.push(FieldError::new(
+                format!("{}.vehicle_id", field),
+                format!("Duplicate route for vehicle {}", route.vehicle_id),
+            ));
+        }
+
+        for (s, step) in route.steps.iter().enumerate() {
+            let (kind, id, known, cancelled) = match &step.step {
+                RouteStep::Job { id, .. } => (
+                    "job",
+                    *id,
+                    updated.jobs.iter().any(|job| job.id == *id),
+                    request.cancelled_jobs.contains(id),
+                ),
+                RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => (
+                    "shipment",
+                    *id,
+                    updated.shipments.iter().any(|shipment| shipment.id == *id),
+                    request.cancelled_shipments.contains(id),
+                ),
+                _ => continue,
+            };
+            let field = format!("{}.steps[{}]", field, s);
+
+            if !known && !cancelled {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Unknown {} ID {}", kind, id),
+                ));
+            } else if matches!(step.step, RouteStep::Job { .. } | RouteStep::Pickup { .. })
+                && !routed.insert((kind, id))
+            {
+                errors.push(FieldError::new(
+                    field,
+                    format!("Duplicate {} ID {} across routes", kind, id),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate a route request, reporting every problem found at once
+pub fn validate_route_request(request: &RouteRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if request.stops.is_empty() {
+        errors.push(FieldError::new("stops", "At least one stop is required"));
+    }
+
+    // Report the fields of the underlying routing request as fields of the route request
+    if let Err(request_errors) = validate_request(&request.routing_request()) {
+        errors.extend(request_errors.into_iter().map(|error| {
+            FieldError {
+                field: error
+                    .field
+                    .replacen("vehicles[0]", "vehicle", 1)
+                    .replacen("jobs[", "stops[", 1),
+                ..error
+            }
+        }));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate a planned route to estimate the remaining arrivals of
+pub fn validate_eta_request(request: &EtaRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if request.steps.is_empty() {
+        errors.push(FieldError::new("steps", "At least one step is required"));
+    }
+    check_location(
+        &mut errors,
+        "current_position",
+        request.current_position,
+        CoordinateOrder::LonLat,
+    );
+
+    let stops = request
+        .steps
+        .iter()
+        .filter(|step| {
+            matches!(
+                step,
+                RouteStep::Job { .. } | RouteStep::Pickup { .. } | RouteStep::Delivery { .. }
+            )
+        })
+        .count();
+    if request.completed_stops > stops {
+        errors.push(FieldError::new(
+            "completed_stops",
+            format!("The route has only {} stops", stops),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate jobs and shipments to insert into the routes of a stored `request`
+///
+/// The new work is validated as part of the updated request. Problems with it are
+/// reported at its position in `insert`.
+pub fn validate_insert_request(
+    request: &RoutingRequest,
+    insert: &InsertRequest,
+) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if insert.jobs.is_empty() && insert.shipments.is_empty() {
+        errors.push(FieldError::new(
+            "jobs",
+            "At least one job or shipment is required",
+        ));
+    }
+
+    let updated = insert.updated_request(request, &[]);
+    if let Err(request_errors) = validate_request(&updated) {
+        errors.extend(request_errors.into_iter().map(|error| FieldError {
+            field: shift_index(&error.field, "jobs", request.jobs.len())
+                .or_else(|| shift_index(&error.field, "shipments", request.shipments.len()))
+                .unwrap_or(error.field),
+            ..error
+        }));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate jobs and shipments to remove from the routes of a stored `request`
+pub fn validate_remove_request(
+    request: &RoutingRequest,
+    remove: &RemoveRequest,
+) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if remove.jobs.is_empty() && remove.shipments.is_empty() {
+        errors.push(FieldError::new(
+            "jobs",
+            "At least one job or shipment is required",
+        ));
+    }
+    for (i, id) in remove.jobs.iter().enumerate() {
+        if !request.jobs.iter().any(|job| job.id == *id) {
+            errors.push(FieldError::new(
+                format!("jobs[{}]", i),
+                format!("Unknown job ID {}", id),
+            ));
+        }
+    }
+    for (i, id) in remove.shipments.iter().enumerate() {
+        if !request.shipments.iter().any(|shipment| shipment.id == *id) {
+            errors.push(FieldError::new(
+                format!("shipments[{}]", i),
+                format!("Unknown shipment ID {}", id),
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// `field` of list `name` with its index lowered by `offset`, if it lies past the offset
+fn shift_index(field: &str, name: &str, offset: usize) -> Option<String> {
+    let rest = field.strip_prefix(name)?.strip_prefix('[')?;
+    let (index, rest) = rest.split_once(']')?;
+    let index = index.parse::<usize>().ok()?.checked_sub(offset)?;
+    Some(format!("{}[{}]{}", name, index, rest))
+}
+
+/// Validate a request whose predefined vehicle steps are checked for feasibility
+///
+/// Besides the routing request checks, at least one vehicle needs steps and every step
+/// must refer to a job or shipment of the request. Shipments delivered before they are
+/// picked up are left to the feasibility report.
+pub fn validate_feasibility_request(request: &RoutingRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = request_errors(request);
+
+    if !request
+        .vehicles
+        .iter()
+        .any(|vehicle| vehicle.steps.as_ref().is_some_and(|steps| !steps.is_empty()))
+    {
+        errors.push(FieldError::new(
+            "vehicles",
+            "At least one vehicle needs steps to check",
+        ));
+    }
+
+    for (i, vehicle) in request.vehicles.iter().enumerate() {
+        for (s, step) in vehicle.steps.iter().flatten().enumerate() {
+            let (kind, id, known) = match step {
+                RouteStep::Job { id, .. } => {
+                    ("job", *id, request.jobs.iter().any(|job| job.id == *id))
+                }
+                RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => (
+                    "shipment",
+                    *id,
+                    request.shipments.iter().any(|shipment| shipment.id == *id),
+                ),
+                _ => continue,
+            };
+            if !known {
+                errors.push(FieldError::new(
+                    format!("vehicles[{}].steps[{}]", i, s),
+                    format!("Unknown {} ID {}", kind, id),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate a matrix request, reporting every problem found at once
+pub fn validate_matrix_request(request: &MatrixRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if request.locations.is_empty() {
+        errors.push(FieldError::new(
+            "locations",
+            "At least one location is required",
+        ));
+    }
+    for (i, location) in request.locations.iter().enumerate() {
+        check_location(
+            &mut errors,
+            &format!("locations[{}]", i),
+            *location,
+            CoordinateOrder::LonLat,
+        );
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Validate solver settings changed at runtime, reporting every problem found at once
+pub fn validate_settings(settings: &SolverSettings) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    for (field, value) in [
+        ("default_max_time", settings.default_max_time as usize),
+        ("default_threads", settings.default_threads as usize),
+        ("cluster_size", settings.cluster_size),
+        ("default_max_tasks", settings.default_max_tasks.map_or(1, |max| max as usize)),
+    ] {
+        if value == 0 {
+            errors.push(FieldError::new(field, "Must be at least 1"));
+        }
+    }
+    for (field, value) in [
+        ("max_snap_distance", settings.max_snap_distance),
+        ("lateness_penalty", settings.lateness_penalty),
+        ("late_task_penalty", settings.late_task_penalty),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            errors.push(FieldError::new(field, "Must be a non-negative number"));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn duplicate_id(field: &str, kind: &str, id: u64) -> FieldError {
+    FieldError::new(
+        format!("{}.id", field),
+        format!("Duplicate {} ID {}", kind, id),
+    )
+}
+
+/// Check that a job of `group` routed to `vehicle` joins the other routed jobs of its group
+fn check_group<'a>(
+    errors: &mut Vec<FieldError>,
+    grouped: &mut HashMap<&'a str, u64>,
+    field: String,
+    group: &'a str,
+    vehicle: u64,
+) {
+    match grouped.get(group) {
+        Some(other) if *other != vehicle => errors.push(FieldError::new(
+            field,
+            format!("Group {} is already routed to vehicle {}", group, other),
+        )),
+        Some(_) => {}
+        None => {
+            grouped.insert(group, vehicle);
+        }
+    }
+}
+
+/// Check that a location is a valid pair of coordinates in `order`
+///
+/// Coordinates that are only valid the other way around are reported as swapped.
+fn check_location(
+    errors: &mut Vec<FieldError>,
+    field: &str,
+    location: [f64; 2],
+    order: CoordinateOrder,
+) {
+    let valid = |[lon, lat]: [f64; 2]| {
+        (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat)
+    };
+    let lonlat = order.lonlat(location);
+    if valid(lonlat) {
+        return;
+    }
+
+    let expected = match order {
+        CoordinateOrder::LonLat => "[longitude, latitude]",
+        CoordinateOrder::LatLon => "[latitude, longitude]",
+    };
+    let hint = if valid([lonlat[1], lonlat[0]]) {
+        ", the coordinates look swapped; see options.coordinate_order"
+    } else {
+        ""
+    };
+    errors.push(FieldError::new(
+        field,
+        format!("Invalid coordinates {:?}, expected {}{}", location, expected, hint),
+    ));
+}
+
+/// Check that non-empty amounts have one entry per capacity dimension
+fn check_dimensions(errors: &mut Vec<FieldError>, field: &str, amounts: &[u32], dims: usize) {
+    if !amounts.is_empty() && amounts.len() != dims {
+        errors.push(FieldError::new(
+            field,
+            format!(
+                "Expected {} dimensions to match the vehicle capacity, got {}",
+                dims,
+                amounts.len()
+            ),
+        ));
+    }
+}
+
+fn check_time_windows(errors: &mut Vec<FieldError>, field: &str, time_windows: &[[i64; 2]]) {
+    for (i, time_window) in time_windows.iter().enumerate() {
+        check_time_window(errors, &format!("{}[{}]", field, i), *time_window);
+    }
+}
+
+/// Check that vehicle shifts are valid, in order and do not overlap
+fn check_shifts(errors: &mut Vec<FieldError>, field: &str, shifts: &[[i64; 2]]) {
+    if shifts.is_empty() {
+        errors.push(FieldError::new(field, "At least one shift is required"));
+    }
+    check_time_windows(errors, field, shifts);
+    for (i, pair) in shifts.windows(2).enumerate() {
+        if pair[1][0] <= pair[0][1] {
+            errors.push(FieldError::new(
+                format!("{}[{}]", field, i + 1),
+                format!("Shift {:?} does not start after shift {:?} ends", pair[1], pair[0]),
+            ));
+        }
+    }
+}
+
+fn check_time_window(errors: &mut Vec<FieldError>, field: &str, time_window: [i64; 2]) {
+    if time_window[0] > time_window[1] {
+        errors.push(FieldError::new(
+            field,
+            format!("Time window {:?} starts after it ends", time_window),
+        ));
+    }
+}
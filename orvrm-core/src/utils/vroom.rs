@@ -0,0 +1,314 @@
+use crate::models::{
+    CoordinateOrder, RouteStep, RoutingRequest, RoutingResponse, ShipmentStep, VehicleRoute,
+};
+use serde_json::{json, Value};
+
+/// Convert a request and its solution to the input and output formats of VROOM
+///
+/// The result holds the VROOM input under `problem` and the VROOM output under `solution`.
+/// VROOM knows skills as integers, so each skill name becomes its index in the sorted list
+/// of all names. Reloads and rests have no VROOM counterpart and are left out of the routes,
+/// and vehicles that refer to depots get the depot locations. Vehicles on their way start
+/// at their current position, without the jobs they completed. Locations are given as
+/// [longitude, latitude] whatever the coordinate order of the request.
+pub fn export(request: &RoutingRequest, response: &RoutingResponse) -> Value {
+    let mut response = response.clone();
+    if request.coordinate_order() == CoordinateOrder::LatLon {
+        for step in response.routes.iter_mut().flat_map(|route| &mut route.steps) {
+            step.swap_coordinates();
+        }
+    }
+
+    let mut request = request.clone();
+    request.resolve_coordinate_order();
+    request.resolve_depots();
+    request.resolve_positions();
+    let request = &request;
+
+    json!({
+        "problem": problem(request),
+        "solution": solution(request, &response),
+    })
+}
+
+/// VROOM input for a request
+fn problem(request: &RoutingRequest) -> Value {
+    let skills = skill_names(request);
+
+    let vehicles: Vec<Value> = request
+        .vehicles
+        .iter()
+        .map(|vehicle| {
+            let costs = vehicle.costs.unwrap_or_default();
+            let breaks: Option<Vec<Value>> = vehicle.breaks.as_ref().map(|breaks| {
+                breaks
+                    .iter()
+                    .map(|brk| {
+                        json!({
+                            "id": brk.id,
+                            "time_windows": brk.time_windows,
+                            "service": brk.service,
+                        })
+                    })
+                    .collect()
+            });
+            compact(json!({
+                "id": vehicle.id,
+                "description": vehicle.description,
+                "profile": vehicle.profile.as_ref().or(request.routing_profile.as_ref()),
+                "start": vehicle.start,
+                "end": vehicle.end,
+                "capacity": (!vehicle.capacity.is_empty()).then_some(&vehicle.capacity),
+                "skills": skill_ids(&skills, vehicle.skills.as_deref()),
+                "time_window": vehicle.time_window,
+                "breaks": breaks,
+                "costs": {
+                    "fixed": costs.fixed.round() as i64,
+                    "per_hour": costs.per_hour.round() as i64,
+                    "per_km": costs.per_km.round() as i64,
+                },
+                "max_tasks": vehicle.max_tasks,
+                "max_travel_time": vehicle.max_travel_time,
+                "max_distance": vehicle.max_distance,
+            }))
+        })
+        .collect();
+
+    let jobs: Vec<Value> = request
+        .jobs
+        .iter()
+        .map(|job| {
+            compact(json!({
+                "id": job.id,
+                "description": job.description,
+                "location": job.location,
+                "setup": job.setup,
+                "service": job.service,
+                "delivery": job.delivery,
+                "pickup": job.pickup,
+                "skills": skill_ids(&skills, job.skills.as_deref()),
+                "priority": job.priority,
+                "time_windows": job.time_windows,
+            }))
+        })
+        .collect();
+
+    let shipment_step = |id: u64, description: &Option<String>, step: &ShipmentStep| {
+        compact(json!({
+            "id": id,
+            "description": description,
+            "location": step.location,
+            "service": step.service,
+            "time_windows": step.time_windows,
+        }))
+    };
+    let shipments: Vec<Value> = request
+        .shipments
+        .iter()
+        .map(|shipment| {
+            compact(json!({
+                "amount": shipment.amount,
+                "skills": skill_ids(&skills, shipment.skills.as_deref()),
+                "priority": shipment.priority,
+                "pickup": shipment_step(shipment.id, &shipment.description, &shipment.pickup),
+                "delivery": shipment_step(shipment.id, &shipment.description, &shipment.delivery),
+            }))
+        })
+        .collect();
+
+    json!({
+        "vehicles": vehicles,
+        "jobs": jobs,
+        "shipments": shipments,
+    })
+}
+
+/// VROOM output for a solution
+fn solution(request: &RoutingRequest, response: &RoutingResponse) -> Value {
+    let summary = &response.summary;
+
+    let mut unassigned = Vec::new();
+    for id in response.unassigned.ids() {
+        let job = request.jobs.iter().find(|job| job.id == id);
+        unassigned.push(compact(json!({
+            "id": id,
+            "location": job.map(|job| job.location),
+            "type": "job",
+        })));
+    }
+    for id in response.unassigned_shipments.ids() {
+        let shipment = request.shipments.iter().find(|shipment| shipment.id == id);
+        for (kind, step) in [
+            ("pickup", shipment.map(|shipment| &shipment.pickup)),
+            ("delivery", shipment.map(|shipment| &shipment.delivery)),
+        ] {
+            unassigned.push(compact(json!({
+                "id": id,
+                "location": step.map(|step| step.location),
+                "type": kind,
+            })));
+        }
+    }
+
+    json!({
+        "code": 0,
+        "summary": {
+            "cost": summary.cost.round() as i64,
+            "routes": summary.routes,
+            "unassigned": summary.unassigned,
+            "service": summary.service_time,
+            "duration": summary.duration,
+            "waiting_time": summary.waiting_time,
+            "distance": summary.distance,
+            "computing_times": {
+                "solving": summary.computing_time,
+            },
+        },
+        "unassigned": unassigned,
+        "routes": response.routes.iter().map(route).collect::<Vec<_>>(),
+    })
+}
+
+/// VROOM output for a single route
+fn route(route: &VehicleRoute) -> Value {
+    let mut steps = Vec::new();
+    for (step, load) in route.steps.iter().zip(route.step_loads()) {
+        let (kind, id, location, service, arrival, waiting_time) = match step {
+            RouteStep::Start {
+                location,
+                arrival_time,
+                ..
+            } => ("start", None, location, None, arrival_time, None),
+            RouteStep::Job {
+                id,
+                location,
+                service,
+                arrival_time,
+                waiting_time,
+                ..
+            } => (
+                "job",
+                Some(id),
+                location,
+                *service,
+                arrival_time,
+                *waiting_time,
+            ),
+            RouteStep::Pickup {
+                id,
+                location,
+                service,
+                arrival_time,
+                waiting_time,
+                ..
+            } => (
+                "pickup",
+                Some(id),
+                location,
+                *service,
+                arrival_time,
+                *waiting_time,
+            ),
+            RouteStep::Delivery {
+                id,
+                location,
+                service,
+                arrival_time,
+                waiting_time,
+                ..
+            } => (
+                "delivery",
+                Some(id),
+                location,
+                *service,
+                arrival_time,
+                *waiting_time,
+            ),
+            RouteStep::Break {
+                id,
+                location,
+                service,
+                arrival_time,
+                ..
+            } => ("break", Some(id), location, *service, arrival_time, None),
+            RouteStep::End {
+                location,
+                arrival_time,
+                ..
+            } => ("end", None, location, None, arrival_time, None),
+            RouteStep::Reload { .. } | RouteStep::Rest { .. } => continue,
+        };
+        let setup = match step {
+            RouteStep::Job { setup, .. } => setup.unwrap_or(0),
+            _ => 0,
+        };
+        steps.push(compact(json!({
+            "type": kind,
+            "id": id,
+            "location": location,
+            "arrival": arrival,
+            "setup": setup,
+            "service": service.unwrap_or(0),
+            "waiting_time": waiting_time.unwrap_or(0),
+            "load": load,
+        })));
+    }
+
+    json!({
+        "vehicle": route.vehicle_id,
+        "cost": route.cost_breakdown.total().round() as i64,
+        "service": route.service_time,
+        "duration": route.duration,
+        "waiting_time": route.waiting_time,
+        "distance": route.distance,
+        "steps": steps,
+    })
+}
+
+/// All skill names of the request, sorted, so a name's position is its VROOM skill
+fn skill_names(request: &RoutingRequest) -> Vec<&str> {
+    let mut names: Vec<&str> = request
+        .vehicles
+        .iter()
+        .flat_map(|vehicle| vehicle.skills.iter().flatten())
+        .chain(
+            request
+                .jobs
+                .iter()
+                .flat_map(|job| job.skills.iter().flatten()),
+        )
+        .chain(
+            request
+                .shipments
+                .iter()
+                .flat_map(|shipment| shipment.skills.iter().flatten()),
+        )
+        .map(String::as_str)
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// VROOM skills for skill names
+fn skill_ids(names: &[&str], skills: Option<&[String]>) -> Option<Vec<usize>> {
+    skills.map(|skills| {
+        skills
+            .iter()
+            .filter_map(|skill| names.binary_search(&skill.as_str()).ok())
+            .collect()
+    })
+}
+
+/// Leave the `null` fields out of an object, as VROOM treats absent fields as unset
+fn compact(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .collect(),
+        ),
+        value => value,
+    }
+}
@@ -0,0 +1,370 @@
+use orvrm_core::models::job::Job;
+use orvrm_core::models::request::{CostMatrix, LockedRoute, RoutingRequest};
+use orvrm_core::models::vehicle::{RouteStep, Vehicle};
+
+#[test]
+fn test_job_serialization() {
+    let job = Job {
+        id: 1,
+        location: [4.8945, 52.3667], // Amsterdam [longitude, latitude]
+        service: 300,
+        setup: None,
+        delivery: Some(vec![10]),
+        pickup: None,
+        time_windows: None,
+        skills: Some(vec!["delivery".to_string()]),
+        load_types: None,
+        priority: Some(1),
+        allowed_vehicles: None,
+        disallowed_vehicles: None,
+        group: None,
+        description: None,
+        metadata: None,
+    };
+
+    let serialized = serde_json::to_string(&job).unwrap();
+    let deserialized: Job = serde_json::from_str(&serialized).unwrap();
+
+    assert_eq!(job.id, deserialized.id);
+    assert_eq!(job.location, deserialized.location);
+    assert_eq!(job.service, deserialized.service);
+    assert_eq!(job.skills, deserialized.skills);
+    assert_eq!(job.priority, deserialized.priority);
+}
+
+#[test]
+fn test_vehicle_serialization() {
+    let vehicle = Vehicle {
+        id: 1,
+        start: Some([4.8945, 52.3667]), // Amsterdam [longitude, latitude]
+        end: Some([4.8945, 52.3667]),   // Amsterdam [longitude, latitude]
+        start_depot: None,
+        end_depot: None,
+        capacity: vec![100],
+        time_window: None,
+        shifts: None,
+        steps: None,
+        skills: Some(vec!["delivery".to_string()]),
+        compatibility: None,
+        breaks: None,
+        max_trips: None,
+        reload_service: None,
+        costs: None,
+        max_travel_time: None,
+        max_distance: None,
+        max_tasks: None,
+        profile: None,
+        departure_time: None,
+        working_rules: None,
+        allowed_zones: None,
+        forbidden_zones: None,
+        service_factor: None,
+        current_position: None,
+        completed_job_ids: Vec::new(),
+        description: None,
+        metadata: None,
+    };
+
+    let serialized = serde_json::to_string(&vehicle).unwrap();
+    let deserialized: Vehicle = serde_json::from_str(&serialized).unwrap();
+
+    assert_eq!(vehicle.id, deserialized.id);
+    assert_eq!(vehicle.start, deserialized.start);
+    assert_eq!(vehicle.end, deserialized.end);
+    assert_eq!(vehicle.capacity, deserialized.capacity);
+    assert_eq!(vehicle.skills, deserialized.skills);
+}
+
+#[test]
+fn test_routing_request_serialization() {
+    let job = Job {
+        id: 1,
+        location: [4.8945, 52.3667], // Amsterdam [longitude, latitude]
+        service: 300,
+        setup: None,
+        delivery: Some(vec![10]),
+        pickup: None,
+        time_windows: None,
+        skills: Some(vec!["delivery".to_string()]),
+        load_types: None,
+        priority: Some(1),
+        allowed_vehicles: None,
+        disallowed_vehicles: None,
+        group: None,
+        description: None,
+        metadata: None,
+    };
+
+    let vehicle = Vehicle {
+        id: 1,
+        start: Some([4.8945, 52.3667]), // Amsterdam [longitude, latitude]
+        end: Some([4.8945, 52.3667]),   // Amsterdam [longitude, latitude]
+        start_depot: None,
+        end_depot: None,
+        capacity: vec![100],
+        time_window: None,
+        shifts: None,
+        steps: None,
+        skills: Some(vec!["delivery".to_string()]),
+        compatibility: None,
+        breaks: None,
+        max_trips: None,
+        reload_service: None,
+        costs: None,
+        max_travel_time: None,
+        max_distance: None,
+        max_tasks: None,
+        profile: None,
+        departure_time: None,
+        working_rules: None,
+        allowed_zones: None,
+        forbidden_zones: None,
+        service_factor: None,
+        current_position: None,
+        completed_job_ids: Vec::new(),
+        description: None,
+        metadata: None,
+    };
+
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![job],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: Some("car".to_string()),
+        options: None,
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let serialized = serde_json::to_string(&request).unwrap();
+    let deserialized: RoutingRequest = serde_json::from_str(&serialized).unwrap();
+
+    assert_eq!(request.vehicles.len(), deserialized.vehicles.len());
+    assert_eq!(request.jobs.len(), deserialized.jobs.len());
+    assert_eq!(request.routing_profile, deserialized.routing_profile);
+}
+
+#[test]
+fn test_vehicle_has_skills() {
+    let vehicle = Vehicle {
+        id: 1,
+        start: Some([4.8945, 52.3667]),
+        end: Some([4.8945, 52.3667]),
+        start_depot: None,
+        end_depot: None,
+        capacity: vec![100],
+        time_window: None,
+        shifts: None,
+        steps: None,
+        skills: Some(vec!["delivery".to_string(), "cooling".to_string()]),
+        compatibility: None,
+        breaks: None,
+        max_trips: None,
+        reload_service: None,
+        costs: None,
+        max_travel_time: None,
+        max_distance: None,
+        max_tasks: None,
+        profile: None,
+        departure_time: None,
+        working_rules: None,
+        allowed_zones: None,
+        forbidden_zones: None,
+        service_factor: None,
+        current_position: None,
+        completed_job_ids: Vec::new(),
+        description: None,
+        metadata: None,
+    };
+
+    assert!(vehicle.has_skills(None));
+    assert!(vehicle.has_skills(Some(&["cooling".to_string()])));
+    assert!(!vehicle.has_skills(Some(&["cooling".to_string(), "crane".to_string()])));
+}
+
+#[test]
+fn test_vehicle_carries_load_types() {
+    let vehicle: Vehicle = serde_json::from_value(serde_json::json!({
+        "id": 1, "capacity": [10], "compatibility": {"load_types": ["frozen", "ambient"]}
+    }))
+    .unwrap();
+    let frozen = ["frozen".to_string()];
+    let mixed = ["frozen".to_string(), "ambient".to_string()];
+
+    assert!(vehicle.carries(None));
+    assert!(vehicle.carries(Some(&mixed)));
+    assert!(!vehicle.carries(Some(&["hazardous".to_string()])));
+    assert!(!vehicle.separates_load_types());
+
+    let mut separated = vehicle.clone();
+    separated.compatibility.as_mut().unwrap().mix = false;
+    assert!(separated.carries(Some(&frozen)));
+    assert!(!separated.carries(Some(&mixed)));
+
+    let mut plain = vehicle;
+    plain.compatibility = None;
+    assert!(plain.carries(Some(&[])));
+    assert!(!plain.carries(Some(&frozen)));
+}
+
+#[test]
+fn test_shipment_request_deserialization() {
+    let json = r#"{
+        "vehicles": [{"id": 1, "start": [4.89, 52.36], "end": [4.89, 52.36], "capacity": [10]}],
+        "jobs": [],
+        "shipments": [{
+            "id": 3,
+            "pickup": {"location": [4.90, 52.37], "service": 120},
+            "delivery": {"location": [4.91, 52.38], "time_windows": [[0, 3600]]},
+            "amount": [5]
+        }]
+    }"#;
+
+    let request: RoutingRequest = serde_json::from_str(json).unwrap();
+
+    assert_eq!(request.shipments.len(), 1);
+    assert_eq!(request.shipments[0].pickup.service, 120);
+    assert_eq!(request.shipments[0].delivery.service, 0);
+    assert_eq!(request.shipments[0].amount, vec![5]);
+}
+
+#[test]
+fn test_vehicle_without_start_or_end() {
+    let vehicle: Vehicle = serde_json::from_str(r#"{"id": 1, "capacity": [4]}"#).unwrap();
+
+    assert_eq!(vehicle.start, None);
+    assert_eq!(vehicle.end, None);
+    assert!(!serde_json::to_string(&vehicle).unwrap().contains("start"));
+}
+
+#[test]
+fn test_resolve_depots() {
+    let mut request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "depots": [
+            {"id": 1, "location": [4.89, 52.37], "time_window": [21600, 72000]},
+            {"id": 2, "location": [5.12, 52.09], "time_window": [0, 64800]}
+        ],
+        "vehicles": [
+            {"id": 1, "start_depot": 1, "end_depot": 2, "capacity": [4]},
+            {"id": 2, "start_depot": 2, "capacity": [4], "time_window": [3600, 36000]},
+            {"id": 3, "start": [4.48, 51.92], "capacity": [4]}
+        ],
+        "jobs": []
+    }))
+    .unwrap();
+    request.resolve_depots();
+
+    let vehicles = &request.vehicles;
+    assert_eq!(vehicles[0].start, Some([4.89, 52.37]));
+    assert_eq!(vehicles[0].end, Some([5.12, 52.09]));
+    assert_eq!(vehicles[0].time_window, Some([21600, 64800]));
+    assert_eq!(vehicles[1].start, Some([5.12, 52.09]));
+    assert_eq!(vehicles[1].end, None);
+    assert_eq!(vehicles[1].time_window, Some([3600, 36000]));
+    assert_eq!(vehicles[2].start, Some([4.48, 51.92]));
+    assert_eq!(vehicles[2].time_window, None);
+}
+
+#[test]
+fn test_resolve_positions() {
+    let mut request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {
+                "id": 1, "start": [4.89, 52.37], "capacity": [10, 4],
+                "current_position": [4.95, 52.35], "completed_job_ids": [1, 2],
+                "steps": [{"type": "job", "id": 2}, {"type": "job", "id": 3}]
+            },
+            {"id": 2, "start": [4.89, 52.37], "capacity": [10, 4]}
+        ],
+        "jobs": [
+            {"id": 1, "location": [4.91, 52.36], "delivery": [3, 0]},
+            {"id": 2, "location": [4.93, 52.35], "pickup": [2, 1]},
+            {"id": 3, "location": [4.97, 52.34], "delivery": [1, 0]}
+        ]
+    }))
+    .unwrap();
+    request.resolve_positions();
+
+    // Completed jobs are left out and what was picked up takes up room
+    let ids: Vec<u64> = request.jobs.iter().map(|job| job.id).collect();
+    assert_eq!(ids, vec![3]);
+    let vehicles = &request.vehicles;
+    assert_eq!(vehicles[0].start, Some([4.95, 52.35]));
+    assert_eq!(vehicles[0].capacity, vec![8, 3]);
+    assert_eq!(vehicles[0].steps.as_ref().unwrap().len(), 1);
+    assert_eq!(vehicles[1].start, Some([4.89, 52.37]));
+    assert_eq!(vehicles[1].capacity, vec![10, 4]);
+}
+
+#[test]
+fn test_locked_route_accepts_previous_steps() {
+    // Steps are copied from a previous response, with a lock flag added
+    let json = r#"{
+        "vehicle_id": 1,
+        "steps": [
+            {"type": "start", "location": [4.89, 52.36], "arrival_time": 0},
+            {"type": "job", "id": 4, "location": [4.90, 52.37], "arrival_time": 300, "locked": true},
+            {"type": "pickup", "id": 2, "location": [4.91, 52.38]},
+            {"type": "end", "location": [4.89, 52.36]}
+        ]
+    }"#;
+
+    let route: LockedRoute = serde_json::from_str(json).unwrap();
+
+    assert!(!route.locked);
+    assert_eq!(route.steps.len(), 4);
+    assert!(matches!(route.steps[1].step, RouteStep::Job { id: 4, .. }));
+    assert!(route.steps[1].locked);
+    assert!(!route.steps[2].locked);
+}
+
+#[test]
+fn test_cost_matrix_with_missing_pairs() {
+    let json = r#"{
+        "locations": [[4.89, 52.36], [4.90, 52.37]],
+        "durations": [[0, null], [300, 0]],
+        "distances": [[0, null], [1800, 0]]
+    }"#;
+
+    let matrix: CostMatrix = serde_json::from_str(json).unwrap();
+
+    assert_eq!(matrix.durations[0][1], f64::INFINITY);
+    assert_eq!(matrix.distances.as_ref().unwrap()[1][0], 1800.0);
+
+    // Pairs without a route are written back as null
+    let serialized = serde_json::to_value(&matrix).unwrap();
+    assert!(serialized["durations"][0][1].is_null());
+}
+
+#[test]
+fn test_times_as_timestamps() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1,
+            "capacity": [10],
+            "time_window": ["2026-10-15T08:00:00+02:00", 1792076400],
+            "departure_time": "2026-10-15T06:30:00Z",
+            "breaks": [{"id": 1, "time_windows": [["2026-10-15T12:00:00+02:00", "2026-10-15T13:00:00+02:00"]]}]
+        }],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "time_windows": [["2026-10-15T09:00:00+02:00", 1792051200]]}],
+        "options": {"departure_time": "2026-10-15T08:00:00+02:00"}
+    }))
+    .unwrap();
+
+    // Timestamps become Unix timestamps, plain numbers stay as they are
+    let vehicle = &request.vehicles[0];
+    assert_eq!(vehicle.time_window, Some([1792044000, 1792076400]));
+    assert_eq!(vehicle.departure_time, Some(1792045800));
+    assert_eq!(vehicle.breaks.as_ref().unwrap()[0].time_windows, vec![[1792058400, 1792062000]]);
+    assert_eq!(request.jobs[0].time_windows, Some(vec![[1792047600, 1792051200]]));
+    assert_eq!(request.options.unwrap().departure_time, Some(1792044000));
+
+    let err = serde_json::from_value::<Job>(serde_json::json!({
+        "id": 1, "location": [1.0, 0.0], "time_windows": [["15-10-2026 08:00", 1792051200]]
+    }))
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid timestamp \"15-10-2026 08:00\""));
+}
@@ -0,0 +1,381 @@
+//! Invariants of the schedules the solver returns, checked on random feasible problems
+//!
+//! Problems are generated by proptest, which shrinks a failing case to a minimal request and
+//! records its seed under `proptest-regressions` so it is retried first on the next run.
+
+use orvrm_core::models::request::RoutingRequest;
+use orvrm_core::models::response::RoutingResponse;
+use orvrm_core::models::vehicle::RouteStep;
+use orvrm_core::services::routing::{RoutingConfig, RoutingService};
+use proptest::prelude::*;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Number of random problems checked per test
+const CASES: u32 = 64;
+
+/// Grid location
+fn point() -> impl Strategy<Value = [f64; 2]> {
+    (0..20u8, 0..20u8).prop_map(|(x, y)| [x as f64, y as f64])
+}
+
+/// Vehicle with a shift long enough to serve every job and shipment on its own
+fn vehicle() -> impl Strategy<Value = serde_json::Value> {
+    (point(), prop::option::of(point()), 10..=30u32).prop_map(|(start, end, capacity)| {
+        json!({
+            "start": start,
+            "end": end.unwrap_or(start),
+            "capacity": [capacity],
+            "time_window": [0, 10_000],
+        })
+    })
+}
+
+/// Delivery or pickup job, with a time window reachable from the start of the shift
+fn job() -> impl Strategy<Value = serde_json::Value> {
+    (
+        point(),
+        0..=30u32,
+        1..=8u32,
+        prop::bool::weighted(0.7),
+        prop::option::of((0..2_000i64, 300..1_500i64)),
+    )
+        .prop_map(|(location, service, amount, delivers, window)| {
+            json!({
+                "location": location,
+                "service": service,
+                "delivery": delivers.then_some([amount]),
+                "pickup": (!delivers).then_some([amount]),
+                "time_windows": window.map(|(open, length)| vec![[open, open + length]]),
+            })
+        })
+}
+
+fn shipment() -> impl Strategy<Value = serde_json::Value> {
+    (point(), 0..=30u32, point(), 0..=30u32, 1..=8u32).prop_map(
+        |(pickup, pickup_service, delivery, delivery_service, amount)| {
+            json!({
+                "pickup": {"location": pickup, "service": pickup_service},
+                "delivery": {"location": delivery, "service": delivery_service},
+                "amount": [amount],
+            })
+        },
+    )
+}
+
+/// Numbers the generated entities from 1 on
+fn with_ids(entities: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    entities
+        .into_iter()
+        .zip(1u64..)
+        .map(|(mut entity, id)| {
+            entity["id"] = json!(id);
+            entity
+        })
+        .collect()
+}
+
+/// Random problem every vehicle can serve each job and shipment of on its own
+///
+/// Locations lie on a grid and travel takes ten time units per unit of Euclidean distance.
+fn random_request(shipments: bool) -> impl Strategy<Value = RoutingRequest> {
+    let shipments = if shipments { 1..=4usize } else { 0..=0 };
+    (
+        prop::collection::vec(vehicle(), 1..=3),
+        prop::collection::vec(job(), 1..=12),
+        prop::collection::vec(shipment(), shipments),
+        any::<u64>(),
+    )
+        .prop_map(|(vehicles, jobs, shipments, seed)| {
+            let mut request: RoutingRequest = serde_json::from_value(json!({
+                "vehicles": with_ids(vehicles),
+                "jobs": with_ids(jobs),
+                "shipments": with_ids(shipments),
+                "options": {"threads": 1, "seed": seed},
+            }))
+            .unwrap();
+            request.matrix = serde_json::from_value(travel_matrix(&request)).unwrap();
+            request
+        })
+}
+
+/// Euclidean travel durations between the distinct locations of `request`
+fn travel_matrix(request: &RoutingRequest) -> serde_json::Value {
+    let mut locations: Vec<[f64; 2]> = Vec::new();
+    let used = request
+        .vehicles
+        .iter()
+        .flat_map(|vehicle| vehicle.start.into_iter().chain(vehicle.end))
+        .chain(request.jobs.iter().map(|job| job.location))
+        .chain(
+            request
+                .shipments
+                .iter()
+                .flat_map(|shipment| [shipment.pickup.location, shipment.delivery.location]),
+        );
+    for location in used {
+        if !locations.contains(&location) {
+            locations.push(location);
+        }
+    }
+    let durations: Vec<Vec<f64>> = locations
+        .iter()
+        .map(|from| {
+            locations
+                .iter()
+                .map(|to| ((from[0] - to[0]).hypot(from[1] - to[1]) * 10.0).round())
+                .collect()
+        })
+        .collect();
+    json!({
+        "locations": locations,
+        "durations": durations,
+    })
+}
+
+/// Times and identity of a step serving a job or shipment
+struct Visit {
+    kind: &'static str,
+    id: u64,
+    busy: i64,
+    arrival_time: i64,
+    waiting_time: i64,
+    departure_time: i64,
+}
+
+fn visit(step: &RouteStep) -> Option<Visit> {
+    let (kind, id, setup, service, arrival_time, waiting_time, departure_time) = match step {
+        RouteStep::Job {
+            id,
+            setup,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            ..
+        } => (
+            "job",
+            id,
+            *setup,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+        ),
+        RouteStep::Pickup {
+            id,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            ..
+        } => (
+            "pickup",
+            id,
+            None,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+        ),
+        RouteStep::Delivery {
+            id,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+            ..
+        } => (
+            "delivery",
+            id,
+            None,
+            service,
+            arrival_time,
+            waiting_time,
+            departure_time,
+        ),
+        _ => return None,
+    };
+    Some(Visit {
+        kind,
+        id: *id,
+        busy: (setup.unwrap_or(0) + service.unwrap_or(0)) as i64,
+        arrival_time: arrival_time.expect("arrival time of the step"),
+        waiting_time: waiting_time.unwrap_or(0),
+        departure_time: departure_time.expect("departure time of the step"),
+    })
+}
+
+/// Violated invariant of the response to `request`, if any
+fn check(request: &RoutingRequest, response: &RoutingResponse) -> Result<(), String> {
+    let mut served: HashMap<(&str, u64), usize> = HashMap::new();
+
+    for route in &response.routes {
+        let vehicle = request
+            .vehicles
+            .iter()
+            .find(|vehicle| vehicle.id == route.vehicle_id)
+            .ok_or(format!("route of unknown vehicle {}", route.vehicle_id))?;
+        let vehicle_id = vehicle.id;
+
+        if route.arrival_times.len() != route.departure_times.len() {
+            return Err(format!(
+                "vehicle {}: arrival and departure times differ in length",
+                vehicle_id
+            ));
+        }
+        let stops = route.steps.iter().filter_map(visit).count();
+        if stops > 0 && route.arrival_times.len() != stops + 2 {
+            return Err(format!(
+                "vehicle {}: {} arrival times for {} stops",
+                vehicle_id,
+                route.arrival_times.len(),
+                stops
+            ));
+        }
+        for (index, (arrival, departure)) in route
+            .arrival_times
+            .iter()
+            .zip(&route.departure_times)
+            .enumerate()
+        {
+            if departure < arrival {
+                return Err(format!(
+                    "vehicle {}: stop {} departs at {} before arriving at {}",
+                    vehicle_id, index, departure, arrival
+                ));
+            }
+            if let Some(next) = route.arrival_times.get(index + 1) {
+                if next < departure {
+                    return Err(format!(
+                        "vehicle {}: stop {} is reached at {} before leaving stop {} at {}",
+                        vehicle_id,
+                        index + 1,
+                        next,
+                        index,
+                        departure
+                    ));
+                }
+            }
+        }
+
+        let mut previous_departure = None;
+        let mut pickups: Vec<u64> = Vec::new();
+        for step in &route.steps {
+            let Some(visit) = visit(step) else {
+                continue;
+            };
+            if visit.departure_time < visit.arrival_time + visit.waiting_time + visit.busy {
+                return Err(format!(
+                    "vehicle {}: {} {} departs at {}, before its arrival at {} plus {} waiting and {} service",
+                    vehicle_id, visit.kind, visit.id, visit.departure_time, visit.arrival_time, visit.waiting_time, visit.busy
+                ));
+            }
+            if previous_departure.is_some_and(|departure| visit.arrival_time < departure) {
+                return Err(format!(
+                    "vehicle {}: {} {} is reached before the previous stop is left",
+                    vehicle_id, visit.kind, visit.id
+                ));
+            }
+            previous_departure = Some(visit.departure_time);
+
+            if visit.kind == "job" {
+                let job = request.jobs.iter().find(|job| job.id == visit.id).unwrap();
+                let start = visit.arrival_time + visit.waiting_time;
+                if let Some(windows) = &job.time_windows {
+                    if !windows
+                        .iter()
+                        .any(|[open, close]| (*open..=*close).contains(&start))
+                    {
+                        return Err(format!(
+                            "vehicle {}: job {} starts at {} outside its time windows",
+                            vehicle_id, visit.id, start
+                        ));
+                    }
+                }
+            }
+            match visit.kind {
+                "pickup" => pickups.push(visit.id),
+                "delivery" if !pickups.contains(&visit.id) => {
+                    return Err(format!(
+                        "vehicle {}: shipment {} is delivered before it is picked up",
+                        vehicle_id, visit.id
+                    ));
+                }
+                _ => {}
+            }
+            *served.entry((visit.kind, visit.id)).or_default() += 1;
+        }
+
+        for (index, load) in route.load_profile.iter().enumerate() {
+            let within = load.len() == vehicle.capacity.len()
+                && load
+                    .iter()
+                    .zip(&vehicle.capacity)
+                    .all(|(load, capacity)| (0..=*capacity as i32).contains(load));
+            if !within {
+                return Err(format!(
+                    "vehicle {}: load {:?} after stop {} exceeds capacity {:?}",
+                    vehicle_id, load, index, vehicle.capacity
+                ));
+            }
+        }
+    }
+
+    let unassigned = response.unassigned.ids();
+    for job in &request.jobs {
+        let count = served.get(&("job", job.id)).copied().unwrap_or(0)
+            + unassigned.iter().filter(|id| **id == job.id).count();
+        if count != 1 {
+            return Err(format!(
+                "job {} is assigned or unassigned {} times",
+                job.id, count
+            ));
+        }
+    }
+    let unassigned = response.unassigned_shipments.ids();
+    for shipment in &request.shipments {
+        let pickups = served.get(&("pickup", shipment.id)).copied().unwrap_or(0);
+        let deliveries = served.get(&("delivery", shipment.id)).copied().unwrap_or(0);
+        let unassigned = unassigned.iter().filter(|id| **id == shipment.id).count();
+        if pickups != deliveries || pickups + unassigned != 1 {
+            return Err(format!(
+                "shipment {} is picked up {} times, delivered {} times and unassigned {} times",
+                shipment.id, pickups, deliveries, unassigned
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Solves `request` and checks the invariants of the response
+fn check_case(request: RoutingRequest) -> Result<(), TestCaseError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let response = runtime
+        .block_on(RoutingService::new(RoutingConfig::default()).process_request(request.clone()))
+        .map_err(|err| TestCaseError::fail(format!("solving failed: {}", err)))?;
+    check(&request, &response).map_err(|violation| {
+        TestCaseError::fail(format!(
+            "{}\nrequest: {}",
+            violation,
+            serde_json::to_string(&request).unwrap()
+        ))
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn test_random_job_schedules_are_feasible(request in random_request(false)) {
+        check_case(request)?;
+    }
+
+    #[test]
+    fn test_random_shipment_schedules_are_feasible(request in random_request(true)) {
+        check_case(request)?;
+    }
+}
@@ -0,0 +1,3228 @@
+use orvrm_core::models::depot::Depot;
+use orvrm_core::models::job::Job;
+use orvrm_core::models::relation::RelationType;
+use orvrm_core::models::request::{
+    Algorithm, BalanceMetric, BalanceOptions, Construction, CoordinateOrder, CostMatrix, DuplicateIds, GeometryFormat, InsertRequest,
+    LnsOptions, LoadOrder, LockedRoute, LockedStep, MatrixRequest, PriorityPolicy, RemoveRequest, ReoptimizeRequest, RoutingOptions, RoutingRequest,
+    TimeWindowPolicy,
+};
+use orvrm_core::models::response::{Unassigned, UnassignedCode};
+use orvrm_core::models::shipment::{Shipment, ShipmentStep};
+use orvrm_core::models::vehicle::{
+    Break, Compatibility, RouteStep, Vehicle, VehicleCosts, VehicleRoute, Violation, ViolationType,
+    WorkingRules, Zone,
+};
+use orvrm_core::services::engine::EngineKind;
+use orvrm_core::services::osrm::{
+    OsrmBackendConfig, OsrmConfig, OsrmPoolConfig, OsrmService, OsrmTableResponse,
+};
+use orvrm_core::services::routing::{RoutingConfig, RoutingService};
+use orvrm_core::services::solver::{
+    self, BreakStop, LocationIndex, Matrix, Problem, ReloadStop, RestStop, Task, TrafficPeriod,
+};
+use orvrm_core::testing::{stub_snap_distance, stub_travel, StubOsrm, STUB_STREET};
+use orvrm_core::utils::polyline;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_routing_service_initialization() {
+    let osrm_config = OsrmConfig {
+        base_url: "http://localhost:5000".to_string(),
+        default_profile: "car".to_string(),
+        timeout_seconds: 30,
+        max_table_size: 100,
+        backends: Default::default(),
+        pool: Default::default(),
+    };
+
+    let routing_config = RoutingConfig {
+        osrm: osrm_config,
+        engine: EngineKind::Osrm,
+        valhalla: Default::default(),
+        graphhopper: Default::default(),
+        default_max_time: 300,
+        default_threads: 4,
+        traffic: Vec::new(),
+        max_snap_distance: 1000.0,
+        quick_search_above: None,
+        cluster_size: 100,
+        default_max_tasks: None,
+        lateness_penalty: 10.0,
+        late_task_penalty: 0.0,
+    };
+
+    // Create the service and verify it doesn't panic
+    let _routing_service = RoutingService::new(routing_config);
+}
+
+fn test_vehicle(id: u64, location: [f64; 2], capacity: u32) -> Vehicle {
+    Vehicle {
+        id,
+        start: Some(location),
+        end: Some(location),
+        start_depot: None,
+        end_depot: None,
+        capacity: vec![capacity],
+        time_window: None,
+        shifts: None,
+        steps: None,
+        skills: None,
+        compatibility: None,
+        breaks: None,
+        max_trips: None,
+        reload_service: None,
+        costs: None,
+        max_travel_time: None,
+        max_distance: None,
+        max_tasks: None,
+        profile: None,
+        departure_time: None,
+        working_rules: None,
+        allowed_zones: None,
+        forbidden_zones: None,
+        service_factor: None,
+        current_position: None,
+        completed_job_ids: Vec::new(),
+        description: None,
+        metadata: None,
+    }
+}
+
+fn test_job(id: u64, location: [f64; 2], delivery: u32) -> Job {
+    Job {
+        id,
+        location,
+        service: 0,
+        setup: None,
+        delivery: Some(vec![delivery]),
+        pickup: None,
+        time_windows: None,
+        skills: None,
+        load_types: None,
+        priority: None,
+        allowed_vehicles: None,
+        disallowed_vehicles: None,
+        group: None,
+        description: None,
+        metadata: None,
+    }
+}
+
+fn test_problem(vehicles: Vec<Vehicle>, jobs: Vec<Job>) -> Problem {
+    test_problem_with_shipments(vehicles, jobs, Vec::new())
+}
+
+/// Build a problem whose matrix uses the straight-line distance between locations
+fn test_problem_with_shipments(
+    vehicles: Vec<Vehicle>,
+    jobs: Vec<Job>,
+    shipments: Vec<Shipment>,
+) -> Problem {
+    let locations = LocationIndex::build(&vehicles, &jobs, &shipments);
+    let points = locations.locations();
+
+    let durations: Vec<Vec<f64>> = points
+        .iter()
+        .map(|a| {
+            points
+                .iter()
+                .map(|b| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt())
+                .collect()
+        })
+        .collect();
+
+    let matrix = Matrix {
+        distances: Some(durations.clone()),
+        durations,
+        snapped: None,
+    };
+
+    Problem::new(vehicles, jobs, shipments, &locations, matrix)
+}
+
+#[test]
+fn test_solver_assigns_all_jobs() {
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 10),
+            test_vehicle(2, [100.0, 0.0], 10),
+        ],
+        (1..=6)
+            .map(|id| test_job(id, [id as f64 * 15.0, 10.0], 3))
+            .collect(),
+    );
+
+    let solution = solver::solve(&problem, 1);
+
+    assert!(solution.unassigned.is_empty());
+    let mut assigned: Vec<usize> = solution
+        .routes
+        .iter()
+        .flat_map(|r| r.tasks.clone())
+        .collect();
+    assigned.sort();
+    assert_eq!(assigned, (0..6).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_quick_search_assigns_all_jobs() {
+    let jobs = (1..=6)
+        .map(|id| test_job(id, [id as f64, (id % 2) as f64], 1))
+        .collect();
+    let problem = test_problem(vec![test_vehicle(1, [0.0, 0.0], 10)], jobs).with_quick_search();
+
+    let solution = solver::solve(&problem, 2);
+    assert!(solution.unassigned.is_empty());
+    assert_eq!(solution.routes[0].tasks.len(), 6);
+}
+
+#[test]
+fn test_lns_solver() {
+    // Each vehicle should serve the three jobs on its own side
+    let jobs = [9.0, 2.0, 7.0, 1.0, 8.0, 3.0]
+        .iter()
+        .enumerate()
+        .map(|(idx, x)| test_job(idx as u64 + 1, [*x, 0.0], 1))
+        .collect();
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 3),
+            test_vehicle(2, [10.0, 0.0], 3),
+        ],
+        jobs,
+    )
+    .with_algorithm(Algorithm::Lns)
+    .with_lns(LnsOptions {
+        removal_fraction: 0.5,
+        regret: 3,
+    })
+    .with_seed(3);
+
+    let solution = solver::solve(&problem, 1);
+    assert!(solution.unassigned.is_empty());
+    let mut served: Vec<Vec<usize>> = solution.routes.iter().map(|r| r.tasks.clone()).collect();
+    for tasks in &mut served {
+        tasks.sort();
+    }
+    assert_eq!(served, vec![vec![1, 3, 5], vec![0, 2, 4]]);
+}
+
+#[test]
+fn test_regret_construction() {
+    // Both jobs close, but only the first vehicle can reach the second one in time
+    let jobs: Vec<Job> = [(1, 10.0), (2, -20.0)]
+        .into_iter()
+        .map(|(id, x)| Job {
+            time_windows: Some(vec![[0, 25]]),
+            ..test_job(id, [x, 0.0], 1)
+        })
+        .collect();
+    let problem = |construction| {
+        test_problem(
+            vec![
+                test_vehicle(1, [0.0, 0.0], 10),
+                test_vehicle(2, [30.0, 0.0], 10),
+            ],
+            jobs.clone(),
+        )
+        .with_construction(construction)
+        .with_algorithm(Algorithm::Greedy)
+    };
+
+    // The first vehicle takes the nearest job, leaving the other one out
+    let solution = solver::solve(&problem(Construction::Nearest), 1);
+    assert_eq!(solution.unassigned, vec![1]);
+
+    for construction in [Construction::Regret2, Construction::Regret3] {
+        let solution = solver::solve(&problem(construction), 1);
+        assert!(solution.unassigned.is_empty());
+        assert_eq!(solution.routes[0].tasks, vec![1]);
+        assert_eq!(solution.routes[1].tasks, vec![0]);
+    }
+}
+
+#[test]
+fn test_strict_priorities() {
+    // Room for two cheap jobs, or for a costly more important one that needs all the room;
+    // the most important job does not fit at all
+    let jobs = vec![
+        test_job(1, [5.0, 0.0], 1),
+        test_job(2, [6.0, 0.0], 1),
+        Job {
+            priority: Some(1),
+            ..test_job(3, [50.0, 0.0], 2)
+        },
+        Job {
+            priority: Some(2),
+            ..test_job(4, [1.0, 0.0], 3)
+        },
+    ];
+    let problem = |policy| {
+        test_problem(vec![test_vehicle(1, [0.0, 0.0], 2)], jobs.clone())
+            .with_unassigned_penalty(20.0)
+            .with_priority_policy(policy)
+    };
+
+    // Weighted by priority, the distant job still costs more than leaving it out
+    let solution = solver::solve(&problem(PriorityPolicy::Weighted), 1);
+    assert_eq!(solution.routes[0].tasks, vec![0, 1]);
+    assert_eq!(solution.unassigned, vec![2, 3]);
+
+    let solution = solver::solve(&problem(PriorityPolicy::Strict), 1);
+    assert_eq!(solution.routes[0].tasks, vec![2]);
+    assert_eq!(solution.unassigned, vec![0, 1, 3]);
+}
+
+#[test]
+fn test_job_groups() {
+    let grouped = |id, location| Job {
+        group: Some("g".to_string()),
+        ..test_job(id, location, 1)
+    };
+    // Each vehicle is nearest to part of the group
+    let vehicles = vec![
+        test_vehicle(1, [0.0, 0.0], 10),
+        test_vehicle(2, [100.0, 0.0], 10),
+    ];
+    let jobs = vec![
+        grouped(1, [10.0, 0.0]),
+        test_job(2, [5.0, 0.0], 1),
+        grouped(3, [90.0, 0.0]),
+        grouped(4, [95.0, 0.0]),
+    ];
+
+    let solution = solver::solve(&test_problem(vehicles, jobs), 1);
+    assert!(solution.unassigned.is_empty());
+    let route = solution
+        .routes
+        .iter()
+        .find(|route| route.tasks.contains(&0))
+        .unwrap();
+    assert!(route.tasks.contains(&2) && route.tasks.contains(&3));
+
+    // Served one after another, the group no longer brackets the job in between
+    let jobs = vec![
+        grouped(1, [10.0, 0.0]),
+        test_job(2, [20.0, 0.0], 1),
+        grouped(3, [30.0, 0.0]),
+    ];
+    let vehicle = Vehicle {
+        end: None,
+        ..test_vehicle(1, [0.0, 0.0], 10)
+    };
+    let problem = || test_problem(vec![vehicle.clone()], jobs.clone());
+
+    let solution = solver::solve(&problem(), 1);
+    assert_eq!(solution.routes[0].tasks, vec![0, 1, 2]);
+
+    let solution = solver::solve(&problem().with_consecutive_groups(), 1);
+    let tasks = &solution.routes[0].tasks;
+    let first = tasks.iter().position(|task| *task == 0).unwrap();
+    let second = tasks.iter().position(|task| *task == 2).unwrap();
+    assert_eq!(first.abs_diff(second), 1);
+    assert!(solution.unassigned.is_empty());
+}
+
+#[test]
+fn test_relations() {
+    let vehicles = vec![
+        test_vehicle(1, [0.0, 0.0], 10),
+        test_vehicle(2, [100.0, 0.0], 10),
+    ];
+    let jobs = vec![test_job(1, [10.0, 0.0], 1), test_job(2, [90.0, 0.0], 1)];
+    let problem = || test_problem(vehicles.clone(), jobs.clone());
+
+    let solution = solver::solve(&problem(), 1);
+    assert_eq!(solution.routes.len(), 2);
+    let solution = solver::solve(
+        &problem().with_relation(RelationType::InSameRoute, vec![0, 1]),
+        1,
+    );
+    assert_eq!(solution.routes.len(), 1);
+    assert!(solution.unassigned.is_empty());
+
+    // Neighbouring jobs split over both vehicles
+    let jobs = vec![test_job(1, [10.0, 0.0], 1), test_job(2, [11.0, 0.0], 1)];
+    let problem = test_problem(vehicles.clone(), jobs)
+        .with_relation(RelationType::NotInSameRoute, vec![0, 1]);
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.routes.len(), 2);
+    assert!(solution.unassigned.is_empty());
+
+    // Sequences turn the jobs on a line around
+    let vehicle = Vehicle {
+        end: None,
+        ..test_vehicle(1, [0.0, 0.0], 10)
+    };
+    let jobs = vec![
+        test_job(1, [10.0, 0.0], 1),
+        test_job(2, [20.0, 0.0], 1),
+        test_job(3, [30.0, 0.0], 1),
+    ];
+    let problem = || test_problem(vec![vehicle.clone()], jobs.clone());
+
+    let solution = solver::solve(&problem(), 1);
+    assert_eq!(solution.routes[0].tasks, vec![0, 1, 2]);
+    let solution = solver::solve(
+        &problem().with_relation(RelationType::InSequence, vec![2, 0]),
+        1,
+    );
+    let tasks = &solution.routes[0].tasks;
+    let position = |task| tasks.iter().position(|t| *t == task).unwrap();
+    assert!(position(2) < position(0));
+    let solution = solver::solve(
+        &problem().with_relation(RelationType::InDirectSequence, vec![2, 0]),
+        1,
+    );
+    assert_eq!(solution.routes[0].tasks, vec![1, 2, 0]);
+}
+
+#[test]
+fn test_lower_bound() {
+    let problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 10)],
+        vec![test_job(1, [3.0, 0.0], 1), test_job(2, [4.0, 0.0], 1)],
+    );
+
+    // Both jobs are reached from each other; the start is further away
+    assert_eq!(problem.lower_bound(100.0), 2.0);
+    // Leaving a job unassigned is cheaper than any leg to it
+    assert_eq!(problem.lower_bound(0.5), 1.0);
+}
+
+#[test]
+fn test_expired_time_limit_returns_constructed_solution() {
+    let jobs = (1..=8)
+        .map(|id| test_job(id, [(id * 37 % 100) as f64, (id * 53 % 100) as f64], 1))
+        .collect();
+    let problem =
+        test_problem(vec![test_vehicle(1, [0.0, 0.0], 10)], jobs).with_time_limit(Duration::ZERO);
+
+    let solution = solver::solve(&problem, 2);
+    assert!(solution.unassigned.is_empty());
+    assert_eq!(solution.routes[0].tasks.len(), 8);
+}
+
+#[test]
+fn test_clustered_solver_assigns_every_job_once() {
+    let vehicles = [[10.0, 10.0], [90.0, 10.0], [90.0, 90.0], [10.0, 90.0]]
+        .into_iter()
+        .enumerate()
+        .map(|(idx, depot)| test_vehicle(idx as u64 + 1, depot, 10))
+        .collect();
+    let jobs = (1..=32)
+        .map(|id| test_job(id, [(id * 37 % 100) as f64, (id * 53 % 100) as f64], 1))
+        .collect();
+    let problem = test_problem(vehicles, jobs);
+
+    let solution = solver::solve_clustered(&problem, 4, 8);
+    assert!(solution.unassigned.is_empty());
+    let mut assigned: Vec<usize> = solution
+        .routes
+        .iter()
+        .flat_map(|route| route.tasks.clone())
+        .collect();
+    assigned.sort();
+    assert_eq!(assigned, (0..32).collect::<Vec<_>>());
+    assert_eq!(solution.routes.len(), 4);
+}
+
+#[test]
+fn test_warm_start_keeps_initial_routes() {
+    let mut cooled = test_job(4, [4.0, 0.0], 1);
+    cooled.skills = Some(vec!["cooling".to_string()]);
+    let jobs = vec![
+        test_job(1, [1.0, 0.0], 1),
+        test_job(2, [2.0, 0.0], 1),
+        test_job(3, [3.0, 0.0], 1),
+        cooled,
+    ];
+
+    // Without time to improve, the solver returns the initial route minus what the vehicle
+    // cannot serve
+    let problem = test_problem(vec![test_vehicle(1, [0.0, 0.0], 10)], jobs)
+        .with_initial_route(0, vec![2, 3, 0, 1])
+        .with_time_limit(Duration::ZERO);
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.routes[0].tasks, vec![2, 0, 1]);
+    assert_eq!(solution.unassigned, vec![3]);
+}
+
+#[test]
+fn test_parallel_solver_not_worse_than_single_thread() {
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 5),
+            test_vehicle(2, [50.0, 50.0], 5),
+            test_vehicle(3, [100.0, 0.0], 5),
+        ],
+        (1..=12)
+            .map(|id| test_job(id, [(id * 37 % 100) as f64, (id * 53 % 100) as f64], 1))
+            .collect(),
+    );
+
+    let single = solver::solve(&problem, 1);
+    let parallel = solver::solve(&problem, 4);
+
+    assert!(parallel.cost <= single.cost);
+}
+
+#[test]
+fn test_seeded_solver_is_deterministic() {
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 5),
+            test_vehicle(2, [50.0, 50.0], 5),
+            test_vehicle(3, [100.0, 0.0], 5),
+        ],
+        (1..=12)
+            .map(|id| test_job(id, [(id * 37 % 100) as f64, (id * 53 % 100) as f64], 1))
+            .collect(),
+    )
+    .with_seed(42);
+
+    let routes = |solution: solver::Solution| -> Vec<(usize, Vec<usize>)> {
+        solution
+            .routes
+            .into_iter()
+            .map(|route| (route.vehicle, route.tasks))
+            .collect()
+    };
+    let first = routes(solver::solve(&problem, 4));
+    for _ in 0..5 {
+        assert_eq!(routes(solver::solve(&problem, 4)), first);
+    }
+}
+
+#[test]
+fn test_solver_respects_skills() {
+    let mut cooled = test_vehicle(1, [0.0, 0.0], 10);
+    cooled.skills = Some(vec!["cooling".to_string()]);
+    let plain = test_vehicle(2, [0.0, 0.0], 10);
+
+    let mut frozen = test_job(1, [10.0, 0.0], 1);
+    frozen.skills = Some(vec!["cooling".to_string()]);
+    let mut crane = test_job(2, [20.0, 0.0], 1);
+    crane.skills = Some(vec!["crane".to_string()]);
+
+    let problem = test_problem(vec![plain, cooled], vec![frozen, crane]);
+    let solution = solver::solve(&problem, 2);
+
+    assert_eq!(solution.routes.len(), 1);
+    assert_eq!(solution.routes[0].vehicle, 1);
+    assert_eq!(solution.routes[0].tasks, vec![0]);
+    assert_eq!(solution.unassigned, vec![1]);
+}
+
+#[test]
+fn test_solver_respects_load_types() {
+    let load_types = |types: &[&str]| Some(types.iter().map(|t| t.to_string()).collect());
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.compatibility = Some(Compatibility {
+        load_types: vec!["frozen".to_string(), "ambient".to_string()],
+        mix: false,
+    });
+
+    let mut frozen = test_job(1, [10.0, 0.0], 1);
+    frozen.load_types = load_types(&["frozen"]);
+    let mut ambient = test_job(2, [20.0, 0.0], 1);
+    ambient.load_types = load_types(&["ambient"]);
+    let mut chemicals = test_job(3, [30.0, 0.0], 1);
+    chemicals.load_types = load_types(&["hazardous"]);
+    let plain = test_job(4, [40.0, 0.0], 1);
+
+    // Without mixing only one of the load types fits on the single trip
+    let problem = test_problem(
+        vec![vehicle.clone()],
+        vec![frozen.clone(), ambient.clone(), chemicals, plain],
+    );
+    assert!(problem.schedule(0, &[0, 1]).is_none());
+    assert!(problem.schedule(0, &[0, 3]).is_some());
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.unassigned.len(), 2);
+    assert!(solution.unassigned.contains(&2));
+
+    // A second trip keeps the load types apart
+    vehicle.max_trips = Some(2);
+    let problem = test_problem(vec![vehicle.clone()], vec![frozen.clone(), ambient.clone()]);
+    let schedule = problem.schedule(0, &[0, 1]).unwrap();
+    assert_eq!(schedule.reloads.len(), 1);
+
+    // A vehicle that mixes carries both on one trip
+    vehicle.max_trips = None;
+    vehicle.compatibility.as_mut().unwrap().mix = true;
+    let problem = test_problem(vec![vehicle], vec![frozen, ambient]);
+    let schedule = problem.schedule(0, &[0, 1]).unwrap();
+    assert!(schedule.reloads.is_empty());
+}
+
+#[test]
+fn test_solver_keeps_load_order() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    let delivery = test_job(1, [10.0, 0.0], 1);
+    let mut pickup = test_job(2, [20.0, 0.0], 0);
+    pickup.delivery = None;
+    pickup.pickup = Some(vec![1]);
+    let jobs = vec![delivery, pickup];
+
+    // Mixed trips may pick up before delivering
+    let problem = test_problem(vec![vehicle.clone()], jobs.clone());
+    assert!(problem.schedule(0, &[1, 0]).is_some());
+
+    // Backhauls come after all deliveries
+    let problem =
+        test_problem(vec![vehicle.clone()], jobs.clone()).with_load_order(LoadOrder::Backhaul);
+    assert!(problem.schedule(0, &[1, 0]).is_none());
+    assert!(problem.schedule(0, &[0, 1]).is_some());
+    let solution = solver::solve(&problem, 1);
+    assert!(solution.unassigned.is_empty());
+    assert_eq!(solution.routes[0].tasks, vec![0, 1]);
+
+    // Separate trips need a reload between delivering and picking up
+    let problem =
+        test_problem(vec![vehicle.clone()], jobs.clone()).with_load_order(LoadOrder::Separate);
+    assert!(problem.schedule(0, &[0, 1]).is_none());
+    vehicle.max_trips = Some(2);
+    let problem = test_problem(vec![vehicle], jobs).with_load_order(LoadOrder::Separate);
+    let schedule = problem.schedule(0, &[0, 1]).unwrap();
+    assert_eq!(schedule.reloads.len(), 1);
+}
+
+#[test]
+fn test_solver_respects_depot_stock() {
+    let depots: Vec<Depot> = serde_json::from_value(serde_json::json!([
+        {"id": 1, "location": [0.0, 0.0], "stock": [3]},
+        {"id": 2, "location": [100.0, 0.0]}
+    ]))
+    .unwrap();
+    let mut near = test_vehicle(1, [0.0, 0.0], 10);
+    near.start_depot = Some(1);
+    let mut far = test_vehicle(2, [100.0, 0.0], 10);
+    far.start_depot = Some(2);
+    let jobs: Vec<Job> = (1..=3)
+        .map(|id| test_job(id, [id as f64, 0.0], 2))
+        .collect();
+
+    // Only one job fits the stock of the nearby depot, the far vehicle serves the rest
+    let problem = test_problem(vec![near.clone(), far], jobs.clone()).with_depot_stock(&depots);
+    let solution = solver::solve(&problem, 1);
+    assert!(solution.unassigned.is_empty());
+    let near_route = solution.routes.iter().find(|r| r.vehicle == 0).unwrap();
+    assert_eq!(near_route.tasks.len(), 1);
+
+    // Without another depot the stock runs out
+    let problem = test_problem(vec![near], jobs).with_depot_stock(&depots);
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.unassigned.len(), 2);
+    assert!(problem.out_of_stock(&solution, solution.unassigned[0]));
+    let stock_outs = problem.stock_outs(&solution);
+    assert_eq!(stock_outs.len(), 1);
+    assert_eq!(stock_outs[0].depot_id, 1);
+    assert_eq!(stock_outs[0].delivered, vec![2]);
+    assert_eq!(stock_outs[0].jobs.len(), 2);
+}
+
+#[test]
+fn test_solver_respects_allowed_vehicles() {
+    let mut own = test_job(1, [10.0, 0.0], 1);
+    own.allowed_vehicles = Some(vec![2]);
+    let mut other = test_job(2, [20.0, 0.0], 1);
+    other.disallowed_vehicles = Some(vec![2]);
+
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 10),
+            test_vehicle(2, [0.0, 0.0], 10),
+        ],
+        vec![own, other],
+    );
+    let solution = solver::solve(&problem, 1);
+
+    assert!(solution.unassigned.is_empty());
+    for route in &solution.routes {
+        let expected = if route.vehicle == 1 { vec![0] } else { vec![1] };
+        assert_eq!(route.tasks, expected);
+    }
+}
+
+#[test]
+fn test_solver_avoids_impassable_legs() {
+    let mut problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 10)],
+        vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+    );
+
+    // Nothing leaves the second job's location
+    for to in [0, 1] {
+        problem.matrix.durations[2][to] = f64::INFINITY;
+    }
+
+    assert!(problem.reaches(0, 0));
+    assert!(!problem.reaches(0, 1));
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.routes[0].tasks, vec![0]);
+    assert_eq!(solution.unassigned, vec![1]);
+}
+
+#[test]
+fn test_osrm_table_accepts_missing_pairs() {
+    let json = r#"{"code": "Ok", "durations": [[0.0, null], [12.5, 0.0]]}"#;
+    let table: OsrmTableResponse = serde_json::from_str(json).unwrap();
+
+    assert_eq!(table.durations[0][1], None);
+    assert_eq!(table.durations[1][0], Some(12.5));
+}
+
+#[test]
+fn test_vehicle_matrix_per_profile() {
+    let mut bike = test_vehicle(2, [0.0, 0.0], 10);
+    bike.profile = Some("bike".to_string());
+    let problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 10), bike],
+        vec![test_job(1, [10.0, 0.0], 1)],
+    );
+
+    // The bike takes twice as long over the same locations
+    let bike_matrix = Matrix {
+        durations: problem
+            .matrix
+            .durations
+            .iter()
+            .map(|row| row.iter().map(|duration| duration * 2.0).collect())
+            .collect(),
+        distances: problem.matrix.distances.clone(),
+        snapped: None,
+    };
+    let problem = problem.with_vehicle_matrix(bike_matrix, &[1]);
+
+    assert_eq!(problem.schedule(0, &[0]).unwrap().travel_duration, 20.0);
+    assert_eq!(problem.schedule(1, &[0]).unwrap().travel_duration, 40.0);
+    assert_eq!(solver::solve(&problem, 1).routes[0].vehicle, 0);
+}
+
+#[test]
+fn test_traffic_slows_rush_hour_departures() {
+    let rush_hour = vec![TrafficPeriod {
+        start: 7 * 3600,
+        end: 8 * 3600 + 60,
+        factor: 1.5,
+    }];
+    assert_eq!(solver::traffic_factor(&rush_hour, 8 * 3600), 1.5);
+    assert_eq!(solver::traffic_factor(&rush_hour, 86_400 + 8 * 3600), 1.5);
+    assert_eq!(solver::traffic_factor(&rush_hour, 8 * 3600 + 60), 1.0);
+
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.departure_time = Some(8 * 3600);
+    let problem =
+        test_problem(vec![vehicle], vec![test_job(1, [100.0, 0.0], 1)]).with_traffic(rush_hour);
+
+    // Leaving in rush hour, but returning after it
+    let schedule = problem.schedule(0, &[0]).unwrap();
+    assert_eq!(schedule.departure_times[0], 8 * 3600);
+    assert_eq!(schedule.arrival_times[1], 8 * 3600 + 150);
+    assert_eq!(schedule.travel_duration, 250.0);
+}
+
+#[test]
+fn test_schedule_tracks_pickups_and_deliveries() {
+    let mut pickup = test_job(2, [20.0, 0.0], 0);
+    pickup.delivery = None;
+    pickup.pickup = Some(vec![3]);
+
+    let problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 4)],
+        vec![test_job(1, [10.0, 0.0], 3), pickup],
+    );
+
+    let schedule = problem.schedule(0, &[0, 1]).unwrap();
+    assert_eq!(
+        schedule.load_profile,
+        vec![vec![3], vec![0], vec![3], vec![3]]
+    );
+
+    // Picking up first would exceed the capacity while the delivery is still on board
+    assert!(problem.schedule(0, &[1, 0]).is_none());
+}
+
+#[test]
+fn test_solver_picks_up_shipments_before_delivery() {
+    let shipment = Shipment {
+        id: 7,
+        pickup: ShipmentStep {
+            location: [50.0, 0.0],
+            service: 0,
+            time_windows: None,
+        },
+        delivery: ShipmentStep {
+            location: [10.0, 0.0],
+            service: 0,
+            time_windows: None,
+        },
+        amount: vec![4],
+        skills: None,
+        load_types: None,
+        priority: None,
+        description: None,
+        metadata: None,
+    };
+
+    let problem = test_problem_with_shipments(
+        vec![test_vehicle(1, [0.0, 0.0], 4)],
+        vec![test_job(1, [30.0, 0.0], 2)],
+        vec![shipment],
+    );
+
+    // Tasks are the job followed by the shipment pickup and delivery
+    let solution = solver::solve(&problem, 2);
+    assert!(solution.unassigned.is_empty());
+
+    let route = &solution.routes[0].tasks;
+    let pickup = route.iter().position(|t| *t == 1).unwrap();
+    let delivery = route.iter().position(|t| *t == 2).unwrap();
+    assert!(pickup < delivery);
+
+    // The job delivery must be dropped before the shipment fits in the vehicle
+    let job = route.iter().position(|t| *t == 0).unwrap();
+    assert!(job < pickup);
+
+    assert!(problem.schedule(0, &[2, 1, 0]).is_none());
+}
+
+#[test]
+fn test_solver_prefers_high_priority_jobs() {
+    let near = test_job(1, [5.0, 0.0], 2);
+    let mut far = test_job(2, [80.0, 0.0], 2);
+    far.priority = Some(5);
+
+    let problem = test_problem(vec![test_vehicle(1, [0.0, 0.0], 2)], vec![near, far]);
+    let solution = solver::solve(&problem, 1);
+
+    assert_eq!(solution.routes[0].tasks, vec![1]);
+    assert_eq!(solution.unassigned, vec![0]);
+}
+
+#[test]
+fn test_load_profile_for_predefined_stops() {
+    let mut pickup = test_job(2, [20.0, 0.0], 0);
+    pickup.delivery = None;
+    pickup.pickup = Some(vec![1, 4]);
+
+    let tasks = Task::build(&[test_job(1, [10.0, 0.0], 3), pickup], &[]);
+    let stops: Vec<&Task> = tasks.iter().collect();
+
+    // The pickup adds a second dimension the vehicle capacity does not declare
+    assert_eq!(
+        solver::load_profile(&stops, 1, &[]),
+        vec![vec![3, 0], vec![0, 0], vec![1, 4], vec![1, 4]]
+    );
+}
+
+#[test]
+fn test_schedule_inserts_breaks() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.breaks = Some(vec![Break {
+        id: 7,
+        time_windows: vec![[5, 12]],
+        service: 30,
+    }]);
+
+    let problem = test_problem(
+        vec![vehicle],
+        vec![test_job(1, [10.0, 0.0], 1), test_job(2, [20.0, 0.0], 1)],
+    );
+
+    // The break window is open after the first job, so it is taken there
+    let schedule = problem.schedule(0, &[0, 1]).unwrap();
+    assert_eq!(
+        schedule.breaks,
+        vec![BreakStop {
+            index: 0,
+            after: 1,
+            arrival_time: 10,
+            departure_time: 40,
+        }]
+    );
+    assert_eq!(schedule.arrival_times, vec![0, 10, 50, 70]);
+
+    // A break whose window has passed before the route starts makes it infeasible
+    let mut late = test_vehicle(2, [0.0, 0.0], 10);
+    late.time_window = Some([20, 1000]);
+    late.breaks = Some(vec![Break {
+        id: 8,
+        time_windows: vec![[0, 10]],
+        service: 30,
+    }]);
+    let problem = test_problem(vec![late], vec![test_job(1, [10.0, 0.0], 1)]);
+    assert!(problem.schedule(0, &[0]).is_none());
+}
+
+#[test]
+fn test_solver_minimizes_vehicle_costs() {
+    let mut expensive = test_vehicle(1, [0.0, 0.0], 10);
+    expensive.costs = Some(VehicleCosts {
+        fixed: 1000.0,
+        ..Default::default()
+    });
+    let mut cheap = test_vehicle(2, [0.0, 0.0], 10);
+    cheap.costs = Some(VehicleCosts {
+        fixed: 0.0,
+        per_hour: 0.0,
+        per_km: 500.0,
+        ..Default::default()
+    });
+
+    // The fixed cost outweighs the cheaper hourly rate of the first vehicle
+    let problem = test_problem(vec![expensive, cheap], vec![test_job(1, [10.0, 0.0], 1)]);
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.routes.len(), 1);
+    assert_eq!(solution.routes[0].vehicle, 1);
+    assert!((solution.cost - 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_solver_balances_routes() {
+    let vehicles = vec![
+        test_vehicle(1, [0.0, 0.0], 10),
+        test_vehicle(2, [0.0, 0.0], 10),
+    ];
+    let jobs: Vec<Job> = (1..=4)
+        .map(|id| test_job(id, [9.0 + id as f64, 0.0], 1))
+        .collect();
+    let stop_counts = |solution: &solver::Solution| {
+        let mut counts: Vec<usize> = solution.routes.iter().map(|r| r.tasks.len()).collect();
+        counts.sort();
+        counts
+    };
+
+    // Without balancing a single vehicle serves the whole cluster
+    let problem = test_problem(vehicles.clone(), jobs.clone());
+    assert_eq!(stop_counts(&solver::solve(&problem, 1)), vec![4]);
+
+    let balance = BalanceOptions {
+        metric: BalanceMetric::Tasks,
+        max_deviation: 0.0,
+        hard: false,
+        weight: 100.0,
+    };
+    for hard in [false, true] {
+        let problem = test_problem(vehicles.clone(), jobs.clone())
+            .with_balance(BalanceOptions { hard, ..balance });
+        let solution = solver::solve(&problem, 1);
+        assert!(solution.unassigned.is_empty());
+        assert_eq!(stop_counts(&solution), vec![2, 2]);
+    }
+
+    // A hard limit leaves work unassigned rather than exceed it
+    let problem = test_problem(vehicles, jobs[..1].to_vec()).with_balance(BalanceOptions {
+        hard: true,
+        ..balance
+    });
+    assert_eq!(solver::solve(&problem, 1).unassigned, vec![0]);
+}
+
+#[test]
+fn test_solver_respects_max_tasks() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.max_tasks = Some(2);
+    let problem = test_problem(
+        vec![vehicle],
+        (1..=3)
+            .map(|id| test_job(id, [id as f64 * 10.0, 0.0], 1))
+            .collect(),
+    );
+
+    assert!(problem.schedule(0, &[0, 1, 2]).is_none());
+    let solution = solver::solve(&problem, 1);
+    assert_eq!(solution.routes[0].tasks.len(), 2);
+    assert_eq!(solution.unassigned.len(), 1);
+    assert_eq!(
+        problem.blocking_limits(&solution, solution.unassigned[0]),
+        vec![ViolationType::MaxTasks]
+    );
+}
+
+#[test]
+fn test_solver_routes_are_not_capped_without_max_tasks() {
+    let problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 20)],
+        (1..=15)
+            .map(|id| test_job(id, [id as f64 * 10.0, 0.0], 1))
+            .collect(),
+    );
+
+    let solution = solver::solve(&problem, 1);
+    assert!(solution.unassigned.is_empty());
+    assert_eq!(solution.routes[0].tasks.len(), 15);
+}
+
+#[test]
+fn test_solver_keeps_locked_routes() {
+    // The far job is locked first on the vehicle that would never pick it
+    let problem = test_problem(
+        vec![
+            test_vehicle(1, [0.0, 0.0], 10),
+            test_vehicle(2, [100.0, 0.0], 10),
+        ],
+        vec![
+            test_job(1, [10.0, 0.0], 1),
+            test_job(2, [20.0, 0.0], 1),
+            test_job(3, [90.0, 0.0], 1),
+        ],
+    )
+    .with_locked_route(0, vec![2]);
+
+    let solution = solver::solve(&problem, 2);
+    assert!(solution.unassigned.is_empty());
+
+    let locked = solution.routes.iter().find(|r| r.vehicle == 0).unwrap();
+    assert_eq!(locked.tasks[0], 2);
+    assert!(solution
+        .routes
+        .iter()
+        .filter(|r| r.vehicle != 0)
+        .all(|r| !r.tasks.contains(&2)));
+}
+
+#[test]
+fn test_schedule_reloads_between_trips() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 2);
+    vehicle.max_trips = Some(2);
+    vehicle.reload_service = Some(5);
+    let jobs: Vec<Job> = (1..=3)
+        .map(|id| test_job(id, [id as f64 * 10.0, 0.0], 1))
+        .collect();
+
+    // The third job no longer fits, so the vehicle returns to reload first
+    let problem = test_problem(vec![vehicle], jobs.clone());
+    let schedule = problem.schedule(0, &[0, 1, 2]).unwrap();
+    assert_eq!(
+        schedule.reloads,
+        vec![ReloadStop {
+            after: 2,
+            arrival_time: 40,
+            departure_time: 45,
+        }]
+    );
+    assert_eq!(schedule.arrival_times, vec![0, 10, 20, 75, 105]);
+    assert_eq!(
+        schedule.load_profile,
+        vec![vec![2], vec![1], vec![0], vec![0], vec![0]]
+    );
+    assert!(solver::solve(&problem, 1).unassigned.is_empty());
+
+    // A single trip cannot carry all jobs
+    let problem = test_problem(vec![test_vehicle(1, [0.0, 0.0], 2)], jobs);
+    assert!(problem.schedule(0, &[0, 1, 2]).is_none());
+    assert_eq!(solver::solve(&problem, 1).unassigned.len(), 1);
+}
+
+#[actix_web::test]
+async fn test_process_request_with_client_matrix() {
+    // No OSRM server is running, so the request must be solved from the matrix alone
+    let service = RoutingService::new(RoutingConfig::default());
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: Some(vec![
+                vec![0.0, 1000.0, 2000.0],
+                vec![1000.0, 0.0, 1000.0],
+                vec![2000.0, 1000.0, 0.0],
+            ]),
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let response = service.process_request(request.clone()).await.unwrap();
+    assert_eq!(response.routes.len(), 1);
+    assert_eq!(response.routes[0].route, vec![1, 2]);
+    assert_eq!(response.summary.duration, 400);
+    assert_eq!(response.summary.distance, 4000);
+    // Without vehicle costs a route costs its travel time
+    assert_eq!(response.summary.cost, 400.0);
+    // Each job is at least a 100 s leg away from anywhere else
+    assert_eq!(response.summary.lower_bound, Some(200.0));
+    assert_eq!(response.summary.gap_percent, Some(50.0));
+
+    // Every request location must be covered by the matrix
+    let mut incomplete = request;
+    incomplete.jobs.push(test_job(3, [3.0, 0.0], 1));
+    assert!(service.process_request(incomplete).await.is_err());
+}
+
+fn job_step(id: u64, locked: bool) -> LockedStep {
+    LockedStep {
+        step: RouteStep::Job {
+            id,
+            location: None,
+            snapped_location: None,
+            snap_distance: None,
+            setup: None,
+            service: None,
+            arrival_time: None,
+            waiting_time: None,
+            departure_time: None,
+            polyline: None,
+            arrival_timestamp: None,
+            departure_timestamp: None,
+            violations: Vec::new(),
+            description: None,
+            metadata: None,
+        },
+        locked,
+    }
+}
+
+#[actix_web::test]
+async fn test_unassigned_penalty_and_limits() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let request = |options: RoutingOptions| RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [10.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(options),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [10.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 1000.0],
+                vec![100.0, 0.0, 900.0],
+                vec![1000.0, 900.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // The far job costs more to serve than leaving it out
+    let cheap = RoutingOptions {
+        unassigned_penalty: Some(500.0),
+        ..RoutingOptions::default()
+    };
+    let response = service.process_request(request(cheap.clone())).await.unwrap();
+    assert_eq!(response.routes[0].route, vec![1]);
+    assert_eq!(response.unassigned.ids(), vec![2]);
+    assert_eq!(response.summary.cost_breakdown.unassigned, 500.0);
+
+    let response = service
+        .process_request(request(RoutingOptions::default()))
+        .await
+        .unwrap();
+    assert!(response.unassigned.ids().is_empty());
+
+    // Leaving out one of two jobs is more than either limit accepts
+    for limited in [
+        RoutingOptions {
+            max_unassigned: Some(0),
+            ..cheap.clone()
+        },
+        RoutingOptions {
+            max_unassigned_percent: Some(25.0),
+            ..cheap.clone()
+        },
+    ] {
+        let err = service.process_request(request(limited)).await.unwrap_err();
+        assert!(err.to_string().contains("1 of 2 jobs and shipments"));
+    }
+    let accepted = RoutingOptions {
+        max_unassigned_percent: Some(50.0),
+        ..cheap
+    };
+    assert!(service.process_request(request(accepted)).await.is_ok());
+}
+
+#[actix_web::test]
+async fn test_solver_algorithms() {
+    let service = RoutingService::new(RoutingConfig::default());
+    // Jobs along a line, listed out of order
+    let positions = [4, 1, 6, 3, 5, 2];
+    let mut locations = vec![[0.0, 0.0]];
+    locations.extend(positions.iter().map(|x| [*x as f64, 0.0]));
+    let durations = locations
+        .iter()
+        .map(|a| locations.iter().map(|b| (a[0] - b[0]).abs() * 100.0).collect())
+        .collect();
+    let matrix = CostMatrix {
+        locations: locations.clone(),
+        durations,
+        distances: None,
+    };
+
+    for name in ["greedy", "local_search", "sa", "tabu", "lns"] {
+        let options: RoutingOptions = serde_json::from_value(
+            serde_json::json!({"algorithm": name, "seed": 7, "max_time": 1}),
+        )
+        .unwrap();
+        let request = RoutingRequest {
+            vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+            jobs: (0..positions.len())
+                .map(|idx| test_job(idx as u64 + 1, locations[idx + 1], 1))
+                .collect(),
+            shipments: vec![],
+            depots: Vec::new(),
+            routing_profile: None,
+            options: Some(options),
+            matrix: Some(matrix.clone()),
+            initial_solution: Vec::new(),
+            relations: Vec::new(),
+            callback_url: None,
+        };
+
+        let response = service.process_request(request).await.unwrap();
+        assert!(response.unassigned.ids().is_empty(), "{}", name);
+        if name != "greedy" {
+            // Out to the farthest job and back, serving the others on the way
+            assert_eq!(response.summary.duration, 1200, "{}", name);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn test_reoptimize_keeps_locked_steps() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: (0..4).map(|x| [x as f64, 0.0]).collect(),
+            durations: (0..4)
+                .map(|a: i32| (0..4).map(|b: i32| ((a - b).abs() * 100) as f64).collect())
+                .collect(),
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // Job 2 was dispatched first, so it stays first even though job 1 is closer
+    let mut reoptimize = ReoptimizeRequest {
+        request,
+        routes: vec![LockedRoute {
+            vehicle_id: 1,
+            steps: vec![job_step(2, true), job_step(1, false)],
+            locked: false,
+        }],
+        new_jobs: vec![test_job(3, [3.0, 0.0], 1)],
+        new_shipments: vec![],
+        cancelled_jobs: vec![],
+        cancelled_shipments: vec![],
+    };
+    let response = service.reoptimize(&reoptimize).await.unwrap();
+    assert_eq!(response.routes[0].route, vec![2, 3, 1]);
+
+    reoptimize.cancelled_jobs = vec![1];
+    let response = service.reoptimize(&reoptimize).await.unwrap();
+    assert_eq!(response.routes[0].route, vec![2, 3]);
+    assert_eq!(response.summary.unassigned, 0);
+
+    // A locked route the vehicle can no longer drive is rejected
+    reoptimize.request.vehicles[0].time_window = Some([0, 100]);
+    assert!(service.reoptimize(&reoptimize).await.is_err());
+}
+
+#[actix_web::test]
+async fn test_insert_keeps_routed_steps_in_order() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let locations = vec![[0.0, 0.0], [1.0, 0.0], [2.0, 1.0], [3.0, 0.0], [4.0, 0.0]];
+    let manhattan = |a: [f64; 2], b: [f64; 2]| 100.0 * ((a[0] - b[0]).abs() + (a[1] - b[1]).abs());
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.steps = Some(vec![job_step(3, false).step, job_step(1, false).step]);
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(3, [3.0, 0.0], 1), test_job(4, [4.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            durations: locations
+                .iter()
+                .map(|a| locations.iter().map(|b| manhattan(*a, *b)).collect())
+                .collect(),
+            locations,
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // The planned route is driven as given, leaving job 4 out
+    let solution = service.process_request(request.clone()).await.unwrap();
+    assert_eq!(solution.routes[0].route, vec![3, 1]);
+
+    let insert = InsertRequest {
+        jobs: vec![test_job(2, [2.0, 1.0], 1)],
+        shipments: vec![],
+    };
+    let inserted = service.insert(&request, &solution, &insert).await.unwrap();
+
+    // The detour to job 2 costs 200 seconds and jobs 3 and 1 keep their order
+    let route = &inserted.solution.routes[0].route;
+    assert_eq!(route.len(), 3);
+    let kept: Vec<u64> = route.iter().copied().filter(|id| *id != 2).collect();
+    assert_eq!(kept, vec![3, 1]);
+    assert_eq!(inserted.insertion_cost, 200.0);
+    assert_eq!(inserted.solution.unassigned.ids(), vec![4]);
+}
+
+#[actix_web::test]
+async fn test_remove_repairs_affected_routes_only() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let locations = vec![
+        [0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0], [5.0, 0.0],
+        [0.0, 10.0], [0.0, 11.0], [0.0, 12.0],
+    ];
+    let manhattan = |a: [f64; 2], b: [f64; 2]| 100.0 * ((a[0] - b[0]).abs() + (a[1] - b[1]).abs());
+    let mut first = test_vehicle(1, [0.0, 0.0], 10);
+    first.steps = Some([3, 1, 5, 2].iter().map(|id| job_step(*id, false).step).collect());
+    let mut second = test_vehicle(2, [0.0, 10.0], 10);
+    second.end = None;
+    second.steps = Some(vec![job_step(7, false).step, job_step(6, false).step]);
+    let request = RoutingRequest {
+        vehicles: vec![first, second],
+        jobs: vec![
+            test_job(1, [1.0, 0.0], 1),
+            test_job(2, [2.0, 0.0], 1),
+            test_job(3, [3.0, 0.0], 1),
+            test_job(5, [5.0, 0.0], 1),
+            test_job(6, [0.0, 11.0], 1),
+            test_job(7, [0.0, 12.0], 1),
+        ],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            durations: locations
+                .iter()
+                .map(|a| locations.iter().map(|b| manhattan(*a, *b)).collect())
+                .collect(),
+            locations,
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let solution = service.process_request(request.clone()).await.unwrap();
+
+    // Without reoptimizing the remaining jobs keep their order
+    let mut remove = RemoveRequest {
+        jobs: vec![5],
+        shipments: vec![],
+        reoptimize: false,
+    };
+    let removed = service.remove(&request, &solution, &remove).await.unwrap();
+    assert_eq!(removed.routes[0].route, vec![3, 1, 2]);
+    assert_eq!(removed.routes[0].duration, 800);
+    assert_eq!(removed.routes[1].route, vec![7, 6]);
+
+    // Reoptimizing only reorders the route job 5 was removed from
+    remove.reoptimize = true;
+    let repaired = service.remove(&request, &solution, &remove).await.unwrap();
+    assert_eq!(repaired.routes[0].duration, 600);
+    assert_eq!(repaired.routes[1].route, vec![7, 6]);
+    assert!(repaired.unassigned.ids().is_empty());
+}
+
+#[actix_web::test]
+async fn test_replan_from_current_position() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.current_position = Some([2.0, 0.0]);
+    vehicle.completed_job_ids = vec![1, 2];
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![
+            test_job(1, [1.0, 0.0], 1),
+            test_job(2, [2.0, 0.0], 1),
+            test_job(3, [3.0, 0.0], 1),
+        ],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0, 300.0],
+                vec![100.0, 0.0, 100.0, 200.0],
+                vec![200.0, 100.0, 0.0, 100.0],
+                vec![300.0, 200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // Only job 3 is left, driven to from where the vehicle is now
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![3]);
+    assert_eq!(route.duration, 400);
+    let Some(RouteStep::Start { location, .. }) = route.steps.first() else {
+        panic!("expected a start step");
+    };
+    assert_eq!(*location, Some([2.0, 0.0]));
+    assert!(response.unassigned.ids().is_empty());
+}
+
+#[actix_web::test]
+async fn test_open_ended_routes() {
+    let mut open_end = test_vehicle(1, [0.0, 0.0], 10);
+    open_end.end = None;
+    let mut request = RoutingRequest {
+        vehicles: vec![open_end],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // The route ends at the last job instead of returning
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request.clone()).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![1, 2]);
+    assert_eq!(route.duration, 200);
+    let Some(RouteStep::End { location, .. }) = route.steps.last() else {
+        panic!("expected an end step");
+    };
+    assert_eq!(*location, Some([2.0, 0.0]));
+
+    // A predefined route without a start begins at its first job
+    let mut open_start = test_vehicle(1, [0.0, 0.0], 10);
+    open_start.start = None;
+    open_start.steps = Some(
+        [2, 1]
+            .into_iter()
+            .map(|id| RouteStep::Job {
+                id,
+                location: None,
+                snapped_location: None,
+                snap_distance: None,
+                setup: None,
+                service: None,
+                arrival_time: None,
+                waiting_time: None,
+                departure_time: None,
+                polyline: None,
+                arrival_timestamp: None,
+                departure_timestamp: None,
+                violations: Vec::new(),
+                description: None,
+                metadata: None,
+            })
+            .collect(),
+    );
+    request.vehicles = vec![open_start];
+
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![2, 1]);
+    assert_eq!(route.duration, 200);
+    assert_eq!(route.arrival_times, vec![0, 0, 100, 200]);
+}
+
+#[actix_web::test]
+async fn test_predefined_route_reports_violations() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 1);
+    vehicle.max_travel_time = Some(150);
+    vehicle.steps = Some(vec![RouteStep::Job {
+        id: 1,
+        location: None,
+        snapped_location: None,
+        snap_distance: None,
+        setup: None,
+        service: None,
+        arrival_time: None,
+        waiting_time: None,
+        departure_time: None,
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+        description: None,
+        metadata: None,
+    }]);
+    let mut job = test_job(1, [1.0, 0.0], 2);
+    job.time_windows = Some(vec![[0, 60]]);
+
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![job],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+
+    // The delivery overloads the vehicle on departure
+    let RouteStep::Start { violations, .. } = &route.steps[0] else {
+        panic!("expected a start step");
+    };
+    assert_eq!(
+        violations,
+        &vec![Violation {
+            kind: ViolationType::Load,
+            amount: 1,
+            dimension: Some(0),
+        }]
+    );
+    let RouteStep::Job { violations, .. } = &route.steps[1] else {
+        panic!("expected a job step");
+    };
+    assert_eq!(
+        violations,
+        &vec![Violation {
+            kind: ViolationType::Delay,
+            amount: 40,
+            dimension: None,
+        }]
+    );
+    assert_eq!(
+        route.violations,
+        vec![
+            Violation {
+                kind: ViolationType::Load,
+                amount: 1,
+                dimension: Some(0),
+            },
+            Violation {
+                kind: ViolationType::MaxTravelTime,
+                amount: 50,
+                dimension: None,
+            }
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_predefined_route_reports_overloaded_stops() {
+    let job_step = |id| RouteStep::Job {
+        id,
+        location: None,
+        snapped_location: None,
+        snap_distance: None,
+        setup: None,
+        service: None,
+        arrival_time: None,
+        waiting_time: None,
+        departure_time: None,
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+        description: None,
+        metadata: None,
+    };
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 3);
+    vehicle.capacity = vec![3, 1];
+    vehicle.steps = Some(vec![job_step(1), job_step(2), job_step(3)]);
+    let mut jobs = vec![
+        test_job(1, [1.0, 0.0], 0),
+        test_job(2, [2.0, 0.0], 0),
+        test_job(3, [3.0, 0.0], 0),
+    ];
+    jobs[0].pickup = Some(vec![2, 2]);
+    jobs[1].pickup = Some(vec![2, 0]);
+
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs,
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]],
+            durations: vec![vec![0.0; 4]; 4],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+
+    // Each stop reports the dimensions its running load exceeds
+    let load_violations: Vec<Vec<Violation>> = route
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            RouteStep::Job { violations, .. } => Some(violations.clone()),
+            _ => None,
+        })
+        .collect();
+    let load = |amount, dimension| Violation {
+        kind: ViolationType::Load,
+        amount,
+        dimension: Some(dimension),
+    };
+    assert_eq!(
+        load_violations,
+        vec![
+            vec![load(1, 1)],
+            vec![load(1, 0), load(1, 1)],
+            vec![load(1, 0), load(1, 1)],
+        ]
+    );
+    assert_eq!(route.violations, vec![load(1, 1)]);
+}
+
+#[actix_web::test]
+async fn test_predefined_route_takes_break_steps_in_place() {
+    let job_step = |id| RouteStep::Job {
+        id,
+        location: None,
+        snapped_location: None,
+        snap_distance: None,
+        setup: None,
+        service: None,
+        arrival_time: None,
+        waiting_time: None,
+        departure_time: None,
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+        description: None,
+        metadata: None,
+    };
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 2);
+    vehicle.breaks = Some(vec![Break {
+        id: 7,
+        time_windows: vec![[300, 400]],
+        service: 30,
+    }]);
+    vehicle.steps = Some(vec![
+        job_step(1),
+        RouteStep::Break {
+            id: 7,
+            location: None,
+            service: None,
+            arrival_time: None,
+            departure_time: None,
+            arrival_timestamp: None,
+            departure_timestamp: None,
+            violations: Vec::new(),
+        },
+        job_step(2),
+    ]);
+
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+
+    // The break waits at job 1 for its window instead of being taken when due
+    let RouteStep::Break {
+        id,
+        arrival_time,
+        departure_time,
+        violations,
+        ..
+    } = &route.steps[2]
+    else {
+        panic!("expected a break step");
+    };
+    assert_eq!(*id, 7);
+    assert_eq!((*arrival_time, *departure_time), (Some(100), Some(330)));
+    assert!(violations.is_empty());
+
+    let RouteStep::Job { arrival_time, .. } = &route.steps[3] else {
+        panic!("expected a job step");
+    };
+    assert_eq!(*arrival_time, Some(430));
+}
+
+#[test]
+fn test_resolve_duplicate_ids() {
+    let request = |policy| RoutingRequest {
+        vehicles: vec![
+            test_vehicle(1, [0.0, 0.0], 4),
+            test_vehicle(1, [0.0, 0.0], 4),
+        ],
+        jobs: vec![
+            test_job(3, [1.0, 0.0], 1),
+            test_job(7, [2.0, 0.0], 1),
+            test_job(3, [3.0, 0.0], 1),
+        ],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            duplicate_ids: Some(policy),
+            ..Default::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let mut rejected = request(DuplicateIds::Reject);
+    assert!(rejected.resolve_duplicate_ids().is_empty());
+    assert_eq!(rejected.jobs.len(), 3);
+
+    let mut dropped = request(DuplicateIds::Drop);
+    assert_eq!(
+        dropped.resolve_duplicate_ids(),
+        vec![
+            "Dropped duplicate vehicle ID 1",
+            "Dropped duplicate job ID 3"
+        ]
+    );
+    assert_eq!(dropped.vehicles.len(), 1);
+    let ids: Vec<u64> = dropped.jobs.iter().map(|job| job.id).collect();
+    assert_eq!(ids, vec![3, 7]);
+
+    // Renamed duplicates get IDs above the highest one in use
+    let mut renamed = request(DuplicateIds::Rename);
+    assert_eq!(
+        renamed.resolve_duplicate_ids(),
+        vec![
+            "Renamed duplicate vehicle ID 1 to 2",
+            "Renamed duplicate job ID 3 to 8"
+        ]
+    );
+    let ids: Vec<u64> = renamed.jobs.iter().map(|job| job.id).collect();
+    assert_eq!(ids, vec![3, 7, 8]);
+    assert_eq!(renamed.jobs[2].location, [3.0, 0.0]);
+}
+
+#[test]
+fn test_take_rests_before_and_during_legs() {
+    let rules = WorkingRules {
+        max_continuous_driving: Some(100),
+        rest_duration: 30,
+        max_shift: None,
+    };
+
+    // A leg that would exceed the limit starts with a rest at the previous stop
+    let (mut driving, mut time) = (80, 1000);
+    let rests = solver::take_rests(&rules, &mut driving, &mut time, 50, 2);
+    assert_eq!(
+        rests,
+        vec![RestStop {
+            after: 2,
+            en_route: false,
+            arrival_time: 1000,
+            departure_time: 1030,
+        }]
+    );
+    assert_eq!((driving, time), (50, 1030));
+
+    // Legs longer than the limit are interrupted by rests en route
+    let (mut driving, mut time) = (0, 0);
+    let rests = solver::take_rests(&rules, &mut driving, &mut time, 250, 0);
+    let times: Vec<(i64, i64)> = rests
+        .iter()
+        .map(|rest| (rest.arrival_time, rest.departure_time))
+        .collect();
+    assert_eq!(times, vec![(100, 130), (230, 260)]);
+    assert!(rests.iter().all(|rest| rest.en_route));
+    assert_eq!((driving, time), (50, 60));
+}
+
+#[actix_web::test]
+async fn test_working_rules_insert_rests_and_limit_shifts() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.working_rules = Some(WorkingRules {
+        max_continuous_driving: Some(150),
+        rest_duration: 30,
+        max_shift: None,
+    });
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![test_job(1, [1.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let service = RoutingService::new(RoutingConfig::default());
+
+    // Driving back would exceed the limit, so the driver rests at the job
+    let response = service.process_request(request.clone()).await.unwrap();
+    let steps = &response.routes[0].steps;
+    assert!(matches!(
+        steps[2],
+        RouteStep::Rest {
+            location: Some([1.0, 0.0]),
+            arrival_time: Some(100),
+            departure_time: Some(130),
+            ..
+        }
+    ));
+    assert!(matches!(
+        steps[3],
+        RouteStep::End {
+            arrival_time: Some(230),
+            ..
+        }
+    ));
+
+    // The rest makes the working day too long for a shift of 200 seconds
+    let mut short_shift = request.clone();
+    if let Some(rules) = &mut short_shift.vehicles[0].working_rules {
+        rules.max_shift = Some(200);
+    }
+    let response = service.process_request(short_shift.clone()).await.unwrap();
+    let Unassigned::Detailed(unassigned) = &response.unassigned else {
+        panic!("expected structured unassigned jobs");
+    };
+    assert_eq!(unassigned[0].code, UnassignedCode::RouteLimit);
+
+    // A predefined route reports the excess instead
+    short_shift.vehicles[0].steps = Some(vec![RouteStep::Job {
+        id: 1,
+        location: None,
+        snapped_location: None,
+        snap_distance: None,
+        setup: None,
+        service: None,
+        arrival_time: None,
+        waiting_time: None,
+        departure_time: None,
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+        description: None,
+        metadata: None,
+    }]);
+    let response = service.process_request(short_shift).await.unwrap();
+    assert_eq!(
+        response.routes[0].violations,
+        vec![Violation {
+            kind: ViolationType::MaxShift,
+            amount: 30,
+            dimension: None,
+        }]
+    );
+}
+
+#[actix_web::test]
+async fn test_unassigned_reason_reports_vehicle_limits() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.max_travel_time = Some(300);
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // The far job alone would take 400 seconds of travel
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    assert_eq!(response.routes[0].route, vec![1]);
+    assert_eq!(response.unassigned.ids(), vec![2]);
+    assert!(response.unassigned_reasons.is_empty());
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    assert_eq!(jobs[0].code, UnassignedCode::RouteLimit);
+    assert!(jobs[0].description.contains("max_travel_time"));
+}
+
+#[actix_web::test]
+async fn test_default_max_tasks_limits_routes() {
+    let mut limited = test_vehicle(2, [0.0, 0.0], 10);
+    limited.max_tasks = Some(2);
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10), limited],
+        jobs: (1..=4)
+            .map(|id| test_job(id, [id as f64, 0.0], 1))
+            .collect(),
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: (0..=4).map(|x| [x as f64, 0.0]).collect(),
+            durations: (0..=4)
+                .map(|a: i32| (0..=4).map(|b: i32| (100 * (a - b).abs()) as f64).collect())
+                .collect(),
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // The configured limit applies to vehicles without their own
+    let service = RoutingService::new(RoutingConfig {
+        default_max_tasks: Some(1),
+        ..RoutingConfig::default()
+    });
+    let response = service.process_request(request).await.unwrap();
+    let mut stops: Vec<usize> = response.routes.iter().map(|r| r.route.len()).collect();
+    stops.sort();
+    assert_eq!(stops, vec![1, 2]);
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].code, UnassignedCode::RouteLimit);
+    assert!(jobs[0].description.contains("max_tasks"));
+}
+
+#[actix_web::test]
+async fn test_stock_outs_are_reported() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "depots": [{"id": 7, "location": [0.0, 0.0], "stock": [3]}],
+        "vehicles": [{"id": 1, "start_depot": 7, "end_depot": 7, "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [2]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [2]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    }))
+    .unwrap();
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    assert_eq!(response.routes[0].route.len(), 1);
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    assert_eq!(jobs[0].code, UnassignedCode::StockOut);
+    let stock_outs = &response.summary.stock_outs;
+    assert_eq!(stock_outs.len(), 1);
+    assert_eq!(stock_outs[0].depot_id, 7);
+    assert_eq!(stock_outs[0].delivered, vec![2]);
+    assert_eq!(stock_outs[0].jobs, vec![jobs[0].id]);
+}
+
+#[actix_web::test]
+async fn test_descriptions_and_metadata_are_echoed() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10],
+            "description": "Van 12", "metadata": {"plate": "AB-123-C"}
+        }],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1],
+                "description": "Bakery", "metadata": {"order": "SO-1", "lines": [3, 4]}},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1]}
+        ],
+        "shipments": [{
+            "id": 3, "amount": [1], "description": "Return crates",
+            "pickup": {"location": [2.0, 0.0]}, "delivery": {"location": [1.0, 0.0]}
+        }],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    }))
+    .unwrap();
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = serde_json::to_value(&response.routes[0]).unwrap();
+    assert_eq!(route["description"], "Van 12");
+    assert_eq!(route["metadata"]["plate"], "AB-123-C");
+
+    let steps = route["steps"].as_array().unwrap();
+    let step = |kind: &str, id: u64| {
+        steps
+            .iter()
+            .find(|step| step["type"] == kind && step["id"] == id)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(step("job", 1)["description"], "Bakery");
+    assert_eq!(step("job", 1)["metadata"]["lines"], serde_json::json!([3, 4]));
+    assert!(step("job", 2).get("description").is_none());
+    assert!(step("job", 2).get("metadata").is_none());
+    assert_eq!(step("pickup", 3)["description"], "Return crates");
+    assert_eq!(step("delivery", 3)["description"], "Return crates");
+}
+
+#[actix_web::test]
+async fn test_cost_breakdown_adds_up() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.costs = Some(VehicleCosts {
+        fixed: 100.0,
+        per_km: 2.0,
+        per_hour_waiting: 1800.0,
+        per_hour_service: 360.0,
+        ..Default::default()
+    });
+    let mut waiting = test_job(1, [1.0, 0.0], 1);
+    waiting.service = 600;
+    waiting.time_windows = Some(vec![[400, 1000]]);
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![waiting, test_job(2, [1.0, 0.0], 20)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: Some(vec![vec![0.0, 1000.0], vec![1000.0, 0.0]]),
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+
+    // Arriving at 100 waits 300 seconds for the window, then serves for 600
+    let route = &response.routes[0].cost_breakdown;
+    assert_eq!(route.fixed, 100.0);
+    assert_eq!(route.travel_time, 200.0);
+    assert_eq!(route.distance, 4.0);
+    assert_eq!(route.waiting_time, 150.0);
+    assert_eq!(route.service_time, 60.0);
+    assert_eq!(route.unassigned, 0.0);
+
+    // The summary adds the unassigned job, which is too large for the vehicle
+    let summary = &response.summary;
+    assert_eq!(summary.cost_breakdown.unassigned, 3600.0);
+    assert_eq!(summary.cost, route.total() + 3600.0);
+    assert_eq!(summary.cost, summary.cost_breakdown.total());
+}
+
+#[actix_web::test]
+async fn test_waiting_and_service_time() {
+    let mut early = test_job(1, [1.0, 0.0], 1);
+    early.service = 60;
+    early.time_windows = Some(vec![[400, 1000]]);
+    let mut open = test_job(2, [2.0, 0.0], 1);
+    open.service = 30;
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![early, open],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 300.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![1, 2]);
+
+    // Arriving at 100 waits for the window opening at 400
+    let waiting: Vec<Option<i64>> = route
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            RouteStep::Job { waiting_time, .. } => Some(*waiting_time),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(waiting, vec![Some(300), Some(0)]);
+    assert_eq!((route.waiting_time, route.service_time), (300, 90));
+    assert_eq!(
+        (response.summary.waiting_time, response.summary.service_time),
+        (300, 90)
+    );
+}
+
+#[actix_web::test]
+async fn test_setup_time_only_when_changing_location() {
+    let job = |id, location| Job {
+        service: 10,
+        setup: Some(50),
+        ..test_job(id, location, 1)
+    };
+    let mut request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![job(1, [1.0, 0.0]), job(2, [1.0, 0.0]), job(3, [2.0, 0.0])],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 300.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let setups = |route: &VehicleRoute| -> Vec<Option<u32>> {
+        route
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                RouteStep::Job { setup, .. } => Some(*setup),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // The solver sets up once at the shared location
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request.clone()).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.route.len(), 3);
+    let mut applied: Vec<Option<u32>> = setups(route);
+    applied.sort();
+    assert_eq!(applied, vec![Some(0), Some(50), Some(50)]);
+    assert_eq!(route.service_time, 130);
+
+    // A predefined route sets up before serving, after waiting for the time window
+    request.jobs[0].time_windows = Some(vec![[150, 1000]]);
+    request.vehicles[0].steps = Some(
+        [1, 2, 3]
+            .into_iter()
+            .map(|id| RouteStep::Job {
+                id,
+                location: None,
+                snapped_location: None,
+                snap_distance: None,
+                setup: None,
+                service: None,
+                arrival_time: None,
+                waiting_time: None,
+                departure_time: None,
+                polyline: None,
+                arrival_timestamp: None,
+                departure_timestamp: None,
+                violations: Vec::new(),
+                description: None,
+                metadata: None,
+            })
+            .collect(),
+    );
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(setups(route), vec![Some(50), Some(0), Some(50)]);
+    assert_eq!(route.arrival_times, vec![0, 100, 210, 320, 580]);
+    assert_eq!(route.departure_times, vec![0, 210, 220, 380, 580]);
+    let RouteStep::Job { waiting_time, .. } = &route.steps[1] else {
+        panic!("expected a job step");
+    };
+    assert_eq!(*waiting_time, Some(50));
+}
+
+#[actix_web::test]
+async fn test_service_factor_scales_stop_durations() {
+    let mut job = test_job(1, [1.0, 0.0], 1);
+    job.service = 100;
+    job.setup = Some(20);
+    let mut trainee = test_vehicle(1, [0.0, 0.0], 10);
+    trainee.service_factor = Some(1.3);
+    let mut request = RoutingRequest {
+        vehicles: vec![trainee],
+        jobs: vec![job],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    // Setup is not scaled, service takes the trainee 130 seconds
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request.clone()).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.departure_times, vec![0, 250, 350]);
+    assert_eq!(route.service_time, 150);
+    assert!(matches!(
+        route.steps[1],
+        RouteStep::Job {
+            service: Some(130),
+            waiting_time: Some(0),
+            ..
+        }
+    ));
+
+    // An experienced driver on a predefined route is faster
+    request.vehicles[0].service_factor = Some(0.8);
+    request.vehicles[0].steps = Some(vec![RouteStep::Job {
+        id: 1,
+        location: None,
+        snapped_location: None,
+        snap_distance: None,
+        setup: None,
+        service: None,
+        arrival_time: None,
+        waiting_time: None,
+        departure_time: None,
+        polyline: None,
+        arrival_timestamp: None,
+        departure_timestamp: None,
+        violations: Vec::new(),
+        description: None,
+        metadata: None,
+    }]);
+    let response = service.process_request(request).await.unwrap();
+    assert_eq!(response.routes[0].departure_times, vec![0, 200, 300]);
+}
+
+#[actix_web::test]
+async fn test_optimize_departure_avoids_waiting() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.time_window = Some([0, 10000]);
+    let mut late = test_job(1, [1.0, 0.0], 1);
+    late.time_windows = Some(vec![[1000, 2000]]);
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![late],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            optimize_departure: Some(true),
+            ..Default::default()
+        }),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request.clone()).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.optimized_departure, Some(900));
+    assert_eq!(route.departure_times, vec![900, 1000, 1100]);
+    assert_eq!(route.waiting_time, 0);
+
+    // Without the option the vehicle leaves at the start of its shift and waits
+    let waiting = RoutingRequest {
+        options: None,
+        ..request
+    };
+    let response = service.process_request(waiting).await.unwrap();
+    let route = &response.routes[0];
+    assert_eq!(route.optimized_departure, None);
+    assert_eq!(route.waiting_time, 900);
+}
+
+#[actix_web::test]
+async fn test_unassigned_codes_and_legacy_ids() {
+    let mut tight = test_job(3, [1.0, 0.0], 1);
+    tight.time_windows = Some(vec![[0, 50]]);
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![
+            test_job(1, [1.0, 0.0], 1),
+            test_job(2, [1.0, 0.0], 20),
+            tight,
+        ],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request.clone()).await.unwrap();
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    let codes: Vec<(u64, UnassignedCode)> = jobs.iter().map(|job| (job.id, job.code)).collect();
+    assert_eq!(
+        codes,
+        vec![
+            (2, UnassignedCode::Capacity),
+            (3, UnassignedCode::TimeWindow)
+        ]
+    );
+
+    // Older clients still get plain IDs and the reasons alongside
+    let legacy = RoutingRequest {
+        options: Some(RoutingOptions {
+            legacy_unassigned: Some(true),
+            ..Default::default()
+        }),
+        ..request
+    };
+    let response = service.process_request(legacy).await.unwrap();
+    assert_eq!(response.unassigned, Unassigned::Ids(vec![2, 3]));
+    assert_eq!(response.unassigned_reasons.len(), 2);
+    assert!(response.unassigned_reasons[0].reason.contains("capacity"));
+}
+
+#[actix_web::test]
+async fn test_vehicle_zones_restrict_served_jobs() {
+    let square = |x0: f64, x1: f64| Zone::Polygon {
+        coordinates: vec![vec![
+            [x0, -1.0],
+            [x1, -1.0],
+            [x1, 1.0],
+            [x0, 1.0],
+            [x0, -1.0],
+        ]],
+    };
+    let mut local = test_vehicle(1, [0.0, 0.0], 10);
+    local.allowed_zones = Some(vec![square(-2.0, 2.0)]);
+    let mut regional = test_vehicle(2, [0.0, 0.0], 10);
+    regional.forbidden_zones = Some(vec![square(0.5, 1.5), square(9.0, 11.0)]);
+
+    let locations: Vec<[f64; 2]> = vec![[0.0, 0.0], [1.0, 0.0], [5.0, 0.0], [10.0, 0.0]];
+    let durations = locations
+        .iter()
+        .map(|from| {
+            locations
+                .iter()
+                .map(|to| (from[0] - to[0]).abs() * 100.0)
+                .collect()
+        })
+        .collect();
+    let request = RoutingRequest {
+        vehicles: vec![local, regional],
+        jobs: vec![
+            test_job(1, [1.0, 0.0], 1),
+            test_job(2, [5.0, 0.0], 1),
+            test_job(3, [10.0, 0.0], 1),
+        ],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations,
+            durations,
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let served: Vec<(u64, Vec<u64>)> = response
+        .routes
+        .iter()
+        .map(|route| (route.vehicle_id, route.route.clone()))
+        .collect();
+    assert_eq!(served, vec![(1, vec![1]), (2, vec![2])]);
+
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    assert_eq!(jobs.len(), 1);
+    assert_eq!((jobs[0].id, jobs[0].code), (3, UnassignedCode::Zone));
+}
+
+#[actix_web::test]
+async fn test_alternative_solutions() {
+    let request = RoutingRequest {
+        vehicles: vec![
+            test_vehicle(1, [0.0, 0.0], 10),
+            test_vehicle(2, [0.0, 0.0], 10),
+        ],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            alternatives: Some(3),
+            seed: Some(7),
+            ..Default::default()
+        }),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![
+                vec![0.0, 100.0, 200.0],
+                vec![100.0, 0.0, 100.0],
+                vec![200.0, 100.0, 0.0],
+            ],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    assert!(!response.alternatives.is_empty());
+    assert!(response.alternatives.len() <= 3);
+
+    // Every alternative assigns the jobs differently, cheapest first
+    let assignment = |routes: &[VehicleRoute]| {
+        let mut assignment: Vec<(u64, Vec<u64>)> = routes
+            .iter()
+            .map(|route| {
+                let mut jobs = route.route.clone();
+                jobs.sort_unstable();
+                (route.vehicle_id, jobs)
+            })
+            .collect();
+        assignment.sort_unstable();
+        assignment
+    };
+    let mut seen = vec![assignment(&response.routes)];
+    for alternative in &response.alternatives {
+        assert!(alternative
+            .routes
+            .iter()
+            .all(|route| route.polyline.is_none()));
+        assert!(!seen.contains(&assignment(&alternative.routes)));
+        seen.push(assignment(&alternative.routes));
+    }
+    assert!(response
+        .alternatives
+        .windows(2)
+        .all(|pair| pair[0].cost <= pair[1].cost));
+}
+
+#[actix_web::test]
+async fn test_vehicle_shifts() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.shifts = Some(vec![[0, 300], [1000, 2000]]);
+    let mut morning = test_job(1, [1.0, 0.0], 1);
+    morning.time_windows = Some(vec![[0, 100]]);
+    let mut afternoon = test_job(2, [1.0, 0.0], 1);
+    afternoon.time_windows = Some(vec![[1200, 1300]]);
+    let request = |jobs: Vec<Job>| RoutingRequest {
+        vehicles: vec![vehicle.clone()],
+        jobs,
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let service = RoutingService::new(RoutingConfig::default());
+
+    // The job's time window lies in the afternoon shift, so the route runs there
+    let response = service
+        .process_request(request(vec![afternoon.clone()]))
+        .await
+        .unwrap();
+    assert_eq!(response.summary.unassigned, 0);
+    let route = &response.routes[0];
+    assert_eq!(route.shift, Some(1));
+    assert_eq!(route.departure_times[0], 1000);
+    assert_eq!(route.departure_times[1], 1200);
+    assert_eq!(route.arrival_times[2], 1300);
+
+    let response = service
+        .process_request(request(vec![morning.clone()]))
+        .await
+        .unwrap();
+    assert_eq!(response.routes[0].shift, Some(0));
+    assert_eq!(response.routes[0].departure_times[0], 0);
+
+    // A route fits in a single shift, so jobs of different shifts cannot share it
+    let response = service
+        .process_request(request(vec![morning, afternoon]))
+        .await
+        .unwrap();
+    assert_eq!(response.summary.unassigned, 1);
+}
+
+#[actix_web::test]
+async fn test_soft_time_windows_allow_priced_lateness() {
+    let mut job = test_job(1, [1.0, 0.0], 1);
+    job.time_windows = Some(vec![[0, 60]]);
+    let request = |options: RoutingOptions| RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![job.clone()],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(options),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let service = RoutingService::new(RoutingConfig::default());
+
+    // Arriving 40 seconds late is infeasible by default
+    let hard = service
+        .process_request(request(RoutingOptions::default()))
+        .await
+        .unwrap();
+    assert_eq!(hard.summary.unassigned, 1);
+
+    let soft = |max_lateness| RoutingOptions {
+        time_window_policy: Some(TimeWindowPolicy::Soft),
+        lateness_penalty: Some(2.0),
+        max_lateness,
+        ..Default::default()
+    };
+    let too_late = service
+        .process_request(request(soft(Some(30))))
+        .await
+        .unwrap();
+    assert_eq!(too_late.summary.unassigned, 1);
+
+    let late = service
+        .process_request(request(soft(Some(60))))
+        .await
+        .unwrap();
+    assert_eq!(late.summary.unassigned, 0);
+    assert_eq!(late.summary.cost, 200.0 + 2.0 * 40.0);
+    let RouteStep::Job { violations, .. } = &late.routes[0].steps[1] else {
+        panic!("expected a job step");
+    };
+    assert_eq!(
+        violations,
+        &vec![Violation {
+            kind: ViolationType::Delay,
+            amount: 40,
+            dimension: None,
+        }]
+    );
+
+    // Each late task costs the same in the summary as in the solver's objective
+    let per_task = RoutingOptions {
+        late_task_penalty: Some(100.0),
+        ..soft(None)
+    };
+    let late = service
+        .process_request(request(per_task.clone()))
+        .await
+        .unwrap();
+    assert_eq!(late.summary.unassigned, 0);
+    assert_eq!(late.summary.cost, 200.0 + 2.0 * 40.0 + 100.0);
+    assert_eq!(late.routes[0].cost_breakdown.violations, 2.0 * 40.0 + 100.0);
+
+    let expensive = RoutingOptions {
+        unassigned_penalty: Some(350.0),
+        ..per_task
+    };
+    let skipped = service
+        .process_request(request(expensive))
+        .await
+        .unwrap();
+    assert_eq!(skipped.summary.unassigned, 1);
+    assert_eq!(skipped.summary.cost, 350.0);
+}
+
+#[actix_web::test]
+async fn test_process_request_with_timestamps() {
+    let mut vehicle = test_vehicle(1, [0.0, 0.0], 10);
+    vehicle.departure_time = Some(1792044000);
+    let request = RoutingRequest {
+        vehicles: vec![vehicle],
+        jobs: vec![test_job(1, [1.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            timestamps: Some(true),
+            timezone: Some("+02:00".to_string()),
+            ..Default::default()
+        }),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let steps = serde_json::to_value(&response.routes[0].steps).unwrap();
+    assert_eq!(steps[0]["departure_timestamp"], "2026-10-15T08:00:00+02:00");
+    assert_eq!(steps[1]["arrival_time"], 1792044100);
+    assert_eq!(steps[1]["arrival_timestamp"], "2026-10-15T08:01:40+02:00");
+    assert_eq!(steps[2]["arrival_timestamp"], "2026-10-15T08:03:20+02:00");
+}
+
+#[actix_web::test]
+async fn test_process_request_with_geojson_output() {
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            geometry_format: Some(GeometryFormat::GeoJson),
+            ..Default::default()
+        }),
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0]],
+            durations: vec![vec![0.0, 100.0], vec![100.0, 0.0]],
+            distances: None,
+        }),
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+
+    let service = RoutingService::new(RoutingConfig::default());
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert!(route.polyline.is_none());
+
+    // Without OSRM geometry the line connects the stops
+    let geojson = route.geojson.as_ref().unwrap();
+    assert_eq!(geojson["type"], "FeatureCollection");
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 4);
+    assert_eq!(features[0]["geometry"]["type"], "LineString");
+    assert_eq!(
+        features[0]["geometry"]["coordinates"],
+        serde_json::json!([[0.0, 0.0], [1.0, 0.0], [0.0, 0.0]])
+    );
+    assert_eq!(features[2]["geometry"]["type"], "Point");
+    assert_eq!(features[2]["properties"]["type"], "job");
+    assert_eq!(features[2]["properties"]["id"], 1);
+}
+
+#[test]
+fn test_solver_reports_improvements() {
+    let problem = test_problem(
+        vec![test_vehicle(1, [0.0, 0.0], 10)],
+        (1..=6)
+            .map(|id| test_job(id, [(id * 7 % 10) as f64, id as f64], 1))
+            .collect(),
+    );
+
+    let costs = std::sync::Mutex::new(Vec::new());
+    let solution = solver::solve_with_progress(&problem, 2, &|solution| {
+        costs.lock().unwrap().push(solution.cost);
+    });
+
+    // Each report improves on the previous one and the last one is the result
+    let costs = costs.into_inner().unwrap();
+    assert!(costs.windows(2).all(|pair| pair[1] < pair[0]));
+    assert_eq!(costs.last().copied(), Some(solution.cost));
+}
+
+/// OSRM table stub whose durations are the longitude difference between coordinates
+#[actix_web::test]
+async fn test_osrm_table_is_split_into_chunks() {
+    let osrm = OsrmService::new(OsrmConfig {
+        max_table_size: 4,
+        ..StubOsrm::start().config()
+    });
+
+    let coordinates: Vec<[f64; 2]> = (0..7).map(|lon| [lon as f64, 0.0]).collect();
+    let table = osrm.table(&coordinates, None, &[], true).await.unwrap();
+
+    for (from, row) in table.durations.iter().enumerate() {
+        for (to, duration) in row.iter().enumerate() {
+            assert_eq!(
+                *duration,
+                Some(stub_travel(coordinates[from], coordinates[to]).0)
+            );
+        }
+    }
+    assert_eq!(table.distances.unwrap()[0][6], Some(6000.0));
+    assert_eq!(table.sources.len(), coordinates.len());
+}
+
+#[actix_web::test]
+async fn test_osrm_backend_per_profile() {
+    // Only the bike profile has a server behind it
+    let osrm = OsrmService::new(OsrmConfig {
+        base_url: "http://127.0.0.1:1".to_string(),
+        backends: HashMap::from([(
+            "bike".to_string(),
+            OsrmBackendConfig {
+                base_url: StubOsrm::start().base_url().to_string(),
+                timeout_seconds: Some(5),
+            },
+        )]),
+        ..OsrmConfig::default()
+    });
+
+    let coordinates = [[0.0, 0.0], [2.0, 0.0]];
+    let table = osrm.table(&coordinates, Some("bike"), &[], false).await.unwrap();
+    assert_eq!(table.durations[0][1], Some(200.0));
+    assert!(osrm.table(&coordinates, Some("car"), &[], false).await.is_err());
+
+    let health = osrm.health().await;
+    let status: Vec<(&str, bool)> = health
+        .iter()
+        .map(|backend| (backend.name.as_str(), backend.healthy))
+        .collect();
+    assert_eq!(status, vec![("default", false), ("bike", true)]);
+}
+
+#[actix_web::test]
+async fn test_snapped_locations_in_steps() {
+    let service = RoutingService::new(RoutingConfig {
+        osrm: StubOsrm::start().config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.2, 0.1], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    // The step keeps the given location next to where the stub moved it
+    let RouteStep::Job {
+        location,
+        snapped_location,
+        snap_distance,
+        ..
+    } = &response.routes[0].steps[1]
+    else {
+        panic!("expected a job step");
+    };
+    assert_eq!(*location, Some([1.2, 0.1]));
+    assert_eq!(*snapped_location, Some([1.0, 0.0]));
+    assert_eq!(
+        *snap_distance,
+        Some(stub_snap_distance([1.2, 0.1]).round() as u32)
+    );
+}
+
+#[actix_web::test]
+async fn test_lat_lon_coordinate_order() {
+    let service = RoutingService::new(RoutingConfig {
+        osrm: StubOsrm::start().config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 3.0], 10)],
+        jobs: vec![test_job(1, [0.1, 1.2], 1), test_job(2, [0.0, 2.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            coordinate_order: Some(CoordinateOrder::LatLon),
+            ..RoutingOptions::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    // Routed as [longitude, latitude], returned in the order of the request
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![2, 1]);
+    let RouteStep::Start { location, .. } = &route.steps[0] else {
+        panic!("expected a start step");
+    };
+    assert_eq!(*location, Some([0.0, 3.0]));
+    let RouteStep::Job {
+        id,
+        location,
+        snapped_location,
+        ..
+    } = &route.steps[2]
+    else {
+        panic!("expected a job step");
+    };
+    assert_eq!(*id, 1);
+    assert_eq!(*location, Some([0.1, 1.2]));
+    assert_eq!(*snapped_location, Some([0.0, 1.0]));
+}
+
+#[actix_web::test]
+async fn test_engine_latency_in_summary() {
+    let osrm = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        osrm: OsrmConfig {
+            pool: OsrmPoolConfig {
+                max_idle_per_host: 1,
+                keep_alive_seconds: 0,
+                ..OsrmPoolConfig::default()
+            },
+            ..osrm.config()
+        },
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request.clone()).await.unwrap();
+    let latency = response.summary.engine_latency.unwrap();
+    assert_eq!(latency.requests, 2);
+    assert!(latency.max_time <= latency.total_time);
+    assert!(latency.mean_time <= latency.max_time);
+
+    // Only the requests of this optimization count, the cached matrix takes none
+    let response = service.process_request(request.clone()).await.unwrap();
+    assert_eq!(response.summary.engine_latency.unwrap().requests, 1);
+    assert_eq!(osrm.requests().len(), 3);
+
+    let request = RoutingRequest {
+        matrix: Some(CostMatrix {
+            locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            durations: vec![vec![0.0, 60.0, 120.0], vec![60.0, 0.0, 60.0], vec![120.0, 60.0, 0.0]],
+            distances: None,
+        }),
+        ..request
+    };
+    let response = service.process_request(request).await.unwrap();
+    assert_eq!(response.summary.engine_latency, None);
+}
+
+#[actix_web::test]
+async fn test_matrix_requests_are_cached() {
+    let osrm = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        osrm: osrm.config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = MatrixRequest {
+        locations: vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+        profile: None,
+    };
+    let matrix = service.matrix(&request).await.unwrap();
+    assert_eq!(osrm.requests().len(), 1);
+
+    // Repeated requests and those for fewer of the locations are cut from the first
+    assert_eq!(service.matrix(&request).await.unwrap().durations, matrix.durations);
+    let subset = MatrixRequest {
+        locations: vec![[2.0, 0.0], [0.0, 0.0]],
+        profile: None,
+    };
+    let cut = service.matrix(&subset).await.unwrap();
+    assert_eq!(cut.durations[0][1], matrix.durations[2][0]);
+    assert_eq!(osrm.requests().len(), 1);
+
+    // Another profile needs a matrix of its own
+    let bike = MatrixRequest {
+        profile: Some("bike".to_string()),
+        ..request
+    };
+    service.matrix(&bike).await.unwrap();
+    assert_eq!(osrm.requests().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_far_snapped_locations_are_unreachable() {
+    let osrm = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        osrm: osrm.config(),
+        max_snap_distance: 500.0,
+        ..RoutingConfig::default()
+    });
+
+    // The stub moves the second job about 700 meters to reach a road
+    assert!(stub_snap_distance([1.5, 0.5]) > 500.0);
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [1.5, 0.5], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: None,
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    assert_eq!(response.routes[0].route, vec![1]);
+    let Unassigned::Detailed(jobs) = &response.unassigned else {
+        panic!("expected detailed unassigned jobs");
+    };
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].id, 2);
+    assert_eq!(jobs[0].code, UnassignedCode::Unreachable);
+}
+
+#[actix_web::test]
+async fn test_excluded_roads_and_avoided_polygons() {
+    let osrm = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        osrm: osrm.config(),
+        ..RoutingConfig::default()
+    });
+
+    // A closed bridge across the whole map at longitude 1.5
+    let bridge = Zone::Polygon {
+        coordinates: vec![vec![
+            [1.4, -10.0],
+            [1.6, -10.0],
+            [1.6, 10.0],
+            [1.4, 10.0],
+            [1.4, -10.0],
+        ]],
+    };
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            exclude: vec!["toll".to_string(), "ferry".to_string()],
+            avoid_polygons: vec![bridge],
+            ..RoutingOptions::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    // The job across the bridge cannot be reached
+    assert_eq!(response.routes[0].route, vec![1]);
+    assert_eq!(response.unassigned.ids(), vec![2]);
+    assert_eq!(
+        osrm.requests(),
+        vec![
+            "table/car?exclude=toll,ferry".to_string(),
+            "route/car?exclude=toll,ferry".to_string()
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_turn_by_turn_instructions() {
+    let service = RoutingService::new(RoutingConfig {
+        osrm: StubOsrm::start().config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = |instructions| RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            instructions: Some(instructions),
+            ..RoutingOptions::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request(true)).await.unwrap();
+
+    // One leg to each job and one back to the start
+    let route = &response.routes[0];
+    assert_eq!(route.legs.len(), 3);
+    assert_eq!(
+        route.legs.iter().map(|leg| leg.distance).collect::<Vec<_>>(),
+        vec![1000, 1000, 2000]
+    );
+    let depart = &route.legs[0].instructions[0];
+    assert_eq!(depart.maneuver, "depart");
+    assert_eq!(depart.modifier.as_deref(), Some("straight"));
+    assert_eq!(depart.name, STUB_STREET);
+    assert_eq!((depart.distance, depart.duration), (1000, 100));
+
+    let response = service.process_request(request(false)).await.unwrap();
+    assert!(response.routes[0].legs.is_empty());
+}
+
+#[actix_web::test]
+async fn test_leg_geometry_in_steps() {
+    let osrm = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        osrm: osrm.config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            leg_geometry: Some(true),
+            ..Default::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+    let route = &response.routes[0];
+    assert!(route.polyline.is_some());
+
+    // Every leg runs from the previous stop to the step it leads to
+    let mut previous = [0.0, 0.0];
+    for step in &route.steps[1..] {
+        let (RouteStep::Job {
+            location, polyline, ..
+        }
+        | RouteStep::End {
+            location, polyline, ..
+        }) = step
+        else {
+            panic!("unexpected step {:?}", step);
+        };
+        let location = location.unwrap();
+        let leg = polyline::decode(polyline.as_deref().unwrap(), 5).unwrap();
+        assert_eq!(leg, vec![previous, location]);
+        previous = location;
+    }
+}
+
+#[actix_web::test]
+async fn test_valhalla_engine() {
+    let valhalla = StubOsrm::start();
+    let service = RoutingService::new(RoutingConfig {
+        engine: EngineKind::Valhalla,
+        valhalla: valhalla.valhalla_config(),
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            geometry: Some(true),
+            instructions: Some(true),
+            ..RoutingOptions::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![1, 2]);
+    assert_eq!(route.duration, 400);
+    assert_eq!(route.distance, 4000);
+    // Valhalla maneuvers are named as OSRM names them
+    let maneuvers: Vec<&str> = route.legs[0]
+        .instructions
+        .iter()
+        .map(|instruction| instruction.maneuver.as_str())
+        .collect();
+    assert_eq!(maneuvers, vec!["depart", "arrive"]);
+    assert_eq!(route.legs[0].instructions[0].distance, 1000);
+    let geometry = polyline::decode(route.polyline.as_deref().unwrap(), 5).unwrap();
+    assert_eq!(
+        geometry,
+        vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [0.0, 0.0]]
+    );
+
+    // The car profile maps to Valhalla's auto costing
+    assert_eq!(
+        valhalla.requests(),
+        vec![
+            "sources_to_targets/auto".to_string(),
+            "route/auto".to_string()
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn test_graphhopper_engine() {
+    let graphhopper = StubOsrm::start();
+    let mut config = graphhopper.graphhopper_config();
+    config
+        .profiles
+        .insert("car".to_string(), "small_truck".to_string());
+    let service = RoutingService::new(RoutingConfig {
+        engine: EngineKind::GraphHopper,
+        graphhopper: config,
+        ..RoutingConfig::default()
+    });
+
+    let request = RoutingRequest {
+        vehicles: vec![test_vehicle(1, [0.0, 0.0], 10)],
+        jobs: vec![test_job(1, [1.0, 0.0], 1), test_job(2, [2.0, 0.0], 1)],
+        shipments: vec![],
+        depots: Vec::new(),
+        routing_profile: None,
+        options: Some(RoutingOptions {
+            geometry: Some(true),
+            ..RoutingOptions::default()
+        }),
+        matrix: None,
+        initial_solution: Vec::new(),
+        relations: Vec::new(),
+        callback_url: None,
+    };
+    let response = service.process_request(request).await.unwrap();
+
+    let route = &response.routes[0];
+    assert_eq!(route.route, vec![1, 2]);
+    assert_eq!(route.duration, 400);
+    assert_eq!(route.distance, 4000);
+    assert!(route.polyline.is_some());
+
+    // The car profile is renamed to the configured GraphHopper profile
+    assert_eq!(
+        graphhopper.requests(),
+        vec![
+            "matrix/small_truck".to_string(),
+            "route/small_truck".to_string()
+        ]
+    );
+}
@@ -0,0 +1,878 @@
+use orvrm_core::models::request::RoutingOptions;
+use orvrm_core::models::{
+    CoordinateOrder, InsertResponse, ReoptimizeRequest, RoutingRequest, RoutingResponse,
+};
+use orvrm_core::services::{RoutingConfig, RoutingService, SolverSettings};
+use orvrm_core::utils::validation::{
+    validate_feasibility_request, validate_reoptimize_request, validate_request, validate_settings,
+};
+use orvrm_core::utils::{csv, geometry, ics, jsprit, msgpack, polyline, solomon, vroom};
+
+#[test]
+fn test_polyline_decode() {
+    let coordinates = polyline::decode("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+    assert_eq!(
+        coordinates,
+        vec![[-120.2, 38.5], [-120.95, 40.7], [-126.453, 43.252]]
+    );
+
+    // Truncated input is rejected
+    assert!(polyline::decode("_p~iF~ps|", 5).is_err());
+
+    // Encoding reverses decoding
+    assert_eq!(
+        polyline::encode(&coordinates, 5),
+        "_p~iF~ps|U_ulLnnqC_mqNvxq`@"
+    );
+}
+
+#[test]
+fn test_polyline_split() {
+    let line = vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [2.0, 1.0], [2.0, 2.0]];
+
+    // Waypoints off the line split it where it passes closest
+    let legs = polyline::split(&line, &[[0.0, 0.0], [1.1, 0.9], [2.0, 2.0]]);
+    assert_eq!(
+        legs,
+        vec![
+            vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]],
+            vec![[1.0, 1.0], [2.0, 1.0], [2.0, 2.0]]
+        ]
+    );
+
+    assert!(polyline::split(&line, &[[0.0, 0.0]]).is_empty());
+}
+
+#[test]
+fn test_polygon_contains() {
+    let outer = vec![[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]];
+    let hole = vec![[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 2.0]];
+    let rings = vec![outer, hole];
+
+    assert!(geometry::polygon_contains(&rings, [3.0, 3.0]));
+    assert!(!geometry::polygon_contains(&rings, [1.5, 1.5]));
+    assert!(!geometry::polygon_contains(&rings, [5.0, 1.0]));
+    assert!(!geometry::polygon_contains(&[], [1.0, 1.0]));
+
+    assert!(geometry::is_valid_polygon(&rings));
+    assert!(!geometry::is_valid_polygon(&[vec![
+        [0.0, 0.0],
+        [1.0, 1.0],
+        [0.0, 0.0]
+    ]]));
+    assert!(!geometry::is_valid_polygon(&[vec![
+        [0.0, 0.0],
+        [1.0, f64::NAN],
+        [1.0, 0.0]
+    ]]));
+}
+
+#[test]
+fn test_validate_request() {
+    let valid: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [4.89, 52.37], "end": [4.89, 52.37], "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18], "delivery": [2], "time_windows": [[0, 100]]}]
+    }))
+    .unwrap();
+    assert!(validate_request(&valid).is_ok());
+
+    let invalid: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1, "start": [4.89, 52.37], "end": [4.89, 152.37], "capacity": [4],
+            "service_factor": 0.0
+        }],
+        "jobs": [
+            {"id": 1, "location": [5.41, 52.18], "delivery": [2, 1]},
+            {"id": 1, "location": [5.41, 52.18], "time_windows": [[100, 0]]}
+        ],
+        "options": {"timestamps": true, "timezone": "Europe/Amsterdam"},
+        "callback_url": "ftp://example.com/done"
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&invalid) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "callback_url",
+            "vehicles[0].end",
+            "vehicles[0].service_factor",
+            "jobs[0].delivery",
+            "jobs[1].id",
+            "jobs[1].time_windows[0]",
+            "options.timezone"
+        ]
+    );
+
+    // Duplicate IDs are accepted when the options resolve them
+    let duplicates: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}, {"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}, {"id": 1, "location": [5.41, 52.18]}],
+        "options": {"duplicate_ids": "rename"}
+    }))
+    .unwrap();
+    assert!(validate_request(&duplicates).is_ok());
+
+    // Allowed vehicles must exist and predefined routes must respect them
+    let excluded: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4], "steps": [{"type": "job", "id": 1}]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18], "allowed_vehicles": [2]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&excluded) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec!["jobs[0].allowed_vehicles[0]", "vehicles[0].steps[0]"]
+    );
+
+    // Depots must be unique and known to the vehicles referring to them
+    let depots: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "depots": [
+            {"id": 1, "location": [4.89, 52.37]},
+            {"id": 1, "location": [4.89, 52.37], "time_window": [100, 0], "stock": [5, 5]}
+        ],
+        "vehicles": [
+            {"id": 1, "start_depot": 1, "end_depot": 1, "capacity": [4], "max_trips": 2},
+            {"id": 2, "start": [4.89, 52.37], "start_depot": 1, "end_depot": 2, "capacity": [4]}
+        ],
+        "jobs": []
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&depots) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "depots[1].id",
+            "depots[1].time_window",
+            "depots[1].stock",
+            "vehicles[1].start_depot",
+            "vehicles[1].end_depot"
+        ]
+    );
+
+    // Break steps of predefined routes must be breaks of their vehicle
+    let unknown_break: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1,
+            "capacity": [4],
+            "breaks": [{"id": 7, "time_windows": [[0, 100]], "service": 30}],
+            "steps": [{"type": "break", "id": 7}, {"type": "break", "id": 8}]
+        }],
+        "jobs": []
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&unknown_break) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, vec!["vehicles[0].steps[1]"]);
+
+    // Initial routes must refer to known vehicles, jobs and shipments, each only once
+    let warm: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}],
+        "initial_solution": [
+            {"vehicle_id": 1, "steps": [{"type": "start"}, {"type": "job", "id": 1}, {"type": "job", "id": 2}]},
+            {"vehicle_id": 3, "steps": [{"type": "job", "id": 1}]}
+        ]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&warm) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "initial_solution[0].steps[2]",
+            "initial_solution[1].vehicle_id",
+            "initial_solution[1].steps[0]"
+        ]
+    );
+
+    // Zones need proper polygons
+    let zoned: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1,
+            "capacity": [4],
+            "allowed_zones": [{"type": "Polygon", "coordinates": [[[4.8, 52.3], [5.0, 52.3], [5.0, 52.4]]]}],
+            "forbidden_zones": [{"type": "MultiPolygon", "coordinates": [[[[4.9, 52.3], [4.9, 52.4]]]]}]
+        }],
+        "jobs": [{"id": 1, "location": [4.9, 52.35]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&zoned) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, vec!["vehicles[0].forbidden_zones[0]"]);
+
+    let lns: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [4.9, 52.35]}],
+        "options": {"algorithm": "lns", "lns": {"removal_fraction": 0.0, "regret": 0}}
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&lns) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, vec!["options.lns.removal_fraction", "options.lns.regret"]);
+
+    // Shifts follow each other without overlap and replace the time window
+    let shifts: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {"id": 1, "capacity": [4], "shifts": [[0, 100], [100, 200], [300, 250]]},
+            {"id": 2, "capacity": [4], "shifts": [[0, 100]], "time_window": [0, 100]}
+        ],
+        "jobs": [{"id": 1, "location": [4.9, 52.35]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&shifts) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "vehicles[0].shifts[2]",
+            "vehicles[0].shifts[1]",
+            "vehicles[1].shifts"
+        ]
+    );
+
+    let empty: RoutingRequest =
+        serde_json::from_value(serde_json::json!({"vehicles": [], "jobs": []})).unwrap();
+    assert!(validate_request(&empty).is_err());
+}
+
+#[test]
+fn test_validate_reoptimize_request() {
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}, {"id": 2, "location": [5.42, 52.18]}]
+    });
+
+    let valid: ReoptimizeRequest = serde_json::from_value(serde_json::json!({
+        "request": request,
+        "routes": [{"vehicle_id": 1, "steps": [{"type": "job", "id": 1, "locked": true}]}],
+        "cancelled_jobs": [2]
+    }))
+    .unwrap();
+    assert!(validate_reoptimize_request(&valid).is_ok());
+
+    let invalid: ReoptimizeRequest = serde_json::from_value(serde_json::json!({
+        "request": request,
+        "routes": [
+            {"vehicle_id": 1, "steps": [{"type": "job", "id": 1}, {"type": "job", "id": 3}]},
+            {"vehicle_id": 2, "steps": [{"type": "job", "id": 1}]}
+        ],
+        "new_jobs": [{"id": 2, "location": [5.43, 52.18]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_reoptimize_request(&invalid) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "jobs[2].id",
+            "routes[0].steps[1]",
+            "routes[1].vehicle_id",
+            "routes[1].steps[0]"
+        ]
+    );
+}
+
+#[test]
+fn test_validate_feasibility_request() {
+    let valid: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4], "steps": [{"type": "job", "id": 1}]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}]
+    }))
+    .unwrap();
+    assert!(validate_feasibility_request(&valid).is_ok());
+
+    let invalid: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {"id": 1, "capacity": [4], "steps": [{"type": "delivery", "id": 1}]},
+            {"id": 2, "capacity": [4]}
+        ],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_feasibility_request(&invalid) else {
+        panic!("expected field errors");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "vehicles[0].steps[0]");
+    assert_eq!(errors[0].message, "Unknown shipment ID 1");
+
+    let unplanned: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_feasibility_request(&unplanned) else {
+        panic!("expected field errors");
+    };
+    assert_eq!(errors[0].field, "vehicles");
+}
+
+#[test]
+fn test_validate_shipment_order() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {"id": 1, "capacity": [4], "steps": [
+                {"type": "delivery", "id": 1}, {"type": "pickup", "id": 1}, {"type": "pickup", "id": 2}
+            ]},
+            {"id": 2, "capacity": [4], "steps": [{"type": "pickup", "id": 3}, {"type": "delivery", "id": 3}]}
+        ],
+        "jobs": [],
+        "shipments": [
+            {"id": 1, "amount": [1], "pickup": {"location": [5.41, 52.18]}, "delivery": {"location": [5.42, 52.18]}},
+            {"id": 2, "amount": [1], "pickup": {"location": [5.41, 52.18]}, "delivery": {"location": [5.42, 52.18]}},
+            {"id": 3, "amount": [1], "pickup": {"location": [5.41, 52.18]}, "delivery": {"location": [5.42, 52.18]}}
+        ]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, vec!["vehicles[0].steps[0]", "vehicles[0].steps[2]"]);
+    assert_eq!(
+        errors[0].message,
+        "Shipment 1 is delivered before it is picked up"
+    );
+    assert_eq!(
+        errors[1].message,
+        "Shipment 2 is picked up but never delivered"
+    );
+
+    // The feasibility check reports the order as a violation instead
+    assert!(validate_feasibility_request(&request).is_ok());
+}
+
+#[test]
+fn test_validate_threads() {
+    let mut request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}],
+        "options": {"threads": 64}
+    }))
+    .unwrap();
+    assert!(validate_request(&request).is_ok());
+
+    request.options.as_mut().unwrap().threads = Some(255);
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+    assert_eq!(errors[0].field, "options.threads");
+    assert_eq!(errors[0].message, "At most 64 threads can be requested");
+}
+
+#[test]
+fn test_validate_completed_jobs() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {"id": 1, "capacity": [4], "current_position": [5.41, 95.0], "completed_job_ids": [1]},
+            {"id": 2, "capacity": [4], "completed_job_ids": [1, 7]}
+        ],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "vehicles[0].current_position",
+            "vehicles[1].completed_job_ids[0]",
+            "vehicles[1].completed_job_ids[1]"
+        ]
+    );
+    assert_eq!(errors[1].message, "Job 1 is already completed by vehicle 1");
+    assert_eq!(errors[2].message, "Unknown job ID 7");
+}
+
+#[test]
+fn test_validate_split_groups() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [
+            {"id": 1, "capacity": [4], "steps": [{"type": "job", "id": 1}, {"type": "job", "id": 2}]},
+            {"id": 2, "capacity": [4], "steps": [{"type": "job", "id": 3}]}
+        ],
+        "jobs": [
+            {"id": 1, "location": [5.41, 52.18], "group": "a"},
+            {"id": 2, "location": [5.42, 52.18], "group": "a"},
+            {"id": 3, "location": [5.43, 52.18], "group": "a"}
+        ]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "vehicles[1].steps[0]");
+    assert_eq!(errors[0].message, "Group a is already routed to vehicle 1");
+}
+
+#[test]
+fn test_validate_relations() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [5.41, 52.18]}, {"id": 2, "location": [5.42, 52.18]}],
+        "relations": [
+            {"type": "in_sequence", "steps": [{"type": "job", "id": 1}, {"type": "job", "id": 2}]},
+            {"type": "not_in_same_route", "steps": [{"type": "job", "id": 1}]},
+            {"type": "in_same_route", "steps": [{"type": "job", "id": 2}, {"type": "pickup", "id": 2}]}
+        ]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, vec!["relations[1].steps", "relations[2].steps[1]"]);
+    assert_eq!(errors[1].message, "Unknown shipment ID 2");
+}
+
+#[test]
+fn test_validate_coordinate_order() {
+    let mut request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [1.29, 103.85], "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [1.35, 103.82]}, {"id": 2, "location": [95.0, 200.0]}]
+    }))
+    .unwrap();
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+
+    // Latitudes above 90 that make sense as longitudes point at swapped coordinates
+    let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+    assert_eq!(fields, vec!["vehicles[0].start", "jobs[0].location", "jobs[1].location"]);
+    assert!(errors[0].message.contains("look swapped"));
+    assert_eq!(
+        errors[2].message,
+        "Invalid coordinates [95.0, 200.0], expected [longitude, latitude]"
+    );
+
+    request.options = Some(RoutingOptions {
+        coordinate_order: Some(CoordinateOrder::LatLon),
+        ..RoutingOptions::default()
+    });
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "Invalid coordinates [95.0, 200.0], expected [latitude, longitude]"
+    );
+}
+
+#[test]
+fn test_validate_settings() {
+    let settings = SolverSettings::from(&RoutingConfig::default());
+    assert!(validate_settings(&settings).is_ok());
+
+    let invalid = SolverSettings {
+        default_threads: 0,
+        default_max_tasks: Some(0),
+        lateness_penalty: -1.0,
+        late_task_penalty: f64::NAN,
+        ..settings
+    };
+    let Err(errors) = validate_settings(&invalid) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "default_threads",
+            "default_max_tasks",
+            "lateness_penalty",
+            "late_task_penalty"
+        ]
+    );
+}
+
+#[test]
+fn test_validate_late_penalties() {
+    // Free lateness is allowed on the server and per request
+    let settings = SolverSettings {
+        lateness_penalty: 0.0,
+        late_task_penalty: 0.0,
+        ..SolverSettings::from(&RoutingConfig::default())
+    };
+    assert!(validate_settings(&settings).is_ok());
+
+    for penalty in [-0.5, f64::INFINITY, f64::NAN] {
+        let invalid = SolverSettings {
+            late_task_penalty: penalty,
+            ..settings.clone()
+        };
+        let Err(errors) = validate_settings(&invalid) else {
+            panic!("expected field errors for {}", penalty);
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "late_task_penalty");
+    }
+
+    let mut request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "capacity": [4]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0]}],
+        "options": {"lateness_penalty": 0.0, "late_task_penalty": 250.0}
+    }))
+    .unwrap();
+    assert!(validate_request(&request).is_ok());
+
+    request.options = Some(RoutingOptions {
+        lateness_penalty: Some(-1.0),
+        late_task_penalty: Some(f64::INFINITY),
+        ..RoutingOptions::default()
+    });
+    let Err(errors) = validate_request(&request) else {
+        panic!("expected field errors");
+    };
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(fields, vec!["options.lateness_penalty", "options.late_task_penalty"]);
+}
+
+#[test]
+fn test_export_formats() {
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{
+            "id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10],
+            "skills": ["fridge", "crane"]
+        }],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 60, "delivery": [2], "skills": ["fridge"]},
+            {"id": 2, "location": [2.0, 0.0], "pickup": [1]}
+        ],
+        "shipments": [{
+            "id": 1, "amount": [3],
+            "pickup": {"location": [0.5, 0.0]},
+            "delivery": {"location": [3.0, 0.0], "time_windows": [[0, 100]]}
+        }]
+    }))
+    .unwrap();
+    let response: RoutingResponse = serde_json::from_value(serde_json::json!({
+        "summary": {
+            "cost": 200.4, "distance": 2000, "duration": 200, "routes": 1, "unassigned": 2,
+            "computing_time": 5
+        },
+        "routes": [{
+            "vehicle_id": 1, "route": [1], "distance": 2000, "duration": 200,
+            "arrival_times": [0, 100, 260], "departure_times": [0, 160, 260],
+            "load_profile": [[2], [0], [0]],
+            "steps": [
+                {"type": "start", "location": [0.0, 0.0], "arrival_time": 0, "departure_time": 0},
+                {"type": "job", "id": 1, "location": [1.0, 0.0], "service": 60,
+                 "arrival_time": 100, "departure_time": 160},
+                {"type": "rest", "service": 60, "arrival_time": 160, "departure_time": 160},
+                {"type": "end", "location": [0.0, 0.0], "arrival_time": 260, "departure_time": 260}
+            ]
+        }],
+        "unassigned": [{"id": 2, "code": "CAPACITY", "description": "Too heavy"}],
+        "unassigned_shipments": [1]
+    }))
+    .unwrap();
+
+    let exported = vroom::export(&request, &response);
+    let problem = &exported["problem"];
+    // Skills are numbered in name order
+    assert_eq!(problem["vehicles"][0]["skills"], serde_json::json!([1, 0]));
+    assert_eq!(problem["vehicles"][0]["costs"]["per_hour"], 3600);
+    assert_eq!(problem["jobs"][0]["skills"], serde_json::json!([1]));
+    assert!(problem["jobs"][1].get("skills").is_none());
+    assert_eq!(problem["shipments"][0]["delivery"]["id"], 1);
+    let solution = &exported["solution"];
+    assert_eq!(solution["summary"]["cost"], 200);
+    assert_eq!(solution["unassigned"].as_array().unwrap().len(), 3);
+    assert_eq!(solution["unassigned"][2]["type"], "delivery");
+    let steps = solution["routes"][0]["steps"].as_array().unwrap();
+    let kinds: Vec<_> = steps
+        .iter()
+        .map(|step| step["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["start", "job", "end"]);
+    assert_eq!(steps[0]["load"], serde_json::json!([2]));
+    assert_eq!(steps[1]["load"], serde_json::json!([0]));
+    assert_eq!(steps[1]["service"], 60);
+
+    let xml = jsprit::export(&request, &response);
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains("<typeId>type-1</typeId>"));
+    assert!(xml.contains("<skills>fridge,crane</skills>"));
+    assert!(xml.contains("<service id=\"1\" type=\"delivery\">"));
+    assert!(xml.contains("<service id=\"2\" type=\"pickup\">"));
+    assert!(xml.contains("<shipment id=\"shipment-1\">"));
+    assert!(xml.contains("<dimension index=\"0\">10</dimension>"));
+    assert!(xml.contains("<act type=\"service\">"));
+    assert!(xml.contains("<serviceId>1</serviceId>"));
+    assert!(xml.contains("<arrTime>100</arrTime>"));
+    assert!(xml.contains("<end>260</end>"));
+    assert!(xml.contains("<job id=\"2\"/>"));
+    assert!(xml.contains("<job id=\"shipment-1\"/>"));
+    assert_eq!(xml.matches("<act ").count(), 1);
+    assert!(xml.trim_end().ends_with("</problem>"));
+}
+
+#[test]
+fn test_csv_export() {
+    let response: RoutingResponse = serde_json::from_value(serde_json::json!({
+        "summary": {
+            "cost": 300.0, "distance": 3100, "duration": 300, "routes": 1, "unassigned": 0,
+            "computing_time": 5
+        },
+        "routes": [{
+            "vehicle_id": 4, "route": [1], "distance": 3100, "duration": 300,
+            "arrival_times": [0, 100, 300], "departure_times": [0, 160, 300],
+            "load_profile": [[2, 1], [0, 0], [0, 0]],
+            "steps": [
+                {"type": "start", "location": [0.0, 0.0], "arrival_time": 0, "departure_time": 0},
+                {"type": "job", "id": 1, "location": [1.0, 0.0], "service": 60,
+                 "arrival_time": 90, "waiting_time": 10, "departure_time": 160},
+                {"type": "break", "id": 9, "service": 40, "arrival_time": 160,
+                 "departure_time": 200},
+                {"type": "end", "location": [0.0, 0.0], "arrival_time": 300, "departure_time": 300}
+            ]
+        }]
+    }))
+    .unwrap();
+
+    let text = csv::export(&response);
+    let rows: Vec<&str> = text.split("\r\n").filter(|row| !row.is_empty()).collect();
+    assert_eq!(
+        rows,
+        [
+            "vehicle_id,sequence,type,id,arrival_time,departure_time,service,waiting_time,load,distance",
+            "4,0,start,,0,0,,,\"2,1\",",
+            "4,1,job,1,90,160,60,10,\"0,0\",",
+            "4,2,break,9,160,200,40,,\"0,0\",",
+            "4,3,end,,300,300,,,\"0,0\",3100",
+        ]
+    );
+}
+
+#[test]
+fn test_msgpack_encoding() {
+    let value = serde_json::json!({
+        "steps": [1, -1, -100, 300, 70000, 1.5, "job", null, true],
+    });
+    let mut expected = vec![0x81, 0xa5];
+    expected.extend_from_slice(b"steps");
+    expected.extend_from_slice(&[0x99, 0x01, 0xff, 0xd0, 0x9c, 0xcd, 0x01, 0x2c]);
+    expected.extend_from_slice(&[0xce, 0x00, 0x01, 0x11, 0x70, 0xcb]);
+    expected.extend_from_slice(&1.5f64.to_be_bytes());
+    expected.extend_from_slice(&[0xa3, b'j', b'o', b'b', 0xc0, 0xc3]);
+    assert_eq!(msgpack::to_vec(&value).unwrap(), expected);
+
+    // Longer strings and lists switch to their sized forms
+    let bytes = msgpack::to_vec(&"x".repeat(40)).unwrap();
+    assert_eq!(bytes[..2], [0xd9, 40]);
+    let bytes = msgpack::to_vec(&vec![0; 20]).unwrap();
+    assert_eq!(bytes[..3], [0xdc, 0x00, 20]);
+
+    // Map keys are strings and non-finite numbers nil, as in JSON
+    let keyed = std::collections::BTreeMap::from([(7u64, f64::NAN)]);
+    assert_eq!(msgpack::to_vec(&keyed).unwrap(), [0x81, 0xa1, b'7', 0xc0]);
+}
+
+#[actix_web::test]
+async fn test_msgpack_routing_response() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 7, "location": [1.0, 0.0], "service": 30, "delivery": [1]},
+            {"id": 8, "location": [2.0, 0.0], "delivery": [20]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    }))
+    .unwrap();
+    let response = service.process_request(request).await.unwrap();
+
+    // Decoding gives back the JSON of the response, and from it the response
+    let bytes = msgpack::to_vec(&response).unwrap();
+    let mut rest = bytes.as_slice();
+    let decoded = decode_msgpack(&mut rest);
+    assert!(rest.is_empty());
+    assert_eq!(decoded, serde_json::to_value(&response).unwrap());
+    let decoded: RoutingResponse = serde_json::from_value(decoded).unwrap();
+    assert_eq!(decoded.routes[0].route, response.routes[0].route);
+    assert_eq!(decoded.unassigned.ids(), vec![8]);
+
+    // Flattened fields are counted as they are written
+    let inserted = InsertResponse {
+        insertion_cost: 12.5,
+        solution: response,
+    };
+    let bytes = msgpack::to_vec(&inserted).unwrap();
+    assert_eq!(
+        decode_msgpack(&mut bytes.as_slice()),
+        serde_json::to_value(&inserted).unwrap()
+    );
+}
+
+/// Decode the MessagePack value at the start of `bytes` as JSON, consuming it
+fn decode_msgpack(bytes: &mut &[u8]) -> serde_json::Value {
+    use serde_json::Value;
+
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> &'a [u8] {
+        let (head, rest) = bytes.split_at(len);
+        *bytes = rest;
+        head
+    }
+    fn uint(bytes: &mut &[u8], len: usize) -> usize {
+        take(bytes, len)
+            .iter()
+            .fold(0, |value, byte| value << 8 | *byte as usize)
+    }
+    fn text(bytes: &mut &[u8], len: usize) -> String {
+        String::from_utf8(take(bytes, len).to_vec()).unwrap()
+    }
+    fn list(bytes: &mut &[u8], len: usize) -> Value {
+        Value::Array((0..len).map(|_| decode_msgpack(bytes)).collect())
+    }
+    fn map(bytes: &mut &[u8], len: usize) -> Value {
+        let mut fields = serde_json::Map::new();
+        for _ in 0..len {
+            let Value::String(name) = decode_msgpack(bytes) else {
+                panic!("map keys are strings");
+            };
+            fields.insert(name, decode_msgpack(bytes));
+        }
+        Value::Object(fields)
+    }
+
+    let marker = take(bytes, 1)[0];
+    match marker {
+        0x00..=0x7f => Value::from(marker),
+        0x80..=0x8f => map(bytes, (marker & 0x0f) as usize),
+        0x90..=0x9f => list(bytes, (marker & 0x0f) as usize),
+        0xa0..=0xbf => Value::from(text(bytes, (marker & 0x1f) as usize)),
+        0xc0 => Value::Null,
+        0xc2 => Value::from(false),
+        0xc3 => Value::from(true),
+        0xcb => Value::from(f64::from_be_bytes(take(bytes, 8).try_into().unwrap())),
+        0xcc => Value::from(uint(bytes, 1)),
+        0xcd => Value::from(uint(bytes, 2)),
+        0xce => Value::from(uint(bytes, 4)),
+        0xcf => Value::from(uint(bytes, 8) as u64),
+        0xd0 => Value::from(take(bytes, 1)[0] as i8),
+        0xd1 => Value::from(i16::from_be_bytes(take(bytes, 2).try_into().unwrap())),
+        0xd2 => Value::from(i32::from_be_bytes(take(bytes, 4).try_into().unwrap())),
+        0xd3 => Value::from(i64::from_be_bytes(take(bytes, 8).try_into().unwrap())),
+        0xd9 => {
+            let len = uint(bytes, 1);
+            Value::from(text(bytes, len))
+        }
+        0xda => {
+            let len = uint(bytes, 2);
+            Value::from(text(bytes, len))
+        }
+        0xdc => {
+            let len = uint(bytes, 2);
+            list(bytes, len)
+        }
+        0xde => {
+            let len = uint(bytes, 2);
+            map(bytes, len)
+        }
+        0xe0..=0xff => Value::from(marker as i8),
+        _ => panic!("unexpected MessagePack marker {:#x}", marker),
+    }
+}
+
+#[test]
+fn test_ics_export() {
+    let response: RoutingResponse = serde_json::from_value(serde_json::json!({
+        "summary": {
+            "cost": 300.0, "distance": 3100, "duration": 300, "routes": 1, "unassigned": 0,
+            "computing_time": 5
+        },
+        "routes": [{
+            "vehicle_id": 4, "route": [1], "distance": 3100, "duration": 300,
+            "arrival_times": [], "departure_times": [], "load_profile": [],
+            "steps": [
+                {"type": "start", "location": [4.9, 52.37], "arrival_time": 1714550400,
+                 "departure_time": 1714550400},
+                {"type": "job", "id": 1, "location": [4.95, 52.36], "service": 3900,
+                 "arrival_time": 1714552200, "waiting_time": 45, "departure_time": 1714556100},
+                {"type": "rest", "service": 60}
+            ]
+        }]
+    }))
+    .unwrap();
+
+    let calendar = ics::export(&response.routes[0], "abc", 1714500000);
+    let lines: Vec<&str> = calendar.split("\r\n").collect();
+    assert_eq!(lines[0], "BEGIN:VCALENDAR");
+    assert_eq!(
+        lines.iter().filter(|line| **line == "BEGIN:VEVENT").count(),
+        2
+    );
+    assert!(lines.contains(&"UID:abc-4-1@orvrm"));
+    assert!(lines.contains(&"DTSTART:20240501T083000Z"));
+    assert!(lines.contains(&"DTEND:20240501T093500Z"));
+    assert!(lines.contains(&"SUMMARY:Job 1"));
+    assert!(lines.contains(&"LOCATION:52.36\\, 4.95"));
+    assert!(lines.contains(&"GEO:52.36;4.95"));
+    assert!(lines.contains(&"DESCRIPTION:Service 1 h 5 min\\nWaiting 45 s"));
+    assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    assert!(lines.iter().all(|line| line.len() <= 75));
+}
+
+#[test]
+fn test_solomon_instance() {
+    let instance = solomon::parse(include_str!("../benches/instances/sample12.txt")).unwrap();
+    assert_eq!(instance.name, "SAMPLE12");
+
+    let request = &instance.request;
+    assert_eq!(request.vehicles.len(), 4);
+    assert!(request.vehicles.iter().all(|vehicle| {
+        vehicle.capacity == vec![60]
+            && vehicle.start == Some([40.0, 50.0])
+            && vehicle.end == Some([40.0, 50.0])
+            && vehicle.time_window == Some([0, 240])
+    }));
+    assert_eq!(request.jobs.len(), 12);
+    let job = &request.jobs[0];
+    assert_eq!(job.id, 1);
+    assert_eq!(job.location, [45.0, 68.0]);
+    assert_eq!(job.delivery, Some(vec![10]));
+    assert_eq!(job.service, 10);
+    assert_eq!(job.time_windows, Some(vec![[20, 90]]));
+
+    // Travel takes the straight-line distance between the coordinates
+    let matrix = request.matrix.as_ref().unwrap();
+    assert_eq!(matrix.locations.len(), 13);
+    let from = matrix.locations.iter().position(|l| *l == [40.0, 50.0]).unwrap();
+    let to = matrix.locations.iter().position(|l| *l == [45.0, 68.0]).unwrap();
+    assert_eq!(matrix.durations[from][to], 5.0f64.hypot(18.0));
+    assert_eq!(matrix.distances.as_ref().unwrap()[from][to], 5.0f64.hypot(18.0));
+
+    assert!(solomon::parse("").is_err());
+    assert!(solomon::parse("C101\nVEHICLE\n25 200\n0 40 50 0 0 1236\n").is_err());
+}
@@ -0,0 +1,126 @@
+use super::openapi::ErrorBody;
+use crate::utils::error::AppError;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, HttpRequest, HttpResponse, Responder, ResponseError};
+use log::info;
+use orvrm_core::services::{RoutingService, SolverSettings};
+use orvrm_core::utils::validation::validate_settings;
+use serde::Deserialize;
+
+/// Access to the admin endpoints, disabled unless a token is set
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Bearer token required by the admin endpoints
+    pub token: Option<String>,
+}
+
+/// Current solver defaults
+#[utoipa::path(get, path = "/api/admin/settings", tag = "admin",
+    responses(
+        (status = 200, description = "Current solver defaults", body = SolverSettings),
+        (status = 401, description = "Missing or wrong admin token", body = ErrorBody),
+        (status = 501, description = "No admin token is configured", body = ErrorBody)
+    ))]
+pub async fn get_settings(
+    http_request: HttpRequest,
+    routing_service: web::Data<RoutingService>,
+    admin: Option<web::Data<AdminConfig>>,
+) -> impl Responder {
+    if let Some(response) = unauthorized(&http_request, admin.as_ref().map(|admin| admin.get_ref()))
+    {
+        return response;
+    }
+
+    HttpResponse::Ok().json(routing_service.settings())
+}
+
+/// Change solver defaults for the optimizations started from now on
+///
+/// The body is merged into the current settings: fields left out keep their value and
+/// `null` turns the quick search off. The changes last until the server restarts.
+#[utoipa::path(patch, path = "/api/admin/settings", tag = "admin", request_body = Object,
+    responses(
+        (status = 200, description = "Updated solver defaults", body = SolverSettings),
+        (status = 400, description = "Invalid settings", body = ErrorBody),
+        (status = 401, description = "Missing or wrong admin token", body = ErrorBody),
+        (status = 501, description = "No admin token is configured", body = ErrorBody)
+    ))]
+pub async fn patch_settings(
+    http_request: HttpRequest,
+    changes: web::Json<serde_json::Value>,
+    routing_service: web::Data<RoutingService>,
+    admin: Option<web::Data<AdminConfig>>,
+) -> impl Responder {
+    if let Some(response) = unauthorized(&http_request, admin.as_ref().map(|admin| admin.get_ref()))
+    {
+        return response;
+    }
+
+    match merge_settings(routing_service.settings(), changes.into_inner()) {
+        Ok(settings) => {
+            info!("Solver settings changed to {:?}", settings);
+            routing_service.update_settings(settings.clone());
+            HttpResponse::Ok().json(settings)
+        }
+        Err(err) => {
+            info!("Rejected solver settings: {}", err);
+            err.error_response()
+        }
+    }
+}
+
+/// Apply the fields of `changes` to `settings`
+fn merge_settings(
+    settings: SolverSettings,
+    changes: serde_json::Value,
+) -> Result<SolverSettings, AppError> {
+    let serde_json::Value::Object(changes) = changes else {
+        return Err(AppError::ValidationError(
+            "Settings must be a JSON object".to_string(),
+        ));
+    };
+
+    let mut merged =
+        serde_json::to_value(settings).map_err(|err| AppError::InternalError(err.to_string()))?;
+    if let Some(merged) = merged.as_object_mut() {
+        merged.extend(changes);
+    }
+    let settings: SolverSettings = serde_json::from_value(merged)
+        .map_err(|err| AppError::ValidationError(format!("Invalid settings: {}", err)))?;
+
+    validate_settings(&settings).map_err(AppError::InvalidFields)?;
+    Ok(settings)
+}
+
+/// Response rejecting an admin request without the right bearer token, if it lacks one
+fn unauthorized(request: &HttpRequest, admin: Option<&AdminConfig>) -> Option<HttpResponse> {
+    let Some(token) = admin
+        .and_then(|admin| admin.token.as_deref())
+        .filter(|token| !token.is_empty())
+    else {
+        return Some(HttpResponse::NotImplemented().json(serde_json::json!({
+            "error": "Admin endpoints are not configured"
+        })));
+    };
+
+    let given = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let valid = given.is_some_and(|given| tokens_match(given, token));
+    (!valid).then(|| {
+        AppError::Unauthorized("A valid admin token is required".to_string()).error_response()
+    })
+}
+
+/// Compare tokens in time independent of where they differ
+pub(super) fn tokens_match(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
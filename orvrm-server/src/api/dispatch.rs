@@ -0,0 +1,360 @@
+use super::limits::RequestLimits;
+use super::rate_limit::{OptimizationPermit, RateLimiter};
+use super::shutdown::ShutdownState;
+use crate::services::jobs::{JobStore, RunningSolve};
+use actix_ws::{Message, MessageStream, Session};
+use futures::StreamExt;
+use log::{info, warn};
+use orvrm_core::models::{
+    Job, LockedRoute, OptimizationProgress, ReoptimizeRequest, RouteStep, RoutingRequest,
+    RoutingResponse, Shipment,
+};
+use orvrm_core::services::routing::{ProgressCallback, RoutingService};
+use orvrm_core::utils::validation::{validate_reoptimize_request, validate_request, FieldError};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{Instrument, Span};
+
+/// Message sent by a dispatching client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Optimize a new problem, replacing the one of the session
+    Solve { request: Box<RoutingRequest> },
+
+    /// Add jobs and shipments to the problem and update the routes
+    Insert {
+        #[serde(default)]
+        jobs: Vec<Job>,
+        #[serde(default)]
+        shipments: Vec<Shipment>,
+    },
+
+    /// Remove jobs and shipments from the problem and update the routes
+    Cancel {
+        #[serde(default)]
+        jobs: Vec<u64>,
+        #[serde(default)]
+        shipments: Vec<u64>,
+    },
+
+    /// Keep the locked steps of these routes in place in later updates
+    Lock { routes: Vec<LockedRoute> },
+}
+
+/// Message sent to a dispatching client
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// A better solution was found while optimizing
+    Progress { progress: OptimizationProgress },
+
+    /// The optimization finished with this response
+    Solution { solution: Box<RoutingResponse> },
+
+    /// The locked routes were accepted
+    Locked { routes: usize },
+
+    /// The last message could not be handled
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        errors: Vec<FieldError>,
+    },
+}
+
+impl ServerMessage {
+    fn error(message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            message: message.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn invalid(errors: Vec<FieldError>) -> Self {
+        ServerMessage::Error {
+            message: format!("Validation error: {} invalid field(s)", errors.len()),
+            errors,
+        }
+    }
+}
+
+/// Checks every solve of a dispatching connection passes before it runs
+#[derive(Clone, Default)]
+pub struct Admission {
+    /// Rate limiter and client key each solve takes an optimization slot from
+    pub rate_limit: Option<(RateLimiter, String)>,
+
+    /// Shutdown state refusing solves while the server drains
+    pub shutdown: Option<ShutdownState>,
+
+    /// Job store whose draining waits for running solves
+    pub jobs: Option<JobStore>,
+
+    /// Size limits of the problems solved
+    pub limits: Option<RequestLimits>,
+}
+
+/// Problem and locked routes of a dispatching connection
+struct DispatchSession {
+    service: RoutingService,
+    session: Session,
+    request: Option<RoutingRequest>,
+    locked: Vec<LockedRoute>,
+    admission: Admission,
+}
+
+/// Handle a dispatching connection in its own task until the client disconnects
+///
+/// Every solve is first checked against `admission`. The session logs with the
+/// correlation ID of the request that opened it.
+pub fn spawn(
+    service: RoutingService,
+    session: Session,
+    messages: MessageStream,
+    admission: Admission,
+) {
+    let dispatch = DispatchSession {
+        service,
+        session,
+        request: None,
+        locked: Vec::new(),
+        admission,
+    };
+    actix_web::rt::spawn(dispatch.run(messages).instrument(Span::current()));
+}
+
+impl DispatchSession {
+    async fn run(mut self, mut messages: MessageStream) {
+        info!("Dispatching session opened");
+
+        while let Some(Ok(message)) = messages.next().await {
+            match message {
+                Message::Text(text) => {
+                    let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(message) => self.handle(message).await,
+                        Err(err) => ServerMessage::error(format!("Invalid message: {}", err)),
+                    };
+                    if send(&mut self.session, &reply).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Ping(bytes) if self.session.pong(&bytes).await.is_err() => break,
+                Message::Close(reason) => {
+                    let _ = self.session.close(reason).await;
+                    info!("Dispatching session closed");
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        info!("Dispatching session ended");
+    }
+
+    async fn handle(&mut self, message: ClientMessage) -> ServerMessage {
+        match message {
+            ClientMessage::Solve { request } => {
+                if let Err(reply) = self.check_limits(&request) {
+                    return reply;
+                }
+                if let Err(errors) = validate_request(&request) {
+                    return ServerMessage::invalid(errors);
+                }
+                let _admitted = match self.admit() {
+                    Ok(admitted) => admitted,
+                    Err(reply) => return reply,
+                };
+
+                let request = *request;
+                let service = self.service.clone();
+                let solving = request.clone();
+                let result = self
+                    .optimize(|progress| async move {
+                        service
+                            .process_request_with_progress(solving, progress)
+                            .await
+                    })
+                    .await;
+                if result.is_ok() {
+                    self.request = Some(request);
+                    self.locked.clear();
+                }
+                reply(result)
+            }
+            ClientMessage::Insert { jobs, shipments } => {
+                let Some(unchanged) = self.unchanged() else {
+                    return no_problem();
+                };
+                self.update(ReoptimizeRequest {
+                    new_jobs: jobs,
+                    new_shipments: shipments,
+                    ..unchanged
+                })
+                .await
+            }
+            ClientMessage::Cancel { jobs, shipments } => {
+                let Some(unchanged) = self.unchanged() else {
+                    return no_problem();
+                };
+                self.update(ReoptimizeRequest {
+                    cancelled_jobs: jobs,
+                    cancelled_shipments: shipments,
+                    ..unchanged
+                })
+                .await
+            }
+            ClientMessage::Lock { routes } => {
+                let Some(unchanged) = self.unchanged() else {
+                    return no_problem();
+                };
+
+                let locking = ReoptimizeRequest {
+                    routes,
+                    ..unchanged
+                };
+                if let Err(errors) = validate_reoptimize_request(&locking) {
+                    return ServerMessage::invalid(errors);
+                }
+                self.locked = locking.routes;
+                ServerMessage::Locked {
+                    routes: self.locked.len(),
+                }
+            }
+        }
+    }
+
+    /// Apply an update to the problem and optimize it again around the locked routes
+    async fn update(&mut self, update: ReoptimizeRequest) -> ServerMessage {
+        if let Err(reply) = self.check_limits(&update.updated_request()) {
+            return reply;
+        }
+        if let Err(errors) = validate_reoptimize_request(&update) {
+            return ServerMessage::invalid(errors);
+        }
+        let _admitted = match self.admit() {
+            Ok(admitted) => admitted,
+            Err(reply) => return reply,
+        };
+
+        let service = self.service.clone();
+        let solving = update.clone();
+        let result = self
+            .optimize(|progress| async move {
+                service.reoptimize_with_progress(&solving, progress).await
+            })
+            .await;
+
+        if result.is_ok() {
+            let request = update.updated_request();
+            for route in &mut self.locked {
+                route.steps.retain(|step| step_exists(&request, &step.step));
+            }
+            self.request = Some(request);
+        }
+        reply(result)
+    }
+
+    /// Update of the current problem that changes nothing yet, once a problem was solved
+    fn unchanged(&self) -> Option<ReoptimizeRequest> {
+        Some(ReoptimizeRequest {
+            request: self.request.clone()?,
+            routes: self.locked.clone(),
+            new_jobs: Vec::new(),
+            new_shipments: Vec::new(),
+            cancelled_jobs: Vec::new(),
+            cancelled_shipments: Vec::new(),
+        })
+    }
+
+    /// Reject a problem exceeding the size limits
+    fn check_limits(&self, request: &RoutingRequest) -> Result<(), ServerMessage> {
+        let Some(limits) = &self.admission.limits else {
+            return Ok(());
+        };
+        limits.check(request).map_err(|err| {
+            info!("Rejected oversized dispatching problem: {}", err);
+            ServerMessage::error(err.to_string())
+        })
+    }
+
+    /// Admit the next solve, returning the optimization slot and drain tracking to hold
+    /// until it has finished
+    fn admit(&self) -> Result<(Option<OptimizationPermit>, Option<RunningSolve>), ServerMessage> {
+        let admission = &self.admission;
+        if admission.shutdown.as_ref().is_some_and(ShutdownState::is_draining) {
+            return Err(ServerMessage::error(
+                "Server is shutting down and no longer accepts optimizations",
+            ));
+        }
+
+        let permit = match &admission.rate_limit {
+            Some((limiter, key)) => limiter.permit(key).map_err(|retry_after| {
+                warn!("Rate limit exceeded for {}", key);
+                ServerMessage::error(format!(
+                    "Rate limit exceeded, retry after {} seconds",
+                    retry_after
+                ))
+            })?,
+            None => None,
+        };
+        Ok((permit, admission.jobs.as_ref().map(JobStore::track)))
+    }
+
+    /// Run an optimization, forwarding its progress to the client while it runs
+    async fn optimize<F, Fut>(&mut self, start: F) -> anyhow::Result<RoutingResponse>
+    where
+        F: FnOnce(ProgressCallback) -> Fut,
+        Fut: Future<Output = anyhow::Result<RoutingResponse>>,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let solving = start(Arc::new(move |progress| {
+            let _ = sender.send(progress);
+        }));
+        tokio::pin!(solving);
+
+        loop {
+            tokio::select! {
+                result = &mut solving => return result,
+                Some(progress) = receiver.recv() => {
+                    // A disconnected client still gets its optimization finished
+                    let _ = send(&mut self.session, &ServerMessage::Progress { progress }).await;
+                }
+            }
+        }
+    }
+}
+
+fn reply(result: anyhow::Result<RoutingResponse>) -> ServerMessage {
+    match result {
+        Ok(response) => ServerMessage::Solution {
+            solution: Box::new(response),
+        },
+        Err(err) => {
+            warn!("Dispatching optimization failed: {}", err);
+            ServerMessage::error(format!("Optimization failed: {}", err))
+        }
+    }
+}
+
+fn no_problem() -> ServerMessage {
+    ServerMessage::error("No problem has been submitted yet")
+}
+
+/// Whether a locked step still refers to a job or shipment of `request`
+fn step_exists(request: &RoutingRequest, step: &RouteStep) -> bool {
+    match step {
+        RouteStep::Job { id, .. } => request.jobs.iter().any(|job| job.id == *id),
+        RouteStep::Pickup { id, .. } | RouteStep::Delivery { id, .. } => {
+            request.shipments.iter().any(|shipment| shipment.id == *id)
+        }
+        _ => true,
+    }
+}
+
+async fn send(session: &mut Session, message: &ServerMessage) -> Result<(), actix_ws::Closed> {
+    let text = serde_json::to_string(message).unwrap_or_default();
+    session.text(text).await
+}
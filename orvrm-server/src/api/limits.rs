@@ -0,0 +1,57 @@
+use crate::utils::error::AppError;
+use orvrm_core::models::RoutingRequest;
+use orvrm_core::services::solver::LocationIndex;
+use serde::Deserialize;
+
+/// Size limits for optimization requests, unlimited when not set
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RequestLimits {
+    /// Maximum number of jobs and shipments together
+    pub max_jobs: Option<usize>,
+
+    /// Maximum number of vehicles
+    pub max_vehicles: Option<usize>,
+
+    /// Maximum number of distinct locations, the side of the travel matrix
+    pub max_matrix_size: Option<usize>,
+}
+
+impl RequestLimits {
+    /// Reject a request that exceeds any of the limits
+    pub fn check(&self, request: &RoutingRequest) -> Result<(), AppError> {
+        let jobs = request.jobs.len() + request.shipments.len();
+        if let Some(max) = self.max_jobs.filter(|max| jobs > *max) {
+            return Err(too_large("jobs and shipments", jobs, max));
+        }
+
+        let vehicles = request.vehicles.len();
+        if let Some(max) = self.max_vehicles.filter(|max| vehicles > *max) {
+            return Err(too_large("vehicles", vehicles, max));
+        }
+
+        if self.max_matrix_size.is_some() {
+            let locations = match &request.matrix {
+                Some(matrix) => matrix.locations.len(),
+                None => LocationIndex::build(&request.vehicles, &request.jobs, &request.shipments)
+                    .locations()
+                    .len(),
+            };
+            self.check_matrix(locations)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reject a travel matrix between more locations than allowed
+    pub fn check_matrix(&self, locations: usize) -> Result<(), AppError> {
+        match self.max_matrix_size.filter(|max| locations > *max) {
+            Some(max) => Err(too_large("matrix locations", locations, max)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn too_large(what: &str, count: usize, max: usize) -> AppError {
+    AppError::RequestTooLarge(format!("{} {} exceed the limit of {}", count, what, max))
+}
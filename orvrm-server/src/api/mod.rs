@@ -0,0 +1,23 @@
+pub mod admin;
+pub mod dispatch;
+pub mod limits;
+pub mod openapi;
+pub mod rate_limit;
+pub mod readiness;
+pub mod request_id;
+pub mod routes;
+pub mod shutdown;
+pub mod tenants;
+pub mod trim;
+pub mod versioning;
+
+pub use admin::AdminConfig;
+pub use limits::RequestLimits;
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use readiness::Readiness;
+pub use request_id::RequestId;
+pub use routes::configure_routes;
+pub use shutdown::ShutdownState;
+pub use tenants::{TenantConfig, Tenants};
+pub use trim::SolutionQuery;
+pub use versioning::{Versioned, API_VERSION};
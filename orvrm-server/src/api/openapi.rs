@@ -0,0 +1,217 @@
+use super::{admin, routes};
+use crate::storage::SolutionSummary;
+use crate::utils::error::FieldError;
+use actix_web::HttpResponse;
+use orvrm_core::services::engine::BackendHealth;
+use serde::Serialize;
+use serde_json::{json, Value};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+
+/// OpenAPI document of the HTTP API
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ORVRM",
+        description = "Vehicle routing optimization on top of OSRM, Valhalla or GraphHopper"
+    ),
+    paths(
+        routes::health_check,
+        routes::osrm_health,
+        routes::liveness,
+        routes::readiness,
+        routes::optimize,
+        routes::optimize_async,
+        routes::optimize_status,
+        routes::optimize_stream,
+        routes::reoptimize,
+        routes::matrix,
+        routes::route,
+        routes::eta,
+        routes::validate,
+        routes::list_solutions,
+        routes::get_solution,
+        routes::insert_jobs,
+        routes::remove_jobs,
+        routes::vehicle_calendar,
+        routes::export_solution,
+        admin::get_settings,
+        admin::patch_settings,
+    ),
+    tags(
+        (name = "optimization", description = "Route optimization, synchronous or as background jobs"),
+        (name = "routing", description = "Travel matrices, timing, arrival estimates and feasibility of fixed routes"),
+        (name = "solutions", description = "Stored optimization results"),
+        (name = "health", description = "Server and routing engine health"),
+        (name = "admin", description = "Runtime tuning, with `Authorization: Bearer <admin.token>`")
+    )
+)]
+pub struct ApiDoc;
+
+/// Body of the server health check
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthStatus {
+    /// Always `ok`
+    pub status: String,
+
+    /// Server version
+    pub version: String,
+}
+
+/// Health of the routing engine backends
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EngineHealth {
+    /// `ok` when every backend is healthy, `degraded` otherwise; readiness also reports
+    /// `starting` before the first probe and `draining` while shutting down
+    pub status: String,
+
+    /// Probe result per backend
+    pub backends: Vec<BackendHealth>,
+}
+
+/// Body of an accepted background optimization
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobAccepted {
+    /// ID to query the job status and stream with
+    pub job_id: Uuid,
+
+    /// Unknown request fields that were ignored
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Listing of stored solutions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SolutionList {
+    /// Stored solutions, oldest first
+    pub solutions: Vec<SolutionSummary>,
+}
+
+/// Body of an error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Error description
+    pub error: String,
+
+    /// Details of the error, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Invalid fields of a rejected request
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// Body of the response to an optimization leaving out more jobs and shipments than its
+/// `max_unassigned` or `max_unassigned_percent` options accept
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnassignedLimitBody {
+    /// Always `Too Many Unassigned`
+    pub error: String,
+
+    /// The limit that was exceeded
+    pub message: String,
+
+    /// Jobs and shipments left unassigned
+    pub unassigned: usize,
+
+    /// Jobs and shipments of the request
+    pub total: usize,
+}
+
+/// Serve the OpenAPI document
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Serve the JSON Schema of routing requests
+pub async fn request_schema() -> HttpResponse {
+    schema_response("RoutingRequest")
+}
+
+/// Serve the JSON Schema of routing responses
+pub async fn response_schema() -> HttpResponse {
+    schema_response("RoutingResponse")
+}
+
+fn schema_response(name: &str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/schema+json")
+        .json(json_schema(name))
+}
+
+/// Standalone JSON Schema of a model of the OpenAPI document
+///
+/// The OpenAPI 3.1 schemas are JSON Schema 2020-12, so the model and the models it refers
+/// to are copied into `$defs` as they are, with their references pointed there.
+pub fn json_schema(name: &str) -> Value {
+    let document = serde_json::to_value(ApiDoc::openapi()).unwrap_or_default();
+    let schemas = &document["components"]["schemas"];
+
+    let mut defs = serde_json::Map::new();
+    let mut pending = vec![name.to_string()];
+    while let Some(name) = pending.pop() {
+        if defs.contains_key(&name) {
+            continue;
+        }
+        let mut schema = schemas[&name].clone();
+        point_refs(&mut schema, &mut pending);
+        defs.insert(name, schema);
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": name,
+        "$ref": format!("#/$defs/{}", name),
+        "$defs": defs,
+    })
+}
+
+/// Point the component references in a schema at `$defs`, collecting the models they name
+fn point_refs(schema: &mut Value, names: &mut Vec<String>) {
+    match schema {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(target) if key == "$ref" => {
+                        if let Some(name) = target.strip_prefix("#/components/schemas/") {
+                            names.push(name.to_string());
+                            *target = format!("#/$defs/{}", name);
+                        }
+                    }
+                    _ => point_refs(value, names),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                point_refs(item, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Serve a Swagger UI page for the OpenAPI document, loading its assets from a CDN
+pub async fn swagger_ui() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>ORVRM API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
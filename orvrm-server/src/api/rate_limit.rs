@@ -0,0 +1,219 @@
+use super::admin::tokens_match;
+use super::tenants::TenantId;
+use super::versioning::unversioned_path;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use log::warn;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Length of a rate limiting window
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Number of tracked clients above which idle clients are forgotten
+const MAX_IDLE_CLIENTS: usize = 10_000;
+
+/// Rate limiting configuration
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Whether rate limiting is enforced
+    pub enabled: bool,
+
+    /// Maximum number of requests per client per minute
+    pub requests_per_minute: u32,
+
+    /// Maximum number of optimizations a client can run at the same time
+    pub max_concurrent_optimizations: u32,
+
+    /// API keys whose clients are limited by key rather than by IP address, next to
+    /// those of the tenants
+    pub api_keys: Vec<String>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            requests_per_minute: 60,
+            max_concurrent_optimizations: 2,
+            api_keys: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClientState {
+    window_start: Instant,
+    requests: u32,
+    running: u32,
+}
+
+/// Per-client request and concurrency limits, keyed by tenant, known API key or IP address
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    clients: Arc<Mutex<HashMap<String, ClientState>>>,
+}
+
+/// Slot for a running optimization, released when dropped
+#[derive(Debug)]
+pub struct OptimizationPermit {
+    limiter: RateLimiter,
+    key: String,
+}
+
+impl Drop for OptimizationPermit {
+    fn drop(&mut self) {
+        if let Some(client) = self.limiter.lock().get_mut(&self.key) {
+            client.running = client.running.saturating_sub(1);
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create a rate limiter with the given configuration
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Count a request from `key`, taking an optimization slot if `optimization` is set
+    ///
+    /// Returns the number of seconds after which the client may retry if a limit is reached.
+    pub fn check(&self, key: &str, optimization: bool) -> Result<Option<OptimizationPermit>, u64> {
+        let now = Instant::now();
+        let mut clients = self.lock();
+
+        if clients.len() > MAX_IDLE_CLIENTS {
+            clients.retain(|_, client| {
+                client.running > 0 || now.duration_since(client.window_start) < WINDOW
+            });
+        }
+
+        let client = clients.entry(key.to_string()).or_insert(ClientState {
+            window_start: now,
+            requests: 0,
+            running: 0,
+        });
+
+        let elapsed = now.duration_since(client.window_start);
+        if elapsed >= WINDOW {
+            client.window_start = now;
+            client.requests = 0;
+        }
+
+        if client.requests >= self.config.requests_per_minute {
+            let remaining = WINDOW.saturating_sub(now.duration_since(client.window_start));
+            return Err(remaining.as_secs().max(1));
+        }
+        if optimization && client.running >= self.config.max_concurrent_optimizations {
+            return Err(1);
+        }
+
+        client.requests += 1;
+        if !optimization {
+            return Ok(None);
+        }
+
+        client.running += 1;
+        Ok(Some(OptimizationPermit {
+            limiter: self.clone(),
+            key: key.to_string(),
+        }))
+    }
+
+    /// Take an optimization slot for `key` outside of the middleware, such as for each
+    /// solve of a WebSocket session, when rate limiting is enforced
+    pub fn permit(&self, key: &str) -> Result<Option<OptimizationPermit>, u64> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        self.check(key, true)
+    }
+
+    /// Client a request is counted for: its tenant, a configured API key or otherwise
+    /// its IP address
+    ///
+    /// Unknown API keys are not trusted, so clients cannot escape their limits by sending
+    /// a new key with every request.
+    pub fn client_key(&self, req: &HttpRequest) -> String {
+        if let Some(TenantId(tenant)) = req.extensions().get::<TenantId>() {
+            return format!("tenant:{}", tenant);
+        }
+        let api_key = req
+            .headers()
+            .get("X-API-Key")
+            .and_then(|key| key.to_str().ok())
+            .filter(|api_key| self.config.api_keys.iter().any(|known| tokens_match(api_key, known)));
+        match api_key {
+            Some(api_key) => format!("key:{}", api_key),
+            None => format!(
+                "ip:{}",
+                req.peer_addr()
+                    .map(|addr| addr.ip().to_string())
+                    .unwrap_or_default()
+            ),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ClientState>> {
+        self.clients.lock().expect("rate limiter lock poisoned")
+    }
+}
+
+/// Middleware enforcing the `RateLimiter` registered as app data, if any
+///
+/// Health checks are never limited. Requests running the solver take an optimization
+/// slot and keep it until the response is sent, unless the handler takes the
+/// `OptimizationPermit` out of the request extensions to hold it for longer. WebSocket
+/// sessions take a slot for each solve with [`RateLimiter::permit`].
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let limiter = req.app_data::<web::Data<RateLimiter>>().cloned();
+    let Some(limiter) = limiter.filter(|limiter| limiter.config.enabled) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let path = unversioned_path(req.path());
+    if path.starts_with("/api/health") {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let key = limiter.client_key(req.request());
+    let optimization = req.method() == Method::POST && runs_solver(&path);
+
+    match limiter.check(&key, optimization) {
+        Ok(permit) => {
+            if let Some(permit) = permit {
+                req.extensions_mut().insert(permit);
+            }
+            next.call(req).await.map(ServiceResponse::map_into_left_body)
+        }
+        Err(retry_after) => {
+            warn!("Rate limit exceeded for {}", key);
+            let response = HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .json(serde_json::json!({
+                    "error": "Too Many Requests",
+                    "message": format!("Rate limit exceeded, retry after {} seconds", retry_after)
+                }));
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+/// Whether a POST to `path` runs the solver
+fn runs_solver(path: &str) -> bool {
+    let solution_update = path.starts_with("/api/solutions/")
+        && (path.ends_with("/insert") || path.ends_with("/remove"));
+    path.starts_with("/api/optimize") || path == "/api/reoptimize" || solution_update
+}
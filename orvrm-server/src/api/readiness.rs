@@ -0,0 +1,69 @@
+use log::{info, warn};
+use orvrm_core::services::engine::BackendHealth;
+use orvrm_core::services::RoutingService;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Routing engine health, probed in the background so readiness checks never wait on it
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    latest: Arc<RwLock<Option<Vec<BackendHealth>>>>,
+}
+
+impl Readiness {
+    /// Create a readiness state that has not probed the engine yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Result of the latest probe, `None` until the first one finished
+    pub fn latest(&self) -> Option<Vec<BackendHealth>> {
+        self.latest
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Record the result of a probe
+    pub fn update(&self, backends: Vec<BackendHealth>) {
+        *self.latest.write().unwrap_or_else(|err| err.into_inner()) = Some(backends);
+    }
+
+    /// Log the backends that became unavailable or recovered since the latest probe
+    fn log_changes(&self, backends: &[BackendHealth]) {
+        let previous = self.latest();
+        for backend in backends {
+            let was_healthy = previous
+                .iter()
+                .flatten()
+                .find(|previous| previous.name == backend.name)
+                .map(|previous| previous.healthy);
+            match (was_healthy, backend.healthy) {
+                (Some(true) | None, false) => warn!(
+                    "Routing backend {} at {} is unavailable: {}",
+                    backend.name,
+                    backend.base_url,
+                    backend.error.as_deref().unwrap_or("no answer")
+                ),
+                (Some(false), true) => info!(
+                    "Routing backend {} at {} is available again",
+                    backend.name, backend.base_url
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    /// Probe the backends of `service` every `interval` for as long as the server runs
+    pub fn spawn_probes(&self, service: RoutingService, interval: Duration) {
+        let readiness = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let backends = service.engine().health().await;
+                readiness.log_changes(&backends);
+                readiness.update(backends);
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+}
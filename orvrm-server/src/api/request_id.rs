@@ -0,0 +1,67 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+use uuid::Uuid;
+
+/// Header carrying the correlation ID of a request, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest correlation ID accepted from a client
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Correlation ID of the request being handled, stored in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Middleware tagging every request with a correlation ID
+///
+/// The ID of a client's `X-Request-Id` header is reused, otherwise a new one is assigned.
+/// All log lines written while handling the request carry the ID, the response returns it
+/// in the same header, and each request ends with an access log line.
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let span = info_span!("request", request_id = %id);
+    let method = req.method().clone();
+    let path = req.path().to_string();
+    let started = Instant::now();
+
+    let mut response = next.call(req).instrument(span.clone()).await?;
+    span.in_scope(|| {
+        info!(
+            status = response.status().as_u16(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "{} {}",
+            method,
+            path
+        )
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(response)
+}
+
+/// Whether a client's correlation ID is short and printable enough to log and echo
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
@@ -0,0 +1,1027 @@
+use actix_web::http::header::ACCEPT;
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, ResponseError, Scope};
+use futures::stream::{self, StreamExt};
+use log::{info, error, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+use super::admin;
+use super::dispatch;
+use super::limits::RequestLimits;
+use super::openapi::{
+    self, EngineHealth, ErrorBody, HealthStatus, JobAccepted, SolutionList, UnassignedLimitBody,
+};
+use super::rate_limit::{OptimizationPermit, RateLimiter};
+use super::readiness::Readiness;
+use super::shutdown::ShutdownState;
+use super::trim::SolutionQuery;
+use super::versioning::Versioned;
+use orvrm_core::models::{
+    CostMatrix, EtaRequest, EtaResponse, FeasibilityReport, InsertRequest, InsertResponse,
+    MatrixRequest, RemoveRequest, ReoptimizeRequest, RouteRequest, RouteResponse, RoutingRequest,
+    RoutingResponse,
+};
+use orvrm_core::services::RoutingService;
+use orvrm_core::utils::{csv, ics, jsprit, msgpack, vroom};
+use orvrm_core::utils::validation::{
+    validate_eta_request, validate_feasibility_request, validate_insert_request,
+    validate_matrix_request, validate_remove_request, validate_reoptimize_request,
+    validate_request, validate_route_request,
+};
+use crate::services::JobStore;
+use crate::services::jobs::JobSnapshot;
+use crate::storage::{SolutionStore, StoredSolution};
+use crate::utils::error::{AppError, UnassignedLimitExceeded};
+
+/// Default number of solutions returned by a listing
+const DEFAULT_LIST_LIMIT: i64 = 100;
+
+/// Health check endpoint
+#[utoipa::path(get, path = "/api/health", tag = "health",
+    responses((status = 200, description = "Server is running", body = HealthStatus)))]
+pub async fn health_check() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION")
+    }))
+}
+
+/// Health of the configured routing engine backends
+#[utoipa::path(get, path = "/api/health/osrm", tag = "health",
+    responses(
+        (status = 200, description = "All backends are healthy", body = EngineHealth),
+        (status = 503, description = "A backend is unavailable", body = EngineHealth)
+    ))]
+pub async fn osrm_health(routing_service: web::Data<RoutingService>) -> impl Responder {
+    let backends = routing_service.engine().health().await;
+    let healthy = backends.iter().all(|backend| backend.healthy);
+    
+    let body = serde_json::json!({
+        "status": if healthy { "ok" } else { "degraded" },
+        "backends": backends
+    });
+    
+    if healthy {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+/// Liveness probe: the server process is up and handling requests
+#[utoipa::path(get, path = "/api/health/live", tag = "health",
+    responses((status = 200, description = "Server is alive", body = HealthStatus)))]
+pub async fn liveness() -> impl Responder {
+    health_check().await
+}
+
+/// Readiness probe: the server accepts optimizations and its routing backends answer
+///
+/// The backends are probed in the background, so this reports the latest probe without
+/// waiting on them. Without background probing the backends are probed right away.
+#[utoipa::path(get, path = "/api/health/ready", tag = "health",
+    responses(
+        (status = 200, description = "Ready for traffic", body = EngineHealth),
+        (status = 503, description = "Starting, shutting down or a backend is unavailable", body = EngineHealth)
+    ))]
+pub async fn readiness(
+    routing_service: web::Data<RoutingService>,
+    readiness: Option<web::Data<Readiness>>,
+    shutdown: Option<web::Data<ShutdownState>>,
+) -> impl Responder {
+    let backends = match readiness {
+        Some(readiness) => readiness.latest(),
+        None => Some(routing_service.engine().health().await),
+    };
+    
+    let status = match &backends {
+        _ if is_draining(shutdown.as_ref().map(|state| state.get_ref())) => "draining",
+        None => "starting",
+        Some(backends) if backends.iter().all(|backend| backend.healthy) => "ok",
+        Some(_) => "degraded",
+    };
+    let body = EngineHealth {
+        status: status.to_string(),
+        backends: backends.unwrap_or_default(),
+    };
+    
+    if status == "ok" {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+/// Process a routing optimization request
+///
+/// With `Accept: text/csv` the routes are returned as CSV, one row per step, and with
+/// `Accept: application/msgpack` the response is MessagePack. The query parameters trim
+/// the returned solution; a stored solution is kept whole.
+#[utoipa::path(post, path = "/api/optimize", tag = "optimization", request_body = RoutingRequest,
+    params(
+        ("vehicles" = Option<String>, Query, description = "Comma-separated IDs of the vehicles whose routes to return"),
+        ("fields" = Option<String>, Query, description = "Comma-separated fields of the response to return, such as `summary,routes.route`"),
+        ("page" = Option<usize>, Query, description = "Page of the routes to return, starting at 1"),
+        ("per_page" = Option<usize>, Query, description = "Number of routes per page, 50 by default"),
+        ("summary_only" = Option<bool>, Query, description = "Only return the summary, unassigned jobs and warnings")
+    ),
+    responses(
+        (status = 200, description = "Optimized routes",
+            content((RoutingResponse = "application/json"), (String = "text/csv"),
+                (RoutingResponse = "application/msgpack"))),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 422, description = "Too many jobs and shipments left unassigned", body = UnassignedLimitBody),
+        (status = 500, description = "Optimization failed", body = ErrorBody)
+    ))]
+pub async fn optimize(
+    http_request: HttpRequest,
+    query: web::Query<SolutionQuery>,
+    request: Versioned<RoutingRequest>,
+    routing_service: web::Data<RoutingService>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    storage: Option<web::Data<SolutionStore>>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    let Versioned { request, warnings, .. } = request;
+    info!("Received optimization request with {} vehicles and {} jobs", 
+        request.vehicles.len(), request.jobs.len());
+    
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return shutting_down();
+    }
+    
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &request) {
+        info!("Rejected oversized optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.process_request(request.clone()).await {
+        Ok(mut response) => {
+            info!("Optimization completed successfully");
+            response.warnings.splice(0..0, warnings);
+            if let Some(storage) = storage {
+                let solution_id = Uuid::new_v4();
+                match storage.save(solution_id, &request, &response).await {
+                    Ok(()) => response.solution_id = Some(solution_id),
+                    Err(err) => warn!("Failed to store solution {}: {:#}", solution_id, err),
+                }
+            }
+            trimmed_response(&http_request, &query, response)
+        },
+        Err(err) => {
+            error!("Optimization failed: {}", err);
+            optimization_failed("Optimization", err)
+        }
+    }
+}
+
+/// Optimize a previous solution again, keeping its locked steps in place
+///
+/// With `Accept: text/csv` the routes are returned as CSV, one row per step, and with
+/// `Accept: application/msgpack` the response is MessagePack.
+#[utoipa::path(post, path = "/api/reoptimize", tag = "optimization", request_body = ReoptimizeRequest,
+    responses(
+        (status = 200, description = "Updated routes",
+            content((RoutingResponse = "application/json"), (String = "text/csv"),
+                (RoutingResponse = "application/msgpack"))),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 422, description = "Too many jobs and shipments left unassigned", body = UnassignedLimitBody),
+        (status = 500, description = "Optimization failed", body = ErrorBody)
+    ))]
+pub async fn reoptimize(
+    http_request: HttpRequest,
+    request: Versioned<ReoptimizeRequest>,
+    routing_service: web::Data<RoutingService>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    storage: Option<web::Data<SolutionStore>>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    let Versioned { request, warnings, .. } = request;
+    info!("Received re-optimization request with {} new jobs and {} cancelled jobs",
+        request.new_jobs.len(), request.cancelled_jobs.len());
+    
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return shutting_down();
+    }
+    
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &request.updated_request()) {
+        info!("Rejected oversized re-optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_reoptimize_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid re-optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.reoptimize(&request).await {
+        Ok(mut response) => {
+            info!("Re-optimization completed successfully");
+            response.warnings.splice(0..0, warnings);
+            if let Some(storage) = storage {
+                let solution_id = Uuid::new_v4();
+                match storage.save(solution_id, &request.updated_request(), &response).await {
+                    Ok(()) => response.solution_id = Some(solution_id),
+                    Err(err) => warn!("Failed to store solution {}: {:#}", solution_id, err),
+                }
+            }
+            solution_response(&http_request, &response)
+        },
+        Err(err) => {
+            error!("Re-optimization failed: {}", err);
+            optimization_failed("Optimization", err)
+        }
+    }
+}
+
+/// Travel durations and distances between a list of locations
+#[utoipa::path(post, path = "/api/matrix", tag = "routing", request_body = MatrixRequest,
+    responses(
+        (status = 200, description = "Travel matrix", body = CostMatrix),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 500, description = "Matrix request failed", body = ErrorBody)
+    ))]
+pub async fn matrix(
+    request: web::Json<MatrixRequest>,
+    routing_service: web::Data<RoutingService>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    if let Some(Err(err)) = limits.map(|limits| limits.check_matrix(request.locations.len())) {
+        info!("Rejected oversized matrix request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_matrix_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid matrix request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.matrix(&request).await {
+        Ok(matrix) => HttpResponse::Ok().json(matrix),
+        Err(err) => {
+            error!("Matrix request failed: {}", err);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Matrix request failed: {}", err)
+            }))
+        }
+    }
+}
+
+/// Time the ordered stops of a single vehicle, with ETAs and waiting times per stop
+#[utoipa::path(post, path = "/api/route", tag = "routing", request_body = RouteRequest,
+    responses(
+        (status = 200, description = "Timed route", body = RouteResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 500, description = "Route request failed", body = ErrorBody)
+    ))]
+pub async fn route(
+    request: web::Json<RouteRequest>,
+    routing_service: web::Data<RoutingService>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &request.routing_request()) {
+        info!("Rejected oversized route request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_route_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid route request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.route(&request).await {
+        Ok(route) => HttpResponse::Ok().json(route),
+        Err(err) => {
+            error!("Route request failed: {}", err);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Route request failed: {}", err)
+            }))
+        }
+    }
+}
+
+/// Estimate the remaining arrivals of a planned route again from the vehicle's current
+/// position, with the delay at each step against the plan
+#[utoipa::path(post, path = "/api/eta", tag = "routing", request_body = EtaRequest,
+    responses(
+        (status = 200, description = "Refreshed arrival estimates", body = EtaResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 500, description = "ETA request failed", body = ErrorBody)
+    ))]
+pub async fn eta(
+    request: web::Json<EtaRequest>,
+    routing_service: web::Data<RoutingService>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    // The steps are timed from the current position
+    if let Some(Err(err)) = limits.map(|limits| limits.check_matrix(request.steps.len() + 1)) {
+        info!("Rejected oversized ETA request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_eta_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid ETA request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.eta(&request).await {
+        Ok(eta) => HttpResponse::Ok().json(eta),
+        Err(err) => {
+            error!("ETA request failed: {}", err);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("ETA request failed: {}", err)
+            }))
+        }
+    }
+}
+
+/// Check manually planned vehicle steps for capacity, time window and shift violations
+///
+/// The steps are timed in the order given, without running the optimizer.
+#[utoipa::path(post, path = "/api/validate", tag = "routing", request_body = RoutingRequest,
+    responses(
+        (status = 200, description = "Feasibility of each route", body = FeasibilityReport),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 500, description = "Feasibility check failed", body = ErrorBody)
+    ))]
+pub async fn validate(
+    request: web::Json<RoutingRequest>,
+    routing_service: web::Data<RoutingService>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &request) {
+        info!("Rejected oversized validate request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_feasibility_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid validate request: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.check_feasibility(request.into_inner()).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => {
+            error!("Feasibility check failed: {}", err);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Feasibility check failed: {}", err)
+            }))
+        }
+    }
+}
+
+/// Open a WebSocket session for interactive dispatching
+///
+/// Each connection gets its own task holding the problem, so clients can solve it, push
+/// job insertions and cancellations and receive updated routes over the same connection.
+pub async fn dispatch_ws(
+    http_request: HttpRequest,
+    body: web::Payload,
+    routing_service: web::Data<RoutingService>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    rate_limiter: Option<web::Data<RateLimiter>>,
+    jobs: Option<web::Data<JobStore>>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return Ok(shutting_down());
+    }
+    
+    // Each solve of the session is limited, refused and drained like an optimization
+    let admission = dispatch::Admission {
+        rate_limit: rate_limiter
+            .map(|limiter| (limiter.get_ref().clone(), limiter.client_key(&http_request))),
+        shutdown: shutdown.map(|state| state.get_ref().clone()),
+        jobs: jobs.map(|jobs| jobs.get_ref().clone()),
+        limits: limits.map(|limits| limits.get_ref().clone()),
+    };
+    let (response, session, messages) = actix_ws::handle(&http_request, body)?;
+    dispatch::spawn(routing_service.get_ref().clone(), session, messages, admission);
+    Ok(response)
+}
+
+/// Start an optimization in the background and return its job ID
+#[utoipa::path(post, path = "/api/optimize/async", tag = "optimization", request_body = RoutingRequest,
+    responses(
+        (status = 202, description = "Optimization job started", body = JobAccepted),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 500, description = "The job could not be queued", body = ErrorBody)
+    ))]
+pub async fn optimize_async(
+    http_request: HttpRequest,
+    request: Versioned<RoutingRequest>,
+    routing_service: web::Data<RoutingService>,
+    jobs: web::Data<JobStore>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    let Versioned { request, warnings, .. } = request;
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return shutting_down();
+    }
+    
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &request) {
+        info!("Rejected oversized optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_request(&request).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid optimization request: {}", err);
+        return err.error_response();
+    }
+    
+    let job_id = match jobs.submit(routing_service.get_ref().clone(), request).await {
+        Ok(job_id) => job_id,
+        Err(err) => {
+            error!("Failed to queue optimization job: {:#}", err);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to queue the optimization: {}", err)
+            }));
+        }
+    };
+    info!("Started optimization job {}", job_id);
+    
+    // Keep the client's optimization slot until the job has finished
+    let permit = http_request.extensions_mut().remove::<OptimizationPermit>();
+    let subscription = if permit.is_some() {
+        jobs.subscribe(job_id).await
+    } else {
+        None
+    };
+    if let (Some(permit), Some((_, Some(mut receiver)))) = (permit, subscription) {
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.is_final() => break,
+                    Err(RecvError::Closed) => break,
+                    _ => continue,
+                }
+            }
+            drop(permit);
+        });
+    }
+    
+    HttpResponse::Accepted().json(JobAccepted { job_id, warnings })
+}
+
+/// Get the status of an optimization job, including its result once completed
+#[utoipa::path(get, path = "/api/optimize/{job_id}", tag = "optimization",
+    params(("job_id" = Uuid, Path, description = "Optimization job ID")),
+    responses(
+        (status = 200, description = "Job status", body = JobSnapshot),
+        (status = 404, description = "Unknown job", body = ErrorBody)
+    ))]
+pub async fn optimize_status(
+    job_id: web::Path<Uuid>,
+    jobs: web::Data<JobStore>,
+) -> impl Responder {
+    match jobs.status(*job_id).await {
+        Some(snapshot) => HttpResponse::Ok().json(snapshot),
+        None => job_not_found(*job_id),
+    }
+}
+
+/// Stream the progress of an optimization job as Server-Sent Events
+///
+/// The stream starts with the latest known event and ends after the job completes or fails.
+#[utoipa::path(get, path = "/api/optimize/{job_id}/stream", tag = "optimization",
+    params(("job_id" = Uuid, Path, description = "Optimization job ID")),
+    responses(
+        (status = 200, description = "`progress`, `completed` and `failed` events", content_type = "text/event-stream", body = String),
+        (status = 404, description = "Unknown job", body = ErrorBody)
+    ))]
+pub async fn optimize_stream(
+    job_id: web::Path<Uuid>,
+    jobs: web::Data<JobStore>,
+) -> impl Responder {
+    let Some((latest, receiver)) = jobs.subscribe(*job_id).await else {
+        return job_not_found(*job_id);
+    };
+    
+    let updates = stream::unfold(receiver, |receiver| async move {
+        let mut receiver = receiver?;
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let receiver = (!event.is_final()).then_some(receiver);
+                    return Some((event, receiver));
+                }
+                // Skip intermediate solutions a slow client missed
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    
+    let events = stream::iter(latest)
+        .chain(updates)
+        .map(|event| Ok::<_, actix_web::Error>(web::Bytes::from(event.to_sse())));
+    
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+/// Get a stored solution with the request it was computed for
+///
+/// With `Accept: text/csv` only the routes are returned, as CSV with one row per step, and
+/// with `Accept: application/msgpack` the solution is MessagePack. The query parameters
+/// trim the returned response; selecting fields or the summary only leaves out the
+/// request, unless `request` is one of the fields.
+#[utoipa::path(get, path = "/api/solutions/{solution_id}", tag = "solutions",
+    params(
+        ("solution_id" = Uuid, Path, description = "Stored solution ID"),
+        ("vehicles" = Option<String>, Query, description = "Comma-separated IDs of the vehicles whose routes to return"),
+        ("fields" = Option<String>, Query, description = "Comma-separated fields of the response to return, such as `summary,routes.route`"),
+        ("page" = Option<usize>, Query, description = "Page of the routes to return, starting at 1"),
+        ("per_page" = Option<usize>, Query, description = "Number of routes per page, 50 by default"),
+        ("summary_only" = Option<bool>, Query, description = "Only return the summary, unassigned jobs and warnings")
+    ),
+    responses(
+        (status = 200, description = "Stored solution",
+            content((StoredSolution = "application/json"), (String = "text/csv"),
+                (StoredSolution = "application/msgpack"))),
+        (status = 404, description = "Unknown solution", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn get_solution(
+    http_request: HttpRequest,
+    solution_id: web::Path<Uuid>,
+    query: web::Query<SolutionQuery>,
+    storage: Option<web::Data<SolutionStore>>,
+) -> impl Responder {
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    match storage.get(*solution_id).await {
+        Ok(Some(mut solution)) => {
+            if let Err(err) = query.trim_routes(&mut solution.response) {
+                return err.error_response();
+            }
+            if wants_csv(&http_request) {
+                return csv_response(&solution.response);
+            }
+            let response = serde_json::to_value(&solution.response).unwrap_or_default();
+            let mut body = serde_json::json!({
+                "id": solution.id,
+                "created_at": solution.created_at,
+                "response": query.select_fields(response),
+            });
+            if query.includes_request() {
+                body["request"] = serde_json::json!(solution.request);
+            }
+            encoded_response(&http_request, &body)
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Solution {} not found", solution_id)
+        })),
+        Err(err) => {
+            error!("Failed to load solution {}: {:#}", solution_id, err);
+            AppError::StorageError(err.to_string()).error_response()
+        }
+    }
+}
+
+/// Insert new jobs and shipments into the routes of a stored solution
+///
+/// Each is placed at its cheapest feasible position across the routes, which otherwise
+/// keep their order. The updated solution is stored under a new ID.
+#[utoipa::path(post, path = "/api/solutions/{solution_id}/insert", tag = "solutions",
+    request_body = InsertRequest,
+    params(("solution_id" = Uuid, Path, description = "Stored solution ID")),
+    responses(
+        (status = 200, description = "Updated routes with the insertion cost", body = InsertResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown solution", body = ErrorBody),
+        (status = 413, description = "Request exceeds the size limits", body = ErrorBody),
+        (status = 422, description = "Too many jobs and shipments left unassigned", body = UnassignedLimitBody),
+        (status = 500, description = "Insertion failed", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn insert_jobs(
+    solution_id: web::Path<Uuid>,
+    insert: web::Json<InsertRequest>,
+    routing_service: web::Data<RoutingService>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    storage: Option<web::Data<SolutionStore>>,
+    limits: Option<web::Data<RequestLimits>>,
+) -> impl Responder {
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return shutting_down();
+    }
+    
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    let solution = match stored_solution(&storage, *solution_id).await {
+        Ok(solution) => solution,
+        Err(response) => return response,
+    };
+    
+    let updated = insert.updated_request(&solution.request, &solution.response.routes);
+    if let Err(err) = check_limits(limits.as_ref().map(|limits| limits.get_ref()), &updated) {
+        info!("Rejected oversized insertion: {}", err);
+        return err.error_response();
+    }
+    
+    if let Err(err) = validate_insert_request(&solution.request, &insert).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid insertion: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.insert(&solution.request, &solution.response, &insert).await {
+        Ok(mut response) => {
+            info!("Inserted {} jobs and {} shipments into solution {}",
+                insert.jobs.len(), insert.shipments.len(), solution_id);
+            let inserted_id = Uuid::new_v4();
+            match storage.save(inserted_id, &updated, &response.solution).await {
+                Ok(()) => response.solution.solution_id = Some(inserted_id),
+                Err(err) => warn!("Failed to store solution {}: {:#}", inserted_id, err),
+            }
+            HttpResponse::Ok().json(response)
+        },
+        Err(err) => {
+            error!("Insertion into solution {} failed: {}", solution_id, err);
+            optimization_failed("Insertion", err)
+        }
+    }
+}
+
+/// Remove jobs and shipments from the routes of a stored solution
+///
+/// The routes are timed again with their remaining steps in order. With `reoptimize` the
+/// routes the work was removed from are also improved among themselves, within the
+/// request's `max_time`. The updated solution is stored under a new ID.
+#[utoipa::path(post, path = "/api/solutions/{solution_id}/remove", tag = "solutions",
+    request_body = RemoveRequest,
+    params(("solution_id" = Uuid, Path, description = "Stored solution ID")),
+    responses(
+        (status = 200, description = "Updated routes", body = RoutingResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Unknown solution", body = ErrorBody),
+        (status = 422, description = "Too many jobs and shipments left unassigned", body = UnassignedLimitBody),
+        (status = 500, description = "Removal failed", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn remove_jobs(
+    solution_id: web::Path<Uuid>,
+    remove: web::Json<RemoveRequest>,
+    routing_service: web::Data<RoutingService>,
+    shutdown: Option<web::Data<ShutdownState>>,
+    storage: Option<web::Data<SolutionStore>>,
+) -> impl Responder {
+    if is_draining(shutdown.as_ref().map(|state| state.get_ref())) {
+        return shutting_down();
+    }
+    
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    let solution = match stored_solution(&storage, *solution_id).await {
+        Ok(solution) => solution,
+        Err(response) => return response,
+    };
+    
+    if let Err(err) = validate_remove_request(&solution.request, &remove).map_err(AppError::InvalidFields) {
+        info!("Rejected invalid removal: {}", err);
+        return err.error_response();
+    }
+    
+    match routing_service.remove(&solution.request, &solution.response, &remove).await {
+        Ok(mut response) => {
+            info!("Removed {} jobs and {} shipments from solution {}",
+                remove.jobs.len(), remove.shipments.len(), solution_id);
+            let updated = remove.updated_request(&solution.request, &solution.response.routes);
+            let repaired_id = Uuid::new_v4();
+            match storage.save(repaired_id, &updated, &response).await {
+                Ok(()) => response.solution_id = Some(repaired_id),
+                Err(err) => warn!("Failed to store solution {}: {:#}", repaired_id, err),
+            }
+            HttpResponse::Ok().json(response)
+        },
+        Err(err) => {
+            error!("Removal from solution {} failed: {}", solution_id, err);
+            optimization_failed("Removal", err)
+        }
+    }
+}
+
+/// Calendar of a vehicle's route in a stored solution, for drivers to subscribe to
+///
+/// The iCalendar file has one event per step, from arrival to departure, with its location
+/// and service time. Step times are read as Unix timestamps.
+#[utoipa::path(get, path = "/api/solutions/{solution_id}/vehicles/{vehicle_id}.ics", tag = "solutions",
+    params(
+        ("solution_id" = Uuid, Path, description = "Stored solution ID"),
+        ("vehicle_id" = u64, Path, description = "Vehicle ID")
+    ),
+    responses(
+        (status = 200, description = "Route of the vehicle as iCalendar", content_type = "text/calendar"),
+        (status = 404, description = "Unknown solution, or the vehicle has no route in it", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn vehicle_calendar(
+    path: web::Path<(Uuid, u64)>,
+    storage: Option<web::Data<SolutionStore>>,
+) -> impl Responder {
+    let (solution_id, vehicle_id) = path.into_inner();
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    let solution = match storage.get(solution_id).await {
+        Ok(Some(solution)) => solution,
+        Ok(None) => return HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Solution {} not found", solution_id)
+        })),
+        Err(err) => {
+            error!("Failed to load solution {}: {:#}", solution_id, err);
+            return AppError::StorageError(err.to_string()).error_response();
+        }
+    };
+    
+    match solution.response.routes.iter().find(|route| route.vehicle_id == vehicle_id) {
+        Some(route) => HttpResponse::Ok()
+            .content_type("text/calendar; charset=utf-8")
+            .body(ics::export(route, &solution_id.to_string(), solution.created_at)),
+        None => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Vehicle {} has no route in solution {}", vehicle_id, solution_id)
+        })),
+    }
+}
+
+/// Query parameters for listing stored solutions
+#[derive(Debug, Deserialize)]
+pub struct SolutionListQuery {
+    /// Only list solutions stored at or after this time, as RFC 3339 or Unix seconds
+    pub since: Option<String>,
+    
+    /// Maximum number of solutions to return
+    pub limit: Option<i64>,
+}
+
+/// List stored solutions, oldest first
+#[utoipa::path(get, path = "/api/solutions", tag = "solutions",
+    params(
+        ("since" = Option<String>, Query, description = "Only list solutions stored at or after this time, as RFC 3339 or Unix seconds"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of solutions to return")
+    ),
+    responses(
+        (status = 200, description = "Stored solutions", body = SolutionList),
+        (status = 400, description = "Invalid query", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn list_solutions(
+    query: web::Query<SolutionListQuery>,
+    storage: Option<web::Data<SolutionStore>>,
+) -> impl Responder {
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    let since = match query.since.as_deref().map(parse_timestamp).transpose() {
+        Ok(since) => since.unwrap_or(0),
+        Err(err) => return err.error_response(),
+    };
+    
+    match storage.list(since, query.limit.unwrap_or(DEFAULT_LIST_LIMIT)).await {
+        Ok(solutions) => HttpResponse::Ok().json(serde_json::json!({
+            "solutions": solutions
+        })),
+        Err(err) => {
+            error!("Failed to list solutions: {:#}", err);
+            AppError::StorageError(err.to_string()).error_response()
+        }
+    }
+}
+
+/// Query parameters for exporting a stored solution
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Format to export to, `vroom` or `jsprit`
+    pub format: Option<String>,
+}
+
+/// Export a stored solution with its problem in the format of another routing tool
+///
+/// `vroom` gives the VROOM input and output as JSON, `jsprit` a jsprit problem XML file
+/// with the solution included.
+#[utoipa::path(get, path = "/api/export/{solution_id}", tag = "solutions",
+    params(
+        ("solution_id" = Uuid, Path, description = "Stored solution ID"),
+        ("format" = String, Query, description = "Format to export to, `vroom` or `jsprit`")
+    ),
+    responses(
+        (status = 200, description = "Problem and solution in the requested format"),
+        (status = 400, description = "Unknown format", body = ErrorBody),
+        (status = 404, description = "Unknown solution", body = ErrorBody),
+        (status = 501, description = "Solution storage is not configured", body = ErrorBody)
+    ))]
+pub async fn export_solution(
+    solution_id: web::Path<Uuid>,
+    query: web::Query<ExportQuery>,
+    storage: Option<web::Data<SolutionStore>>,
+) -> impl Responder {
+    let format = query.format.as_deref().unwrap_or_default();
+    if !["vroom", "jsprit"].contains(&format) {
+        return AppError::ValidationError(format!(
+            "Unknown export format '{}', expected vroom or jsprit", format
+        )).error_response();
+    }
+    
+    let Some(storage) = storage else {
+        return storage_disabled();
+    };
+    
+    match storage.get(*solution_id).await {
+        Ok(Some(solution)) if format == "vroom" => HttpResponse::Ok()
+            .json(vroom::export(&solution.request, &solution.response)),
+        Ok(Some(solution)) => HttpResponse::Ok()
+            .content_type("application/xml")
+            .body(jsprit::export(&solution.request, &solution.response)),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Solution {} not found", solution_id)
+        })),
+        Err(err) => {
+            error!("Failed to load solution {}: {:#}", solution_id, err);
+            AppError::StorageError(err.to_string()).error_response()
+        }
+    }
+}
+
+/// Load a stored solution, or the response to send when it cannot be loaded
+async fn stored_solution(storage: &SolutionStore, solution_id: Uuid) -> Result<StoredSolution, HttpResponse> {
+    match storage.get(solution_id).await {
+        Ok(Some(solution)) => Ok(solution),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("Solution {} not found", solution_id)
+        }))),
+        Err(err) => {
+            error!("Failed to load solution {}: {:#}", solution_id, err);
+            Err(AppError::StorageError(err.to_string()).error_response())
+        }
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a number of Unix seconds
+fn parse_timestamp(value: &str) -> Result<i64, AppError> {
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Ok(seconds);
+    }
+    
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp())
+        .map_err(|_| AppError::ValidationError(format!(
+            "Invalid since value '{}', expected an RFC 3339 timestamp or Unix seconds", value
+        )))
+}
+
+/// Respond with a solution as JSON, or as CSV or MessagePack when the client asks for it
+fn solution_response(http_request: &HttpRequest, response: &RoutingResponse) -> HttpResponse {
+    if wants_csv(http_request) {
+        csv_response(response)
+    } else {
+        encoded_response(http_request, response)
+    }
+}
+
+/// Respond with the trimmed parts of a solution a client asked for, as JSON, CSV or
+/// MessagePack
+fn trimmed_response(
+    http_request: &HttpRequest,
+    query: &SolutionQuery,
+    mut response: RoutingResponse,
+) -> HttpResponse {
+    if let Err(err) = query.trim_routes(&mut response) {
+        return err.error_response();
+    }
+    if wants_csv(http_request) {
+        return csv_response(&response);
+    }
+    let response = serde_json::to_value(&response).unwrap_or_default();
+    encoded_response(http_request, &query.select_fields(response))
+}
+
+/// Respond with a body as MessagePack when the client accepts it, as JSON otherwise
+fn encoded_response(http_request: &HttpRequest, body: &impl Serialize) -> HttpResponse {
+    if !accepts(http_request, &["application/msgpack", "application/x-msgpack"]) {
+        return HttpResponse::Ok().json(body);
+    }
+    match msgpack::to_vec(body) {
+        Ok(bytes) => HttpResponse::Ok().content_type("application/msgpack").body(bytes),
+        Err(err) => AppError::InternalError(format!("Failed to encode the response: {}", err))
+            .error_response(),
+    }
+}
+
+fn csv_response(response: &RoutingResponse) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .body(csv::export(response))
+}
+
+fn wants_csv(http_request: &HttpRequest) -> bool {
+    accepts(http_request, &["text/csv"])
+}
+
+fn accepts(http_request: &HttpRequest, media_types: &[&str]) -> bool {
+    http_request
+        .headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| media_types.iter().any(|media_type| accept.contains(media_type)))
+}
+
+fn storage_disabled() -> HttpResponse {
+    HttpResponse::NotImplemented().json(serde_json::json!({
+        "error": "Solution storage is not configured"
+    }))
+}
+
+fn check_limits(limits: Option<&RequestLimits>, request: &RoutingRequest) -> Result<(), AppError> {
+    limits.map_or(Ok(()), |limits| limits.check(request))
+}
+
+/// Response to a failed optimization, with solutions leaving out more jobs and shipments
+/// than the request accepts as the client's to resolve
+fn optimization_failed(action: &str, err: anyhow::Error) -> HttpResponse {
+    match err.downcast::<UnassignedLimitExceeded>() {
+        Ok(exceeded) => AppError::TooManyUnassigned(exceeded).error_response(),
+        Err(err) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("{} failed: {}", action, err)
+        })),
+    }
+}
+
+fn is_draining(shutdown: Option<&ShutdownState>) -> bool {
+    shutdown.is_some_and(ShutdownState::is_draining)
+}
+
+fn shutting_down() -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .insert_header(("Connection", "close"))
+        .json(serde_json::json!({
+            "error": "Server is shutting down and no longer accepts optimizations"
+        }))
+}
+
+fn job_not_found(job_id: Uuid) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": format!("Optimization job {} not found", job_id)
+    }))
+}
+
+/// Configure API routes
+///
+/// The API is served under `/api/v1` and, for clients from before versioning, under `/api`.
+pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope("/api/v1")).service(api_scope("/api"));
+}
+
+fn api_scope(path: &str) -> Scope {
+    web::scope(path)
+        .route("/openapi.json", web::get().to(openapi::openapi_json))
+        .route("/schema/request", web::get().to(openapi::request_schema))
+        .route("/schema/response", web::get().to(openapi::response_schema))
+        .route("/health", web::get().to(health_check))
+        .route("/health/osrm", web::get().to(osrm_health))
+        .route("/health/live", web::get().to(liveness))
+        .route("/health/ready", web::get().to(readiness))
+        .route("/optimize", web::post().to(optimize))
+        .route("/optimize/async", web::post().to(optimize_async))
+        .route("/optimize/{job_id}", web::get().to(optimize_status))
+        .route("/optimize/{job_id}/stream", web::get().to(optimize_stream))
+        .route("/reoptimize", web::post().to(reoptimize))
+        .route("/ws", web::get().to(dispatch_ws))
+        .route("/matrix", web::post().to(matrix))
+        .route("/route", web::post().to(route))
+        .route("/eta", web::post().to(eta))
+        .route("/validate", web::post().to(validate))
+        .route("/solutions", web::get().to(list_solutions))
+        .route("/solutions/{solution_id}", web::get().to(get_solution))
+        .route("/solutions/{solution_id}/insert", web::post().to(insert_jobs))
+        .route("/solutions/{solution_id}/remove", web::post().to(remove_jobs))
+        .route(
+            "/solutions/{solution_id}/vehicles/{vehicle_id}.ics",
+            web::get().to(vehicle_calendar),
+        )
+        .route("/export/{solution_id}", web::get().to(export_solution))
+        .route("/admin/settings", web::get().to(admin::get_settings))
+        .route("/admin/settings", web::patch().to(admin::patch_settings))
+} 
\ No newline at end of file
@@ -0,0 +1,188 @@
+use super::admin::tokens_match;
+use super::limits::RequestLimits;
+use super::rate_limit::{RateLimitConfig, RateLimiter};
+use super::versioning::unversioned_path;
+use crate::services::JobStore;
+use crate::storage::SolutionStore;
+use crate::utils::error::AppError;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Extensions, ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, ResponseError};
+use log::warn;
+use orvrm_core::services::RoutingService;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Customer sharing the deployment, identified by the API key of its requests
+///
+/// Settings left out fall back to those of the deployment.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+    /// API key the tenant sends in the `X-API-Key` header
+    pub api_key: String,
+
+    /// OSRM server routing the tenant's requests, for all profiles
+    #[serde(default)]
+    pub osrm_url: Option<String>,
+
+    /// Routing profile of the tenant's requests that do not set one
+    #[serde(default)]
+    pub default_profile: Option<String>,
+
+    /// Rate limits of the tenant's clients
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// Size limits of the tenant's optimization requests
+    #[serde(default)]
+    pub limits: Option<RequestLimits>,
+}
+
+/// Name of the tenant a request was verified for, in the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantId(pub String);
+
+/// Services a tenant's requests are handled with
+#[derive(Clone)]
+struct Tenant {
+    name: String,
+    api_key: String,
+    routing_service: web::Data<RoutingService>,
+    rate_limiter: web::Data<RateLimiter>,
+    limits: web::Data<RequestLimits>,
+    storage: Option<web::Data<SolutionStore>>,
+    jobs: web::Data<JobStore>,
+}
+
+/// Tenants of the deployment, empty when it serves a single customer
+#[derive(Clone, Default)]
+pub struct Tenants {
+    tenants: Arc<Vec<Tenant>>,
+}
+
+impl Tenants {
+    /// Give each configured tenant its own routing, limits, solution storage and view of
+    /// the jobs, based on the services of the deployment
+    pub fn new(
+        configs: &HashMap<String, TenantConfig>,
+        routing_service: &RoutingService,
+        rate_limiter: &RateLimiter,
+        limits: &RequestLimits,
+        storage: Option<&SolutionStore>,
+        jobs: &JobStore,
+    ) -> Self {
+        let tenants = configs
+            .iter()
+            .map(|(name, config)| {
+                let routing_service = match (&config.osrm_url, &config.default_profile) {
+                    (None, None) => routing_service.clone(),
+                    (osrm_url, default_profile) => {
+                        let mut osrm = routing_service.osrm_config().clone();
+                        if let Some(osrm_url) = osrm_url {
+                            osrm.base_url = osrm_url.clone();
+                            osrm.backends = HashMap::new();
+                        }
+                        if let Some(default_profile) = default_profile {
+                            osrm.default_profile = default_profile.clone();
+                        }
+                        routing_service.with_osrm(osrm)
+                    }
+                };
+                let rate_limiter = match &config.rate_limit {
+                    Some(rate_limit) => RateLimiter::new(rate_limit.clone()),
+                    None => rate_limiter.clone(),
+                };
+                let storage = storage.map(|storage| storage.for_tenant(name));
+                let jobs = jobs.clone().for_tenant(name);
+                let jobs = match &storage {
+                    Some(storage) => jobs.with_storage(storage.clone()),
+                    None => jobs,
+                };
+
+                Tenant {
+                    name: name.clone(),
+                    api_key: config.api_key.clone(),
+                    routing_service: web::Data::new(routing_service),
+                    rate_limiter: web::Data::new(rate_limiter),
+                    limits: web::Data::new(config.limits.clone().unwrap_or_else(|| limits.clone())),
+                    storage: storage.map(web::Data::new),
+                    jobs: web::Data::new(jobs),
+                }
+            })
+            .collect();
+
+        Self {
+            tenants: Arc::new(tenants),
+        }
+    }
+
+    /// Whether any tenant is configured
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    /// Routing service and job store the queued jobs of the tenant `name` run with
+    pub fn job_services(&self, name: &str) -> Option<(RoutingService, JobStore)> {
+        let tenant = self.tenants.iter().find(|tenant| tenant.name == name)?;
+        Some((
+            tenant.routing_service.get_ref().clone(),
+            tenant.jobs.get_ref().clone(),
+        ))
+    }
+
+    fn find(&self, api_key: &str) -> Option<&Tenant> {
+        // Every key is compared so the time taken does not reveal which keys exist
+        self.tenants.iter().fold(None, |found, tenant| {
+            if tokens_match(api_key, &tenant.api_key) {
+                Some(tenant)
+            } else {
+                found
+            }
+        })
+    }
+}
+
+/// Middleware handling each request with the services of its tenant, when the
+/// [`Tenants`] registered as app data are not empty
+///
+/// Requests without the API key of a tenant are rejected, except for the health checks,
+/// the API description and the admin endpoints, which have a token of their own. The
+/// tenant's services take the place of the deployment's app data, so it runs before the
+/// rate limiting.
+pub async fn tenant(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let tenants = req.app_data::<web::Data<Tenants>>().cloned();
+    let Some(tenants) = tenants.filter(|tenants| !tenants.is_empty()) else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    let path = unversioned_path(req.path());
+    let public = ["/api/health", "/api/openapi.json", "/api/schema/", "/api/docs", "/api/admin/"];
+    if public.iter().any(|prefix| path.starts_with(prefix)) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let api_key = req.headers().get("X-API-Key").and_then(|key| key.to_str().ok());
+    let Some(tenant) = api_key.and_then(|api_key| tenants.find(api_key)).cloned() else {
+        warn!("Rejected a request without a valid API key for {}", path);
+        let response = AppError::Unauthorized("A valid API key is required".to_string())
+            .error_response();
+        return Ok(req.into_response(response).map_into_right_body());
+    };
+
+    let mut services = Extensions::new();
+    services.insert(tenant.routing_service);
+    services.insert(tenant.rate_limiter);
+    services.insert(tenant.limits);
+    services.insert(tenant.jobs);
+    if let Some(storage) = tenant.storage {
+        services.insert(storage);
+    }
+    req.add_data_container(Rc::new(services));
+    req.extensions_mut().insert(TenantId(tenant.name));
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
@@ -0,0 +1,144 @@
+use crate::utils::error::AppError;
+use orvrm_core::models::{RoutePage, RoutingResponse};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Number of routes per page when paging without `per_page`
+const DEFAULT_PER_PAGE: usize = 50;
+
+/// Fields left out of a solution that only needs its summary
+const DETAIL_FIELDS: [&str; 3] = ["routes", "geometry", "alternatives"];
+
+/// Query parameters trimming a returned solution to the parts a client needs
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SolutionQuery {
+    /// Comma-separated IDs of the vehicles whose routes to return
+    pub vehicles: Option<String>,
+
+    /// Comma-separated fields of the response to return, such as `summary,routes.route`
+    pub fields: Option<String>,
+
+    /// Page of the routes to return, starting at 1
+    pub page: Option<usize>,
+
+    /// Number of routes per page
+    pub per_page: Option<usize>,
+
+    /// Only return the summary, unassigned jobs and warnings, without the routes
+    #[serde(default)]
+    pub summary_only: bool,
+}
+
+impl SolutionQuery {
+    /// Keep the routes of the requested vehicles and page
+    ///
+    /// The summary and alternatives still describe the whole solution.
+    pub fn trim_routes(&self, response: &mut RoutingResponse) -> Result<(), AppError> {
+        if let Some(vehicles) = &self.vehicles {
+            let vehicle_ids = vehicles
+                .split(',')
+                .map(|id| id.trim().parse::<u64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| AppError::ValidationError(format!(
+                    "Invalid vehicles value '{}', expected comma-separated vehicle IDs", vehicles
+                )))?;
+            response.routes.retain(|route| vehicle_ids.contains(&route.vehicle_id));
+        }
+
+        if self.page.is_some() || self.per_page.is_some() {
+            let page = self.page.unwrap_or(1);
+            let per_page = self.per_page.unwrap_or(DEFAULT_PER_PAGE);
+            if page == 0 || per_page == 0 {
+                return Err(AppError::ValidationError(
+                    "page and per_page start at 1".to_string(),
+                ));
+            }
+            let total_routes = response.routes.len();
+            response.routes = response
+                .routes
+                .drain(..)
+                .skip((page - 1).saturating_mul(per_page))
+                .take(per_page)
+                .collect();
+            response.page = Some(RoutePage {
+                page,
+                per_page,
+                total_routes,
+                total_pages: total_routes.div_ceil(per_page),
+            });
+        }
+
+        // The geometries are those of the routes that are left
+        if response.geometry.is_some() {
+            response.geometry = Some(
+                response
+                    .routes
+                    .iter()
+                    .filter_map(|route| route.polyline.clone())
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the stored request belongs in a returned solution, which it only does
+    /// when no fields are selected or `request` is one of them
+    pub fn includes_request(&self) -> bool {
+        match &self.fields {
+            Some(fields) => fields.split(',').any(|field| field.trim() == "request"),
+            None => !self.summary_only,
+        }
+    }
+
+    /// Keep the selected fields of a response serialized as JSON
+    ///
+    /// Fields of routes and other lists apply to each of their items. The page is kept
+    /// whenever the routes were paged.
+    pub fn select_fields(&self, mut response: Value) -> Value {
+        if self.summary_only {
+            if let Value::Object(fields) = &mut response {
+                for field in DETAIL_FIELDS {
+                    fields.remove(field);
+                }
+            }
+        }
+
+        let Some(fields) = &self.fields else {
+            return response;
+        };
+        let mut paths: Vec<Vec<&str>> = fields
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .map(|field| field.split('.').collect())
+            .collect();
+        paths.push(vec!["page"]);
+        select(&response, &paths)
+    }
+}
+
+/// Copy the parts of a JSON value at the given paths
+fn select(value: &Value, paths: &[Vec<&str>]) -> Value {
+    if paths.iter().any(Vec::is_empty) {
+        return value.clone();
+    }
+
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(|item| select(item, paths)).collect()),
+        Value::Object(fields) => {
+            let mut selected = Map::new();
+            for (name, field) in fields {
+                let rest: Vec<Vec<&str>> = paths
+                    .iter()
+                    .filter(|path| path[0] == name)
+                    .map(|path| path[1..].to_vec())
+                    .collect();
+                if !rest.is_empty() {
+                    selected.insert(name.clone(), select(field, &rest));
+                }
+            }
+            Value::Object(selected)
+        }
+        _ => value.clone(),
+    }
+}
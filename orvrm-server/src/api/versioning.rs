@@ -0,0 +1,112 @@
+use crate::utils::error::AppError;
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_ignored::Path;
+
+/// Version of the request and response models served by this server
+pub const API_VERSION: u32 = 1;
+
+/// Request body that is either the request itself or wrapped in a versioned envelope
+///
+/// An envelope looks like `{"version": 1, "request": {...}}`. Fields the server does not
+/// know are ignored rather than rejected, so clients written against a newer model keep
+/// working, and each one is reported in `warnings`.
+#[derive(Debug)]
+pub struct Versioned<T> {
+    /// The parsed request
+    pub request: T,
+
+    /// Version the client asked for, `None` for a bare request
+    pub version: Option<u32>,
+
+    /// Unknown fields that were ignored
+    pub warnings: Vec<String>,
+}
+
+/// Versioned wrapper around a request body
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    version: u32,
+    request: serde_json::Value,
+}
+
+impl<T: DeserializeOwned> Versioned<T> {
+    /// Parse a request body, unwrapping the envelope when it has one
+    pub fn parse(body: serde_json::Value) -> Result<Self, AppError> {
+        let mut warnings = Vec::new();
+
+        let enveloped = body
+            .as_object()
+            .is_some_and(|body| body.contains_key("version"));
+        let (body, version, prefix) = if enveloped {
+            let envelope: Envelope = deserialize(body, "", &mut warnings)?;
+            if envelope.version != API_VERSION {
+                return Err(AppError::ValidationError(format!(
+                    "Unsupported API version {}, this server supports version {}",
+                    envelope.version, API_VERSION
+                )));
+            }
+            (envelope.request, Some(envelope.version), "request")
+        } else {
+            (body, None, "")
+        };
+
+        let request = deserialize(body, prefix, &mut warnings)?;
+        Ok(Versioned {
+            request,
+            version,
+            warnings,
+        })
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Versioned<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let body = web::Json::<serde_json::Value>::from_request(request, payload);
+        Box::pin(async move { Ok(Versioned::parse(body.await?.into_inner())?) })
+    }
+}
+
+/// Path of a request with the version left out, so `/api/v1/optimize` becomes `/api/optimize`
+pub fn unversioned_path(path: &str) -> String {
+    match path.strip_prefix("/api/v1") {
+        Some(rest) => format!("/api{}", rest),
+        None => path.to_string(),
+    }
+}
+
+/// Deserialize `value`, adding a warning for every field that was ignored
+fn deserialize<T: DeserializeOwned>(
+    value: serde_json::Value,
+    prefix: &str,
+    warnings: &mut Vec<String>,
+) -> Result<T, AppError> {
+    serde_ignored::deserialize(value, |path| {
+        warnings.push(format!(
+            "Unknown field {} was ignored",
+            field_path(prefix, &path)
+        ))
+    })
+    .map_err(|err| AppError::ValidationError(format!("Invalid request body: {}", err)))
+}
+
+/// Path of a field in the notation of validation errors, such as `vehicles[0].breaks`
+fn field_path(prefix: &str, path: &Path) -> String {
+    match path {
+        Path::Root => prefix.to_string(),
+        Path::Seq { parent, index } => format!("{}[{}]", field_path(prefix, parent), index),
+        Path::Map { parent, key } => match field_path(prefix, parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(prefix, parent),
+    }
+}
@@ -0,0 +1,75 @@
+use crate::utils::error::AppError;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use log::info;
+use orvrm_core::models::RoutingRequest;
+use orvrm_core::services::{RoutingConfig, RoutingService};
+use orvrm_core::utils::validation::validate_request;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Open Source Rust Vehicle Route Machine
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (the default)
+    Serve,
+
+    /// Optimize a routing request read from a file, without starting the HTTP server
+    Solve {
+        /// JSON file containing the routing request
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// File to write the JSON response to, standard output if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Optimize the request in `input` and write the response to `output`
+///
+/// Runs the same validation and optimization as `POST /api/optimize`.
+pub async fn solve(config: RoutingConfig, input: &Path, output: Option<&Path>) -> Result<()> {
+    let json =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    let request: RoutingRequest = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse routing request in {}", input.display()))?;
+
+    if let Err(errors) = validate_request(&request) {
+        let details: Vec<String> = errors
+            .iter()
+            .map(|error| format!("  {}: {}", error.field, error.message))
+            .collect();
+        bail!(
+            "{}\n{}",
+            AppError::InvalidFields(errors),
+            details.join("\n")
+        );
+    }
+
+    info!(
+        "Optimizing {} vehicles and {} jobs from {}",
+        request.vehicles.len(),
+        request.jobs.len(),
+        input.display()
+    );
+    let response = RoutingService::new(config).process_request(request).await?;
+    let json = serde_json::to_string_pretty(&response)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+            info!("Wrote solution to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
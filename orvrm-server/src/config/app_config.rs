@@ -0,0 +1,246 @@
+use serde::Deserialize;
+use config::{Config, ConfigError, File, Environment};
+use std::env;
+use std::collections::HashMap;
+use orvrm_core::services::{
+    EngineKind, GraphHopperConfig, OsrmConfig, RoutingConfig, ValhallaConfig,
+};
+use orvrm_core::services::osrm::{OsrmBackendConfig, OsrmPoolConfig};
+use orvrm_core::services::solver::TrafficPeriod;
+use crate::api::{AdminConfig, RateLimitConfig, RequestLimits, TenantConfig};
+use crate::services::{JobQueueConfig, NatsConfig, WebhookConfig};
+use crate::storage::StorageConfig;
+use crate::utils::logging::LogFormat;
+use super::TlsConfig;
+
+/// Application configuration
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Server configuration
+    pub server: ServerConfig,
+    
+    /// OSRM configuration
+    pub osrm: OsrmConfig,
+    
+    /// Routing configuration
+    pub routing: RoutingConfig,
+    
+    /// Rate limiting configuration
+    pub rate_limit: RateLimitConfig,
+    
+    /// Optimization request size limits
+    pub limits: RequestLimits,
+    
+    /// Solution storage configuration
+    pub storage: StorageConfig,
+    
+    /// Admin endpoint configuration
+    pub admin: AdminConfig,
+    
+    /// Callback delivery configuration
+    pub webhooks: WebhookConfig,
+    
+    /// Shared job queue configuration
+    pub jobs: JobQueueConfig,
+    
+    /// Intake of requests from NATS
+    pub nats: NatsConfig,
+    
+    /// Tenants sharing the deployment, by name
+    pub tenants: HashMap<String, TenantConfig>,
+}
+
+/// Server configuration
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServerConfig {
+    /// Host to bind to
+    pub host: String,
+    
+    /// Port to listen on
+    pub port: u16,
+    
+    /// Number of worker threads
+    pub workers: usize,
+    
+    /// Seconds to wait for running optimizations to finish when shutting down
+    #[serde(default = "default_shutdown_grace_seconds")]
+    pub shutdown_grace_seconds: u64,
+    
+    /// Whether to serve a Swagger UI for the OpenAPI document at `/api/docs`
+    #[serde(default)]
+    pub swagger_ui: bool,
+    
+    /// Certificate and key to serve HTTPS with instead of plain HTTP
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    
+    /// Format of the log lines
+    #[serde(default)]
+    pub log_format: LogFormat,
+    
+    /// Seconds between the background probes of the routing backends for readiness
+    #[serde(default = "default_readiness_interval_seconds")]
+    pub readiness_interval_seconds: u64,
+}
+
+fn default_shutdown_grace_seconds() -> u64 {
+    30
+}
+
+fn default_readiness_interval_seconds() -> u64 {
+    10
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: num_cpus::get(),
+            shutdown_grace_seconds: default_shutdown_grace_seconds(),
+            swagger_ui: false,
+            tls: None,
+            log_format: LogFormat::default(),
+            readiness_interval_seconds: default_readiness_interval_seconds(),
+        }
+    }
+}
+
+/// Internal configuration structure for deserialization
+#[derive(Debug, Deserialize)]
+struct ConfigFile {
+    server: Option<ServerConfig>,
+    osrm: Option<OsrmConfigFile>,
+    valhalla: Option<ValhallaConfig>,
+    graphhopper: Option<GraphHopperConfig>,
+    routing: Option<RoutingConfigFile>,
+    rate_limit: Option<RateLimitConfig>,
+    limits: Option<RequestLimits>,
+    storage: Option<StorageConfig>,
+    admin: Option<AdminConfig>,
+    webhooks: Option<WebhookConfig>,
+    jobs: Option<JobQueueConfig>,
+    nats: Option<NatsConfig>,
+    tenants: Option<HashMap<String, TenantConfig>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsrmConfigFile {
+    base_url: Option<String>,
+    default_profile: Option<String>,
+    timeout_seconds: Option<u64>,
+    max_table_size: Option<usize>,
+    backends: Option<HashMap<String, OsrmBackendConfig>>,
+    pool: Option<OsrmPoolConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoutingConfigFile {
+    engine: Option<EngineKind>,
+    default_max_time: Option<u32>,
+    default_threads: Option<u8>,
+    traffic: Option<Vec<TrafficPeriod>>,
+    max_snap_distance: Option<f64>,
+    quick_search_above: Option<usize>,
+    cluster_size: Option<usize>,
+    default_max_tasks: Option<u32>,
+    lateness_penalty: Option<f64>,
+    late_task_penalty: Option<f64>,
+}
+
+impl AppConfig {
+    /// Load configuration from file and environment variables
+    pub fn load() -> Result<Self, ConfigError> {
+        // Determine the run mode
+        let run_mode = env::var("RUN_MODE").unwrap_or_else(|_| "development".to_string());
+        
+        // Build configuration
+        let builder = Config::builder()
+            // Add configuration from files
+            .add_source(File::with_name("config/default").required(false))
+            .add_source(File::with_name(&format!("config/{}", run_mode)).required(false))
+            // Add configuration from environment variables
+            .add_source(
+                Environment::with_prefix("APP")
+                    .separator("__")
+                    .ignore_empty(true)
+            );
+            
+        // Build and convert the config
+        let config: ConfigFile = builder.build()?.try_deserialize()?;
+        
+        // Create server config
+        let server = config.server.unwrap_or_default();
+        
+        // Create OSRM config
+        let osrm_file = config.osrm.unwrap_or(OsrmConfigFile {
+            base_url: None,
+            default_profile: None,
+            timeout_seconds: None,
+            max_table_size: None,
+            backends: None,
+            pool: None,
+        });
+        
+        let osrm = OsrmConfig {
+            base_url: osrm_file.base_url.unwrap_or_else(|| "http://localhost:5000".to_string()),
+            default_profile: osrm_file.default_profile.unwrap_or_else(|| "car".to_string()),
+            timeout_seconds: osrm_file.timeout_seconds.unwrap_or(30),
+            max_table_size: osrm_file.max_table_size.unwrap_or(100),
+            backends: osrm_file.backends.unwrap_or_default(),
+            pool: osrm_file.pool.unwrap_or_default(),
+        };
+        
+        // Create routing config
+        let routing_file = config.routing.unwrap_or(RoutingConfigFile {
+            engine: None,
+            default_max_time: None,
+            default_threads: None,
+            traffic: None,
+            max_snap_distance: None,
+            quick_search_above: None,
+            cluster_size: None,
+            default_max_tasks: None,
+            lateness_penalty: None,
+            late_task_penalty: None,
+        });
+        
+        let routing = RoutingConfig {
+            osrm: osrm.clone(),
+            engine: routing_file.engine.unwrap_or_default(),
+            valhalla: config.valhalla.unwrap_or_default(),
+            graphhopper: config.graphhopper.unwrap_or_default(),
+            default_max_time: routing_file.default_max_time.unwrap_or(30),
+            default_threads: routing_file.default_threads.unwrap_or(4),
+            traffic: routing_file.traffic.unwrap_or_default(),
+            max_snap_distance: routing_file
+                .max_snap_distance
+                .unwrap_or(RoutingConfig::default().max_snap_distance),
+            quick_search_above: routing_file.quick_search_above,
+            cluster_size: routing_file
+                .cluster_size
+                .unwrap_or(RoutingConfig::default().cluster_size),
+            default_max_tasks: routing_file.default_max_tasks,
+            lateness_penalty: routing_file
+                .lateness_penalty
+                .unwrap_or(RoutingConfig::default().lateness_penalty),
+            late_task_penalty: routing_file
+                .late_task_penalty
+                .unwrap_or(RoutingConfig::default().late_task_penalty),
+        };
+        
+        Ok(AppConfig {
+            server,
+            osrm,
+            routing,
+            rate_limit: config.rate_limit.unwrap_or_default(),
+            limits: config.limits.unwrap_or_default(),
+            storage: config.storage.unwrap_or_default(),
+            admin: config.admin.unwrap_or_default(),
+            webhooks: config.webhooks.unwrap_or_default(),
+            jobs: config.jobs.unwrap_or_default(),
+            nats: config.nats.unwrap_or_default(),
+            tenants: config.tenants.unwrap_or_default(),
+        })
+    }
+} 
\ No newline at end of file
@@ -1,3 +1,5 @@
 pub mod app_config;
+pub mod tls;
 
 pub use app_config::AppConfig;
+pub use tls::TlsConfig;
@@ -0,0 +1,61 @@
+use rustls::crypto::ring;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Certificate and key to terminate TLS with, in PEM format
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// Certificate chain, the server certificate first
+    pub cert_path: PathBuf,
+
+    /// Private key of the server certificate, as PKCS#8, PKCS#1 or SEC1
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Read the certificate chain and key into a rustls server configuration
+    pub fn server_config(&self) -> io::Result<rustls::ServerConfig> {
+        let certs = read_certs(&self.cert_path)?;
+        let key = read_key(&self.key_path)?;
+
+        rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn read_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<io::Result<Vec<_>>>()?;
+    if certs.is_empty() {
+        return Err(invalid(path, "contains no certificates"));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    let mut reader = BufReader::new(open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| invalid(path, "contains no private key"))
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    File::open(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Cannot open {}: {}", path.display(), err),
+        )
+    })
+}
+
+fn invalid(path: &Path, problem: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} {}", path.display(), problem),
+    )
+}
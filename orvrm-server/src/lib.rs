@@ -1,6 +1,7 @@
 // Export modules for testing
 pub mod api;
+pub mod cli;
 pub mod config;
-pub mod models;
 pub mod services;
+pub mod storage;
 pub mod utils;
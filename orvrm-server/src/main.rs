@@ -0,0 +1,209 @@
+use actix_web::{web, App, HttpServer, middleware::from_fn};
+use log::{info, error, warn};
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Parser;
+use orvrm_core::services::{RoutingService, RoutingConfig};
+use orvrm_server::api::{self, RateLimiter, Readiness, ShutdownState, Tenants};
+use orvrm_server::api::shutdown::shutdown_signal;
+use orvrm_server::cli::{self, Cli, Command};
+use orvrm_server::config::AppConfig;
+use orvrm_server::services::{JobStore, NatsConsumer, RedisBackend, Webhooks};
+use orvrm_server::storage::SolutionStore;
+use orvrm_server::utils::logging;
+
+#[actix_web::main]
+async fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    
+    // Load configuration
+    let config = AppConfig::load();
+    
+    // Initialize logger, in the default format when the configuration cannot be loaded
+    logging::init(config.as_ref().map(|config| config.server.log_format).unwrap_or_default());
+    
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load configuration: {}", e);
+            return Err(io::Error::other(e));
+        }
+    };
+    
+    match cli.command {
+        Some(Command::Solve { input, output }) => {
+            if let Err(e) = cli::solve(config.routing.clone(), &input, output.as_deref()).await {
+                error!("{:#}", e);
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::Serve) | None => serve(config).await,
+    }
+}
+
+/// Run the HTTP server until a shutdown signal is received
+async fn serve(config: AppConfig) -> io::Result<()> {
+    let scheme = if config.server.tls.is_some() { "https" } else { "http" };
+    info!("Starting ORVRM server on {}://{}:{}", scheme, config.server.host, config.server.port);
+    
+    // Create routing service
+    let routing_config = RoutingConfig {
+        osrm: config.osrm.clone(),
+        engine: config.routing.engine,
+        valhalla: config.routing.valhalla.clone(),
+        graphhopper: config.routing.graphhopper.clone(),
+        default_max_time: config.routing.default_max_time,
+        default_threads: config.routing.default_threads,
+        traffic: config.routing.traffic.clone(),
+        max_snap_distance: config.routing.max_snap_distance,
+        quick_search_above: config.routing.quick_search_above,
+        cluster_size: config.routing.cluster_size,
+        default_max_tasks: config.routing.default_max_tasks,
+        lateness_penalty: config.routing.lateness_penalty,
+        late_task_penalty: config.routing.late_task_penalty,
+    };
+    
+    let routing_service = RoutingService::new(routing_config);
+    
+    // Connect to the solution database, if configured
+    let solution_store = match &config.storage.url {
+        Some(url) => match SolutionStore::connect(url).await {
+            Ok(store) => {
+                info!("Storing solutions in the configured database");
+                Some(store)
+            }
+            Err(e) => {
+                error!("Failed to open solution storage: {:#}", e);
+                return Err(io::Error::other(e));
+            }
+        },
+        None => None,
+    };
+    
+    let job_store = JobStore::new()
+        .with_webhooks(Webhooks::new(config.webhooks.clone()))
+        .with_retention(Duration::from_secs(config.jobs.result_ttl_seconds));
+    let job_store = match solution_store.clone() {
+        Some(store) => job_store.with_storage(store),
+        None => job_store,
+    };
+    
+    // Share the job queue with the other servers, if configured
+    let job_store = match &config.jobs.redis_url {
+        Some(_) => match RedisBackend::connect(&config.jobs).await {
+            Ok(backend) => {
+                info!("Queueing optimization jobs in Redis");
+                job_store.with_backend(Arc::new(backend))
+            }
+            Err(e) => {
+                error!("Failed to connect to the job queue: {:#}", e);
+                return Err(io::Error::other(e));
+            }
+        },
+        None => job_store,
+    };
+    
+    // Optimize the requests published on NATS, if configured
+    if config.nats.url.is_some() {
+        let nats = NatsConsumer::start(
+            &config.nats,
+            routing_service.clone(),
+            job_store.clone(),
+            config.limits.clone(),
+        );
+        if let Err(e) = nats.await {
+            error!("Failed to subscribe to NATS: {:#}", e);
+            return Err(io::Error::other(e));
+        }
+        info!("Optimizing requests from NATS subject {}", config.nats.subject);
+    }
+    let rate_limiter = RateLimiter::new(config.rate_limit.clone());
+    
+    // Serve each customer with its own routing, limits and solutions, if configured
+    let tenants = Tenants::new(
+        &config.tenants,
+        &routing_service,
+        &rate_limiter,
+        &config.limits,
+        solution_store.as_ref(),
+        &job_store,
+    );
+    if !tenants.is_empty() {
+        info!("Serving {} tenants", config.tenants.len());
+    }
+    
+    // Take jobs from the shared queue, each with the services of its tenant
+    job_store.spawn_workers(routing_service.clone(), tenants.clone(), config.jobs.workers);
+    
+    // Probe the routing backends in the background for the readiness check
+    let readiness = Readiness::new();
+    readiness.spawn_probes(
+        routing_service.clone(),
+        Duration::from_secs(config.server.readiness_interval_seconds.max(1)),
+    );
+    
+    let limits = config.limits.clone();
+    let admin = config.admin.clone();
+    let swagger_ui = config.server.swagger_ui;
+    let shutdown = ShutdownState::new();
+    let grace_period = config.server.shutdown_grace_seconds;
+    
+    // Start HTTP server, handling shutdown signals ourselves
+    let app_job_store = job_store.clone();
+    let app_shutdown = shutdown.clone();
+    let server = HttpServer::new(move || {
+        let mut app = App::new()
+            .wrap(from_fn(api::rate_limit::rate_limit))
+            .wrap(from_fn(api::tenants::tenant))
+            .wrap(from_fn(api::request_id::request_id))
+            .app_data(web::Data::new(routing_service.clone()))
+            .app_data(web::Data::new(rate_limiter.clone()))
+            .app_data(web::Data::new(limits.clone()))
+            .app_data(web::Data::new(app_job_store.clone()))
+            .app_data(web::Data::new(app_shutdown.clone()))
+            .app_data(web::Data::new(readiness.clone()))
+            .app_data(web::Data::new(admin.clone()))
+            .app_data(web::Data::new(tenants.clone()));
+        if let Some(store) = &solution_store {
+            app = app.app_data(web::Data::new(store.clone()));
+        }
+        if swagger_ui {
+            app = app.route("/api/docs", web::get().to(api::openapi::swagger_ui));
+        }
+        app.configure(api::configure_routes)
+    });
+    
+    // Terminate TLS ourselves when a certificate is configured
+    let address = (config.server.host.clone(), config.server.port);
+    let server = match &config.server.tls {
+        Some(tls) => server.bind_rustls_0_23(address, tls.server_config()?)?,
+        None => server.bind(address)?,
+    };
+    let server = server
+        .workers(config.server.workers)
+        .shutdown_timeout(grace_period)
+        .disable_signals()
+        .run();
+    
+    let handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutdown requested, draining running optimizations for up to {}s", grace_period);
+        
+        // Refuse new optimizations while background jobs finish
+        shutdown.begin();
+        if !job_store.drain(Duration::from_secs(grace_period)).await {
+            warn!("{} optimization jobs still running after the grace period", job_store.running());
+        }
+        
+        // Stop accepting connections and let in-flight requests finish
+        handle.stop(true).await;
+    });
+    
+    server.await?;
+    info!("Server stopped");
+    Ok(())
+}
@@ -0,0 +1,513 @@
+use super::queue::{JobBackend, QueuedJob};
+use super::webhooks::Webhooks;
+use crate::api::tenants::Tenants;
+use crate::storage::SolutionStore;
+use anyhow::Result;
+use log::{error, info, warn};
+use orvrm_core::models::{OptimizationProgress, RoutingRequest, RoutingResponse};
+use orvrm_core::services::routing::{ProgressCallback, RoutingService};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{Instrument, Span};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Number of events buffered for slow stream subscribers
+const EVENT_BUFFER: usize = 64;
+
+/// Interval at which draining checks for running jobs
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest a queue worker waits for a job before checking whether it should stop
+const QUEUE_WAIT: Duration = Duration::from_secs(1);
+
+/// Pause of a queue worker after the queue failed
+const QUEUE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Interval at which the state of a job running on another server is checked
+const REMOTE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Time a finished job is kept by default, the default result TTL of the shared queue
+const DEFAULT_RETENTION: Duration = Duration::from_secs(86400);
+
+/// State of an asynchronous optimization job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// The solver is still running
+    Running,
+
+    /// The optimization finished and its result is available
+    Completed,
+
+    /// The optimization failed
+    Failed,
+}
+
+/// Event published while an optimization job runs
+#[derive(Debug, Clone)]
+pub enum JobEvent {
+    /// A better solution was found
+    Progress(OptimizationProgress),
+
+    /// The optimization finished with this response
+    Completed(Box<RoutingResponse>),
+
+    /// The optimization failed with this error
+    Failed(String),
+}
+
+impl JobEvent {
+    /// Whether no further events follow this one
+    pub fn is_final(&self) -> bool {
+        !matches!(self, JobEvent::Progress(_))
+    }
+
+    /// Encode the event as a Server-Sent Events message
+    pub fn to_sse(&self) -> String {
+        let (name, data) = match self {
+            JobEvent::Progress(progress) => ("progress", serde_json::to_string(progress)),
+            JobEvent::Completed(response) => ("completed", serde_json::to_string(response)),
+            JobEvent::Failed(error) => (
+                "failed",
+                serde_json::to_string(&serde_json::json!({ "error": error })),
+            ),
+        };
+
+        format!("event: {}\ndata: {}\n\n", name, data.unwrap_or_default())
+    }
+}
+
+/// Current state of a job as returned by status queries
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobSnapshot {
+    /// Job identifier
+    pub job_id: Uuid,
+
+    /// Current status
+    pub status: JobStatus,
+
+    /// Best solution found so far while the job is running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<OptimizationProgress>,
+
+    /// Final response once the job has completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<RoutingResponse>,
+
+    /// Error message if the job failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl JobSnapshot {
+    /// State of a job that has not reported anything yet
+    pub fn running(job_id: Uuid) -> Self {
+        Self {
+            job_id,
+            status: JobStatus::Running,
+            progress: None,
+            result: None,
+            error: None,
+        }
+    }
+
+    /// State of a job after `event`
+    fn after(job_id: Uuid, event: Option<&JobEvent>) -> Self {
+        let mut snapshot = Self::running(job_id);
+        match event {
+            Some(JobEvent::Progress(progress)) => snapshot.progress = Some(progress.clone()),
+            Some(JobEvent::Completed(response)) => {
+                snapshot.status = JobStatus::Completed;
+                snapshot.result = Some(response.as_ref().clone());
+            }
+            Some(JobEvent::Failed(error)) => {
+                snapshot.status = JobStatus::Failed;
+                snapshot.error = Some(error.clone());
+            }
+            None => {}
+        }
+        snapshot
+    }
+
+    /// Latest event of the job, if it reported any
+    fn event(self) -> Option<JobEvent> {
+        match self.status {
+            JobStatus::Running => self.progress.map(JobEvent::Progress),
+            JobStatus::Completed => Some(JobEvent::Completed(Box::new(self.result?))),
+            JobStatus::Failed => Some(JobEvent::Failed(self.error.unwrap_or_default())),
+        }
+    }
+}
+
+struct JobEntry {
+    latest: Option<JobEvent>,
+    sender: broadcast::Sender<JobEvent>,
+
+    /// Whether the job runs on another server and is only followed here
+    remote: bool,
+
+    /// Tenant that submitted the job
+    tenant: Option<String>,
+
+    /// When the job completed or failed
+    finished_at: Option<Instant>,
+}
+
+/// Registry of asynchronous optimization jobs
+///
+/// Jobs run in this process unless a shared [`JobBackend`] queues them for whichever
+/// server takes them first. Finished jobs are forgotten after the retention time. A store
+/// scoped to a tenant shares the jobs of the deployment, but only reports the tenant's.
+#[derive(Clone)]
+pub struct JobStore {
+    jobs: Arc<Mutex<HashMap<Uuid, JobEntry>>>,
+    tenant: Option<String>,
+    storage: Option<SolutionStore>,
+    webhooks: Webhooks,
+    backend: Option<Arc<dyn JobBackend>>,
+    stopping: Arc<AtomicBool>,
+    retention: Duration,
+
+    /// Number of optimizations running outside of jobs, such as WebSocket solves
+    solving: Arc<AtomicUsize>,
+}
+
+/// Optimization running outside of a job, counted by [`JobStore::drain`] until dropped
+pub struct RunningSolve {
+    solving: Arc<AtomicUsize>,
+}
+
+impl Drop for RunningSolve {
+    fn drop(&mut self) {
+        self.solving.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for JobStore {
+    fn default() -> Self {
+        Self {
+            jobs: Arc::default(),
+            tenant: None,
+            storage: None,
+            webhooks: Webhooks::default(),
+            backend: None,
+            stopping: Arc::default(),
+            retention: DEFAULT_RETENTION,
+            solving: Arc::default(),
+        }
+    }
+}
+
+impl JobStore {
+    /// Create an empty job store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget finished jobs `retention` after they completed or failed
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Submit jobs for `tenant` and only report the jobs it submitted
+    pub fn for_tenant(mut self, tenant: &str) -> Self {
+        self.tenant = Some(tenant.to_string());
+        self
+    }
+
+    /// Persist completed jobs in `storage`, using the job ID as solution ID
+    pub fn with_storage(mut self, storage: SolutionStore) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Deliver the callbacks of requests with a `callback_url` with `webhooks`
+    pub fn with_webhooks(mut self, webhooks: Webhooks) -> Self {
+        self.webhooks = webhooks;
+        self
+    }
+
+    /// Queue jobs in `backend` instead of running them right away
+    ///
+    /// Jobs only run on servers with workers taking them from the queue, see
+    /// [`Self::spawn_workers`].
+    pub fn with_backend(mut self, backend: Arc<dyn JobBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Start optimizing `request` in the background, or queue it, and return the job ID
+    ///
+    /// Once the job has completed or failed, its status is posted to the request's
+    /// `callback_url`, if it has one.
+    pub async fn submit(&self, service: RoutingService, request: RoutingRequest) -> Result<Uuid> {
+        let job_id = Uuid::new_v4();
+        match &self.backend {
+            Some(backend) => {
+                let job = QueuedJob {
+                    job_id,
+                    tenant: self.tenant.clone(),
+                    request,
+                };
+                backend.push(&job).await?
+            }
+            None => {
+                self.register(job_id, false);
+                // The job logs with the correlation ID of the request that submitted it
+                let job = self.clone().run(job_id, service, request);
+                tokio::spawn(job.instrument(Span::current()));
+            }
+        }
+        Ok(job_id)
+    }
+
+    /// Optimize jobs from the shared queue on `workers` background tasks, one job per task
+    /// at a time, until draining starts
+    ///
+    /// The jobs of a tenant run with the routing service and job store the tenant has in
+    /// `tenants`, other jobs with `service` and this store.
+    pub fn spawn_workers(&self, service: RoutingService, tenants: Tenants, workers: usize) {
+        let Some(backend) = self.backend.clone() else {
+            return;
+        };
+        for _ in 0..workers {
+            let store = self.clone();
+            let backend = Arc::clone(&backend);
+            let service = service.clone();
+            let tenants = tenants.clone();
+            tokio::spawn(async move {
+                while !store.stopping.load(Ordering::Relaxed) {
+                    match backend.pop(QUEUE_WAIT).await {
+                        Ok(Some(job)) => {
+                            info!("Took optimization job {} from the queue", job.job_id);
+                            let services = match &job.tenant {
+                                Some(tenant) => tenants.job_services(tenant),
+                                None => Some((service.clone(), store.clone())),
+                            };
+                            let Some((service, store)) = services else {
+                                reject_unknown_tenant(backend.as_ref(), &job).await;
+                                continue;
+                            };
+                            store.register(job.job_id, false);
+                            store.clone().run(job.job_id, service, job.request).await;
+                        }
+                        Ok(None) => {}
+                        Err(err) => {
+                            warn!("Failed to take a job from the queue: {:#}", err);
+                            tokio::time::sleep(QUEUE_RETRY_DELAY).await;
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    /// Optimize a registered job, then store its result and deliver its callback
+    async fn run(self, job_id: Uuid, service: RoutingService, request: RoutingRequest) {
+        let progress_store = self.clone();
+        let progress: ProgressCallback = Arc::new(move |progress| {
+            progress_store.publish(job_id, JobEvent::Progress(progress));
+        });
+
+        let event = match service
+            .process_request_with_progress(request.clone(), progress)
+            .await
+        {
+            Ok(mut response) => {
+                info!("Optimization job {} completed", job_id);
+                if let Some(storage) = &self.storage {
+                    match storage.save(job_id, &request, &response).await {
+                        Ok(()) => response.solution_id = Some(job_id),
+                        Err(err) => warn!("Failed to store solution {}: {:#}", job_id, err),
+                    }
+                }
+                JobEvent::Completed(Box::new(response))
+            }
+            Err(err) => {
+                error!("Optimization job {} failed: {}", job_id, err);
+                JobEvent::Failed(err.to_string())
+            }
+        };
+        let snapshot = JobSnapshot::after(job_id, Some(&event));
+        self.publish(job_id, event);
+
+        // Progress stays on this server; other servers see the outcome
+        if let Some(backend) = &self.backend {
+            if let Err(err) = backend.save(self.tenant.as_deref(), &snapshot).await {
+                error!("Failed to record the outcome of job {}: {:#}", job_id, err);
+            }
+        }
+        // Retried callbacks do not hold up the worker that ran the job
+        if let Some(url) = request.callback_url {
+            let webhooks = self.webhooks.clone();
+            let delivery = async move {
+                webhooks.deliver(&url, &snapshot).await;
+            };
+            tokio::spawn(delivery.instrument(Span::current()));
+        }
+    }
+
+    /// Current state of a job
+    pub async fn status(&self, job_id: Uuid) -> Option<JobSnapshot> {
+        if let Some(entry) = self.forget_expired().get(&job_id) {
+            // Other tenants' jobs are not reported
+            if entry.tenant != self.tenant {
+                return None;
+            }
+            return Some(JobSnapshot::after(job_id, entry.latest.as_ref()));
+        }
+        self.load(job_id).await
+    }
+
+    /// Latest event of a job and, while it is running, a receiver for the events that follow
+    ///
+    /// Jobs running on another server report their outcome only.
+    pub async fn subscribe(
+        &self,
+        job_id: Uuid,
+    ) -> Option<(Option<JobEvent>, Option<broadcast::Receiver<JobEvent>>)> {
+        if !self.forget_expired().contains_key(&job_id) {
+            let event = self.load(job_id).await?.event();
+            if event.as_ref().is_some_and(JobEvent::is_final) {
+                return Some((event, None));
+            }
+            if self.register(job_id, true) {
+                tokio::spawn(self.clone().follow(job_id));
+            }
+        }
+
+        let jobs = self.lock();
+        let entry = jobs.get(&job_id).filter(|entry| entry.tenant == self.tenant)?;
+        let finished = entry.latest.as_ref().is_some_and(JobEvent::is_final);
+        let receiver = (!finished).then(|| entry.sender.subscribe());
+        Some((entry.latest.clone(), receiver))
+    }
+
+    /// Count an optimization running outside of a job until the returned guard is dropped
+    pub fn track(&self) -> RunningSolve {
+        self.solving.fetch_add(1, Ordering::SeqCst);
+        RunningSolve {
+            solving: self.solving.clone(),
+        }
+    }
+
+    /// Number of jobs and tracked optimizations running on this server that have not
+    /// finished yet
+    pub fn running(&self) -> usize {
+        let jobs = self
+            .lock()
+            .values()
+            .filter(|entry| !entry.remote && !entry.latest.as_ref().is_some_and(JobEvent::is_final))
+            .count();
+        jobs + self.solving.load(Ordering::SeqCst)
+    }
+
+    /// Stop taking jobs from the queue and wait until all running jobs and tracked
+    /// optimizations have finished, up to `timeout`
+    ///
+    /// Returns whether every job finished in time.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.stopping.store(true, Ordering::Relaxed);
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if self.running() == 0 {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Add a job without events, returning whether it was not known yet
+    fn register(&self, job_id: Uuid, remote: bool) -> bool {
+        let mut jobs = self.forget_expired();
+        if jobs.contains_key(&job_id) {
+            return false;
+        }
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        jobs.insert(
+            job_id,
+            JobEntry {
+                latest: None,
+                sender,
+                remote,
+                tenant: self.tenant.clone(),
+                finished_at: None,
+            },
+        );
+        true
+    }
+
+    /// Publish the outcome of a job running on another server once the backend has it
+    async fn follow(self, job_id: Uuid) {
+        loop {
+            tokio::time::sleep(REMOTE_POLL_INTERVAL).await;
+            let Some(snapshot) = self.load(job_id).await else {
+                self.publish(job_id, JobEvent::Failed("The job is no longer known".to_string()));
+                return;
+            };
+            if let Some(event) = snapshot.event().filter(JobEvent::is_final) {
+                self.publish(job_id, event);
+                return;
+            }
+        }
+    }
+
+    /// State of a job recorded in the backend
+    async fn load(&self, job_id: Uuid) -> Option<JobSnapshot> {
+        match self.backend.as_ref()?.load(self.tenant.as_deref(), job_id).await {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                warn!("Failed to look up job {}: {:#}", job_id, err);
+                None
+            }
+        }
+    }
+
+    fn publish(&self, job_id: Uuid, event: JobEvent) {
+        let mut jobs = self.lock();
+        if let Some(entry) = jobs.get_mut(&job_id) {
+            // Sending only fails when nobody is listening
+            let _ = entry.sender.send(event.clone());
+            if event.is_final() {
+                entry.finished_at = Some(Instant::now());
+            }
+            entry.latest = Some(event);
+        }
+    }
+
+    /// Lock the jobs after dropping those finished longer than the retention time ago
+    fn forget_expired(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, JobEntry>> {
+        let mut jobs = self.lock();
+        jobs.retain(|_, entry| {
+            entry
+                .finished_at
+                .is_none_or(|finished_at| finished_at.elapsed() < self.retention)
+        });
+        jobs
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, JobEntry>> {
+        self.jobs.lock().expect("job store lock poisoned")
+    }
+}
+
+/// Fail a queued job of a tenant this server does not know, which may only be configured
+/// on the server that accepted it
+async fn reject_unknown_tenant(backend: &dyn JobBackend, job: &QueuedJob) {
+    let tenant = job.tenant.as_deref().unwrap_or_default();
+    warn!("Job {} is of the unknown tenant {}", job.job_id, tenant);
+    let event = JobEvent::Failed(format!("Unknown tenant {}", tenant));
+    let snapshot = JobSnapshot::after(job.job_id, Some(&event));
+    if let Err(err) = backend.save(job.tenant.as_deref(), &snapshot).await {
+        error!("Failed to record the outcome of job {}: {:#}", job.job_id, err);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod jobs;
+pub mod nats;
+pub mod queue;
+pub mod redis;
+pub mod webhooks;
+
+pub use jobs::JobStore;
+pub use nats::{NatsConfig, NatsConsumer};
+pub use queue::{JobBackend, JobQueueConfig, MemoryBackend};
+pub use redis::RedisBackend;
+pub use webhooks::{WebhookConfig, Webhooks};
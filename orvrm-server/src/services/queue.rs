@@ -0,0 +1,146 @@
+use super::jobs::JobSnapshot;
+use anyhow::Result;
+use async_trait::async_trait;
+use orvrm_core::models::RoutingRequest;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// Shared queue of asynchronous optimizations
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobQueueConfig {
+    /// Redis URL, such as `redis://:password@localhost:6379/0`. Without it every job runs
+    /// on the server that accepted it.
+    #[serde(default)]
+    pub redis_url: Option<String>,
+
+    /// Number of queued jobs each server optimizes at the same time
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+
+    /// Prefix of the Redis keys
+    #[serde(default = "default_key_prefix")]
+    pub key_prefix: String,
+
+    /// Seconds the state and result of a finished job are kept, in Redis and in memory
+    #[serde(default = "default_result_ttl_seconds")]
+    pub result_ttl_seconds: u64,
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+fn default_key_prefix() -> String {
+    "orvrm".to_string()
+}
+
+fn default_result_ttl_seconds() -> u64 {
+    86400
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            workers: default_workers(),
+            key_prefix: default_key_prefix(),
+            result_ttl_seconds: default_result_ttl_seconds(),
+        }
+    }
+}
+
+/// Optimization waiting in the queue for a server to pick it up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    /// Job identifier
+    pub job_id: Uuid,
+
+    /// Tenant that submitted the job, whose routing settings and storage it runs with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+
+    /// Request to optimize
+    pub request: RoutingRequest,
+}
+
+/// Queue and result store that servers share to spread optimizations among them
+///
+/// Any server may accept a job, another may run it, and every server can report its state.
+/// The states of a tenant's jobs are kept apart from those of other tenants and of the
+/// deployment, so they are only found with the tenant that submitted them.
+#[async_trait]
+pub trait JobBackend: Send + Sync {
+    /// Queue a job and record it as running
+    async fn push(&self, job: &QueuedJob) -> Result<()>;
+
+    /// Take the oldest queued job, waiting up to `timeout` for one to arrive
+    async fn pop(&self, timeout: Duration) -> Result<Option<QueuedJob>>;
+
+    /// Record the latest state of a job of `tenant`
+    async fn save(&self, tenant: Option<&str>, snapshot: &JobSnapshot) -> Result<()>;
+
+    /// Latest recorded state of a job of `tenant`
+    async fn load(&self, tenant: Option<&str>, job_id: Uuid) -> Result<Option<JobSnapshot>>;
+}
+
+/// Job queue within a single process, for tests and embedding
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    queue: Mutex<VecDeque<QueuedJob>>,
+    snapshots: Mutex<HashMap<(Option<String>, Uuid), JobSnapshot>>,
+    arrived: Notify,
+}
+
+impl MemoryBackend {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl JobBackend for MemoryBackend {
+    async fn push(&self, job: &QueuedJob) -> Result<()> {
+        self.save(job.tenant.as_deref(), &JobSnapshot::running(job.job_id))
+            .await?;
+        self.queue
+            .lock()
+            .expect("job queue lock poisoned")
+            .push_back(job.clone());
+        self.arrived.notify_one();
+        Ok(())
+    }
+
+    async fn pop(&self, timeout: Duration) -> Result<Option<QueuedJob>> {
+        let take = || {
+            self.queue
+                .lock()
+                .expect("job queue lock poisoned")
+                .pop_front()
+        };
+        if let Some(job) = take() {
+            return Ok(Some(job));
+        }
+        let _ = tokio::time::timeout(timeout, self.arrived.notified()).await;
+        Ok(take())
+    }
+
+    async fn save(&self, tenant: Option<&str>, snapshot: &JobSnapshot) -> Result<()> {
+        self.snapshots
+            .lock()
+            .expect("job queue lock poisoned")
+            .insert((tenant.map(str::to_string), snapshot.job_id), snapshot.clone());
+        Ok(())
+    }
+
+    async fn load(&self, tenant: Option<&str>, job_id: Uuid) -> Result<Option<JobSnapshot>> {
+        let snapshots = self.snapshots.lock().expect("job queue lock poisoned");
+        Ok(snapshots
+            .get(&(tenant.map(str::to_string), job_id))
+            .cloned())
+    }
+}
@@ -0,0 +1,201 @@
+use super::jobs::JobSnapshot;
+use super::queue::{JobBackend, JobQueueConfig, QueuedJob};
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+/// Default port of a Redis server
+const DEFAULT_PORT: u16 = 6379;
+
+/// Time allowed to connect to the Redis server
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Job queue and result store in Redis, shared by every server connected to it
+///
+/// Queued jobs are kept in the list `<prefix>:queue` and job states under
+/// `<prefix>:job:<job id>`, expiring after the configured time. Every command uses a
+/// connection of its own, so a server waiting for jobs does not hold up the others.
+#[derive(Debug, Clone)]
+pub struct RedisBackend {
+    address: String,
+    password: Option<String>,
+    database: Option<u32>,
+    prefix: String,
+    ttl_seconds: u64,
+}
+
+/// Reply to a Redis command
+#[derive(Debug, Clone, PartialEq)]
+enum Reply {
+    Status(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+impl RedisBackend {
+    /// Connect to the Redis server at `config.redis_url` and check that it answers
+    pub async fn connect(config: &JobQueueConfig) -> Result<Self> {
+        let url = config
+            .redis_url
+            .as_deref()
+            .context("No Redis URL configured")?;
+        let url = reqwest::Url::parse(url).context("Invalid Redis URL")?;
+        if url.scheme() != "redis" {
+            bail!("Redis URLs start with redis://");
+        }
+        let host = url.host_str().context("The Redis URL has no host")?;
+        let database = match url.path().trim_start_matches('/') {
+            "" => None,
+            database => Some(database.parse().context("Invalid Redis database number")?),
+        };
+
+        let backend = Self {
+            address: format!("{}:{}", host, url.port().unwrap_or(DEFAULT_PORT)),
+            password: url.password().map(str::to_string),
+            database,
+            prefix: config.key_prefix.clone(),
+            ttl_seconds: config.result_ttl_seconds.max(1),
+        };
+        match backend.command(&["PING"]).await? {
+            Reply::Status(pong) if pong == "PONG" => Ok(backend),
+            reply => bail!("Unexpected reply to PING: {:?}", reply),
+        }
+    }
+
+    fn queue_key(&self) -> String {
+        format!("{}:queue", self.prefix)
+    }
+
+    /// Key of a job's state, within the keys of its tenant if it has one
+    fn job_key(&self, tenant: Option<&str>, job_id: Uuid) -> String {
+        match tenant {
+            Some(tenant) => format!("{}:tenant:{}:job:{}", self.prefix, tenant, job_id),
+            None => format!("{}:job:{}", self.prefix, job_id),
+        }
+    }
+
+    /// Run a command on a new connection, after authenticating and selecting the database
+    async fn command(&self, args: &[&str]) -> Result<Reply> {
+        let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&self.address))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to Redis at {}", self.address))?
+            .with_context(|| format!("Failed to connect to Redis at {}", self.address))?;
+        let mut stream = BufReader::new(stream);
+
+        if let Some(password) = &self.password {
+            call(&mut stream, &["AUTH", password]).await?;
+        }
+        if let Some(database) = self.database {
+            call(&mut stream, &["SELECT", &database.to_string()]).await?;
+        }
+        call(&mut stream, args).await
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<()> {
+        let ttl = self.ttl_seconds.to_string();
+        self.command(&["SET", key, value, "EX", &ttl]).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl JobBackend for RedisBackend {
+    async fn push(&self, job: &QueuedJob) -> Result<()> {
+        let snapshot = serde_json::to_string(&JobSnapshot::running(job.job_id))?;
+        self.set(&self.job_key(job.tenant.as_deref(), job.job_id), &snapshot)
+            .await?;
+        let job = serde_json::to_string(job)?;
+        self.command(&["LPUSH", &self.queue_key(), &job]).await?;
+        Ok(())
+    }
+
+    async fn pop(&self, timeout: Duration) -> Result<Option<QueuedJob>> {
+        // BRPOP waits whole seconds, and zero would wait forever
+        let seconds = timeout.as_secs().max(1).to_string();
+        match self
+            .command(&["BRPOP", &self.queue_key(), &seconds])
+            .await?
+        {
+            Reply::Array(Some(items)) => match items.as_slice() {
+                [_, Reply::Bulk(Some(job))] => Ok(Some(
+                    serde_json::from_slice(job).context("Invalid job in the Redis queue")?,
+                )),
+                _ => bail!("Unexpected reply to BRPOP"),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    async fn save(&self, tenant: Option<&str>, snapshot: &JobSnapshot) -> Result<()> {
+        let value = serde_json::to_string(snapshot)?;
+        self.set(&self.job_key(tenant, snapshot.job_id), &value).await
+    }
+
+    async fn load(&self, tenant: Option<&str>, job_id: Uuid) -> Result<Option<JobSnapshot>> {
+        match self.command(&["GET", &self.job_key(tenant, job_id)]).await? {
+            Reply::Bulk(Some(value)) => Ok(Some(
+                serde_json::from_slice(&value).context("Invalid job state in Redis")?,
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Send a command in the Redis protocol and read its reply
+async fn call(stream: &mut BufReader<TcpStream>, args: &[&str]) -> Result<Reply> {
+    let mut message = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        message.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        message.extend_from_slice(arg.as_bytes());
+        message.extend_from_slice(b"\r\n");
+    }
+    stream.get_mut().write_all(&message).await?;
+    read_reply(stream).await
+}
+
+/// Read one reply, failing on Redis errors
+async fn read_reply(stream: &mut BufReader<TcpStream>) -> Result<Reply> {
+    let line = read_line(stream).await?;
+    let (kind, rest) = line.split_at(1);
+    match kind {
+        "+" => Ok(Reply::Status(rest.to_string())),
+        "-" => bail!("Redis error: {}", rest),
+        ":" => Ok(Reply::Integer(rest.parse()?)),
+        "$" => {
+            let Ok(len) = usize::try_from(rest.parse::<i64>()?) else {
+                return Ok(Reply::Bulk(None));
+            };
+            let mut value = vec![0; len + 2];
+            stream.read_exact(&mut value).await?;
+            value.truncate(len);
+            Ok(Reply::Bulk(Some(value)))
+        }
+        "*" => {
+            let Ok(len) = usize::try_from(rest.parse::<i64>()?) else {
+                return Ok(Reply::Array(None));
+            };
+            let mut items = Vec::with_capacity(len);
+            for _ in 0..len {
+                items.push(Box::pin(read_reply(stream)).await?);
+            }
+            Ok(Reply::Array(Some(items)))
+        }
+        _ => bail!("Unexpected reply from Redis: {}", line),
+    }
+}
+
+async fn read_line(stream: &mut BufReader<TcpStream>) -> Result<String> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        bail!("Redis closed the connection");
+    }
+    let line = line.trim_end_matches("\r\n");
+    if line.is_empty() {
+        bail!("Empty reply from Redis");
+    }
+    Ok(line.to_string())
+}
@@ -0,0 +1,137 @@
+use crate::services::jobs::JobSnapshot;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Header carrying the signature of a callback body
+pub const SIGNATURE_HEADER: &str = "X-Orvrm-Signature";
+
+/// Delivery of the callbacks that report finished asynchronous optimizations
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// Secret to sign callback bodies with; callbacks are not signed when unset
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    /// Number of times a failed delivery is tried again
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Seconds to wait before the first retry, doubling for every retry after it
+    #[serde(default = "default_retry_delay_seconds")]
+    pub retry_delay_seconds: u64,
+
+    /// Seconds to wait for the receiver to answer a delivery
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_delay_seconds() -> u64 {
+    5
+}
+
+fn default_timeout_seconds() -> u64 {
+    10
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            max_retries: default_max_retries(),
+            retry_delay_seconds: default_retry_delay_seconds(),
+            timeout_seconds: default_timeout_seconds(),
+        }
+    }
+}
+
+/// Client posting job outcomes to the callback URLs of their requests
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl Default for Webhooks {
+    fn default() -> Self {
+        Self::new(WebhookConfig::default())
+    }
+}
+
+impl Webhooks {
+    /// Create a client delivering callbacks as `config` sets out
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_seconds))
+            .build()
+            .unwrap_or_default();
+        Self { config, client }
+    }
+
+    /// Post the final state of a job to `url`, trying again while the receiver fails
+    ///
+    /// A delivery succeeds when the receiver answers with a 2xx status. Returns whether
+    /// any attempt succeeded.
+    pub async fn deliver(&self, url: &str, snapshot: &JobSnapshot) -> bool {
+        let body = match serde_json::to_vec(snapshot) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("Failed to encode callback of job {}: {}", snapshot.job_id, err);
+                return false;
+            }
+        };
+
+        let mut delay = Duration::from_secs(self.config.retry_delay_seconds);
+        for attempt in 0..=self.config.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+
+            let mut request = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(body.clone());
+            if let Some(secret) = &self.config.secret {
+                request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+            }
+
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!("Delivered callback of job {}", snapshot.job_id);
+                    return true;
+                }
+                Ok(response) => warn!(
+                    "Callback of job {} was answered with {} (attempt {})",
+                    snapshot.job_id,
+                    response.status(),
+                    attempt + 1
+                ),
+                Err(err) => warn!(
+                    "Callback of job {} failed: {} (attempt {})",
+                    snapshot.job_id,
+                    err,
+                    attempt + 1
+                ),
+            }
+        }
+
+        warn!("Gave up delivering the callback of job {}", snapshot.job_id);
+        false
+    }
+}
+
+/// Signature of a callback body, as `sha256=` followed by the hex HMAC-SHA256 under `secret`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use orvrm_core::models::{RoutingRequest, RoutingResponse, RoutingSummary};
+use serde::{Deserialize, Serialize};
+use sqlx::any::{install_default_drivers, AnyPoolOptions};
+use sqlx::{AnyPool, Row};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Maximum number of solutions returned by a listing
+pub const MAX_LIST_LIMIT: i64 = 1000;
+
+/// Solution storage configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StorageConfig {
+    /// Database URL, such as `sqlite://orvrm.db?mode=rwc` or `postgres://user@host/orvrm`.
+    /// Solutions are not persisted when unset.
+    pub url: Option<String>,
+}
+
+/// A persisted optimization request together with its response
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StoredSolution {
+    /// Solution identifier
+    pub id: Uuid,
+
+    /// Time the solution was stored, as a Unix timestamp in seconds
+    pub created_at: i64,
+
+    /// Request that was optimized
+    pub request: RoutingRequest,
+
+    /// Response returned for the request
+    pub response: RoutingResponse,
+}
+
+/// Listing entry for a persisted solution
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SolutionSummary {
+    /// Solution identifier
+    pub id: Uuid,
+
+    /// Time the solution was stored, as a Unix timestamp in seconds
+    pub created_at: i64,
+
+    /// Summary of the response
+    pub summary: RoutingSummary,
+}
+
+/// SQL database holding optimization requests and responses
+///
+/// Every store sees only the solutions of its own tenant, see [`Self::for_tenant`].
+#[derive(Debug, Clone)]
+pub struct SolutionStore {
+    pool: AnyPool,
+
+    /// Tenant the solutions are stored for, empty without tenants
+    tenant: String,
+}
+
+impl SolutionStore {
+    /// Connect to the database at `url` and create the solutions table if needed
+    pub async fn connect(url: &str) -> Result<Self> {
+        install_default_drivers();
+
+        // Every SQLite connection to an in-memory database sees its own database
+        let max_connections = if url.starts_with("sqlite:") { 1 } else { 5 };
+        let pool = AnyPoolOptions::new()
+            .max_connections(max_connections)
+            .connect(url)
+            .await
+            .context("Failed to connect to the solution database")?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS solutions (
+                id TEXT PRIMARY KEY,
+                created_at BIGINT NOT NULL,
+                request TEXT NOT NULL,
+                response TEXT NOT NULL,
+                tenant TEXT NOT NULL DEFAULT ''
+            )",
+        )
+        .execute(&pool)
+        .await
+        .context("Failed to create the solutions table")?;
+
+        // Tables created before tenants existed hold the solutions of no tenant
+        if sqlx::query("SELECT tenant FROM solutions LIMIT 1")
+            .fetch_optional(&pool)
+            .await
+            .is_err()
+        {
+            sqlx::query("ALTER TABLE solutions ADD COLUMN tenant TEXT NOT NULL DEFAULT ''")
+                .execute(&pool)
+                .await
+                .context("Failed to add the tenant column to the solutions table")?;
+        }
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS solutions_created_at ON solutions (created_at)")
+            .execute(&pool)
+            .await
+            .context("Failed to create the solutions index")?;
+
+        Ok(Self {
+            pool,
+            tenant: String::new(),
+        })
+    }
+
+    /// Store sharing the database, holding only the solutions of `tenant`
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            tenant: tenant.to_string(),
+        }
+    }
+
+    /// Persist a request and its response under `id`
+    pub async fn save(
+        &self,
+        id: Uuid,
+        request: &RoutingRequest,
+        response: &RoutingResponse,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO solutions (id, created_at, request, response, tenant)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(id.to_string())
+        .bind(chrono::Utc::now().timestamp())
+        .bind(serde_json::to_string(request)?)
+        .bind(serde_json::to_string(response)?)
+        .bind(&self.tenant)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store solution")?;
+
+        Ok(())
+    }
+
+    /// Load a persisted solution
+    pub async fn get(&self, id: Uuid) -> Result<Option<StoredSolution>> {
+        let row = sqlx::query(
+            "SELECT created_at, request, response FROM solutions WHERE id = $1 AND tenant = $2",
+        )
+        .bind(id.to_string())
+        .bind(&self.tenant)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to load solution")?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(StoredSolution {
+            id,
+            created_at: row.try_get("created_at")?,
+            request: serde_json::from_str(&row.try_get::<String, _>("request")?)?,
+            response: serde_json::from_str(&row.try_get::<String, _>("response")?)?,
+        }))
+    }
+
+    /// List solutions stored at or after `since`, oldest first
+    pub async fn list(&self, since: i64, limit: i64) -> Result<Vec<SolutionSummary>> {
+        let rows = sqlx::query(
+            "SELECT id, created_at, response FROM solutions
+             WHERE created_at >= $1 AND tenant = $2 ORDER BY created_at, id LIMIT $3",
+        )
+        .bind(since)
+        .bind(&self.tenant)
+        .bind(limit.clamp(1, MAX_LIST_LIMIT))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list solutions")?;
+
+        rows.iter()
+            .map(|row| {
+                let response: RoutingResponse =
+                    serde_json::from_str(&row.try_get::<String, _>("response")?)?;
+                Ok(SolutionSummary {
+                    id: row.try_get::<String, _>("id")?.parse()?,
+                    created_at: row.try_get("created_at")?,
+                    summary: response.summary,
+                })
+            })
+            .collect()
+    }
+}
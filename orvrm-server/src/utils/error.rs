@@ -0,0 +1,99 @@
+use thiserror::Error;
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::json;
+
+pub use orvrm_core::services::routing::UnassignedLimitExceeded;
+pub use orvrm_core::utils::validation::FieldError;
+
+/// Application error types
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Configuration error: {0}")]
+    ConfigError(#[from] config::ConfigError),
+    
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    
+    #[error("OSRM service error: {0}")]
+    #[allow(dead_code)]
+    OsrmError(String),
+    
+    #[error("Routing error: {0}")]
+    #[allow(dead_code)]
+    RoutingError(String),
+    
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+    
+    #[error("Validation error: {} invalid field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+    
+    #[error("Request too large: {0}")]
+    RequestTooLarge(String),
+    
+    #[error("Too many unassigned: {0}")]
+    TooManyUnassigned(UnassignedLimitExceeded),
+    
+    #[error("Storage error: {0}")]
+    StorageError(String),
+    
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    
+    #[error("Internal server error: {0}")]
+    InternalError(String),
+}
+
+impl ResponseError for AppError {
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AppError::ValidationError(msg) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "Validation Error",
+                    "message": msg
+                }))
+            },
+            AppError::InvalidFields(errors) => {
+                HttpResponse::BadRequest().json(json!({
+                    "error": "Validation Error",
+                    "message": self.to_string(),
+                    "errors": errors
+                }))
+            },
+            AppError::RequestTooLarge(msg) => {
+                HttpResponse::PayloadTooLarge().json(json!({
+                    "error": "Request Too Large",
+                    "message": msg
+                }))
+            },
+            AppError::TooManyUnassigned(exceeded) => {
+                HttpResponse::UnprocessableEntity().json(json!({
+                    "error": "Too Many Unassigned",
+                    "message": exceeded.message,
+                    "unassigned": exceeded.unassigned,
+                    "total": exceeded.total
+                }))
+            },
+            AppError::Unauthorized(msg) => {
+                HttpResponse::Unauthorized()
+                    .insert_header(("WWW-Authenticate", "Bearer"))
+                    .json(json!({
+                        "error": "Unauthorized",
+                        "message": msg
+                    }))
+            },
+            AppError::OsrmError(msg) => {
+                HttpResponse::ServiceUnavailable().json(json!({
+                    "error": "OSRM Service Error",
+                    "message": msg
+                }))
+            },
+            _ => {
+                HttpResponse::InternalServerError().json(json!({
+                    "error": "Internal Server Error",
+                    "message": self.to_string()
+                }))
+            }
+        }
+    }
+} 
\ No newline at end of file
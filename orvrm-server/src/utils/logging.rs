@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use tracing_subscriber::EnvFilter;
+
+/// Format of the log lines written to standard output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One JSON object per line, with the correlation ID of the request being handled
+    #[default]
+    Json,
+
+    /// Human-readable lines for development
+    Text,
+}
+
+/// Install the global logger, which also picks up the `log` lines of the routing core
+///
+/// The level is taken from `RUST_LOG` and defaults to `info`.
+pub fn init(format: LogFormat) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init(),
+        LogFormat::Text => builder.init(),
+    }
+}
@@ -0,0 +1,2061 @@
+use actix_web::{test, web, App};
+use futures::{SinkExt, StreamExt};
+use orvrm_core::services::engine::{BackendHealth, EngineKind};
+use orvrm_core::services::osrm::OsrmConfig;
+use orvrm_core::services::routing::{RoutingConfig, RoutingService};
+use orvrm_core::testing::StubOsrm;
+use orvrm_server::api::admin::AdminConfig;
+use orvrm_server::api::limits::RequestLimits;
+use orvrm_server::api::rate_limit::{rate_limit, RateLimitConfig, RateLimiter};
+use orvrm_server::api::readiness::Readiness;
+use orvrm_server::api::request_id::request_id;
+use orvrm_server::api::routes::{configure_routes, health_check};
+use orvrm_server::api::shutdown::ShutdownState;
+use orvrm_server::api::tenants::{tenant, TenantConfig, Tenants};
+use orvrm_server::services::jobs::JobStore;
+use orvrm_server::services::webhooks::{self, WebhookConfig, Webhooks, SIGNATURE_HEADER};
+use orvrm_server::storage::SolutionStore;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[actix_web::test]
+async fn test_health_check() {
+    let app = test::init_service(App::new().route("/health", web::get().to(health_check))).await;
+
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert!(resp.status().is_success());
+
+    let body = test::read_body(resp).await;
+    let response: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(response["status"], "ok");
+    assert!(response["version"].is_string());
+}
+
+#[actix_web::test]
+async fn test_api_routes_configuration() {
+    // Create a mock routing service
+    let osrm_config = OsrmConfig {
+        base_url: "http://localhost:5000".to_string(),
+        default_profile: "car".to_string(),
+        timeout_seconds: 30,
+        max_table_size: 100,
+        backends: Default::default(),
+        pool: Default::default(),
+    };
+
+    let routing_config = RoutingConfig {
+        osrm: osrm_config,
+        engine: EngineKind::Osrm,
+        valhalla: Default::default(),
+        graphhopper: Default::default(),
+        default_max_time: 300,
+        default_threads: 4,
+        traffic: Vec::new(),
+        max_snap_distance: 1000.0,
+        quick_search_above: None,
+        cluster_size: 100,
+        default_max_tasks: None,
+        lateness_penalty: 10.0,
+        late_task_penalty: 0.0,
+    };
+
+    let routing_service = RoutingService::new(routing_config);
+
+    // Initialize the test service with our route configuration
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(routing_service))
+            .configure(configure_routes),
+    )
+    .await;
+
+    // Test that the health endpoint is configured correctly
+    let req = test::TestRequest::get().uri("/api/health").to_request();
+    let resp = test::call_service(&app, req).await;
+
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_request_id_header() {
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(request_id))
+            .route("/health", web::get().to(health_check)),
+    )
+    .await;
+
+    // Requests without an ID get a new one
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let resp = test::call_service(&app, req).await;
+    let assigned = resp
+        .headers()
+        .get("X-Request-Id")
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(uuid::Uuid::parse_str(assigned).is_ok());
+
+    // The client's ID is returned as is
+    let req = test::TestRequest::get()
+        .uri("/health")
+        .insert_header(("X-Request-Id", "checkout-42"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Request-Id").unwrap(), "checkout-42");
+
+    // IDs that are unfit to log are replaced
+    let req = test::TestRequest::get()
+        .uri("/health")
+        .insert_header(("X-Request-Id", "a".repeat(200)))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Request-Id").unwrap().len(), 36);
+}
+
+#[actix_web::test]
+async fn test_liveness_and_readiness_probes() {
+    let osrm = StubOsrm::start();
+    let readiness = Readiness::new();
+    let shutdown = ShutdownState::new();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .app_data(web::Data::new(readiness.clone()))
+            .app_data(web::Data::new(shutdown.clone()))
+            .configure(configure_routes),
+    )
+    .await;
+    let probe = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+    let resp = test::call_service(&app, probe("/api/health/live")).await;
+    assert!(resp.status().is_success());
+
+    // Not ready before the backends were probed
+    let resp = test::call_service(&app, probe("/api/health/ready")).await;
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "starting");
+
+    readiness.update(vec![BackendHealth {
+        name: "default".to_string(),
+        base_url: "http://localhost:5000".to_string(),
+        healthy: true,
+        error: None,
+    }]);
+    let resp = test::call_service(&app, probe("/api/health/ready")).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["backends"][0]["healthy"], true);
+
+    readiness.update(vec![BackendHealth {
+        name: "default".to_string(),
+        base_url: "http://localhost:5000".to_string(),
+        healthy: false,
+        error: Some("connection refused".to_string()),
+    }]);
+    let resp = test::call_service(&app, probe("/api/health/ready")).await;
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "degraded");
+
+    // A draining server is alive but no longer ready
+    shutdown.begin();
+    let resp = test::call_service(&app, probe("/api/health/ready")).await;
+    assert_eq!(resp.status(), 503);
+    let resp = test::call_service(&app, probe("/api/health/live")).await;
+    assert!(resp.status().is_success());
+
+    // Without background probing the backends are probed on request
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+    let resp = test::call_service(&app, probe("/api/health/ready")).await;
+    assert!(resp.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_admin_settings() {
+    let service = RoutingService::new(RoutingConfig::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(service.clone()))
+            .app_data(web::Data::new(AdminConfig {
+                token: Some("s3cret".to_string()),
+            }))
+            .configure(configure_routes),
+    )
+    .await;
+    let get = |token: &str| {
+        test::TestRequest::get()
+            .uri("/api/admin/settings")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request()
+    };
+    let patch = |changes: serde_json::Value| {
+        test::TestRequest::patch()
+            .uri("/api/admin/settings")
+            .insert_header(("Authorization", "Bearer s3cret"))
+            .set_json(changes)
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, get("wrong")).await;
+    assert_eq!(resp.status(), 401);
+
+    let settings: serde_json::Value = test::call_and_read_body_json(&app, get("s3cret")).await;
+    assert_eq!(settings["default_max_time"], 30);
+
+    // Changes are merged into the settings of the shared service
+    let req = patch(serde_json::json!({"default_max_time": 5, "quick_search_above": 500}));
+    let settings: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(settings["default_max_time"], 5);
+    assert_eq!(settings["default_threads"], 4);
+    assert_eq!(service.settings().default_max_time, 5);
+    assert_eq!(service.settings().quick_search_above, Some(500));
+
+    // Invalid and unknown settings change nothing
+    for changes in [
+        serde_json::json!({"default_threads": 0}),
+        serde_json::json!({"cache_size": 10}),
+    ] {
+        let resp = test::call_service(&app, patch(changes)).await;
+        assert_eq!(resp.status(), 400);
+    }
+    assert_eq!(service.settings().default_threads, 4);
+
+    // Without a token the endpoints are off
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(service))
+            .configure(configure_routes),
+    )
+    .await;
+    let resp = test::call_service(&app, get("s3cret")).await;
+    assert_eq!(resp.status(), 501);
+}
+
+#[actix_web::test]
+async fn test_openapi_document() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri("/api/openapi.json")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+
+    let document: serde_json::Value = test::read_body_json(resp).await;
+    assert!(document["openapi"].as_str().unwrap().starts_with("3."));
+    let optimize = &document["paths"]["/api/optimize"]["post"];
+    assert_eq!(
+        optimize["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/RoutingRequest"
+    );
+    for schema in [
+        "RoutingRequest",
+        "RoutingResponse",
+        "Job",
+        "Vehicle",
+        "Shipment",
+    ] {
+        assert!(
+            document["components"]["schemas"][schema].is_object(),
+            "{}",
+            schema
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_json_schemas() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .configure(configure_routes),
+    )
+    .await;
+
+    for (uri, name, model) in [
+        ("/api/schema/request", "RoutingRequest", "Job"),
+        ("/api/schema/response", "RoutingResponse", "VehicleRoute"),
+    ] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/schema+json"
+        );
+
+        let schema: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(schema["$ref"], format!("#/$defs/{}", name));
+        assert!(schema["$defs"][name].is_object());
+        assert!(schema["$defs"][model].is_object(), "{}", model);
+
+        // Every reference resolves within the schema itself
+        let text = schema.to_string();
+        assert!(!text.contains("#/components/"));
+        for reference in text.split("\"#/$defs/").skip(1) {
+            let target = &reference[..reference.find('"').unwrap()];
+            assert!(schema["$defs"][target].is_object(), "{}", target);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn test_optimize_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [
+            {"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]},
+            {"id": 2, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10], "profile": "bike"}
+        ],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1], "allowed_vehicles": [1]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1], "allowed_vehicles": [1]}
+        ],
+        "options": {"geometry": true}
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    let route = &resp["routes"][0];
+    assert_eq!(route["vehicle_id"], 1);
+    assert_eq!(route["route"], serde_json::json!([1, 2]));
+    assert_eq!(route["duration"], 400);
+    assert_eq!(route["distance"], 4000);
+    assert_eq!(
+        route["arrival_times"],
+        serde_json::json!([0, 100, 200, 400])
+    );
+    assert!(route["polyline"].is_string());
+    assert_eq!(resp["summary"]["unassigned"], 0);
+
+    // The bike gets its own matrix
+    let requests = osrm.requests();
+    assert!(requests.contains(&"table/car".to_string()));
+    assert!(requests.contains(&"table/bike".to_string()));
+    assert!(requests.contains(&"route/car".to_string()));
+}
+
+#[actix_web::test]
+async fn test_versioned_optimize_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "version": 1,
+        "request": {
+            "vehicles": [{"id": 1, "start": [0.0, 0.0], "capacity": [10], "colour": "red"}],
+            "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/v1/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["routes"][0]["route"], serde_json::json!([1]));
+    assert_eq!(
+        resp["warnings"],
+        serde_json::json!(["Unknown field request.vehicles[0].colour was ignored"])
+    );
+
+    // Envelopes of other versions are rejected
+    let req = test::TestRequest::post()
+        .uri("/api/v1/optimize")
+        .set_json(serde_json::json!({"version": 2, "request": request["request"]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_optimize_predefined_route_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{
+            "id": 1,
+            "start": [0.0, 0.0],
+            "end": [0.0, 0.0],
+            "capacity": [10],
+            "steps": [{"type": "job", "id": 2}, {"type": "job", "id": 1}]
+        }],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 60, "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1]}
+        ]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    let route = &resp["routes"][0];
+    assert_eq!(route["route"], serde_json::json!([2, 1]));
+    assert_eq!(route["duration"], 400);
+    assert_eq!(
+        route["arrival_times"],
+        serde_json::json!([0, 200, 300, 460])
+    );
+    assert_eq!(
+        route["departure_times"],
+        serde_json::json!([0, 200, 360, 460])
+    );
+    assert_eq!(osrm.requests(), vec!["route/car".to_string()]);
+}
+
+#[actix_web::test]
+async fn test_reoptimize_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1]}
+        ]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let previous: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(previous["routes"][0]["route"], serde_json::json!([1, 2]));
+
+    // Job 1 has been dispatched, job 2 is cancelled and job 3 comes in
+    let mut route = previous["routes"][0].clone();
+    route["steps"][1]["locked"] = serde_json::json!(true);
+    let reoptimize = serde_json::json!({
+        "request": request,
+        "routes": [route],
+        "new_jobs": [{"id": 3, "location": [3.0, 0.0], "delivery": [1]}],
+        "cancelled_jobs": [2]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/reoptimize")
+        .set_json(&reoptimize)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["routes"][0]["route"], serde_json::json!([1, 3]));
+    assert_eq!(resp["summary"]["unassigned"], 0);
+
+    // Locked routes must belong to known vehicles
+    let mut unknown = reoptimize;
+    unknown["routes"][0]["vehicle_id"] = serde_json::json!(9);
+    let req = test::TestRequest::post()
+        .uri("/api/reoptimize")
+        .set_json(&unknown)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_matrix_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "locations": [[0.0, 0.0], [1.0, 0.0], [3.0, 0.0]],
+        "profile": "bike"
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/matrix")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    assert_eq!(resp["locations"], request["locations"]);
+    assert_eq!(resp["durations"][0], serde_json::json!([0.0, 100.0, 300.0]));
+    assert_eq!(resp["distances"][2][1], 2000.0);
+    assert_eq!(osrm.requests(), vec!["table/bike".to_string()]);
+
+    let req = test::TestRequest::post()
+        .uri("/api/matrix")
+        .set_json(serde_json::json!({"locations": [[0.0, 91.0]]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
+
+#[actix_web::test]
+async fn test_route_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicle": {"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]},
+        "stops": [
+            {"id": 2, "location": [2.0, 0.0], "service": 60, "time_windows": [[300, 1000]]},
+            {"id": 1, "location": [1.0, 0.0]}
+        ],
+        "options": {"geometry": true}
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/route")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // The stops keep their order, with a wait for the time window of the first one
+    assert_eq!(resp["route"], serde_json::json!([2, 1]));
+    assert_eq!(resp["distance"], 4000);
+    assert_eq!(resp["waiting_time"], 100);
+    assert_eq!(
+        resp["stops"],
+        serde_json::json!([
+            {"id": 2, "arrival_time": 200, "waiting_time": 100, "departure_time": 360},
+            {"id": 1, "arrival_time": 460, "waiting_time": 0, "departure_time": 460}
+        ])
+    );
+    assert!(resp["polyline"].is_string());
+
+    let req = test::TestRequest::post()
+        .uri("/api/route")
+        .set_json(serde_json::json!({
+            "vehicle": {"id": 1, "capacity": [10]},
+            "stops": [{"id": 1, "location": [1.0, 95.0]}]
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["errors"][0]["field"], "stops[0].location");
+}
+
+#[actix_web::test]
+async fn test_eta_from_current_position() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let steps = serde_json::json!([
+        {"type": "start", "location": [0.0, 0.0], "arrival_time": 0, "departure_time": 0},
+        {"type": "job", "id": 1, "location": [1.0, 0.0], "arrival_time": 100, "departure_time": 160},
+        {
+            "type": "job", "id": 2, "location": [2.0, 0.0],
+            "arrival_time": 260, "waiting_time": 40, "departure_time": 400
+        },
+        {"type": "end", "location": [0.0, 0.0], "arrival_time": 600}
+    ]);
+    let req = test::TestRequest::post()
+        .uri("/api/eta")
+        .set_json(serde_json::json!({
+            "steps": steps,
+            "current_position": [1.0, 0.0],
+            "current_time": 250,
+            "completed_stops": 1
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // Leaving job 1 late uses up the planned waiting at job 2 and delays the rest
+    assert_eq!(
+        resp["stops"],
+        serde_json::json!([
+            {"id": 2, "type": "job", "arrival_time": 350, "departure_time": 450, "delay": 90},
+            {"type": "end", "arrival_time": 650, "departure_time": 650, "delay": 50}
+        ])
+    );
+    assert_eq!(osrm.requests(), vec!["route/car".to_string()]);
+
+    let req = test::TestRequest::post()
+        .uri("/api/eta")
+        .set_json(serde_json::json!({
+            "steps": steps,
+            "current_position": [1.0, 0.0],
+            "current_time": 250,
+            "completed_stops": 3
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["errors"][0]["field"], "completed_stops");
+}
+
+#[actix_web::test]
+async fn test_validate_with_stub_osrm() {
+    let osrm = StubOsrm::start();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{
+            "id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [2],
+            "time_window": [0, 300],
+            "steps": [{"type": "job", "id": 1}, {"type": "job", "id": 2}]
+        }],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "pickup": [3]},
+            {"id": 2, "location": [2.0, 0.0], "time_windows": [[0, 100]]},
+            {"id": 3, "location": [3.0, 0.0]}
+        ]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/validate")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // The pickup overloads the vehicle from the first stop on, the second stop is late and
+    // the shift runs over
+    assert_eq!(resp["feasible"], false);
+    assert_eq!(resp["unplanned_jobs"], serde_json::json!([3]));
+    let route = &resp["routes"][0];
+    assert_eq!(route["stops"][0]["load"], serde_json::json!([3]));
+    assert_eq!(
+        route["stops"][0]["violations"],
+        serde_json::json!([{"type": "load", "amount": 1, "dimension": 0}])
+    );
+    assert_eq!(route["stops"][1]["arrival_time"], 200);
+    assert_eq!(
+        route["stops"][1]["violations"],
+        serde_json::json!([
+            {"type": "delay", "amount": 100},
+            {"type": "load", "amount": 1, "dimension": 0}
+        ])
+    );
+    assert_eq!(
+        route["violations"],
+        serde_json::json!([{"type": "delay", "amount": 100}])
+    );
+
+    let req = test::TestRequest::post()
+        .uri("/api/validate")
+        .set_json(serde_json::json!({
+            "vehicles": [{"id": 1, "capacity": [2], "steps": [{"type": "job", "id": 4}]}],
+            "jobs": [{"id": 1, "location": [1.0, 0.0]}]
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["errors"][0]["field"], "vehicles[0].steps[0]");
+}
+
+#[actix_web::test]
+async fn test_too_many_unassigned() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig::default())))
+            .configure(configure_routes),
+    )
+    .await;
+
+    // The far job costs more to serve than leaving it out, which the limit does not accept
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+            {"id": 2, "location": [10.0, 0.0], "delivery": [1]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [10.0, 0.0]],
+            "durations": [[0, 100, 1000], [100, 0, 900], [1000, 900, 0]]
+        },
+        "options": {"unassigned_penalty": 500.0, "max_unassigned": 0}
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"], "Too Many Unassigned");
+    assert_eq!(body["unassigned"], 1);
+    assert_eq!(body["total"], 2);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .ends_with("more than the 0 allowed by options.max_unassigned"));
+}
+
+#[actix_web::test]
+async fn test_delivery_before_pickup() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{
+            "id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [4],
+            "steps": [{"type": "delivery", "id": 1}, {"type": "pickup", "id": 1}]
+        }],
+        "jobs": [],
+        "shipments": [{
+            "id": 1, "amount": [1],
+            "pickup": {"location": [1.0, 0.0]},
+            "delivery": {"location": [2.0, 0.0]}
+        }],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    });
+
+    // Optimizing rejects the route as invalid input
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body["errors"],
+        serde_json::json!([{
+            "field": "vehicles[0].steps[0]",
+            "message": "Shipment 1 is delivered before it is picked up"
+        }])
+    );
+
+    // Checking its feasibility reports it at the delivery
+    let req = test::TestRequest::post()
+        .uri("/api/validate")
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let report: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(report["feasible"], false);
+    let stops = &report["routes"][0]["stops"];
+    assert_eq!(stops[0]["type"], "delivery");
+    assert_eq!(
+        stops[0]["violations"],
+        serde_json::json!([{"type": "precedence", "amount": 1}])
+    );
+    assert!(stops[1].get("violations").is_none());
+}
+
+/// Read server messages until the reply to the last client message, skipping progress
+async fn ws_reply<S>(framed: &mut S) -> serde_json::Value
+where
+    S: futures::Stream<Item = Result<awc::ws::Frame, awc::error::WsProtocolError>> + Unpin,
+{
+    loop {
+        let frame = framed.next().await.unwrap().unwrap();
+        let awc::ws::Frame::Text(text) = frame else {
+            continue;
+        };
+        let message: serde_json::Value = serde_json::from_slice(&text).unwrap();
+        if message["type"] != "progress" {
+            return message;
+        }
+    }
+}
+
+async fn ws_send<S>(framed: &mut S, message: serde_json::Value)
+where
+    S: futures::Sink<awc::ws::Message, Error = awc::error::WsProtocolError> + Unpin,
+{
+    framed
+        .send(awc::ws::Message::Text(message.to_string().into()))
+        .await
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn test_dispatch_websocket() {
+    let osrm = StubOsrm::start();
+    let config = osrm.config();
+    let mut srv = actix_test::start(move || {
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: config.clone(),
+                ..RoutingConfig::default()
+            })))
+            .configure(configure_routes)
+    });
+    let mut framed = srv.ws_at("/api/ws").await.unwrap();
+
+    // Updates need a problem first
+    ws_send(
+        &mut framed,
+        serde_json::json!({"type": "cancel", "jobs": [1]}),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+
+    ws_send(
+        &mut framed,
+        serde_json::json!({
+            "type": "solve",
+            "request": {
+                "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+                "jobs": [
+                    {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+                    {"id": 2, "location": [2.0, 0.0], "delivery": [1]}
+                ]
+            }
+        }),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "solution");
+    assert_eq!(
+        reply["solution"]["routes"][0]["route"],
+        serde_json::json!([1, 2])
+    );
+
+    // Job 2 is dispatched first from now on
+    ws_send(
+        &mut framed,
+        serde_json::json!({
+            "type": "lock",
+            "routes": [{"vehicle_id": 1, "steps": [{"type": "job", "id": 2, "locked": true}]}]
+        }),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "locked");
+
+    ws_send(
+        &mut framed,
+        serde_json::json!({
+            "type": "insert",
+            "jobs": [{"id": 3, "location": [3.0, 0.0], "delivery": [1]}]
+        }),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(
+        reply["solution"]["routes"][0]["route"],
+        serde_json::json!([2, 3, 1])
+    );
+
+    ws_send(
+        &mut framed,
+        serde_json::json!({"type": "cancel", "jobs": [3]}),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(
+        reply["solution"]["routes"][0]["route"],
+        serde_json::json!([2, 1])
+    );
+
+    // Invalid updates are reported without ending the session
+    ws_send(
+        &mut framed,
+        serde_json::json!({"type": "insert", "jobs": [{"id": 1, "location": [1.0, 0.0]}]}),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+    assert_eq!(reply["errors"][0]["field"], "jobs[2].id");
+
+    ws_send(&mut framed, serde_json::json!({"type": "unknown"})).await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+
+    ws_send(
+        &mut framed,
+        serde_json::json!({"type": "cancel", "jobs": [1]}),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(
+        reply["solution"]["routes"][0]["route"],
+        serde_json::json!([2])
+    );
+}
+
+#[actix_web::test]
+async fn test_optimize_job_stream() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(JobStore::new()))
+            .configure(configure_routes),
+    )
+    .await;
+
+    // A client matrix keeps OSRM out of the loop
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let job_id = resp["job_id"].as_str().unwrap().to_string();
+
+    // The stream ends once the job has completed
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}/stream", job_id))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.trim_end().ends_with('}'));
+    assert!(body.contains("event: completed\n"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}", job_id))
+        .to_request();
+    let status: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(status["status"], "completed");
+    assert_eq!(
+        status["result"]["routes"][0]["route"],
+        serde_json::json!([1])
+    );
+
+    // Unknown jobs are reported as such
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}", uuid::Uuid::new_v4()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+/// Signature header and body of every callback a receiver got
+type Deliveries = Arc<Mutex<Vec<(Option<String>, String)>>>;
+
+#[actix_web::test]
+async fn test_optimize_job_callback() {
+    // The receiver fails the first delivery and records every attempt
+    let deliveries = Deliveries::default();
+    let received = Arc::clone(&deliveries);
+    let receiver = actix_test::start(move || {
+        let received = Arc::clone(&received);
+        App::new().route(
+            "/done",
+            web::post().to(move |req: actix_web::HttpRequest, body: String| {
+                let received = Arc::clone(&received);
+                async move {
+                    let signature = req
+                        .headers()
+                        .get(SIGNATURE_HEADER)
+                        .map(|value| value.to_str().unwrap().to_string());
+                    let mut received = received.lock().unwrap();
+                    received.push((signature, body));
+                    if received.len() == 1 {
+                        actix_web::HttpResponse::InternalServerError().finish()
+                    } else {
+                        actix_web::HttpResponse::Ok().finish()
+                    }
+                }
+            }),
+        )
+    });
+
+    let webhooks = Webhooks::new(WebhookConfig {
+        secret: Some("s3cret".to_string()),
+        retry_delay_seconds: 0,
+        ..WebhookConfig::default()
+    });
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(JobStore::new().with_webhooks(webhooks)))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        },
+        "callback_url": receiver.url("/done")
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let job_id = resp["job_id"].as_str().unwrap().to_string();
+
+    for _ in 0..100 {
+        if deliveries.lock().unwrap().len() >= 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // The retry carries the signed job status
+    let deliveries = deliveries.lock().unwrap();
+    assert_eq!(deliveries.len(), 2);
+    let (signature, body) = &deliveries[1];
+    let expected = webhooks::signature("s3cret", body.as_bytes());
+    assert_eq!(signature.as_deref(), Some(expected.as_str()));
+    let status: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(status["job_id"], job_id.as_str());
+    assert_eq!(status["status"], "completed");
+    assert_eq!(status["result"]["routes"][0]["route"], serde_json::json!([1]));
+}
+
+#[actix_web::test]
+async fn test_rate_limiter_limits_concurrent_optimizations() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        enabled: true,
+        requests_per_minute: 10,
+        max_concurrent_optimizations: 1,
+        api_keys: Vec::new(),
+    });
+
+    let permit = limiter.check("key:a", true).unwrap();
+    assert!(permit.is_some());
+    assert_eq!(limiter.check("key:a", true).unwrap_err(), 1);
+
+    // Other clients and other requests are not affected
+    assert!(limiter.check("key:b", true).is_ok());
+    assert!(limiter.check("key:a", false).is_ok());
+
+    drop(permit);
+    assert!(limiter.check("key:a", true).is_ok());
+}
+
+#[actix_web::test]
+async fn test_rate_limit_middleware() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        enabled: true,
+        requests_per_minute: 2,
+        max_concurrent_optimizations: 1,
+        api_keys: vec!["tenant".to_string(), "other".to_string()],
+    });
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(rate_limit))
+            .app_data(web::Data::new(limiter))
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(JobStore::new()))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let status_request = |api_key: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/optimize/{}", uuid::Uuid::new_v4()))
+            .insert_header(("X-API-Key", api_key.to_string()))
+            .to_request()
+    };
+
+    for _ in 0..2 {
+        let resp = test::call_service(&app, status_request("tenant")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    let resp = test::call_service(&app, status_request("tenant")).await;
+    assert_eq!(
+        resp.status(),
+        actix_web::http::StatusCode::TOO_MANY_REQUESTS
+    );
+    assert!(resp.headers().contains_key("Retry-After"));
+
+    // Limits are tracked per API key, and health checks are exempt
+    let resp = test::call_service(&app, status_request("other")).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    let req = test::TestRequest::get()
+        .uri("/api/health")
+        .insert_header(("X-API-Key", "tenant"))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+
+    // Unknown API keys count against the address, however often they change
+    for attempt in 0..3 {
+        let resp = test::call_service(&app, status_request(&format!("random-{}", attempt))).await;
+        let expected = if attempt < 2 {
+            actix_web::http::StatusCode::NOT_FOUND
+        } else {
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        };
+        assert_eq!(resp.status(), expected);
+    }
+}
+
+#[actix_web::test]
+async fn test_solver_endpoints_take_optimization_slots() {
+    // No optimization may run, every other request is allowed
+    let limiter = RateLimiter::new(RateLimitConfig {
+        enabled: true,
+        requests_per_minute: 100,
+        max_concurrent_optimizations: 0,
+        api_keys: Vec::new(),
+    });
+    let service = RoutingService::new(RoutingConfig::default());
+    let app_limiter = limiter.clone();
+    let app_service = service.clone();
+    let mut srv = actix_test::start(move || {
+        App::new()
+            .wrap(actix_web::middleware::from_fn(rate_limit))
+            .app_data(web::Data::new(app_limiter.clone()))
+            .app_data(web::Data::new(app_service.clone()))
+            .app_data(web::Data::new(JobStore::new()))
+            .configure(configure_routes)
+    });
+
+    let solution_id = uuid::Uuid::new_v4();
+    for action in ["insert", "remove"] {
+        let resp = srv
+            .post(format!("/api/solutions/{}/{}", solution_id, action))
+            .send_json(&serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
+    }
+    let resp = srv
+        .get(format!("/api/optimize/{}", solution_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+    // Each WebSocket solve takes a slot of its own
+    let mut framed = srv.ws_at("/api/ws").await.unwrap();
+    ws_send(
+        &mut framed,
+        serde_json::json!({
+            "type": "solve",
+            "request": {
+                "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+                "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}]
+            }
+        }),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+    assert!(reply["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("Rate limit exceeded"));
+}
+
+#[actix_web::test]
+async fn test_optimize_rejects_oversized_requests() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(JobStore::new()))
+            .app_data(web::Data::new(RequestLimits {
+                max_jobs: Some(1),
+                ..RequestLimits::default()
+            }))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "service": 0, "delivery": [1]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    });
+
+    for uri in ["/api/optimize", "/api/optimize/async"] {
+        let req = test::TestRequest::post()
+            .uri(uri)
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(
+            body["message"],
+            "2 jobs and shipments exceed the limit of 1"
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_limits_apply_to_matrices_etas_validation_and_dispatching() {
+    let mut srv = actix_test::start(|| {
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig::default())))
+            .app_data(web::Data::new(RequestLimits {
+                max_jobs: Some(1),
+                max_matrix_size: Some(2),
+                ..RequestLimits::default()
+            }))
+            .configure(configure_routes)
+    });
+
+    let resp = srv
+        .post("/api/matrix")
+        .send_json(&serde_json::json!({
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]]
+        }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Two steps and the current position make three locations
+    let mut resp = srv
+        .post("/api/eta")
+        .send_json(&serde_json::json!({
+            "steps": [
+                {"type": "start", "location": [0.0, 0.0], "arrival_time": 0},
+                {"type": "end", "location": [0.0, 0.0], "arrival_time": 100}
+            ],
+            "current_position": [1.0, 0.0],
+            "current_time": 50,
+            "completed_stops": 0
+        }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["message"], "3 matrix locations exceed the limit of 2");
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "service": 0, "delivery": [1]}
+        ]
+    });
+    let mut resp = srv.post("/api/validate").send_json(&request).await.unwrap();
+    assert_eq!(resp.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["message"], "2 jobs and shipments exceed the limit of 1");
+
+    let mut framed = srv.ws_at("/api/ws").await.unwrap();
+    ws_send(
+        &mut framed,
+        serde_json::json!({"type": "solve", "request": request}),
+    )
+    .await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+    assert_eq!(
+        reply["message"],
+        "Request too large: 2 jobs and shipments exceed the limit of 1"
+    );
+}
+
+#[actix_web::test]
+async fn test_shutdown_drains_jobs_and_rejects_optimizations() {
+    let jobs = JobStore::new();
+    let shutdown = ShutdownState::new();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(jobs.clone()))
+            .app_data(web::Data::new(shutdown.clone()))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .set_json(&request)
+        .to_request();
+    assert_eq!(
+        test::call_service(&app, req).await.status(),
+        actix_web::http::StatusCode::ACCEPTED
+    );
+
+    // Running jobs finish, new optimizations are refused
+    shutdown.begin();
+    assert!(jobs.drain(std::time::Duration::from_secs(10)).await);
+    assert_eq!(jobs.running(), 0);
+
+    for uri in ["/api/optimize", "/api/optimize/async"] {
+        let req = test::TestRequest::post()
+            .uri(uri)
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_shutdown_drains_and_refuses_websocket_solves() {
+    let jobs = JobStore::new();
+    let shutdown = ShutdownState::new();
+    let app_jobs = jobs.clone();
+    let app_shutdown = shutdown.clone();
+    let mut srv = actix_test::start(move || {
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig::default())))
+            .app_data(web::Data::new(app_jobs.clone()))
+            .app_data(web::Data::new(app_shutdown.clone()))
+            .configure(configure_routes)
+    });
+    let mut framed = srv.ws_at("/api/ws").await.unwrap();
+    let solve = serde_json::json!({
+        "type": "solve",
+        "request": {
+            "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+            "jobs": [{"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+            "matrix": {
+                "locations": [[0.0, 0.0], [1.0, 0.0]],
+                "durations": [[0, 100], [100, 0]]
+            }
+        }
+    });
+    ws_send(&mut framed, solve.clone()).await;
+    assert_eq!(ws_reply(&mut framed).await["type"], "solution");
+    assert_eq!(jobs.running(), 0);
+
+    // Draining waits for solves running outside of jobs
+    let running = jobs.track();
+    assert_eq!(jobs.running(), 1);
+    assert!(!jobs.drain(Duration::from_millis(200)).await);
+    drop(running);
+    assert!(jobs.drain(Duration::from_secs(1)).await);
+
+    // Open sessions can no longer solve once the server drains
+    shutdown.begin();
+    ws_send(&mut framed, solve).await;
+    let reply = ws_reply(&mut framed).await;
+    assert_eq!(reply["type"], "error");
+    assert!(reply["message"]
+        .as_str()
+        .unwrap()
+        .contains("shutting down"));
+}
+
+#[actix_web::test]
+async fn test_solutions_are_stored_and_retrievable() {
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}", solution_id))
+        .to_request();
+    let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(stored["request"]["jobs"][0]["id"], 1);
+    assert_eq!(stored["response"]["routes"], resp["routes"]);
+
+    // Listings accept Unix seconds and RFC 3339 timestamps
+    let req = test::TestRequest::get()
+        .uri("/api/solutions?since=0")
+        .to_request();
+    let list: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(list["solutions"][0]["id"], solution_id.as_str());
+    assert_eq!(list["solutions"][0]["summary"]["routes"], 1);
+
+    let req = test::TestRequest::get()
+        .uri("/api/solutions?since=2999-01-01T00:00:00Z")
+        .to_request();
+    let list: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(list["solutions"], serde_json::json!([]));
+
+    let req = test::TestRequest::get()
+        .uri("/api/solutions?since=yesterday")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}", uuid::Uuid::new_v4()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_solutions_are_trimmed_to_the_requested_parts() {
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [
+            {"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [1]},
+            {"id": 2, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [1]},
+            {"id": 3, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [1]}
+        ],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "service": 0, "delivery": [1]},
+            {"id": 3, "location": [3.0, 0.0], "service": 0, "delivery": [1]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]],
+            "durations": [[0, 100, 200, 300], [100, 0, 100, 200], [200, 100, 0, 100], [300, 200, 100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize?summary_only=true")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["summary"]["routes"], 3);
+    assert!(resp.get("routes").is_none());
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+
+    // The stored solution keeps all routes
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}?vehicles=2", solution_id))
+        .to_request();
+    let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(stored["request"]["jobs"][0]["id"], 1);
+    assert_eq!(stored["response"]["routes"].as_array().unwrap().len(), 1);
+    assert_eq!(stored["response"]["routes"][0]["vehicle_id"], 2);
+    assert_eq!(stored["response"]["summary"]["routes"], 3);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}?page=2&per_page=2", solution_id))
+        .to_request();
+    let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(stored["response"]["routes"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        stored["response"]["page"],
+        serde_json::json!({"page": 2, "per_page": 2, "total_routes": 3, "total_pages": 2})
+    );
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}?fields=summary.cost,routes.vehicle_id", solution_id))
+        .to_request();
+    let stored: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert!(stored.get("request").is_none());
+    assert_eq!(stored["id"], solution_id.as_str());
+    let response = stored["response"].as_object().unwrap();
+    assert_eq!(response.len(), 2);
+    assert_eq!(response["summary"].as_object().unwrap().len(), 1);
+    assert_eq!(
+        response["routes"],
+        serde_json::json!([{"vehicle_id": 1}, {"vehicle_id": 2}, {"vehicle_id": 3}])
+    );
+
+    for query in ["vehicles=two", "page=0"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/solutions/{}?{}", solution_id, query))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn test_tenants_have_own_routing_limits_and_solutions() {
+    let osrm = StubOsrm::start();
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let routing_service = RoutingService::new(RoutingConfig::default());
+    let rate_limiter = RateLimiter::new(RateLimitConfig::default());
+    let jobs = JobStore::new().with_storage(storage.clone());
+    let configs = [
+        (
+            "acme".to_string(),
+            TenantConfig {
+                api_key: "acme-key".to_string(),
+                osrm_url: None,
+                default_profile: None,
+                rate_limit: None,
+                limits: Some(RequestLimits {
+                    max_jobs: Some(1),
+                    ..RequestLimits::default()
+                }),
+            },
+        ),
+        (
+            "globex".to_string(),
+            TenantConfig {
+                api_key: "globex-key".to_string(),
+                osrm_url: Some(osrm.base_url().to_string()),
+                default_profile: Some("truck".to_string()),
+                rate_limit: None,
+                limits: None,
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let tenants = Tenants::new(
+        &configs,
+        &routing_service,
+        &rate_limiter,
+        &RequestLimits::default(),
+        Some(&storage),
+        &jobs,
+    );
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(rate_limit))
+            .wrap(actix_web::middleware::from_fn(tenant))
+            .app_data(web::Data::new(routing_service))
+            .app_data(web::Data::new(rate_limiter))
+            .app_data(web::Data::new(RequestLimits::default()))
+            .app_data(web::Data::new(jobs))
+            .app_data(web::Data::new(storage))
+            .app_data(web::Data::new(tenants))
+            .configure(configure_routes),
+    )
+    .await;
+
+    // Health checks are open, everything else needs the key of a tenant
+    let req = test::TestRequest::get().uri("/api/health").to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+    for api_key in [None, Some("unknown")] {
+        let mut req = test::TestRequest::get().uri("/api/solutions");
+        if let Some(api_key) = api_key {
+            req = req.insert_header(("X-API-Key", api_key));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    // Each tenant has its own size limits
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "service": 0, "delivery": [1]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+            "durations": [[0, 100, 200], [100, 0, 100], [200, 100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .insert_header(("X-API-Key", "acme-key"))
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+    let mut small = request.clone();
+    small["jobs"].as_array_mut().unwrap().truncate(1);
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .insert_header(("X-API-Key", "acme-key"))
+        .set_json(&small)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+
+    // Solutions are only visible to the tenant that stored them
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}", solution_id))
+        .insert_header(("X-API-Key", "acme-key"))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}", solution_id))
+        .insert_header(("X-API-Key", "globex-key"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+    for (api_key, count) in [("acme-key", 1), ("globex-key", 0)] {
+        let req = test::TestRequest::get()
+            .uri("/api/solutions")
+            .insert_header(("X-API-Key", api_key))
+            .to_request();
+        let list: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list["solutions"].as_array().unwrap().len(), count);
+    }
+
+    // So are asynchronous jobs, though all tenants share the job store
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .insert_header(("X-API-Key", "acme-key"))
+        .set_json(&small)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let job_id = resp["job_id"].as_str().unwrap().to_string();
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}/stream", job_id))
+        .insert_header(("X-API-Key", "acme-key"))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert!(String::from_utf8(body.to_vec()).unwrap().contains("event: completed\n"));
+    for uri in [format!("/api/optimize/{}", job_id), format!("/api/optimize/{}/stream", job_id)] {
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("X-API-Key", "globex-key"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    // The second tenant routes with its own OSRM server and default profile
+    let mut routed = request.clone();
+    routed.as_object_mut().unwrap().remove("matrix");
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .insert_header(("X-API-Key", "globex-key"))
+        .set_json(&routed)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["summary"]["unassigned"], 0);
+    assert!(osrm.requests().contains(&"table/truck".to_string()));
+}
+
+#[actix_web::test]
+async fn test_insert_into_stored_solution() {
+    let osrm = StubOsrm::start();
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RoutingService::new(RoutingConfig {
+                osrm: osrm.config(),
+                ..RoutingConfig::default()
+            })))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+            {"id": 2, "location": [3.0, 0.0], "delivery": [1]}
+        ]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+    let route = resp["routes"][0]["route"].clone();
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/solutions/{}/insert", solution_id))
+        .set_json(serde_json::json!({
+            "jobs": [{"id": 3, "location": [3.0, 0.0], "service": 60, "delivery": [1]}]
+        }))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // The new job joins the job at its location for free and the others keep their order
+    let inserted: Vec<u64> = serde_json::from_value(resp["routes"][0]["route"].clone()).unwrap();
+    let kept: Vec<u64> = inserted.iter().copied().filter(|id| *id != 3).collect();
+    assert_eq!(serde_json::to_value(&kept).unwrap(), route);
+    let position = inserted.iter().position(|id| *id == 3).unwrap();
+    let next_to_job = [position.checked_sub(1), Some(position + 1)]
+        .into_iter()
+        .flatten()
+        .any(|neighbour| inserted.get(neighbour) == Some(&2));
+    assert!(next_to_job);
+    assert_eq!(resp["insertion_cost"], 0.0);
+    assert_ne!(resp["solution_id"], solution_id.as_str());
+
+    // All locations were known, so the matrix came from the cache
+    let tables = osrm.requests().iter().filter(|r| r.starts_with("table")).count();
+    assert_eq!(tables, 1);
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/solutions/{}/insert", solution_id))
+        .set_json(serde_json::json!({"jobs": [{"id": 2, "location": [2.0, 0.0]}]}))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["errors"][0]["field"], "jobs[0].id");
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/solutions/{}/insert", uuid::Uuid::new_v4()))
+        .set_json(serde_json::json!({"jobs": [{"id": 4, "location": [2.0, 0.0]}]}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_remove_from_stored_solution() {
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1]},
+            {"id": 3, "location": [3.0, 0.0], "delivery": [1]}
+        ],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]],
+            "durations": [[0, 100, 200, 300], [100, 0, 100, 200], [200, 100, 0, 100], [300, 200, 100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+    let route: Vec<u64> = serde_json::from_value(resp["routes"][0]["route"].clone()).unwrap();
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/solutions/{}/remove", solution_id))
+        .set_json(serde_json::json!({"jobs": [3]}))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+    // The route skips job 3 and is timed again
+    let kept: Vec<u64> = route.into_iter().filter(|id| *id != 3).collect();
+    assert_eq!(resp["routes"][0]["route"], serde_json::to_value(&kept).unwrap());
+    assert_eq!(resp["routes"][0]["duration"], 400);
+    assert_ne!(resp["solution_id"], solution_id.as_str());
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/solutions/{}/remove", solution_id))
+        .set_json(serde_json::json!({"jobs": [9]}))
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(resp["errors"][0]["field"], "jobs[0]");
+    assert_eq!(resp["errors"][0]["message"], "Unknown job ID 9");
+}
+
+#[actix_web::test]
+async fn test_optimize_as_csv() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 7, "location": [1.0, 0.0], "service": 30, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]],
+            "distances": [[0, 900], [900, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .insert_header(("Accept", "text/csv"))
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let rows: Vec<&str> = body.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert!(rows[0].starts_with("vehicle_id,sequence,type,id,"));
+    assert!(rows[1].starts_with("1,0,start,,0,0,"));
+    assert!(rows[2].starts_with("1,1,job,7,100,130,30,"));
+    assert!(rows[3].starts_with("1,2,end,,230,230,"));
+    assert!(rows[3].ends_with(",0,1800"));
+}
+
+#[actix_web::test]
+async fn test_optimize_as_msgpack() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 7, "location": [1.0, 0.0], "service": 30, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize?fields=routes.vehicle_id")
+        .insert_header(("Accept", "application/msgpack"))
+        .set_json(&request)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(resp.headers().get("content-type").unwrap(), "application/msgpack");
+    let body = test::read_body(resp).await;
+    let mut expected = vec![0x81, 0xa6];
+    expected.extend_from_slice(b"routes");
+    expected.extend_from_slice(&[0x91, 0x81, 0xaa]);
+    expected.extend_from_slice(b"vehicle_id");
+    expected.push(0x01);
+    assert_eq!(body.to_vec(), expected);
+}
+
+#[actix_web::test]
+async fn test_export_stored_solution() {
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 7, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/export/{}?format=vroom", solution_id))
+        .to_request();
+    let exported: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(exported["problem"]["jobs"][0]["id"], 7);
+    assert_eq!(exported["solution"]["routes"][0]["vehicle"], 1);
+    assert_eq!(exported["solution"]["routes"][0]["steps"][1]["id"], 7);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/export/{}?format=jsprit", solution_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/xml"
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("<serviceId>7</serviceId>"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/export/{}?format=ortools", solution_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/api/export/{}?format=vroom",
+            uuid::Uuid::new_v4()
+        ))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_vehicle_calendar() {
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                RoutingService::new(RoutingConfig::default()),
+            ))
+            .app_data(web::Data::new(storage))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{
+            "id": 3, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10],
+            "time_window": [1714550400, 1714593600]
+        }],
+        "jobs": [{"id": 7, "location": [1.0, 0.0], "service": 600, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 1800], [1800, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let solution_id = resp["solution_id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/solutions/{}/vehicles/3.ics", solution_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/calendar; charset=utf-8"
+    );
+    let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(body.contains("SUMMARY:Job 7\r\n"));
+    assert!(body.contains("DTSTART:20240501T083000Z\r\nDTEND:20240501T084000Z\r\n"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/solutions/{}/vehicles/4.ics", solution_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,43 @@
+use orvrm_core::services::RoutingConfig;
+use orvrm_server::cli;
+use std::fs;
+
+#[actix_web::test]
+async fn test_solve_from_file() {
+    let dir = std::env::temp_dir().join(format!("orvrm-cli-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("problem.json");
+    let output = dir.join("solution.json");
+
+    // A client matrix keeps OSRM out of the loop
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "service": 0, "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    fs::write(&input, request.to_string()).unwrap();
+
+    cli::solve(RoutingConfig::default(), &input, Some(&output))
+        .await
+        .unwrap();
+    let solution: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(solution["routes"][0]["route"], serde_json::json!([1]));
+    assert_eq!(solution["summary"]["duration"], 200);
+
+    // Invalid requests are rejected with their field errors
+    fs::write(
+        &input,
+        serde_json::json!({"vehicles": [], "jobs": []}).to_string(),
+    )
+    .unwrap();
+    let err = cli::solve(RoutingConfig::default(), &input, Some(&output))
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("vehicles: At least one vehicle is required"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
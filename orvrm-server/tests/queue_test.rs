@@ -0,0 +1,598 @@
+use actix_web::{test, web, App};
+use orvrm_core::models::RoutingRequest;
+use orvrm_core::services::routing::{RoutingConfig, RoutingService};
+use orvrm_core::services::OsrmConfig;
+use orvrm_core::testing::StubOsrm;
+use orvrm_server::api::limits::RequestLimits;
+use orvrm_server::api::rate_limit::{RateLimitConfig, RateLimiter};
+use orvrm_server::api::routes::configure_routes;
+use orvrm_server::api::tenants::{tenant, TenantConfig, Tenants};
+use orvrm_server::services::jobs::{JobSnapshot, JobStatus, JobStore};
+use orvrm_server::services::nats::{NatsConfig, NatsConsumer};
+use orvrm_server::services::queue::{JobBackend, JobQueueConfig, MemoryBackend, QueuedJob};
+use orvrm_server::services::redis::RedisBackend;
+use orvrm_server::services::webhooks::{WebhookConfig, Webhooks};
+use orvrm_server::storage::SolutionStore;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+#[actix_web::test]
+async fn test_queued_job_runs_on_another_server() {
+    let backend = Arc::new(MemoryBackend::new());
+    let service = RoutingService::new(RoutingConfig::default());
+
+    // This server only accepts jobs; the worker server optimizes them
+    let accepting = JobStore::new().with_backend(backend.clone());
+    let worker = JobStore::new().with_backend(backend.clone());
+    worker.spawn_workers(service.clone(), Tenants::default(), 1);
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(service))
+            .app_data(web::Data::new(accepting))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let job_id = resp["job_id"].as_str().unwrap().to_string();
+
+    // The stream follows the job until the worker has finished it
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}/stream", job_id))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("event: completed\n"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}", job_id))
+        .to_request();
+    let status: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(status["status"], "completed");
+    assert_eq!(
+        status["result"]["routes"][0]["route"],
+        serde_json::json!([1])
+    );
+
+    // Draining stops the worker from taking further jobs
+    assert!(worker.drain(Duration::from_secs(5)).await);
+}
+
+#[actix_web::test]
+async fn test_queued_tenant_jobs_run_with_the_tenant_services() {
+    let osrm = StubOsrm::start();
+    let backend = Arc::new(MemoryBackend::new());
+    let storage = SolutionStore::connect("sqlite::memory:").await.unwrap();
+    let configs: HashMap<String, TenantConfig> = ["acme", "globex"]
+        .into_iter()
+        .map(|name| {
+            let config = TenantConfig {
+                api_key: format!("{}-key", name),
+                osrm_url: (name == "globex").then(|| osrm.base_url().to_string()),
+                default_profile: (name == "globex").then(|| "truck".to_string()),
+                rate_limit: None,
+                limits: None,
+            };
+            (name.to_string(), config)
+        })
+        .collect();
+
+    // The deployment's own OSRM server is unreachable, only the tenant's can route
+    let service = RoutingService::new(RoutingConfig::default());
+    let tenants_of = |jobs: &JobStore| {
+        Tenants::new(
+            &configs,
+            &service,
+            &RateLimiter::new(RateLimitConfig::default()),
+            &RequestLimits::default(),
+            Some(&storage),
+            jobs,
+        )
+    };
+    let accepting = JobStore::new()
+        .with_backend(backend.clone())
+        .with_storage(storage.clone());
+    let worker = JobStore::new()
+        .with_backend(backend.clone())
+        .with_storage(storage.clone());
+    worker.spawn_workers(service.clone(), tenants_of(&worker), 1);
+    let app = test::init_service(
+        App::new()
+            .wrap(actix_web::middleware::from_fn(tenant))
+            .app_data(web::Data::new(service.clone()))
+            .app_data(web::Data::new(tenants_of(&accepting)))
+            .app_data(web::Data::new(accepting))
+            .app_data(web::Data::new(storage.clone()))
+            .configure(configure_routes),
+    )
+    .await;
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}]
+    });
+    let req = test::TestRequest::post()
+        .uri("/api/optimize/async")
+        .insert_header(("X-API-Key", "globex-key"))
+        .set_json(&request)
+        .to_request();
+    let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let job_id = resp["job_id"].as_str().unwrap().to_string();
+
+    // Other tenants cannot follow or look up the job
+    for uri in [format!("/api/optimize/{}", job_id), format!("/api/optimize/{}/stream", job_id)] {
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("X-API-Key", "acme-key"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/optimize/{}/stream", job_id))
+        .insert_header(("X-API-Key", "globex-key"))
+        .to_request();
+    let body = test::call_and_read_body(&app, req).await;
+    assert!(String::from_utf8(body.to_vec()).unwrap().contains("event: completed\n"));
+
+    // The worker routed with the tenant's OSRM server and profile and stored the solution
+    // with the tenant's solutions
+    assert!(osrm.requests().contains(&"table/truck".to_string()));
+    let job_id: uuid::Uuid = job_id.parse().unwrap();
+    assert!(storage.for_tenant("globex").get(job_id).await.unwrap().is_some());
+    assert!(storage.for_tenant("acme").get(job_id).await.unwrap().is_none());
+    assert!(storage.get(job_id).await.unwrap().is_none());
+
+    assert!(worker.drain(Duration::from_secs(5)).await);
+}
+
+#[actix_web::test]
+async fn test_callbacks_do_not_hold_up_the_worker() {
+    // The callback receiver accepts connections but never answers
+    let receiver = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let callback_url = format!("http://{}/done", receiver.local_addr().unwrap());
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = receiver.accept().await {
+            held.push(stream);
+        }
+    });
+
+    let backend = Arc::new(MemoryBackend::new());
+    let worker = JobStore::new()
+        .with_backend(backend.clone())
+        .with_webhooks(Webhooks::new(WebhookConfig {
+            max_retries: 0,
+            timeout_seconds: 60,
+            ..WebhookConfig::default()
+        }));
+    worker.spawn_workers(
+        RoutingService::new(RoutingConfig::default()),
+        Tenants::default(),
+        1,
+    );
+
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        },
+        "callback_url": callback_url
+    }))
+    .unwrap();
+    let jobs: Vec<QueuedJob> = (0..2)
+        .map(|_| QueuedJob {
+            job_id: uuid::Uuid::new_v4(),
+            tenant: None,
+            request: request.clone(),
+        })
+        .collect();
+    for job in &jobs {
+        backend.push(job).await.unwrap();
+    }
+
+    // The single worker finishes the second job while the first callback is pending
+    let finished = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let snapshot = backend.load(None, jobs[1].job_id).await.unwrap().unwrap();
+            if snapshot.status == JobStatus::Completed {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(finished.is_ok());
+}
+
+#[actix_web::test]
+async fn test_finished_jobs_are_forgotten() {
+    let store = JobStore::new().with_retention(Duration::from_millis(200));
+    let service = RoutingService::new(RoutingConfig::default());
+    let request: RoutingRequest = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    }))
+    .unwrap();
+
+    let job_id = store.submit(service.clone(), request.clone()).await.unwrap();
+    assert!(store.drain(Duration::from_secs(10)).await);
+    let status = store.status(job_id).await.unwrap();
+    assert_eq!(status.status, JobStatus::Completed);
+
+    // Once the retention time has passed, the result is dropped with the next lookup
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(store.status(job_id).await.is_none());
+    assert!(store.subscribe(job_id).await.is_none());
+}
+
+#[actix_web::test]
+async fn test_redis_backend() {
+    let redis = StubRedis::start().await;
+    let backend = RedisBackend::connect(&JobQueueConfig {
+        redis_url: Some(format!("redis://:secret@{}/2", redis.address)),
+        ..JobQueueConfig::default()
+    })
+    .await
+    .unwrap();
+
+    let request = serde_json::from_value(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0]}]
+    }))
+    .unwrap();
+    let job = QueuedJob {
+        job_id: uuid::Uuid::new_v4(),
+        tenant: None,
+        request,
+    };
+    backend.push(&job).await.unwrap();
+
+    // Queued jobs are recorded as running and taken once
+    let snapshot = backend.load(None, job.job_id).await.unwrap().unwrap();
+    assert_eq!(snapshot.status, JobStatus::Running);
+    let taken = backend.pop(Duration::from_secs(1)).await.unwrap().unwrap();
+    assert_eq!(taken.job_id, job.job_id);
+    assert_eq!(taken.tenant, None);
+    assert_eq!(taken.request.jobs[0].id, 1);
+    assert!(backend.pop(Duration::from_secs(1)).await.unwrap().is_none());
+
+    let mut failed = JobSnapshot::running(job.job_id);
+    failed.status = JobStatus::Failed;
+    failed.error = Some("No vehicles".to_string());
+    backend.save(None, &failed).await.unwrap();
+    let snapshot = backend.load(None, job.job_id).await.unwrap().unwrap();
+    assert_eq!(snapshot.status, JobStatus::Failed);
+    assert!(backend.load(None, uuid::Uuid::new_v4()).await.unwrap().is_none());
+
+    // Jobs of a tenant travel with the tenant and keep their state under its keys
+    let tenant_job = QueuedJob {
+        job_id: uuid::Uuid::new_v4(),
+        tenant: Some("acme".to_string()),
+        ..job
+    };
+    backend.push(&tenant_job).await.unwrap();
+    let taken = backend.pop(Duration::from_secs(1)).await.unwrap().unwrap();
+    assert_eq!(taken.tenant.as_deref(), Some("acme"));
+    assert!(backend
+        .load(Some("acme"), tenant_job.job_id)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(backend.load(None, tenant_job.job_id).await.unwrap().is_none());
+    assert!(backend
+        .load(Some("globex"), tenant_job.job_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Every connection authenticates and selects the database first
+    let commands = redis.commands();
+    assert_eq!(&commands[..3], ["AUTH secret", "SELECT 2", "PING"]);
+    assert!(commands
+        .iter()
+        .any(|c| c.starts_with("SET orvrm:job:") && c.ends_with(" EX 86400")));
+    assert!(commands
+        .iter()
+        .any(|c| c.starts_with("SET orvrm:tenant:acme:job:")));
+    assert!(commands.iter().any(|c| c.starts_with("LPUSH orvrm:queue ")));
+}
+
+#[actix_web::test]
+async fn test_nats_consumer() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let config = NatsConfig {
+        url: Some(format!("nats://user:secret@{}", address)),
+        max_concurrent: 1,
+        ..NatsConfig::default()
+    };
+    let service = RoutingService::new(RoutingConfig::default());
+    let limits = RequestLimits {
+        max_jobs: Some(1),
+        ..RequestLimits::default()
+    };
+    let starting = tokio::spawn(async move {
+        NatsConsumer::start(&config, service, JobStore::new(), limits).await
+    });
+    let mut stream = BufReader::new(listener.accept().await.unwrap().0);
+    stream
+        .get_mut()
+        .write_all(b"INFO {\"server_id\":\"stub\"}\r\n")
+        .await
+        .unwrap();
+
+    // The consumer authenticates, joins the queue group and waits for the server to answer
+    let mut handshake = Vec::new();
+    for _ in 0..3 {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        handshake.push(line.trim_end().to_string());
+    }
+    let options: serde_json::Value =
+        serde_json::from_str(handshake[0].strip_prefix("CONNECT ").unwrap()).unwrap();
+    assert_eq!(options["user"], "user");
+    assert_eq!(options["pass"], "secret");
+    assert_eq!(handshake[1..], ["SUB orvrm.requests orvrm 1", "PING"]);
+    stream.get_mut().write_all(b"PONG\r\n").await.unwrap();
+    starting.await.unwrap().unwrap();
+
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    })
+    .to_string();
+    let oversized = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [
+            {"id": 1, "location": [1.0, 0.0], "delivery": [1]},
+            {"id": 2, "location": [2.0, 0.0], "delivery": [1]}
+        ]
+    })
+    .to_string();
+    let message = format!(
+        "PING\r\nMSG orvrm.requests 1 _INBOX.7 {}\r\n{}\r\nMSG orvrm.requests 1 4\r\nnope\r\nMSG orvrm.requests 1 _INBOX.8 {}\r\n{}\r\n",
+        request.len(),
+        request,
+        oversized.len(),
+        oversized
+    );
+    stream.get_mut().write_all(message.as_bytes()).await.unwrap();
+
+    // Messages are optimized one at a time, solutions go to the reply subject and other
+    // outcomes to the result subject
+    let mut published = HashMap::new();
+    while published.len() < 3 {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        let args: Vec<&str> = line.split_whitespace().collect();
+        if args[0] != "PUB" {
+            assert_eq!(args, ["PONG"]);
+            continue;
+        }
+        let mut payload = vec![0; args[2].parse::<usize>().unwrap() + 2];
+        stream.read_exact(&mut payload).await.unwrap();
+        payload.truncate(payload.len() - 2);
+        let snapshot: JobSnapshot = serde_json::from_slice(&payload).unwrap();
+        published.insert(args[1].to_string(), snapshot);
+    }
+    let solved = &published["_INBOX.7"];
+    assert_eq!(solved.status, JobStatus::Completed);
+    assert_eq!(solved.result.as_ref().unwrap().routes[0].route, vec![1]);
+    let invalid = &published["orvrm.results"];
+    assert_eq!(invalid.status, JobStatus::Failed);
+    assert!(invalid.error.as_ref().unwrap().starts_with("Invalid request"));
+    let oversized = &published["_INBOX.8"];
+    assert_eq!(oversized.status, JobStatus::Failed);
+    assert!(oversized
+        .error
+        .as_ref()
+        .unwrap()
+        .ends_with("2 jobs and shipments exceed the limit of 1"));
+}
+
+#[actix_web::test]
+async fn test_nats_consumer_while_busy() {
+    // OSRM accepts connections but never answers, keeping the only slot busy
+    let osrm = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let osrm_address = osrm.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((stream, _)) = osrm.accept().await {
+            held.push(stream);
+        }
+    });
+    let service = RoutingService::new(RoutingConfig {
+        osrm: OsrmConfig {
+            base_url: format!("http://{}", osrm_address),
+            timeout_seconds: 1,
+            ..OsrmConfig::default()
+        },
+        ..RoutingConfig::default()
+    });
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let config = NatsConfig {
+        url: Some(format!("nats://{}", address)),
+        max_concurrent: 1,
+        ..NatsConfig::default()
+    };
+    let starting = tokio::spawn(async move {
+        NatsConsumer::start(&config, service, JobStore::new(), RequestLimits::default()).await
+    });
+    let mut stream = nats_handshake(&listener).await;
+    starting.await.unwrap().unwrap();
+
+    let stuck = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}]
+    })
+    .to_string();
+    let request = serde_json::json!({
+        "vehicles": [{"id": 1, "start": [0.0, 0.0], "end": [0.0, 0.0], "capacity": [10]}],
+        "jobs": [{"id": 1, "location": [1.0, 0.0], "delivery": [1]}],
+        "matrix": {
+            "locations": [[0.0, 0.0], [1.0, 0.0]],
+            "durations": [[0, 100], [100, 0]]
+        }
+    })
+    .to_string();
+    let message = format!(
+        "MSG orvrm.requests 1 _INBOX.1 {}\r\n{}\r\nMSG orvrm.requests 1 _INBOX.2 {}\r\n{}\r\nPING\r\n",
+        stuck.len(),
+        stuck,
+        request.len(),
+        request
+    );
+    stream.get_mut().write_all(message.as_bytes()).await.unwrap();
+
+    // Pings are answered while the second message waits for the slot
+    let mut line = String::new();
+    stream.read_line(&mut line).await.unwrap();
+    assert_eq!(line, "PONG\r\n");
+
+    // Outcomes of the messages are published once the consumer has reconnected
+    drop(stream);
+    let mut stream = nats_handshake(&listener).await;
+    let mut published = HashMap::new();
+    while published.len() < 2 {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        let args: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(args[0], "PUB");
+        let mut payload = vec![0; args[2].parse::<usize>().unwrap() + 2];
+        stream.read_exact(&mut payload).await.unwrap();
+        payload.truncate(payload.len() - 2);
+        let snapshot: JobSnapshot = serde_json::from_slice(&payload).unwrap();
+        published.insert(args[1].to_string(), snapshot);
+    }
+    assert_eq!(published["_INBOX.1"].status, JobStatus::Failed);
+    assert_eq!(published["_INBOX.2"].status, JobStatus::Completed);
+}
+
+/// Accept a connection of the NATS consumer and answer its subscription
+async fn nats_handshake(listener: &TcpListener) -> BufReader<TcpStream> {
+    let mut stream = BufReader::new(listener.accept().await.unwrap().0);
+    stream
+        .get_mut()
+        .write_all(b"INFO {\"server_id\":\"stub\"}\r\n")
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+    }
+    stream.get_mut().write_all(b"PONG\r\n").await.unwrap();
+    stream
+}
+
+/// Redis server keeping strings and lists in memory, for the commands the backend uses
+struct StubRedis {
+    address: String,
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+#[derive(Default)]
+struct StubData {
+    strings: HashMap<String, String>,
+    lists: HashMap<String, VecDeque<String>>,
+}
+
+impl StubRedis {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let data = Arc::new(Mutex::new(StubData::default()));
+
+        let log = Arc::clone(&commands);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&log), Arc::clone(&data)));
+            }
+        });
+        Self { address, commands }
+    }
+
+    fn commands(&self) -> Vec<String> {
+        self.commands.lock().unwrap().clone()
+    }
+}
+
+async fn serve(stream: TcpStream, log: Arc<Mutex<Vec<String>>>, data: Arc<Mutex<StubData>>) {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let count: usize = line.trim_end()[1..].parse().unwrap();
+        let mut args = Vec::new();
+        for _ in 0..count {
+            let mut header = String::new();
+            stream.read_line(&mut header).await.unwrap();
+            let len: usize = header.trim_end()[1..].parse().unwrap();
+            let mut arg = vec![0; len + 2];
+            stream.read_exact(&mut arg).await.unwrap();
+            arg.truncate(len);
+            args.push(String::from_utf8(arg).unwrap());
+        }
+        log.lock().unwrap().push(args.join(" "));
+
+        let bulk = |value: Option<String>| match value {
+            Some(value) => format!("${}\r\n{}\r\n", value.len(), value),
+            None => "$-1\r\n".to_string(),
+        };
+        let reply = {
+            let mut data = data.lock().unwrap();
+            match args[0].as_str() {
+                "PING" => "+PONG\r\n".to_string(),
+                "SET" => {
+                    data.strings.insert(args[1].clone(), args[2].clone());
+                    "+OK\r\n".to_string()
+                }
+                "GET" => bulk(data.strings.get(&args[1]).cloned()),
+                "LPUSH" => {
+                    let list = data.lists.entry(args[1].clone()).or_default();
+                    list.push_front(args[2].clone());
+                    format!(":{}\r\n", list.len())
+                }
+                "BRPOP" => match data.lists.get_mut(&args[1]).and_then(VecDeque::pop_back) {
+                    Some(value) => {
+                        format!("*2\r\n{}{}", bulk(Some(args[1].clone())), bulk(Some(value)))
+                    }
+                    None => "*-1\r\n".to_string(),
+                },
+                _ => "+OK\r\n".to_string(),
+            }
+        };
+        stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+    }
+}
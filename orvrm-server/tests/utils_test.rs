@@ -0,0 +1,91 @@
+use actix_web::{http::StatusCode, ResponseError};
+use orvrm_core::models::RoutingRequest;
+use orvrm_server::api::versioning::{Versioned, API_VERSION};
+use orvrm_server::config::TlsConfig;
+use orvrm_server::utils::error::{AppError, FieldError};
+
+#[test]
+fn test_app_error_response() {
+    // Test validation error
+    let validation_error = AppError::ValidationError("Invalid input".to_string());
+    let response = validation_error.error_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Test OSRM error
+    let osrm_error = AppError::OsrmError("OSRM service unavailable".to_string());
+    let response = osrm_error.error_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // Test internal error
+    let internal_error = AppError::InternalError("Something went wrong".to_string());
+    let response = internal_error.error_response();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[test]
+fn test_invalid_fields_response() {
+    let errors = vec![FieldError::new("jobs[0].location", "Latitude out of range")];
+    let response = AppError::InvalidFields(errors).error_response();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_versioned_request_parsing() {
+    // A bare request is accepted as is, with unknown fields reported
+    let bare: Versioned<RoutingRequest> = Versioned::parse(serde_json::json!({
+        "vehicles": [{"id": 1, "capacity": [4], "colour": "red"}],
+        "jobs": [],
+        "priority_mode": "strict"
+    }))
+    .unwrap();
+    assert_eq!(bare.version, None);
+    assert_eq!(bare.request.vehicles.len(), 1);
+    assert_eq!(
+        bare.warnings,
+        vec![
+            "Unknown field priority_mode was ignored",
+            "Unknown field vehicles[0].colour was ignored"
+        ]
+    );
+
+    // An envelope is unwrapped
+    let enveloped: Versioned<RoutingRequest> = Versioned::parse(serde_json::json!({
+        "version": API_VERSION,
+        "request": {"vehicles": [], "jobs": [{"id": 1, "location": [0.0, 0.0], "size": 3}]}
+    }))
+    .unwrap();
+    assert_eq!(enveloped.version, Some(API_VERSION));
+    assert_eq!(
+        enveloped.warnings,
+        vec!["Unknown field request.jobs[0].size was ignored"]
+    );
+
+    // Versions the server does not know are rejected
+    let unsupported = Versioned::<RoutingRequest>::parse(serde_json::json!({
+        "version": API_VERSION + 1,
+        "request": {"vehicles": [], "jobs": []}
+    }));
+    assert!(matches!(unsupported, Err(AppError::ValidationError(_))));
+}
+
+#[test]
+fn test_tls_config_reports_unusable_files() {
+    let missing = TlsConfig {
+        cert_path: "/nonexistent/cert.pem".into(),
+        key_path: "/nonexistent/key.pem".into(),
+    };
+    let err = missing.server_config().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("/nonexistent/cert.pem"));
+
+    let empty = std::env::temp_dir().join(format!("orvrm-empty-{}.pem", std::process::id()));
+    std::fs::write(&empty, "no certificates here\n").unwrap();
+    let no_certs = TlsConfig {
+        cert_path: empty.clone(),
+        key_path: empty.clone(),
+    };
+    let err = no_certs.server_config().unwrap_err();
+    std::fs::remove_file(&empty).unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("contains no certificates"));
+}
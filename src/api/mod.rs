@@ -1,3 +0,0 @@
-pub mod routes;
-
-pub use routes::configure_routes;
@@ -1,9 +0,0 @@
-pub mod job;
-pub mod vehicle;
-pub mod request;
-pub mod response;
-
-pub use job::Job;
-pub use vehicle::{VehicleRoute, RouteStep};
-pub use request::RoutingRequest;
-pub use response::{RoutingResponse, RoutingSummary};
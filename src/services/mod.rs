@@ -1,5 +0,0 @@
-pub mod osrm;
-pub mod routing;
-
-pub use osrm::OsrmConfig;
-pub use routing::{RoutingService, RoutingConfig};